@@ -0,0 +1,53 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Measures repeated `available_monitors` calls, which are served from a cache between display
+//! changes.
+//!
+//! This doesn't use the default harness, as windows must be created on the main thread on macOS,
+//! but it reports in the same format as `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use tao::{event_loop::EventLoop, window::WindowBuilder};
+
+const ITERATIONS: u32 = 10_000;
+const SAMPLES: usize = 20;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+  let mut samples: Vec<Duration> = (0..SAMPLES)
+    .map(|_| {
+      let start = Instant::now();
+      for _ in 0..ITERATIONS {
+        f();
+      }
+      start.elapsed() / ITERATIONS
+    })
+    .collect();
+  samples.sort();
+  let median = samples[SAMPLES / 2].as_nanos();
+  let deviation = samples[SAMPLES - 1].as_nanos() - samples[0].as_nanos();
+  println!(
+    "test {} ... bench: {:>11} ns/iter (+/- {})",
+    name, median, deviation
+  );
+}
+
+fn main() {
+  let event_loop = EventLoop::new();
+  let window = WindowBuilder::new()
+    .with_visible(false)
+    .build(&event_loop)
+    .unwrap();
+
+  let count = window.available_monitors().count();
+  bench("window_available_monitors", || {
+    assert_eq!(window.available_monitors().count(), count);
+  });
+  bench("event_loop_available_monitors", || {
+    assert_eq!(event_loop.available_monitors().count(), count);
+  });
+
+  println!();
+  println!("test result: ok. 0 passed; 0 failed; 0 ignored; 2 measured; 0 filtered out");
+}
@@ -0,0 +1,70 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+// Protects only a panel of the window from screen captures, the rest of the window stays visible
+// in them.
+
+#[cfg(target_os = "windows")]
+fn main() {
+  use tao::{
+    dpi::{LogicalPosition, LogicalSize},
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::windows::WindowExtWindows,
+    window::{Rect, WindowBuilder},
+  };
+  use windows::Win32::{
+    Foundation::{HWND, RECT},
+    Graphics::Gdi::{CreateSolidBrush, DeleteObject, FillRect, GetDC, ReleaseDC, HGDIOBJ},
+    UI::WindowsAndMessaging::GetClientRect,
+  };
+  env_logger::init();
+  let event_loop = EventLoop::new();
+
+  let window = WindowBuilder::new()
+    .with_title("Only the panel is hidden from screen captures")
+    .with_inner_size(LogicalSize::new(600.0, 400.0))
+    .build(&event_loop)
+    .unwrap();
+
+  let panel = window
+    .set_protected_region(Some(Rect::new(
+      LogicalPosition::new(50.0, 50.0),
+      LogicalSize::new(200.0, 100.0),
+    )))
+    .unwrap()
+    .unwrap();
+
+  // Fills a window with a color, 0x00BBGGRR
+  let fill = |hwnd: HWND, color: u32| unsafe {
+    let mut rect = RECT::default();
+    GetClientRect(hwnd, &mut rect);
+    let hdc = GetDC(hwnd);
+    let brush = CreateSolidBrush(color);
+    FillRect(hdc, &rect, brush);
+    DeleteObject(HGDIOBJ(brush.0));
+    ReleaseDC(hwnd, hdc);
+  };
+
+  event_loop.run(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      // The panel is drawn along with the window
+      Event::RedrawRequested(window_id) if window_id == window.id() => {
+        fill(HWND(window.hwnd() as _), 0x00e0e0e0);
+        fill(panel, 0x003030c0);
+      }
+      Event::WindowEvent {
+        event: WindowEvent::CloseRequested,
+        ..
+      } => *control_flow = ControlFlow::Exit,
+      _ => (),
+    }
+  });
+}
+
+#[cfg(not(target_os = "windows"))]
+fn main() {
+  println!("This platform doesn't support protecting a region of a window.");
+}
@@ -72,13 +72,17 @@ fn main() {
   menu_bar_menu.add_submenu("My app", true, first_menu);
   menu_bar_menu.add_submenu("Other menu", true, second_menu);
 
-  let window = WindowBuilder::new()
-    .with_title("A fantastic window!")
-    .with_menu(menu_bar_menu)
-    .build(&event_loop)
-    .unwrap();
+  let window_builder = WindowBuilder::new().with_title("A fantastic window!");
 
-  event_loop.run(move |event, _, control_flow| {
+  // the menu bar belongs to the application on macOS and to each window elsewhere
+  #[cfg(target_os = "macos")]
+  event_loop.set_application_menu(menu_bar_menu);
+  #[cfg(not(target_os = "macos"))]
+  let window_builder = window_builder.with_menu(menu_bar_menu);
+
+  let window = window_builder.build(&event_loop).unwrap();
+
+  event_loop.run(move |event, _event_loop, control_flow| {
     *control_flow = ControlFlow::Wait;
 
     match event {
@@ -116,7 +120,10 @@ fn main() {
         let mut my_app_menu = Menu::new();
         my_app_menu.add_item(MenuItemAttributes::new("New menu!"));
         menu_bar_menu.add_submenu("My app", true, my_app_menu);
-        window.set_menu(Some(menu_bar_menu))
+        #[cfg(target_os = "macos")]
+        _event_loop.set_application_menu(menu_bar_menu);
+        #[cfg(not(target_os = "macos"))]
+        window.set_menu(Some(menu_bar_menu));
       }
       Event::MenuEvent {
         menu_id,
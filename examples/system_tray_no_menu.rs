@@ -4,7 +4,7 @@
 // System tray is supported and availabled only if feature flag is enabled.
 // Platform: Windows, Linux and macOS.
 #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
-#[cfg(feature = "tray")]
+#[cfg(all(feature = "tray", not(feature = "headless")))]
 fn main() {
   use std::collections::HashMap;
   #[cfg(target_os = "linux")]
@@ -168,3 +168,10 @@ fn main() {
 fn main() {
   println!("This platform doesn't have the `tray` feature enabled.");
 }
+
+// The headless backend has no system tray.
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+#[cfg(all(feature = "tray", feature = "headless"))]
+fn main() {
+  println!("The headless backend doesn't support system_tray.");
+}
@@ -148,6 +148,39 @@ pub fn validate_scale_factor(scale_factor: f64) -> bool {
   scale_factor.is_sign_positive() && scale_factor.is_normal()
 }
 
+/// How the scale factors reported by Tao are rounded, see
+/// [`EventLoopBuilder::with_scale_factor_rounding`](crate::event_loop::EventLoopBuilder::with_scale_factor_rounding).
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScaleFactorRounding {
+  /// Scale factors are reported as given by the platform, e.g. `1.5` for 150% scaling. This is
+  /// the default.
+  Exact,
+  /// Scale factors are rounded to the nearest integer, halfway values being rounded up, so
+  /// `1.25` becomes `1.0` and `1.5` becomes `2.0`.
+  RoundToNearestInt,
+  /// Scale factors are rounded down to an integer, so `1.5` becomes `1.0`.
+  Floor,
+}
+
+impl ScaleFactorRounding {
+  /// Applies the rounding to `scale_factor`. Integer roundings never return less than `1.0`.
+  pub fn round(self, scale_factor: f64) -> f64 {
+    match self {
+      ScaleFactorRounding::Exact => scale_factor,
+      ScaleFactorRounding::RoundToNearestInt => scale_factor.round().max(1.0),
+      ScaleFactorRounding::Floor => scale_factor.floor().max(1.0),
+    }
+  }
+}
+
+impl Default for ScaleFactorRounding {
+  fn default() -> Self {
+    ScaleFactorRounding::Exact
+  }
+}
+
 /// A position represented in logical pixels.
 ///
 /// The position is stored as floats, so please be careful. Casting floats to integers truncates the
@@ -491,3 +524,56 @@ impl<P: Pixel> From<LogicalPosition<P>> for Position {
     Position::Logical(position.cast())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn position_round_trip() {
+    for &scale_factor in &[1.0, 1.25, 1.5, 2.0, 3.0] {
+      let physical = PhysicalPosition::new(123.0, -45.5);
+      let logical: LogicalPosition<f64> = physical.to_logical(scale_factor);
+      assert_eq!(logical.to_physical::<f64>(scale_factor), physical);
+      assert_eq!(
+        LogicalPosition::<f64>::from_physical(physical, scale_factor),
+        logical
+      );
+    }
+
+    let logical: LogicalPosition<f64> = PhysicalPosition::new(300, 150).to_logical(1.5);
+    assert_eq!(logical, LogicalPosition::new(200.0, 100.0));
+    assert_eq!(
+      logical.to_physical::<u32>(1.5),
+      PhysicalPosition::new(300, 150)
+    );
+  }
+
+  #[test]
+  #[should_panic]
+  fn position_invalid_scale_factor() {
+    PhysicalPosition::new(1.0, 1.0).to_logical::<f64>(0.0);
+  }
+
+  #[test]
+  fn scale_factor_rounding() {
+    let round = |rounding: ScaleFactorRounding| {
+      [0.75, 1.0, 1.25, 1.5, 1.75, 2.0]
+        .iter()
+        .map(|&scale_factor| rounding.round(scale_factor))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(
+      round(ScaleFactorRounding::Exact),
+      [0.75, 1.0, 1.25, 1.5, 1.75, 2.0]
+    );
+    assert_eq!(
+      round(ScaleFactorRounding::RoundToNearestInt),
+      [1.0, 1.0, 1.0, 2.0, 2.0, 2.0]
+    );
+    assert_eq!(
+      round(ScaleFactorRounding::Floor),
+      [1.0, 1.0, 1.0, 1.0, 1.0, 2.0]
+    );
+  }
+}
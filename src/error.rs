@@ -16,6 +16,16 @@ pub enum ExternalError {
   Os(OsError),
 }
 
+/// The error type for when the event loop could not be created.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum EventLoopError {
+  /// The OS cannot create the event loop, e.g. because no display server is available.
+  Os(OsError),
+  /// Another event loop exists in the process, only one can exist at a time.
+  AlreadyRunning,
+}
+
 /// The error type for when the requested operation is not supported by the backend.
 #[derive(Clone)]
 pub struct NotSupportedError {
@@ -70,6 +80,17 @@ impl fmt::Display for ExternalError {
   }
 }
 
+impl fmt::Display for EventLoopError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+    match self {
+      EventLoopError::Os(e) => e.fmt(f),
+      EventLoopError::AlreadyRunning => {
+        f.pad("an event loop already exists in the process, only one can exist at a time")
+      }
+    }
+  }
+}
+
 impl fmt::Debug for NotSupportedError {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
     f.debug_struct("NotSupportedError").finish()
@@ -84,4 +105,5 @@ impl fmt::Display for NotSupportedError {
 
 impl error::Error for OsError {}
 impl error::Error for ExternalError {}
+impl error::Error for EventLoopError {}
 impl error::Error for NotSupportedError {}
@@ -25,6 +25,7 @@
 //!         event_handler(RedrawRequested(w), ..., &mut control_flow);
 //!     }
 //!     event_handler(RedrawEventsCleared, ..., &mut control_flow);
+//!     event_handler(AboutToWait, ..., &mut control_flow);
 //!
 //!     start_cause = wait_if_necessary(control_flow);
 //! }
@@ -45,7 +46,7 @@ use crate::{
   keyboard::{self, ModifiersState},
   menu::{MenuId, MenuType},
   platform_impl,
-  window::{Theme, WindowId},
+  window::{Insets, Orientation, Theme, WindowId},
 };
 
 /// Describes a generic event.
@@ -81,11 +82,15 @@ pub enum Event<'a, T: 'static> {
 
   /// Emitted when a menu has been clicked. There are two types of menu event. One comes from the
   /// menu bar, the other comes from the status bar.
+  ///
+  /// `tray_id` is the [`TrayId`] of the system tray whose menu was clicked, `None` for the menu
+  /// bar.
   #[non_exhaustive]
   MenuEvent {
     window_id: Option<WindowId>,
     menu_id: MenuId,
     origin: MenuType,
+    tray_id: Option<TrayId>,
   },
 
   /// Emitted when tray has been clicked.
@@ -95,6 +100,7 @@ pub enum Event<'a, T: 'static> {
   /// - **iOS / Android / Linux:** Unsupported.
   #[non_exhaustive]
   TrayEvent {
+    id: TrayId,
     bounds: Rectangle,
     event: TrayEvent,
     position: PhysicalPosition<f64>,
@@ -107,10 +113,41 @@ pub enum Event<'a, T: 'static> {
   /// - **iOS / Android:** Unsupported.
   GlobalShortcutEvent(AcceleratorId),
 
+  /// Emitted when a global shortcut registered with
+  /// [`ShortcutManager::register_with_release`] is released, after its
+  /// [`Event::GlobalShortcutEvent`], e.g. to implement push-to-talk.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`ShortcutManager::register_with_release`]: crate::global_shortcut::ShortcutManager::register_with_release
+  GlobalShortcutReleased(AcceleratorId),
+
   /// Emitted when the application has been suspended.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Android:** Emitted when the `ANativeWindow` is destroyed (`onNativeWindowDestroyed`),
+  ///   typically when the app goes to the background. Any rendering surface created for the
+  ///   window must be dropped before returning from the event handler, and no
+  ///   [`Event::RedrawRequested`] or [`WindowEvent::Resized`] is emitted until the next
+  ///   [`Event::Resumed`].
   Suspended,
 
   /// Emitted when the application has been resumed.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Android:** Emitted when the `ANativeWindow` is created (`onNativeWindowCreated`). A
+  ///   rendering surface can only be created for the window after this event, and must be created
+  ///   again after every [`Event::Suspended`].
+  ///
+  ///   Across a backgrounding and foregrounding cycle, the events are emitted in the order of the
+  ///   activity callbacks: `Suspended` when the app goes to the background, then `Resumed` when it
+  ///   comes back. When the activity is destroyed (`onDestroy`), [`WindowEvent::Destroyed`] is
+  ///   emitted after the last `Suspended`, followed by [`Event::LoopDestroyed`], and the event
+  ///   loop exits.
   Resumed,
 
   /// Emitted when all of the event loop's input events have been processed and redraw processing
@@ -145,6 +182,8 @@ pub enum Event<'a, T: 'static> {
   /// the window is requested to redraw. But widgets it contains are usually not tied to its signal.
   /// So if you really want to draw each component, please consider using `connect_draw` method
   /// from [`WidgetExt`] directly.**
+  /// - **macOS / Windows:** While the user resizes the window, it's emitted on each step of the
+  /// resize, after the matching `Resized`.
   ///
   /// [`WidgetExt`]: https://gtk-rs.org/gtk3-rs/stable/latest/docs/gtk/prelude/trait.WidgetExt.html
   RedrawRequested(WindowId),
@@ -157,10 +196,25 @@ pub enum Event<'a, T: 'static> {
   /// tasks have been completed.
   RedrawEventsCleared,
 
+  /// Emitted exactly once per iteration of the event loop, right after `RedrawEventsCleared`,
+  /// as the last event before the loop waits according to its `ControlFlow`.
+  ///
+  /// It comes after all the input events and the `RedrawRequested` events of the iteration, so
+  /// it's the place for per-frame bookkeeping, and for choosing the `ControlFlow` of the wait.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS / iOS:** Emitted when the run loop is about to sleep.
+  /// - **Windows:** Emitted once the message queue is drained.
+  /// - **Linux:** Emitted after the events of the GLib iteration.
+  AboutToWait,
+
   /// Emitted when the event loop is being shut down.
   ///
   /// This is irreversable - if this event is emitted, it is guaranteed to be the last event that
   /// gets emitted. You generally want to treat this as an "do on quit" event.
+  ///
+  /// All [`WindowEvent::Destroyed`] events are emitted before this one.
   LoopDestroyed,
 }
 
@@ -181,6 +235,7 @@ impl<T: Clone> Clone for Event<'static, T> {
       MainEventsCleared => MainEventsCleared,
       RedrawRequested(wid) => RedrawRequested(*wid),
       RedrawEventsCleared => RedrawEventsCleared,
+      AboutToWait => AboutToWait,
       LoopDestroyed => LoopDestroyed,
       Suspended => Suspended,
       Resumed => Resumed,
@@ -188,21 +243,26 @@ impl<T: Clone> Clone for Event<'static, T> {
         window_id,
         menu_id,
         origin,
+        tray_id,
       } => MenuEvent {
         window_id: *window_id,
         menu_id: *menu_id,
         origin: *origin,
+        tray_id: *tray_id,
       },
       TrayEvent {
+        id,
         bounds,
         event,
         position,
       } => TrayEvent {
+        id: *id,
         bounds: *bounds,
         event: *event,
         position: *position,
       },
       GlobalShortcutEvent(accelerator_id) => GlobalShortcutEvent(*accelerator_id),
+      GlobalShortcutReleased(accelerator_id) => GlobalShortcutReleased(*accelerator_id),
     }
   }
 }
@@ -218,6 +278,7 @@ impl<'a, T> Event<'a, T> {
       MainEventsCleared => Ok(MainEventsCleared),
       RedrawRequested(wid) => Ok(RedrawRequested(wid)),
       RedrawEventsCleared => Ok(RedrawEventsCleared),
+      AboutToWait => Ok(AboutToWait),
       LoopDestroyed => Ok(LoopDestroyed),
       Suspended => Ok(Suspended),
       Resumed => Ok(Resumed),
@@ -225,21 +286,26 @@ impl<'a, T> Event<'a, T> {
         window_id,
         menu_id,
         origin,
+        tray_id,
       } => Ok(MenuEvent {
         window_id,
         menu_id,
         origin,
+        tray_id,
       }),
       TrayEvent {
+        id,
         bounds,
         event,
         position,
       } => Ok(TrayEvent {
+        id,
         bounds,
         event,
         position,
       }),
       GlobalShortcutEvent(accelerator_id) => Ok(GlobalShortcutEvent(accelerator_id)),
+      GlobalShortcutReleased(accelerator_id) => Ok(GlobalShortcutReleased(accelerator_id)),
     }
   }
 
@@ -257,6 +323,7 @@ impl<'a, T> Event<'a, T> {
       MainEventsCleared => Some(MainEventsCleared),
       RedrawRequested(wid) => Some(RedrawRequested(wid)),
       RedrawEventsCleared => Some(RedrawEventsCleared),
+      AboutToWait => Some(AboutToWait),
       LoopDestroyed => Some(LoopDestroyed),
       Suspended => Some(Suspended),
       Resumed => Some(Resumed),
@@ -264,21 +331,26 @@ impl<'a, T> Event<'a, T> {
         window_id,
         menu_id,
         origin,
+        tray_id,
       } => Some(MenuEvent {
         window_id,
         menu_id,
         origin,
+        tray_id,
       }),
       TrayEvent {
+        id,
         bounds,
         event,
         position,
       } => Some(TrayEvent {
+        id,
         bounds,
         event,
         position,
       }),
       GlobalShortcutEvent(accelerator_id) => Some(GlobalShortcutEvent(accelerator_id)),
+      GlobalShortcutReleased(accelerator_id) => Some(GlobalShortcutReleased(accelerator_id)),
     }
   }
 }
@@ -331,10 +403,17 @@ pub enum WindowEvent<'a> {
 
   /// The window has been destroyed.
   ///
+  /// This is emitted exactly once per window, and always before [`Event::LoopDestroyed`]. Windows
+  /// that are still alive when the process exits through [`EventLoop::run`] are destroyed at that
+  /// point. The [`WindowId`] of the event is still the one of the destroyed window.
+  ///
   /// ## Platform-specific
   ///
-  /// - **Windows / Linux:** Only fired if the [`crate::window::Window`] is dropped.
-  /// - **macOS:** Fired if the [`crate::window::Window`] is dropped or the dock `Quit` item is clicked.
+  /// - **Windows:** Emitted from `WM_NCDESTROY`.
+  /// - **macOS:** Emitted from `windowWillClose:`, which also happens when the dock `Quit` item is clicked.
+  /// - **Linux:** Emitted from the GTK `destroy` signal.
+  ///
+  /// [`EventLoop::run`]: crate::event_loop::EventLoop::run
   Destroyed,
 
   /// A file has been dropped into the window.
@@ -408,10 +487,33 @@ pub enum WindowEvent<'a> {
   CursorLeft { device_id: DeviceId },
 
   /// A mouse wheel movement or touchpad scroll occurred.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Always a `LineDelta`, fractional for high-precision wheels and touchpads.
+  ///   The phase is always `Moved`.
+  /// - **macOS:** A `PixelDelta` for touchpads and other precise devices, a `LineDelta` for
+  ///   discrete wheels. A touchpad gesture is `Started` when the fingers touch the touchpad,
+  ///   which is the cue to stop any ongoing kinetic scrolling, then `Moved`, and `Ended` when
+  ///   the fingers are lifted. The momentum scrolling that may follow is reported with a
+  ///   `Moved` phase and its own `momentum_phase`.
+  /// - **Linux:** Always a `LineDelta`, fractional for smooth scrolling devices. The phase is
+  ///   `Ended` when the fingers are lifted from the touchpad and `Moved` otherwise.
   MouseWheel {
     device_id: DeviceId,
     delta: MouseScrollDelta,
+    /// The phase of the scroll gesture.
     phase: TouchPhase,
+    /// The phase of the momentum (inertia) scrolling following a gesture, `None` when the event
+    /// isn't part of one. Momentum scrolling is `Started` after the fingers are lifted, `Moved`
+    /// while it slows down, and `Ended` once it stops, or `Cancelled` when interrupted.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** Derived from `NSEvent.momentumPhase`.
+    /// - **Windows / Linux / iOS / Android:** Always `None`, momentum scrolling is either
+    ///   unsupported or indistinguishable from the gesture.
+    momentum_phase: Option<TouchPhase>,
     #[deprecated = "Deprecated in favor of WindowEvent::ModifiersChanged"]
     modifiers: ModifiersState,
   },
@@ -458,7 +560,16 @@ pub enum WindowEvent<'a> {
   /// is pointed to by the `new_inner_size` reference. By default, this will contain the size suggested
   /// by the OS, but it can be changed to any value.
   ///
+  /// `scale_factor` is rounded as set with [`EventLoopBuilder::with_scale_factor_rounding`], in
+  /// which case `new_inner_size` is adjusted to the rounded scale factor.
+  ///
+  /// While this event is handled, [`Window::current_monitor`] already returns the monitor the
+  /// window is moving to, even if the window hasn't been moved there yet.
+  ///
   /// For more information about DPI in general, see the [`dpi`](crate::dpi) module.
+  ///
+  /// [`Window::current_monitor`]: crate::window::Window::current_monitor
+  /// [`EventLoopBuilder::with_scale_factor_rounding`]: crate::event_loop::EventLoopBuilder::with_scale_factor_rounding
   ScaleFactorChanged {
     scale_factor: f64,
     new_inner_size: &'a mut PhysicalSize<u32>,
@@ -478,6 +589,106 @@ pub enum WindowEvent<'a> {
   ///
   /// - **Linux / macOS / Android / iOS:** Unsupported
   DecorationsClick,
+
+  /// The user started moving or resizing the window.
+  ///
+  /// Until [`WindowEvent::MoveResizeEnded`], the event loop keeps iterating and emitting
+  /// `RedrawRequested` for the window at about 60 frames per second, expensive work can be
+  /// throttled meanwhile.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Emitted when the modal move/resize loop starts, on `WM_ENTERSIZEMOVE`.
+  /// - **Linux / macOS / Android / iOS:** Unsupported
+  MoveResizeStarted,
+
+  /// The user stopped moving or resizing the window, see [`WindowEvent::MoveResizeStarted`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux / macOS / Android / iOS:** Unsupported
+  MoveResizeEnded,
+
+  /// The cursor entered or left a button of a custom titlebar, `None` when it left all of them.
+  ///
+  /// The buttons are non-client areas, so this replaces `CursorMoved` to draw their hover state.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Emitted for the buttons declared with `WindowExtWindows::set_nc_hit_regions`,
+  ///   on `WM_NCMOUSEMOVE` and `WM_NCMOUSELEAVE`. Hovering the maximize button also shows the
+  ///   snap layouts on Windows 11.
+  /// - **Linux / macOS / Android / iOS:** Unsupported
+  CaptionButtonHovered(Option<CaptionButton>),
+
+  /// A button of a custom titlebar was pressed or released with the left mouse button, see
+  /// [`WindowEvent::CaptionButtonHovered`].
+  ///
+  /// The window is minimized, maximized or restored, or sent `CloseRequested` on release.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Emitted on `WM_NCLBUTTONDOWN` and `WM_NCLBUTTONUP`.
+  /// - **Linux / macOS / Android / iOS:** Unsupported
+  CaptionButtonInput {
+    button: CaptionButton,
+    state: ElementState,
+  },
+
+  /// The space taken by the decorations or the menu bar changed, contains the new
+  /// [`Window::content_insets`].
+  ///
+  /// It's followed by a `Resized` event when the content area changed size as well, e.g. when the
+  /// menu bar is shown or hidden while the window keeps its size.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** Emitted when the header bar of client-side decorations or the menu bar are
+  ///   shown, hidden or change size.
+  /// - **Windows / macOS / Android / iOS:** Unsupported
+  ///
+  /// [`Window::content_insets`]: crate::window::Window::content_insets
+  DecorationsChanged(Insets),
+
+  /// The safe area of the window changed, contains the new [`Window::safe_area_insets`], in
+  /// logical units.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS:** Emitted on `-[UIView safeAreaInsetsDidChange]`, e.g. when the device rotates.
+  /// - **Windows / Linux / macOS / Android:** Unsupported
+  ///
+  /// [`Window::safe_area_insets`]: crate::window::Window::safe_area_insets
+  SafeAreaChanged(Insets),
+
+  /// The orientation of the window content changed, e.g. when the device rotates.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS:** Emitted when the interface orientation changes.
+  /// - **Android:** Emitted when the orientation of the configuration changes, which doesn't
+  ///   distinguish the flipped orientations: only `Portrait` and `Landscape` are reported.
+  /// - **Windows / Linux / macOS:** Unsupported
+  OrientationChanged(Orientation),
+
+  /// The on-screen keyboard appeared, disappeared or changed size, see
+  /// [`Window::set_soft_input_visible`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS:** Emitted on `UIKeyboardWillChangeFrameNotification`, before the keyboard is
+  ///   animated, so the content can be moved along with it.
+  /// - **Android:** Emitted when the content rect of the activity changes. The height includes
+  ///   the other system UI at the bottom of the window, like the navigation bar, and the
+  ///   activity must use the `adjustResize` soft input mode for the keyboard to be included.
+  /// - **Windows / Linux / macOS:** Unsupported
+  ///
+  /// [`Window::set_soft_input_visible`]: crate::window::Window::set_soft_input_visible
+  SoftInputChanged {
+    /// The physical height of the bottom part of the window covered by the keyboard, `0` when
+    /// it's hidden.
+    height: u32,
+  },
 }
 
 impl Clone for WindowEvent<'static> {
@@ -525,11 +736,13 @@ impl Clone for WindowEvent<'static> {
         device_id,
         delta,
         phase,
+        momentum_phase,
         modifiers,
       } => MouseWheel {
         device_id: *device_id,
         delta: *delta,
         phase: *phase,
+        momentum_phase: *momentum_phase,
         modifiers: *modifiers,
       },
       #[allow(deprecated)]
@@ -568,6 +781,17 @@ impl Clone for WindowEvent<'static> {
         unreachable!("Static event can't be about scale factor changing")
       }
       DecorationsClick => DecorationsClick,
+      MoveResizeStarted => MoveResizeStarted,
+      MoveResizeEnded => MoveResizeEnded,
+      CaptionButtonHovered(button) => CaptionButtonHovered(*button),
+      CaptionButtonInput { button, state } => CaptionButtonInput {
+        button: *button,
+        state: *state,
+      },
+      DecorationsChanged(insets) => DecorationsChanged(*insets),
+      SafeAreaChanged(insets) => SafeAreaChanged(*insets),
+      OrientationChanged(orientation) => OrientationChanged(*orientation),
+      SoftInputChanged { height } => SoftInputChanged { height: *height },
     };
   }
 }
@@ -612,11 +836,13 @@ impl<'a> WindowEvent<'a> {
         device_id,
         delta,
         phase,
+        momentum_phase,
         modifiers,
       } => Some(MouseWheel {
         device_id,
         delta,
         phase,
+        momentum_phase,
         modifiers,
       }),
       #[allow(deprecated)]
@@ -653,6 +879,14 @@ impl<'a> WindowEvent<'a> {
       ThemeChanged(theme) => Some(ThemeChanged(theme)),
       ScaleFactorChanged { .. } => None,
       DecorationsClick => Some(DecorationsClick),
+      MoveResizeStarted => Some(MoveResizeStarted),
+      MoveResizeEnded => Some(MoveResizeEnded),
+      CaptionButtonHovered(button) => Some(CaptionButtonHovered(button)),
+      CaptionButtonInput { button, state } => Some(CaptionButtonInput { button, state }),
+      DecorationsChanged(insets) => Some(DecorationsChanged(insets)),
+      SafeAreaChanged(insets) => Some(SafeAreaChanged(insets)),
+      OrientationChanged(orientation) => Some(OrientationChanged(orientation)),
+      SoftInputChanged { height } => Some(SoftInputChanged { height }),
     }
   }
 }
@@ -662,6 +896,18 @@ impl<'a> WindowEvent<'a> {
 /// Whenever you receive an event arising from a particular input device, this event contains a `DeviceId` which
 /// identifies its origin. Note that devices may be virtual (representing an on-screen cursor and keyboard focus) or
 /// physical. Virtual devices typically aggregate inputs from multiple physical devices.
+///
+/// Where the OS provides one, the `DeviceId` of keyboard and mouse events identifies the physical
+/// device, and stays the same for that device during the session.
+///
+/// ## Platform-specific
+///
+/// - **Windows:** The raw input device handle. Key and mouse button window events use the device
+///   whose raw input reported that key or button, cursor and wheel events the device of the last
+///   mouse input. Touch events use a dummy id.
+/// - **Linux:** The XInput2 device id on X11, an id derived from the `GdkDevice` otherwise.
+/// - **macOS / iOS / Android:** Unsupported, all events report the same `DeviceId`. AppKit events
+///   don't identify the device they come from.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DeviceId(pub(crate) platform_impl::DeviceId);
 
@@ -789,13 +1035,19 @@ pub struct KeyEvent {
   /// `logical_key` is `Key::Enter`, this field is `Some("\r")`.
   ///
   /// This is `None` if the current keypress cannot
-  /// be interpreted as text.
+  /// be interpreted as text, and for key releases.
   ///
   /// See also: `text_with_all_modifiers()`
   pub text: Option<&'static str>,
 
   pub location: keyboard::KeyLocation,
   pub state: ElementState,
+
+  /// Whether or not this key is a key repeat event.
+  ///
+  /// On some systems, holding down a key for some period of time causes that key to be
+  /// repeated as though it were being pressed and released repeatedly. This field is `true`
+  /// if and only if this event is the result of one of those repeats.
   pub repeat: bool,
 
   pub(crate) platform_specific: platform_impl::KeyEventExtra,
@@ -849,6 +1101,35 @@ pub enum TouchPhase {
   Cancelled,
 }
 
+/// Identifier of a system tray.
+///
+/// Whenever you receive an event arising from a particular tray, this event contains a `TrayId`
+/// which identifies its origin.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrayId(pub u16);
+
+impl From<TrayId> for u16 {
+  fn from(s: TrayId) -> u16 {
+    s.0
+  }
+}
+
+impl TrayId {
+  /// Return an empty `TrayId`.
+  pub const EMPTY: TrayId = TrayId(0);
+
+  /// Create new `TrayId` from a String.
+  pub fn new(unique_string: &str) -> TrayId {
+    TrayId(crate::menu::hash_string_to_u16(unique_string))
+  }
+
+  /// Whenever this tray is empty.
+  pub fn is_empty(self) -> bool {
+    Self::EMPTY == self
+  }
+}
+
 /// Describes available tray events.
 // FIXME: add `hover` to TrayEvent for all platforms.
 #[non_exhaustive]
@@ -999,9 +1280,31 @@ pub enum MouseButton {
   Left,
   Right,
   Middle,
+  /// The back thumb button, `XBUTTON1` on Windows.
+  Back,
+  /// The forward thumb button, `XBUTTON2` on Windows.
+  Forward,
+  /// Any other button, identified by its platform button number.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The `XBUTTON` value, the window messages only carry five buttons.
+  /// - **macOS:** The `buttonNumber` of the `NSEvent`, starting at `5`.
+  /// - **Linux:** The X11 button number, starting at `10`. Buttons `4` to `7` are reported as
+  ///   `MouseWheel` events.
   Other(u16),
 }
 
+/// Describes a button of a custom titlebar, see [`WindowEvent::CaptionButtonHovered`].
+#[non_exhaustive]
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CaptionButton {
+  Minimize,
+  Maximize,
+  Close,
+}
+
 /// Describes a difference in the mouse scroll wheel state.
 #[non_exhaustive]
 #[derive(Debug, Clone, Copy, PartialEq)]
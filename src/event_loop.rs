@@ -13,9 +13,32 @@
 //! [event_loop_proxy]: crate::event_loop::EventLoopProxy
 //! [send_event]: crate::event_loop::EventLoopProxy::send_event
 use instant::Instant;
-use std::{error, fmt, ops::Deref};
+use std::{
+  cell::Cell,
+  error, fmt,
+  ops::Deref,
+  rc::Rc,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
+  task::{Wake, Waker},
+};
 
-use crate::{event::Event, monitor::MonitorHandle, platform_impl};
+#[cfg(feature = "testing")]
+use std::collections::VecDeque;
+
+#[cfg(feature = "testing")]
+use crate::window::WindowId;
+
+use crate::{
+  dpi::ScaleFactorRounding,
+  error::EventLoopError,
+  event::{Event, WindowEvent},
+  menu::MenuBar,
+  monitor::MonitorHandle,
+  platform_impl,
+};
 
 /// Provides a way to retrieve events from the system and from the windows that were registered to
 /// the events loop.
@@ -32,9 +55,55 @@ use crate::{event::Event, monitor::MonitorHandle, platform_impl};
 ///
 pub struct EventLoop<T: 'static> {
   pub(crate) event_loop: platform_impl::EventLoop<T>,
+  pub(crate) _active: ActiveGuard,
   pub(crate) _marker: ::std::marker::PhantomData<*mut ()>, // Not Send nor Sync
 }
 
+/// Whether an `EventLoop` exists in the process.
+static EVENT_LOOP_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Marks the event loop as active until it's dropped. Since `EventLoop::run` never returns, a
+/// running event loop stays active.
+///
+/// The headless backend doesn't share any state between its event loops, so several of them can
+/// exist at once, e.g. one per test.
+pub(crate) struct ActiveGuard(());
+
+impl ActiveGuard {
+  pub(crate) fn acquire() -> Result<Self, EventLoopError> {
+    if cfg!(feature = "headless") {
+      Ok(ActiveGuard(()))
+    } else if EVENT_LOOP_ACTIVE.swap(true, Ordering::AcqRel) {
+      Err(EventLoopError::AlreadyRunning)
+    } else {
+      Ok(ActiveGuard(()))
+    }
+  }
+}
+
+impl Drop for ActiveGuard {
+  fn drop(&mut self) {
+    if !cfg!(feature = "headless") {
+      EVENT_LOOP_ACTIVE.store(false, Ordering::Release);
+    }
+  }
+}
+
+/// Builds an [`EventLoop`], reporting failures instead of panicking.
+///
+/// ```no_run
+/// use tao::event_loop::EventLoopBuilder;
+///
+/// match EventLoopBuilder::new().build() {
+///   Ok(event_loop) => { /* create windows and run the event loop */ }
+///   Err(e) => eprintln!("no display available: {}", e),
+/// }
+/// ```
+pub struct EventLoopBuilder<T: 'static> {
+  scale_factor_rounding: ScaleFactorRounding,
+  _marker: ::std::marker::PhantomData<T>,
+}
+
 /// Target that associates windows with an `EventLoop`.
 ///
 /// This type exists to allow you to create new windows while Tao executes
@@ -44,9 +113,102 @@ pub struct EventLoop<T: 'static> {
 #[derive(Clone)]
 pub struct EventLoopWindowTarget<T: 'static> {
   pub(crate) p: platform_impl::EventLoopWindowTarget<T>,
+  /// Shared by the clones, which are handed to the same event handler.
+  pub(crate) control_flow: Rc<ControlFlowState>,
+  /// Set with `EventLoopBuilder::with_scale_factor_rounding`, shared by the clones.
+  pub(crate) scale_factor_rounding: Rc<Cell<ScaleFactorRounding>>,
+  /// Window events sent with `EventLoopProxy::inject_window_event`.
+  #[cfg(feature = "testing")]
+  pub(crate) injected_events: InjectedEvents,
   pub(crate) _marker: ::std::marker::PhantomData<*mut ()>, // Not Send nor Sync
 }
 
+/// The control flow of the event handler call in progress, for
+/// [`EventLoopWindowTarget::set_control_flow`].
+#[derive(Default)]
+pub(crate) struct ControlFlowState {
+  current: Cell<ControlFlow>,
+  /// Counts the `set_control_flow` calls, so a call is noticed even when a nested event handler
+  /// call started and ended in between.
+  sequence: Cell<u64>,
+}
+
+/// The window events sent with `EventLoopProxy::inject_window_event`, dispatched after the next
+/// `NewEvents`.
+#[cfg(feature = "testing")]
+pub(crate) type InjectedEvents = Arc<Mutex<VecDeque<(WindowId, WindowEvent<'static>)>>>;
+
+/// Lets the event handler set the control flow through the target as well as through its
+/// `&mut ControlFlow`.
+pub(crate) fn wrap_event_handler<T: 'static>(
+  mut event_handler: impl FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
+) -> impl FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow) {
+  move |event: Event<'_, T>, target: &EventLoopWindowTarget<T>, control_flow: &mut ControlFlow| {
+    #[cfg(feature = "testing")]
+    let new_events = matches!(event, Event::NewEvents(_));
+    let event = round_scale_factor(event, target.scale_factor_rounding.get());
+    dispatch(&mut event_handler, event, target, control_flow);
+
+    #[cfg(feature = "testing")]
+    if new_events {
+      let injected = std::mem::take(&mut *target.injected_events.lock().unwrap());
+      for (window_id, event) in injected {
+        let event = Event::WindowEvent { window_id, event };
+        dispatch(&mut event_handler, event, target, control_flow);
+      }
+    }
+  }
+}
+
+/// Applies the `ScaleFactorRounding` of the event loop to `ScaleFactorChanged`, keeping the
+/// logical size of `new_inner_size`.
+fn round_scale_factor<T>(event: Event<'_, T>, rounding: ScaleFactorRounding) -> Event<'_, T> {
+  match event {
+    Event::WindowEvent {
+      window_id,
+      event: WindowEvent::ScaleFactorChanged {
+        scale_factor,
+        new_inner_size,
+      },
+    } => {
+      let rounded = rounding.round(scale_factor);
+      if rounded != scale_factor {
+        *new_inner_size = new_inner_size
+          .to_logical::<f64>(scale_factor)
+          .to_physical(rounded);
+      }
+      Event::WindowEvent {
+        window_id,
+        event: WindowEvent::ScaleFactorChanged {
+          scale_factor: rounded,
+          new_inner_size,
+        },
+      }
+    }
+    event => event,
+  }
+}
+
+fn dispatch<T: 'static>(
+  event_handler: &mut impl FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
+  event: Event<'_, T>,
+  target: &EventLoopWindowTarget<T>,
+  control_flow: &mut ControlFlow,
+) {
+  let before = *control_flow;
+  target.control_flow.current.set(before);
+  let sequence = target.control_flow.sequence.get();
+  event_handler(event, target, control_flow);
+  // The writes through `&mut ControlFlow` can't be ordered with the `set_control_flow` calls, a
+  // write that changed it is taken as the last one, unless an exit was requested
+  if target.control_flow.sequence.get() != sequence {
+    let requested = target.control_flow.current.get();
+    if *control_flow == before || matches!(requested, ControlFlow::ExitWithCode(_)) {
+      *control_flow = requested;
+    }
+  }
+}
+
 impl<T> fmt::Debug for EventLoop<T> {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     f.pad("EventLoop { .. }")
@@ -115,6 +277,78 @@ impl Default for ControlFlow {
   }
 }
 
+impl EventLoopBuilder<()> {
+  /// Starts building a new event loop.
+  #[inline]
+  pub fn new() -> Self {
+    Self::with_user_event()
+  }
+}
+
+impl Default for EventLoopBuilder<()> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> EventLoopBuilder<T> {
+  /// Starts building a new event loop that can receive user events of type `T`.
+  #[inline]
+  pub fn with_user_event() -> Self {
+    Self {
+      scale_factor_rounding: Default::default(),
+      _marker: ::std::marker::PhantomData,
+    }
+  }
+
+  /// Rounds the scale factors reported by Tao, for code that can only handle integer scale
+  /// factors. Defaults to [`ScaleFactorRounding::Exact`].
+  ///
+  /// The rounded scale factor is returned by [`Window::scale_factor`] for the windows of the event
+  /// loop, and emitted by [`WindowEvent::ScaleFactorChanged`], whose `new_inner_size` is adjusted
+  /// to keep the same logical size. Logical sizes and positions given to `Window` methods are
+  /// converted to physical pixels with it too. [`MonitorHandle::scale_factor`] isn't tied to an
+  /// event loop and still returns the exact scale factor.
+  ///
+  /// Logical sizes and positions given to the `WindowBuilder` are still converted with the exact
+  /// scale factor, since it isn't known before the window is created.
+  ///
+  /// [`Window::scale_factor`]: crate::window::Window::scale_factor
+  #[inline]
+  pub fn with_scale_factor_rounding(mut self, rounding: ScaleFactorRounding) -> Self {
+    self.scale_factor_rounding = rounding;
+    self
+  }
+
+  /// Builds a new event loop.
+  ///
+  /// All caveats documented in [`EventLoop::new`] apply to this function, except that failing to
+  /// connect to the windowing system is reported as an error.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** Fails if GTK can't be initialized, e.g. because no display is available.
+  /// - **iOS:** Can only be called on the main thread.
+  ///
+  /// Fails with [`EventLoopError::AlreadyRunning`] while another event loop exists, only one
+  /// event loop can exist in the process at a time. The `headless` backend has no such limit.
+  #[inline]
+  pub fn build(self) -> Result<EventLoop<T>, EventLoopError> {
+    // Checked first, creating a second platform event loop can hang
+    let active = ActiveGuard::acquire()?;
+    let event_loop = platform_impl::EventLoop::new().map_err(EventLoopError::Os)?;
+    event_loop
+      .window_target()
+      .scale_factor_rounding
+      .set(self.scale_factor_rounding);
+    Ok(EventLoop {
+      event_loop,
+      _active: active,
+      _marker: ::std::marker::PhantomData,
+    })
+  }
+}
+
 impl EventLoop<()> {
   /// Builds a new event loop with a `()` as the user event type.
   ///
@@ -128,6 +362,11 @@ impl EventLoop<()> {
   /// ## Platform-specific
   ///
   /// - **iOS:** Can only be called on the main thread.
+  ///
+  /// ## Panics
+  ///
+  /// Panics if the event loop can't be created, e.g. while another event loop exists, use
+  /// [`EventLoopBuilder::build`] to handle that case.
   pub fn new() -> EventLoop<()> {
     EventLoop::<()>::with_user_event()
   }
@@ -147,11 +386,15 @@ impl<T> EventLoop<T> {
   /// ## Platform-specific
   ///
   /// - **iOS:** Can only be called on the main thread.
+  ///
+  /// ## Panics
+  ///
+  /// Panics if the event loop can't be created, e.g. while another event loop exists, use
+  /// [`EventLoopBuilder::build`] to handle that case.
   pub fn with_user_event() -> EventLoop<T> {
-    EventLoop {
-      event_loop: platform_impl::EventLoop::new(),
-      _marker: ::std::marker::PhantomData,
-    }
+    EventLoopBuilder::with_user_event()
+      .build()
+      .expect("Failed to create the event loop")
   }
 
   /// Hijacks the calling thread and initializes the tao event loop with the provided
@@ -174,13 +417,15 @@ impl<T> EventLoop<T> {
   where
     F: 'static + FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
   {
-    self.event_loop.run(event_handler)
+    self.event_loop.run(wrap_event_handler(event_handler))
   }
 
   /// Creates an `EventLoopProxy` that can be used to dispatch user events to the main event loop.
   pub fn create_proxy(&self) -> EventLoopProxy<T> {
     EventLoopProxy {
       event_loop_proxy: self.event_loop.create_proxy(),
+      #[cfg(feature = "testing")]
+      injected_events: self.event_loop.window_target().injected_events.clone(),
     }
   }
 }
@@ -194,6 +439,18 @@ impl<T> Deref for EventLoop<T> {
 
 impl<T> EventLoopWindowTarget<T> {
   /// Returns the list of all the monitors available on the system.
+  ///
+  /// This doesn't need any window to exist, so it can be used from the event callback, e.g. on
+  /// `NewEvents(StartCause::Init)`, to position the first window.
+  ///
+  /// The list is cached until the display configuration changes, so calling this repeatedly is
+  /// cheap.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS:** Returns a monitor for each `UIScreen`. Can only be called on the main
+  ///   thread.
+  /// - **Android:** Returns a single monitor for the device display.
   #[inline]
   pub fn available_monitors(&self) -> impl Iterator<Item = MonitorHandle> {
     self
@@ -205,22 +462,106 @@ impl<T> EventLoopWindowTarget<T> {
 
   /// Returns the primary monitor of the system.
   ///
-  /// Returns `None` if it can't identify any monitor as a primary one.
+  /// Returns `None` if it can't identify any monitor as a primary one. Like
+  /// [`available_monitors`](Self::available_monitors), this doesn't need any window to exist.
   #[inline]
   pub fn primary_monitor(&self) -> Option<MonitorHandle> {
     self.p.primary_monitor()
   }
+
+  /// Sets the menu bar of the application.
+  ///
+  /// The menu bar can't be removed on macOS, set a minimal menu instead.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows / Linux / iOS / Android:** Unsupported, menus belong to a window there, see
+  ///   [`Window::set_menu`](crate::window::Window::set_menu).
+  #[inline]
+  pub fn set_application_menu(&self, menu: MenuBar) {
+    self.p.set_application_menu(menu.0.menu_platform)
+  }
+
+  /// Sets the control flow to apply when the event handler returns, like writing to its
+  /// `&mut ControlFlow` argument but from anywhere the target is available.
+  ///
+  /// The last call applies when the event handler returns, unless the handler also changed its
+  /// `&mut ControlFlow` while handling the same event: that write takes precedence, whether it
+  /// happened before or after the call. `ControlFlow::ExitWithCode` always takes precedence, and
+  /// stays sticky.
+  #[inline]
+  pub fn set_control_flow(&self, control_flow: ControlFlow) {
+    self.control_flow.current.set(control_flow);
+    self
+      .control_flow
+      .sequence
+      .set(self.control_flow.sequence.get().wrapping_add(1));
+  }
+
+  /// Returns the control flow the event handler was called with, which includes the writes to
+  /// `&mut ControlFlow` while handling the previous events, or the one given to
+  /// [`set_control_flow`](Self::set_control_flow) since.
+  ///
+  /// A write to `&mut ControlFlow` while handling the current event is only seen from the next
+  /// event on.
+  #[inline]
+  pub fn control_flow(&self) -> ControlFlow {
+    self.control_flow.current.get()
+  }
+
+  /// Changes when [`DeviceEvent`]s are delivered, it can be called at any time, e.g. to only
+  /// listen to the raw mouse motion while the application needs it.
+  ///
+  /// By default, device events are delivered as if this was called with
+  /// [`DeviceEvents::Always`] on Windows, [`DeviceEvents::WhenFocused`] on macOS and
+  /// [`DeviceEvents::Never`] on Linux.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Registers or unregisters the raw input devices.
+  /// - **macOS:** [`DeviceEvents::Always`] installs a global event monitor, which only reports
+  ///   the mouse events while the application isn't focused.
+  /// - **Linux:** Selects or deselects the raw XInput2 events on X11. On Wayland, only
+  ///   [`DeviceEvent::MouseMotion`] is delivered, with the `zwp_relative_pointer_v1` protocol and
+  ///   the `wayland-protocols` feature, and only while the pointer is over a window of the
+  ///   application.
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`DeviceEvent`]: crate::event::DeviceEvent
+  /// [`DeviceEvent::MouseMotion`]: crate::event::DeviceEvent::MouseMotion
+  #[inline]
+  pub fn listen_device_events(&self, allowed: DeviceEvents) {
+    self.p.listen_device_events(allowed)
+  }
+}
+
+/// When to deliver [`DeviceEvent`]s, see [`EventLoopWindowTarget::listen_device_events`].
+///
+/// [`DeviceEvent`]: crate::event::DeviceEvent
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DeviceEvents {
+  /// Deliver device events, even while no window of the application is focused.
+  Always,
+  /// Only deliver device events while a window of the application is focused.
+  WhenFocused,
+  /// Never deliver device events.
+  Never,
 }
 
 /// Used to send custom events to `EventLoop`.
 pub struct EventLoopProxy<T: 'static> {
   event_loop_proxy: platform_impl::EventLoopProxy<T>,
+  #[cfg(feature = "testing")]
+  injected_events: InjectedEvents,
 }
 
 impl<T: 'static> Clone for EventLoopProxy<T> {
   fn clone(&self) -> Self {
     Self {
       event_loop_proxy: self.event_loop_proxy.clone(),
+      #[cfg(feature = "testing")]
+      injected_events: self.injected_events.clone(),
     }
   }
 }
@@ -230,10 +571,103 @@ impl<T: 'static> EventLoopProxy<T> {
   /// `UserEvent(event)` event in the event loop, where `event` is the value passed to this
   /// function.
   ///
+  /// ## Ordering
+  ///
+  /// - User events are emitted in the order they were sent, across all the proxies of the event
+  ///   loop. Events sent from different threads are ordered by when their `send_event` call
+  ///   returned.
+  /// - A user event is emitted after the `NewEvents` and before the `MainEventsCleared` of an
+  ///   iteration of the event loop, never while redrawing. An event sent before an iteration
+  ///   starts is emitted in that iteration, or the one woken up by it.
+  /// - Their order relative to the window events of the same iteration is platform-specific:
+  ///   - **macOS / Android / headless:** After the window events of the iteration.
+  ///   - **Windows / Linux:** In the order the user events and the window events were queued, so a
+  ///     window event already queued when the user event is sent is emitted before it.
+  ///
   /// Returns an `Err` if the associated `EventLoop` no longer exists.
   pub fn send_event(&self, event: T) -> Result<(), EventLoopClosed<T>> {
     self.event_loop_proxy.send_event(event)
   }
+
+  /// Wakes up the `EventLoop` from which this proxy was created, without sending an event.
+  ///
+  /// The event loop starts a new iteration with a `NewEvents` event, as if it received an event,
+  /// which lets it check state shared with other threads. Wake-ups sent before that iteration
+  /// starts are coalesced into it.
+  ///
+  /// Returns an `Err` if the associated `EventLoop` no longer exists.
+  pub fn wake_up(&self) -> Result<(), EventLoopClosed<()>> {
+    self.event_loop_proxy.wake_up()
+  }
+
+  /// Returns a [`Waker`] that [wakes up](EventLoopProxy::wake_up) the `EventLoop` from which
+  /// this proxy was created.
+  ///
+  /// This integrates async code with the event loop without busy-waiting: poll the futures of
+  /// the application, or an async runtime like a `tokio` `LocalSet`, with this waker from the
+  /// event loop, e.g. on `MainEventsCleared`, and let the loop `Wait`. When a future can make
+  /// progress, the waker starts a new iteration of the loop, which polls it again.
+  ///
+  /// Waking after the event loop exited does nothing.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Posts a message to the event loop thread.
+  /// - **macOS / iOS:** Signals a run loop source of the main run loop.
+  /// - **Linux:** Wakes up the default GLib `MainContext`, which also lets GLib sources attached
+  ///   to it, like file descriptors watched with `glib::unix_fd_add_local`, run on the event
+  ///   loop.
+  /// - **Android:** Wakes up the looper of the event loop thread.
+  pub fn waker(&self) -> Waker
+  where
+    T: Send,
+  {
+    Waker::from(Arc::new(ProxyWaker(Mutex::new(self.clone()))))
+  }
+
+  /// Sends `event` to the window `window_id` of the `EventLoop` from which this proxy was
+  /// created, as if it came from the system, to simulate input in tests.
+  ///
+  /// Unlike [`EventLoopProxy::send_event`], this emits an [`Event::WindowEvent`], which is
+  /// dispatched to the event handler right after the `NewEvents` of the next iteration of the
+  /// event loop, in the order the events were injected. The window isn't affected by the event:
+  /// e.g. injecting a [`WindowEvent::Resized`] doesn't resize it.
+  ///
+  /// Returns an `Err` with the event if the associated `EventLoop` no longer exists.
+  ///
+  /// Only available with the `testing` feature.
+  #[cfg(feature = "testing")]
+  pub fn inject_window_event(
+    &self,
+    window_id: WindowId,
+    event: WindowEvent<'static>,
+  ) -> Result<(), EventLoopClosed<WindowEvent<'static>>> {
+    self
+      .injected_events
+      .lock()
+      .unwrap()
+      .push_back((window_id, event));
+    if self.wake_up().is_err() {
+      // Nothing dispatches the events of a closed event loop, this is the one just pushed.
+      let (_, event) = self.injected_events.lock().unwrap().pop_back().unwrap();
+      return Err(EventLoopClosed(event));
+    }
+    Ok(())
+  }
+}
+
+/// Wakes up the event loop for [`EventLoopProxy::waker`].
+struct ProxyWaker<T: 'static>(Mutex<EventLoopProxy<T>>);
+
+impl<T: Send + 'static> Wake for ProxyWaker<T> {
+  fn wake(self: Arc<Self>) {
+    self.wake_by_ref();
+  }
+
+  fn wake_by_ref(self: &Arc<Self>) {
+    // Fails only once the event loop exited, it has nothing left to poll then.
+    let _ = self.0.lock().unwrap().wake_up();
+  }
 }
 
 impl<T: 'static> fmt::Debug for EventLoopProxy<T> {
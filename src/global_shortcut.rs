@@ -13,6 +13,9 @@
 //! ## Platform-specific
 //!
 //! - **Linux**: Only works on x11. See [#331](https://github.com/tauri-apps/tao/issues/331) for more information.
+//! - **macOS**: Shortcuts whose key is a modifier, e.g. `Accelerator::new(None, KeyCode::AltRight)`,
+//!   are watched by an event tap instead of being registered as Carbon hotkeys. They need the
+//!   accessibility permission, and also emit `Event::GlobalShortcutReleased` when released.
 //!
 //! ```rust,ignore
 //! let mut hotkey_manager = ShortcutManager::new(&event_loop);
@@ -55,6 +58,13 @@ impl ShortcutManager {
   }
 
   /// Register a global shortcut of `Accelerator` who trigger `GlobalShortcutEvent` in the event loop.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** The key can be a modifier key, e.g. `KeyCode::AltRight` for a push-to-talk
+  ///   shortcut. Such a shortcut is pressed while its key is held with exactly its modifiers, and
+  ///   always emits `GlobalShortcutReleased` as with `register_with_release`. Fails with `ShortcutManagerError::InvalidAccelerator`
+  ///   when the application doesn't have the accessibility permission.
   pub fn register(
     &mut self,
     accelerator: Accelerator,
@@ -65,10 +75,34 @@ impl ShortcutManager {
       ));
     }
     self.registered_hotkeys.push(accelerator.clone());
-    self.p.register(accelerator)
+    self.p.register(accelerator, false)
+  }
+
+  /// Register a global shortcut of `Accelerator` who trigger `GlobalShortcutEvent` when pressed
+  /// and `GlobalShortcutReleased` when released in the event loop, e.g. for push-to-talk.
+  /// Holding the shortcut emits a single `GlobalShortcutEvent`, the key repeats are ignored.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The release is detected by a low-level keyboard hook.
+  pub fn register_with_release(
+    &mut self,
+    accelerator: Accelerator,
+  ) -> Result<GlobalShortcut, ShortcutManagerError> {
+    if self.is_registered(&accelerator) {
+      return Err(ShortcutManagerError::AcceleratorAlreadyRegistered(
+        accelerator,
+      ));
+    }
+    self.registered_hotkeys.push(accelerator.clone());
+    self.p.register(accelerator, true)
   }
 
   /// Unregister all `Accelerator` registered by the manager instance.
+  ///
+  /// The shortcuts registered by other managers and processes are left untouched, and the
+  /// manager can register a fresh set of shortcuts afterwards. All the shortcuts are unregistered
+  /// even when one fails, the first error is returned.
   pub fn unregister_all(&mut self) -> Result<(), ShortcutManagerError> {
     self.registered_hotkeys = Vec::new();
     self.p.unregister_all()
@@ -1,8 +1,8 @@
 // Copyright 2019-2021 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::platform_impl::PlatformIcon;
-use std::{error::Error, fmt, io, mem};
+use crate::{dpi::PhysicalSize, platform_impl::PlatformIcon};
+use std::{error::Error, fmt, fs, io, mem, path::Path};
 
 #[repr(C)]
 #[derive(Debug)]
@@ -17,7 +17,8 @@ pub(crate) const PIXEL_SIZE: usize = mem::size_of::<Pixel>();
 
 #[non_exhaustive]
 #[derive(Debug)]
-/// An error produced when using `Icon::from_rgba` with invalid arguments.
+/// An error produced when using `Icon::from_rgba` with invalid arguments, or when
+/// `Icon::from_path` fails to load the file.
 pub enum BadIcon {
   /// Produced when the length of the `rgba` argument isn't divisible by 4, thus `rgba` can't be
   /// safely interpreted as 32bpp RGBA pixels.
@@ -34,6 +35,23 @@ pub enum BadIcon {
   },
   /// Produced when underlying OS functionality failed to create the icon
   OsError(io::Error),
+  /// Produced when `Icon::from_path` can't decode the file, e.g. because it isn't a PNG or ICO
+  /// file, or it uses an unsupported pixel format.
+  DecodeError(String),
+  /// Produced when `Icon::from_rgba_sizes` is given no image.
+  NoSizes,
+  /// Produced when `CustomCursor::from_animation` is given no frame.
+  NoFrames,
+  /// Produced when a frame of an animated cursor has a zero duration.
+  ZeroFrameDuration,
+  /// Produced when the hotspot of a cursor is outside of its image.
+  #[non_exhaustive]
+  HotspotOutOfBounds {
+    width: u32,
+    height: u32,
+    hotspot_x: u32,
+    hotspot_y: u32,
+  },
 }
 
 impl fmt::Display for BadIcon {
@@ -53,6 +71,19 @@ impl fmt::Display for BadIcon {
                 width, height, pixel_count, width_x_height,
             ),
             BadIcon::OsError(e) => write!(f, "OS error when instantiating the icon: {:?}", e),
+            BadIcon::DecodeError(reason) => write!(f, "Failed to decode the icon: {}", reason),
+            BadIcon::NoSizes => write!(f, "The icon must contain at least one image."),
+            BadIcon::NoFrames => write!(f, "The cursor animation must contain at least one frame."),
+            BadIcon::ZeroFrameDuration => write!(f, "The frames of a cursor animation must have a non-zero duration."),
+            BadIcon::HotspotOutOfBounds {
+                width,
+                height,
+                hotspot_x,
+                hotspot_y,
+            } => write!(f,
+                "The hotspot ({:?}, {:?}) is outside of the {:?}x{:?} cursor image.",
+                hotspot_x, hotspot_y, width, height,
+            ),
         }
   }
 }
@@ -113,6 +144,13 @@ mod constructors {
       let _ = RgbaIcon::from_rgba(rgba, width, height)?;
       Ok(NoIcon)
     }
+
+    pub fn from_rgba_sizes(sizes: Vec<(Vec<u8>, u32, u32)>) -> Result<Self, BadIcon> {
+      for (rgba, width, height) in sizes {
+        let _ = RgbaIcon::from_rgba(rgba, width, height)?;
+      }
+      Ok(NoIcon)
+    }
   }
 }
 
@@ -138,4 +176,327 @@ impl Icon {
       inner: PlatformIcon::from_rgba(rgba, width, height)?,
     })
   }
+
+  /// Creates an `Icon` from the same image in several sizes, e.g. 16x16, 32x32, 48x48 and
+  /// 256x256, so that the system picks the best one for each place the icon is shown in.
+  ///
+  /// Each image is given as 32bpp RGBA data with its width and height, with the same
+  /// requirements as [`Icon::from_rgba`]. Returns [`BadIcon::NoSizes`] when `sizes` is empty.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The window icon uses the image matching the small icon size at the window's
+  ///   DPI, and the taskbar icon the one matching the big icon size. A window icon with several
+  ///   sizes is also used as the taskbar icon when there's none.
+  /// - **macOS / iOS / Android:** Unsupported, there are no window icons.
+  pub fn from_rgba_sizes(sizes: Vec<(Vec<u8>, u32, u32)>) -> Result<Self, BadIcon> {
+    if sizes.is_empty() {
+      return Err(BadIcon::NoSizes);
+    }
+    Ok(Icon {
+      inner: PlatformIcon::from_rgba_sizes(sizes)?,
+    })
+  }
+
+  /// Loads an `Icon` from a PNG or ICO file.
+  ///
+  /// ICO files usually contain the same icon in several sizes: specify `size` to pick the
+  /// smallest image that's at least that large, or `None` to pick the largest one. `size` is
+  /// ignored for PNG files, which contain a single image.
+  ///
+  /// PNG files, and the PNG images of ICO files, are only decoded with the `png` feature, and
+  /// fail with `BadIcon::DecodeError` otherwise.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** This method takes precedence over [`IconExtWindows::from_path`], which loads
+  ///   ICO files with the system instead and also scales the icon to `size`. Call it as
+  ///   `<Icon as IconExtWindows>::from_path` to use it.
+  ///
+  /// [`IconExtWindows::from_path`]: crate::platform::windows::IconExtWindows::from_path
+  pub fn from_path<P: AsRef<Path>>(
+    path: P,
+    size: Option<PhysicalSize<u32>>,
+  ) -> Result<Self, BadIcon> {
+    let bytes = fs::read(path).map_err(BadIcon::OsError)?;
+    let icon = decode::decode(&bytes, size)?;
+    Icon::from_rgba(icon.rgba, icon.width, icon.height)
+  }
+
+  /// Loads an `Icon` from an ICO resource embedded in this executable or library, with
+  /// `LoadImageW`.
+  ///
+  /// Specify `size` to load a specific icon size from the resource, or `None` to load the
+  /// default icon size. Windows scales the icon when the size doesn't exist in the resource.
+  ///
+  /// This is the same as [`IconExtWindows::from_resource`].
+  ///
+  /// [`IconExtWindows::from_resource`]: crate::platform::windows::IconExtWindows::from_resource
+  #[cfg(all(target_os = "windows", not(feature = "headless")))]
+  pub fn from_resource(ordinal: u16, size: Option<PhysicalSize<u32>>) -> Result<Self, BadIcon> {
+    Ok(Icon {
+      inner: PlatformIcon::from_resource(ordinal, size)?,
+    })
+  }
+}
+
+/// Decoding of the PNG and ICO files for `Icon::from_path`.
+mod decode {
+  use super::{BadIcon, RgbaIcon};
+  use crate::dpi::PhysicalSize;
+  use std::convert::TryInto;
+
+  const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+  const ICONDIR_SIZE: usize = 6;
+  const ICONDIRENTRY_SIZE: usize = 16;
+
+  fn error(reason: impl Into<String>) -> BadIcon {
+    BadIcon::DecodeError(reason.into())
+  }
+
+  fn u16_at(bytes: &[u8], offset: usize) -> Result<u16, BadIcon> {
+    bytes
+      .get(offset..offset + 2)
+      .map(|b| u16::from_le_bytes(b.try_into().unwrap()))
+      .ok_or_else(|| error("unexpected end of file"))
+  }
+
+  fn u32_at(bytes: &[u8], offset: usize) -> Result<u32, BadIcon> {
+    bytes
+      .get(offset..offset + 4)
+      .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+      .ok_or_else(|| error("unexpected end of file"))
+  }
+
+  pub(super) fn decode(bytes: &[u8], size: Option<PhysicalSize<u32>>) -> Result<RgbaIcon, BadIcon> {
+    if bytes.starts_with(PNG_SIGNATURE) {
+      decode_png(bytes)
+    } else if bytes.starts_with(&[0, 0, 1, 0]) {
+      decode_ico(bytes, size)
+    } else {
+      Err(error("the file is neither a PNG nor an ICO file"))
+    }
+  }
+
+  #[cfg(not(feature = "png"))]
+  fn decode_png(_bytes: &[u8]) -> Result<RgbaIcon, BadIcon> {
+    Err(error("decoding PNG images requires the `png` feature"))
+  }
+
+  #[cfg(feature = "png")]
+  fn decode_png(bytes: &[u8]) -> Result<RgbaIcon, BadIcon> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+      .read_info()
+      .map_err(|e| error(format!("invalid PNG: {}", e)))?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader
+      .next_frame(&mut buf)
+      .map_err(|e| error(format!("invalid PNG: {}", e)))?;
+    buf.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+      png::ColorType::Rgba => buf,
+      png::ColorType::Rgb => buf
+        .chunks_exact(3)
+        .flat_map(|p| [p[0], p[1], p[2], 0xff])
+        .collect(),
+      png::ColorType::GrayscaleAlpha => buf
+        .chunks_exact(2)
+        .flat_map(|p| [p[0], p[0], p[0], p[1]])
+        .collect(),
+      png::ColorType::Grayscale => buf.iter().flat_map(|&p| [p, p, p, 0xff]).collect(),
+      png::ColorType::Indexed => return Err(error("unexpected indexed PNG after expansion")),
+    };
+    RgbaIcon::from_rgba(rgba, info.width, info.height)
+  }
+
+  fn decode_ico(bytes: &[u8], size: Option<PhysicalSize<u32>>) -> Result<RgbaIcon, BadIcon> {
+    let count = u16_at(bytes, 4)? as usize;
+    let mut best: Option<(u32, u32, u16, usize)> = None;
+    for index in 0..count {
+      let entry = ICONDIR_SIZE + index * ICONDIRENTRY_SIZE;
+      let dimension = |offset| {
+        bytes
+          .get(entry + offset)
+          .map(|&d| if d == 0 { 256 } else { d as u32 })
+          .ok_or_else(|| error("unexpected end of file"))
+      };
+      let (width, height) = (dimension(0)?, dimension(1)?);
+      let bit_count = u16_at(bytes, entry + 6)?;
+
+      let is_better = match best {
+        None => true,
+        Some((best_width, best_height, best_bit_count, _)) => {
+          let area = width * height;
+          let best_area = best_width * best_height;
+          let fits = |w, h| size.map_or(true, |size| w >= size.width && h >= size.height);
+          match (fits(width, height), fits(best_width, best_height)) {
+            (true, false) => true,
+            (false, true) => false,
+            // Among the images that are large enough, the smallest one is the closest match,
+            // when none is large enough (or no size is requested) the largest one is.
+            (true, true) if size.is_some() && area != best_area => area < best_area,
+            _ if area != best_area => area > best_area,
+            _ => bit_count > best_bit_count,
+          }
+        }
+      };
+      if is_better {
+        best = Some((width, height, bit_count, entry));
+      }
+    }
+
+    let (_, _, _, entry) = best.ok_or_else(|| error("the ICO file contains no image"))?;
+    let len = u32_at(bytes, entry + 8)? as usize;
+    let offset = u32_at(bytes, entry + 12)? as usize;
+    let image = offset
+      .checked_add(len)
+      .and_then(|end| bytes.get(offset..end))
+      .ok_or_else(|| error("unexpected end of file"))?;
+
+    if image.starts_with(PNG_SIGNATURE) {
+      decode_png(image)
+    } else {
+      decode_dib(image)
+    }
+  }
+
+  /// Decodes the `BITMAPINFOHEADER` image of an ICO entry: a bottom-up bitmap followed by a 1bpp
+  /// transparency mask, both twice as high as the icon in the header.
+  fn decode_dib(image: &[u8]) -> Result<RgbaIcon, BadIcon> {
+    let header_size = u32_at(image, 0)? as usize;
+    let width = u32_at(image, 4)? as i32;
+    let height = u32_at(image, 8)? as i32 / 2;
+    let bit_count = u16_at(image, 14)?;
+    let compression = u32_at(image, 16)?;
+    if width <= 0 || height <= 0 {
+      return Err(error("invalid bitmap dimensions"));
+    }
+    if compression != 0 {
+      return Err(error("compressed bitmaps aren't supported"));
+    }
+    let (width, height) = (width as usize, height as usize);
+
+    // The header is untrusted, its sizes may overflow on 32-bit targets
+    let too_large = || error("the bitmap is too large");
+    let stride = |bits_per_row: Option<usize>| {
+      bits_per_row
+        .and_then(|bits| bits.checked_add(31))
+        .map(|bits| bits / 32 * 4)
+        .ok_or_else(too_large)
+    };
+    let color_stride = stride(width.checked_mul(bit_count as usize))?;
+    let mask_stride = stride(Some(width))?;
+    let colors = header_size;
+    let mask = color_stride
+      .checked_mul(height)
+      .and_then(|size| size.checked_add(colors))
+      .ok_or_else(too_large)?;
+    let end = mask_stride
+      .checked_mul(height)
+      .and_then(|size| size.checked_add(mask))
+      .ok_or_else(too_large)?;
+    let rgba_len = width
+      .checked_mul(height)
+      .and_then(|pixels| pixels.checked_mul(4))
+      .ok_or_else(too_large)?;
+    if image.len() < end {
+      return Err(error("unexpected end of file"));
+    }
+
+    let masked =
+      |row: usize, x: usize| image[mask + row * mask_stride + x / 8] & (0x80 >> (x % 8)) != 0;
+    let mut rgba = vec![0; rgba_len];
+    for y in 0..height {
+      let row = height - 1 - y;
+      for x in 0..width {
+        let pixel = &image[colors + row * color_stride..];
+        let (b, g, r, a) = match bit_count {
+          32 => (
+            pixel[x * 4],
+            pixel[x * 4 + 1],
+            pixel[x * 4 + 2],
+            pixel[x * 4 + 3],
+          ),
+          24 => (pixel[x * 3], pixel[x * 3 + 1], pixel[x * 3 + 2], 0xff),
+          _ => return Err(error(format!("{}bpp bitmaps aren't supported", bit_count))),
+        };
+        let a = if masked(row, x) { 0 } else { a };
+        let i = (y * width + x) * 4;
+        rgba[i..i + 4].copy_from_slice(&[r, g, b, a]);
+      }
+    }
+
+    // Old 32bpp icons leave the alpha channel empty and only rely on the mask
+    if bit_count == 32 && rgba.chunks_exact(4).all(|p| p[3] == 0) {
+      for y in 0..height {
+        let row = height - 1 - y;
+        for x in 0..width {
+          rgba[(y * width + x) * 4 + 3] = if masked(row, x) { 0 } else { 0xff };
+        }
+      }
+    }
+
+    RgbaIcon::from_rgba(rgba, width as u32, height as u32)
+  }
+
+  #[test]
+  fn test_decode_ico_picks_best_size() {
+    // A 32bpp bitmap entry filled with one color, with an empty transparency mask
+    fn dib(size: u32, color: [u8; 4]) -> Vec<u8> {
+      let mut dib = Vec::new();
+      for field in [40, size, size * 2] {
+        dib.extend_from_slice(&field.to_le_bytes());
+      }
+      dib.extend_from_slice(&1u16.to_le_bytes());
+      dib.extend_from_slice(&32u16.to_le_bytes());
+      dib.resize(40, 0);
+      for _ in 0..size * size {
+        dib.extend_from_slice(&color);
+      }
+      dib.resize(
+        dib.len() + ((size as usize + 31) / 32 * 4) * size as usize,
+        0,
+      );
+      dib
+    }
+
+    let images = [dib(16, [0, 0, 0xff, 0xff]), dib(32, [0xff, 0, 0, 0x80])];
+    let mut ico = vec![0, 0, 1, 0, images.len() as u8, 0];
+    let mut offset = ICONDIR_SIZE + images.len() * ICONDIRENTRY_SIZE;
+    for (image, size) in images.iter().zip([16u8, 32]) {
+      ico.extend_from_slice(&[size, size, 0, 0, 1, 0, 32, 0]);
+      ico.extend_from_slice(&(image.len() as u32).to_le_bytes());
+      ico.extend_from_slice(&(offset as u32).to_le_bytes());
+      offset += image.len();
+    }
+    for image in &images {
+      ico.extend_from_slice(image);
+    }
+
+    let largest = decode(&ico, None).unwrap();
+    assert_eq!((largest.width, largest.height), (32, 32));
+    assert_eq!(&largest.rgba[..4], &[0, 0, 0xff, 0x80]);
+    let closest = decode(&ico, Some(PhysicalSize::new(12, 12))).unwrap();
+    assert_eq!((closest.width, closest.height), (16, 16));
+    assert_eq!(&closest.rgba[..4], &[0xff, 0, 0, 0xff]);
+    assert!(matches!(
+      decode(b"GIF89a", None),
+      Err(BadIcon::DecodeError(_))
+    ));
+  }
+
+  #[test]
+  fn test_decode_dib_rejects_huge_dimensions() {
+    let mut dib = Vec::new();
+    for field in [40, i32::MAX as u32, (i32::MAX as u32) & !1] {
+      dib.extend_from_slice(&field.to_le_bytes());
+    }
+    dib.extend_from_slice(&1u16.to_le_bytes());
+    dib.extend_from_slice(&32u16.to_le_bytes());
+    dib.resize(48, 0);
+    assert!(matches!(decode_dib(&dib), Err(BadIcon::DecodeError(_))));
+  }
 }
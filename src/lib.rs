@@ -176,7 +176,7 @@ pub mod monitor;
 mod platform_impl;
 
 #[cfg(any(target_os = "windows", target_os = "macos",))]
-#[cfg(feature = "tray")]
+#[cfg(all(feature = "tray", not(feature = "headless")))]
 pub mod system_tray;
 #[cfg(all(
   any(
@@ -187,6 +187,7 @@ pub mod system_tray;
     target_os = "openbsd"
   ),
   feature = "tray",
+  not(feature = "headless"),
   any(feature = "gtk-tray", feature = "ayatana-tray"),
   not(all(feature = "gtk-tray", feature = "ayatana-tray"))
 ))]
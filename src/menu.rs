@@ -441,7 +441,7 @@ pub enum MenuType {
   ContextMenu,
 }
 
-fn hash_string_to_u16(title: &str) -> u16 {
+pub(crate) fn hash_string_to_u16(title: &str) -> u16 {
   let mut s = DefaultHasher::new();
   title.to_uppercase().hash(&mut s);
   s.finish() as u16
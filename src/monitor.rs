@@ -145,7 +145,7 @@ impl MonitorHandle {
   ///
   /// ## Platform-specific
   ///
-  /// - **Android:** Always returns 1.0.
+  /// - **Android:** Returns the display density relative to 160 DPI.
   #[inline]
   pub fn scale_factor(&self) -> f64 {
     self.inner.scale_factor()
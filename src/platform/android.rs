@@ -1,7 +1,7 @@
 // Copyright 2019-2021 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#![cfg(any(target_os = "android"))]
+#![cfg(all(target_os = "android", not(feature = "headless")))]
 
 use crate::{
   event_loop::{EventLoop, EventLoopWindowTarget},
@@ -0,0 +1,132 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(feature = "headless")]
+
+//! The `headless` backend replaces the native one when the `headless` feature is enabled.
+//!
+//! No window is ever shown: windows only keep their state in memory and queue the events a
+//! window manager would send, e.g. [`WindowEvent::Resized`] after
+//! [`Window::set_inner_size`]. Input is simulated with the `inject_*` methods of
+//! [`EventLoopWindowTargetExtHeadless`].
+//!
+//! The backend is chosen at compile time, so it's never used automatically when no display is
+//! available. Enable the feature for the test runs that need it, e.g.
+//! `cargo test --features headless`.
+//!
+//! [`Window::set_inner_size`]: crate::window::Window::set_inner_size
+
+use crate::{
+  dpi::{PhysicalPosition, PhysicalSize},
+  event::{DeviceEvent, DeviceId, ElementState, Event, KeyEvent, WindowEvent},
+  event_loop::EventLoopWindowTarget,
+  keyboard::{Key, KeyCode, KeyLocation},
+  platform_impl,
+  window::WindowId,
+};
+
+/// A fake monitor, see [`EventLoopWindowTargetExtHeadless::set_monitors`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadlessMonitor {
+  pub name: String,
+  pub position: PhysicalPosition<i32>,
+  pub size: PhysicalSize<u32>,
+  pub scale_factor: f64,
+  /// In Hz.
+  pub refresh_rate: u16,
+}
+
+impl Default for HeadlessMonitor {
+  /// A single 1920x1080 monitor at 60Hz with a scale factor of 1.0.
+  fn default() -> Self {
+    HeadlessMonitor {
+      name: "Headless".to_owned(),
+      position: PhysicalPosition::new(0, 0),
+      size: PhysicalSize::new(1920, 1080),
+      scale_factor: 1.0,
+      refresh_rate: 60,
+    }
+  }
+}
+
+/// Additional methods on `EventLoopWindowTarget` that are specific to the headless backend.
+pub trait EventLoopWindowTargetExtHeadless<T> {
+  /// Queues an event, it's delivered on the next iteration of the event loop, before the
+  /// events queued by windows and the user events.
+  ///
+  /// Window and device events are easier to build with
+  /// [`inject_window_event`](Self::inject_window_event) and
+  /// [`inject_device_event`](Self::inject_device_event).
+  fn inject_event(&self, event: Event<'static, T>);
+
+  /// Queues an [`Event::WindowEvent`], e.g. a [`WindowEvent::CursorMoved`] to simulate input.
+  fn inject_window_event(&self, window_id: WindowId, event: WindowEvent<'static>);
+
+  /// Queues a [`WindowEvent::KeyboardInput`] that isn't synthetic, see [`key_event`] to build
+  /// the `event`.
+  fn inject_keyboard_input(&self, window_id: WindowId, event: KeyEvent);
+
+  /// Queues an [`Event::DeviceEvent`].
+  fn inject_device_event(&self, device_id: DeviceId, event: DeviceEvent);
+
+  /// Replaces the monitors, the first one is the primary monitor.
+  ///
+  /// Each event loop has its own monitors, shared by its windows, which default to a single
+  /// [`HeadlessMonitor::default`]. Windows report the monitor containing their top-left corner
+  /// as their current monitor, and its scale factor as theirs.
+  fn set_monitors(&self, monitors: Vec<HeadlessMonitor>);
+}
+
+impl<T> EventLoopWindowTargetExtHeadless<T> for EventLoopWindowTarget<T> {
+  fn inject_event(&self, event: Event<'static, T>) {
+    self.p.inject_event(event)
+  }
+
+  fn inject_window_event(&self, window_id: WindowId, event: WindowEvent<'static>) {
+    self.p.inject_event(Event::WindowEvent { window_id, event })
+  }
+
+  fn inject_keyboard_input(&self, window_id: WindowId, event: KeyEvent) {
+    self.inject_window_event(
+      window_id,
+      WindowEvent::KeyboardInput {
+        device_id: DeviceId(platform_impl::DeviceId::dummy()),
+        event,
+        is_synthetic: false,
+      },
+    )
+  }
+
+  fn inject_device_event(&self, device_id: DeviceId, event: DeviceEvent) {
+    self.p.inject_event(Event::DeviceEvent { device_id, event })
+  }
+
+  fn set_monitors(&self, monitors: Vec<HeadlessMonitor>) {
+    self.p.set_monitors(monitors)
+  }
+}
+
+/// Builds a [`KeyEvent`] to inject, as a standard key that isn't a
+/// repeat.
+///
+/// `text` is also used as the text with all modifiers, and `logical_key` as the key without
+/// modifiers.
+pub fn key_event(
+  physical_key: KeyCode,
+  logical_key: Key<'static>,
+  text: Option<&'static str>,
+  state: ElementState,
+) -> KeyEvent {
+  KeyEvent {
+    physical_key,
+    logical_key: logical_key.clone(),
+    text,
+    location: KeyLocation::Standard,
+    state,
+    repeat: false,
+    platform_specific: platform_impl::KeyEventExtra {
+      text_with_all_modifiers: text,
+      key_without_modifiers: logical_key,
+    },
+  }
+}
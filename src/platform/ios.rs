@@ -1,7 +1,7 @@
 // Copyright 2019-2021 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#![cfg(target_os = "ios")]
+#![cfg(all(target_os = "ios", not(feature = "headless")))]
 
 use std::os::raw::c_void;
 
@@ -85,6 +85,11 @@ pub trait WindowExtIOS {
   /// and then calls
   /// [`-[UIViewController setNeedsUpdateOfScreenEdgesDeferringSystemGestures]`](https://developer.apple.com/documentation/uikit/uiviewcontroller/2887507-setneedsupdateofscreenedgesdefer?language=objc).
   ///
+  /// A swipe from a deferred edge is delivered to the application as touch events, and the
+  /// system gesture only triggers on a second swipe. For an immersive mode, e.g. in games, combine
+  /// it with [`WindowExtIOS::set_prefers_home_indicator_hidden`] and
+  /// [`WindowExtIOS::set_prefers_status_bar_hidden`].
+  ///
   /// This only has an effect on iOS 11.0+.
   fn set_preferred_screen_edges_deferring_system_gestures(&self, edges: ScreenEdge);
 
@@ -1,7 +1,7 @@
 // Copyright 2019-2021 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#![cfg(target_os = "macos")]
+#![cfg(all(target_os = "macos", not(feature = "headless")))]
 
 use std::os::raw::c_void;
 
@@ -53,7 +53,43 @@ pub trait WindowExtMacOS {
   fn has_shadow(&self) -> bool;
 
   /// Sets whether or not the window has shadow.
+  ///
+  /// Transparent windows have no shadow unless it's enabled explicitly, since AppKit computes it
+  /// from the window contents only when asked to. Call [`invalidate_shadow`] after rendering to
+  /// make the shadow follow the opaque pixels.
+  ///
+  /// [`invalidate_shadow`]: Self::invalidate_shadow
   fn set_has_shadow(&self, has_shadow: bool);
+
+  /// Recomputes the window shadow from the current window contents.
+  fn invalidate_shadow(&self);
+
+  /// Sets which Option keys are treated as Alt.
+  ///
+  /// When an Option key is treated as Alt, key presses with it held don't compose special
+  /// characters or start dead-key sequences, and report the key as if Option wasn't pressed,
+  /// with the Alt modifier set. The default is [`OptionAsAlt::None`].
+  fn set_option_as_alt(&self, option_as_alt: OptionAsAlt);
+
+  /// Returns which Option keys are treated as Alt.
+  fn option_as_alt(&self) -> OptionAsAlt;
+
+  /// Adds an `NSVisualEffectView` below the content of the window, or removes it with `None`.
+  ///
+  /// The effect view follows the size of the window, and the content drawn above it must be
+  /// transparent for it to show through.
+  fn set_vibrancy(&self, vibrancy: Option<Vibrancy>);
+
+  /// Sets whether the window can be shown over the fullscreen windows of other applications,
+  /// with the `NSWindowCollectionBehaviorFullScreenAuxiliary` collection behavior.
+  ///
+  /// This is meant for overlays, which also need a window level above the fullscreen window,
+  /// see `Window::set_always_on_top`, and to be on the fullscreen space of the other application.
+  /// Windows are only on the space they were opened on by default, add the
+  /// `NSWindowCollectionBehaviorCanJoinAllSpaces` collection behavior through the `NSWindow` to
+  /// show them on every space. An application with the regular activation policy can only show
+  /// its windows there while active, use [`ActivationPolicy::Accessory`] otherwise.
+  fn set_fullscreen_auxiliary(&self, fullscreen_auxiliary: bool);
 }
 
 impl WindowExtMacOS for Window {
@@ -86,6 +122,127 @@ impl WindowExtMacOS for Window {
   fn set_has_shadow(&self, has_shadow: bool) {
     self.window.set_has_shadow(has_shadow)
   }
+
+  #[inline]
+  fn invalidate_shadow(&self) {
+    self.window.invalidate_shadow()
+  }
+
+  #[inline]
+  fn set_option_as_alt(&self, option_as_alt: OptionAsAlt) {
+    self.window.set_option_as_alt(option_as_alt)
+  }
+
+  #[inline]
+  fn option_as_alt(&self) -> OptionAsAlt {
+    self.window.option_as_alt()
+  }
+
+  #[inline]
+  fn set_vibrancy(&self, vibrancy: Option<Vibrancy>) {
+    self.window.set_vibrancy(vibrancy)
+  }
+
+  #[inline]
+  fn set_fullscreen_auxiliary(&self, fullscreen_auxiliary: bool) {
+    self.window.set_fullscreen_auxiliary(fullscreen_auxiliary)
+  }
+}
+
+/// Which Option keys are treated as Alt, see [`WindowExtMacOS::set_option_as_alt`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionAsAlt {
+  /// Both Option keys compose characters, as usual on macOS.
+  None,
+  /// Only the left Option key is treated as Alt.
+  OnlyLeft,
+  /// Only the right Option key is treated as Alt.
+  OnlyRight,
+  /// Both Option keys are treated as Alt.
+  Both,
+}
+
+impl Default for OptionAsAlt {
+  fn default() -> Self {
+    OptionAsAlt::None
+  }
+}
+
+/// Corresponds to `NSVisualEffectMaterial`, the look of the blur of a window.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualEffectMaterial {
+  Titlebar = 3,
+  Selection = 4,
+  Menu = 5,
+  Popover = 6,
+  Sidebar = 7,
+  HeaderView = 10,
+  Sheet = 11,
+  WindowBackground = 12,
+  HudWindow = 13,
+  FullScreenUI = 15,
+  Tooltip = 17,
+  ContentBackground = 18,
+  UnderWindowBackground = 21,
+  UnderPageBackground = 22,
+}
+
+impl Default for VisualEffectMaterial {
+  fn default() -> Self {
+    VisualEffectMaterial::UnderWindowBackground
+  }
+}
+
+/// Corresponds to `NSVisualEffectBlendingMode`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualEffectBlendingMode {
+  /// Blurs what is behind the window.
+  BehindWindow = 0,
+  /// Blurs the content of the window below the effect view.
+  WithinWindow = 1,
+}
+
+impl Default for VisualEffectBlendingMode {
+  fn default() -> Self {
+    VisualEffectBlendingMode::BehindWindow
+  }
+}
+
+/// Corresponds to `NSVisualEffectState`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualEffectState {
+  /// The effect looks active while the window is active.
+  FollowsWindowActiveState = 0,
+  Active = 1,
+  Inactive = 2,
+}
+
+impl Default for VisualEffectState {
+  fn default() -> Self {
+    VisualEffectState::Active
+  }
+}
+
+/// The look of an `NSVisualEffectView`, see [`WindowExtMacOS::set_vibrancy`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Vibrancy {
+  pub material: VisualEffectMaterial,
+  pub blending_mode: VisualEffectBlendingMode,
+  pub state: VisualEffectState,
+}
+
+impl Vibrancy {
+  /// Creates a `Vibrancy` blurring what is behind the window with `material`.
+  pub fn new(material: VisualEffectMaterial) -> Self {
+    Self {
+      material,
+      ..Default::default()
+    }
+  }
 }
 
 /// Corresponds to `NSApplicationActivationPolicy`.
@@ -337,7 +494,18 @@ pub trait WindowBuilderExtMacOS {
   /// Build window with `resizeIncrements` property. Values must not be 0.
   fn with_resize_increments(self, increments: LogicalSize<f64>) -> WindowBuilder;
   fn with_disallow_hidpi(self, disallow_hidpi: bool) -> WindowBuilder;
+  /// Sets whether the window has a shadow. Defaults to `true`, or `false` for transparent windows.
   fn with_has_shadow(self, has_shadow: bool) -> WindowBuilder;
+  /// Sets whether a click on the window while it's inactive is also delivered as a
+  /// `WindowEvent::MouseInput`, instead of only activating the window. Defaults to `true`.
+  fn with_accepts_first_mouse(self, accepts_first_mouse: bool) -> WindowBuilder;
+  /// Sets the look of the blur of `WindowBuilder::with_blur` and `Window::set_blur`, see
+  /// [`WindowExtMacOS::set_vibrancy`]. Defaults to `Vibrancy::default()`, with the
+  /// [`VisualEffectMaterial::UnderWindowBackground`] material.
+  fn with_vibrancy(self, vibrancy: Vibrancy) -> WindowBuilder;
+  /// Sets whether the window can be shown over the fullscreen windows of other applications,
+  /// see [`WindowExtMacOS::set_fullscreen_auxiliary`]. Defaults to `false`.
+  fn with_fullscreen_auxiliary(self, fullscreen_auxiliary: bool) -> WindowBuilder;
 }
 
 impl WindowBuilderExtMacOS for WindowBuilder {
@@ -400,7 +568,25 @@ impl WindowBuilderExtMacOS for WindowBuilder {
 
   #[inline]
   fn with_has_shadow(mut self, has_shadow: bool) -> WindowBuilder {
-    self.platform_specific.has_shadow = has_shadow;
+    self.platform_specific.has_shadow = Some(has_shadow);
+    self
+  }
+
+  #[inline]
+  fn with_accepts_first_mouse(mut self, accepts_first_mouse: bool) -> WindowBuilder {
+    self.platform_specific.accepts_first_mouse = accepts_first_mouse;
+    self
+  }
+
+  #[inline]
+  fn with_vibrancy(mut self, vibrancy: Vibrancy) -> WindowBuilder {
+    self.platform_specific.vibrancy = vibrancy;
+    self
+  }
+
+  #[inline]
+  fn with_fullscreen_auxiliary(mut self, fullscreen_auxiliary: bool) -> WindowBuilder {
+    self.platform_specific.fullscreen_auxiliary = fullscreen_auxiliary;
     self
   }
 }
@@ -511,6 +697,11 @@ pub trait SystemTrayBuilderExtMacOS {
   /// You can use the alpha channel in the image to adjust the opacity of black content.
   ///
   fn with_icon_as_template(self, is_template: bool) -> Self;
+
+  /// Sets the text shown next to the tray icon in the menu bar.
+  ///
+  /// See [`SystemTray::set_title`] to update it at runtime.
+  fn with_title(self, title: &str) -> Self;
 }
 
 #[cfg(feature = "tray")]
@@ -519,13 +710,22 @@ impl SystemTrayBuilderExtMacOS for SystemTrayBuilder {
     self.0.system_tray.icon_is_template = is_template;
     self
   }
+
+  fn with_title(mut self, title: &str) -> Self {
+    self.0.system_tray.title = Some(title.to_string());
+    self
+  }
 }
 
 #[cfg(feature = "tray")]
 pub trait SystemTrayExtMacOS {
   /// Sets the icon as a [template](https://developer.apple.com/documentation/appkit/nsimage/1520017-template?language=objc).
   ///
-  /// You need to update this value before changing the icon.
+  /// The current icon is updated immediately, and the value is kept for icons set later with
+  /// [`SystemTray::set_icon`].
+  ///
+  /// Images you mark as template images should consist of only black and clear colors.
+  /// You can use the alpha channel in the image to adjust the opacity of black content.
   ///
   fn set_icon_as_template(&mut self, is_template: bool);
 }
@@ -533,6 +733,6 @@ pub trait SystemTrayExtMacOS {
 #[cfg(feature = "tray")]
 impl SystemTrayExtMacOS for SystemTray {
   fn set_icon_as_template(&mut self, is_template: bool) {
-    self.0.icon_is_template = is_template
+    self.0.set_icon_as_template(is_template)
   }
 }
@@ -11,14 +11,17 @@
 //!  - `unix`
 //!  - `windows`
 //!
+//! Or only `headless` when the `headless` feature is enabled.
+//!
 //! And the following platform-specific module:
 //!
 //! - `global_shortcut` (available on `windows`, `unix`, `macos`)
-//! - `run_return` (available on `windows`, `unix`, `macos`, and `android`)
+//! - `run_return` (available on `windows`, `unix`, `macos`, `android`, and `headless`)
 //!
 //! However only the module corresponding to the platform you're compiling to will be available.
 
 pub mod android;
+pub mod headless;
 pub mod ios;
 pub mod macos;
 pub mod run_return;
@@ -1,17 +1,21 @@
 // Copyright 2019-2021 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#![cfg(any(
-  target_os = "linux",
-  target_os = "dragonfly",
-  target_os = "freebsd",
-  target_os = "netbsd",
-  target_os = "openbsd"
+#![cfg(all(
+  any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  ),
+  not(feature = "headless")
 ))]
 
 pub use crate::platform_impl::{hit_test, EventLoop as UnixEventLoop};
 use crate::{
-  event_loop::EventLoop,
+  error::ExternalError,
+  event_loop::{ActiveGuard, EventLoop},
   window::{Window, WindowBuilder},
 };
 
@@ -22,6 +26,38 @@ pub trait WindowExtUnix {
 
   /// Whether to show the window icon in the taskbar or not.
   fn set_skip_taskbar(&self, skip: bool);
+
+  /// Sets the startup notification token of the window.
+  ///
+  /// The first window created by tao already uses the `XDG_ACTIVATION_TOKEN` or
+  /// `DESKTOP_STARTUP_ID` environment variables set by the launcher. They are left in the
+  /// environment, use `std::process::Command::env_remove` for child processes that shouldn't
+  /// inherit the token.
+  ///
+  /// Use this when the token is received another way, for example over IPC from a second
+  /// instance of the application, so the compositor allows the window to take focus.
+  fn set_startup_id(&self, startup_id: &str);
+
+  /// Returns the `HeaderBar` used as the titlebar of this window, if it was built with
+  /// [`WindowBuilderExtUnix::with_header_bar`].
+  ///
+  /// The header bar can be customized like any GTK widget, for example to place the window
+  /// buttons with `header_bar.set_decoration_layout(Some("close,minimize,maximize:"))` or to add
+  /// custom widgets next to them.
+  fn header_bar(&self) -> Option<&gtk::HeaderBar>;
+
+  /// Locks the cursor in place while the window has the focus, or releases it.
+  ///
+  /// The cursor stays visible unless hidden with [`Window::set_cursor_visible`], use
+  /// [`DeviceEvent::MouseMotion`] to track the pointer motion. Locking replaces a grab made with
+  /// [`Window::set_cursor_grab`], and releasing either one releases the pointer.
+  ///
+  /// Only supported on Wayland, with the `zwp_pointer_constraints_v1` protocol and the
+  /// `wayland-protocols` feature. Returns an [`ExternalError::NotSupported`] on X11, without the
+  /// feature or if the compositor doesn't support the protocol.
+  ///
+  /// [`DeviceEvent::MouseMotion`]: crate::event::DeviceEvent::MouseMotion
+  fn set_cursor_lock(&self, lock: bool) -> Result<(), ExternalError>;
 }
 
 impl WindowExtUnix for Window {
@@ -32,11 +68,86 @@ impl WindowExtUnix for Window {
   fn set_skip_taskbar(&self, skip: bool) {
     self.window.set_skip_taskbar(skip);
   }
+
+  fn set_startup_id(&self, startup_id: &str) {
+    self.window.set_startup_id(startup_id);
+  }
+
+  fn header_bar(&self) -> Option<&gtk::HeaderBar> {
+    self.window.header_bar()
+  }
+
+  fn set_cursor_lock(&self, lock: bool) -> Result<(), ExternalError> {
+    self.window.set_cursor_lock(lock)
+  }
 }
 
 pub trait WindowBuilderExtUnix {
   /// Whether to create the window icon with the taskbar icon or not.
   fn with_skip_taskbar(self, skip: bool) -> WindowBuilder;
+
+  /// Build window with the given `general` and `instance` names.
+  ///
+  /// On X11, this sets the `WM_CLASS` property to `instance` and `general`. On Wayland, the
+  /// `app_id` is set to `general`.
+  ///
+  /// `general` should match the `StartupWMClass` entry of the application's `.desktop` file so
+  /// desktop environments can group windows and pick the right icon.
+  ///
+  /// The names can only be set when building the window, as they must be applied before the
+  /// window is realized.
+  fn with_name(self, general: impl Into<String>, instance: impl Into<String>) -> WindowBuilder;
+
+  /// Build window with the X11 override-redirect attribute, so the window manager doesn't
+  /// decorate, move or focus it. This is meant for popups such as tooltips and completion lists,
+  /// which are placed at exactly the requested position and still receive pointer input.
+  ///
+  /// Only supported on X11, this is ignored on Wayland.
+  fn with_override_redirect(self, override_redirect: bool) -> WindowBuilder;
+
+  /// Build window as a Wayland layer-shell surface on the given `layer`, anchored to the
+  /// `anchor` edges of the output. `exclusive_zone` is the amount of pixels the compositor should
+  /// keep clear of other surfaces along the anchored edge, `-1` to not be moved by other
+  /// exclusive zones.
+  ///
+  /// This is meant for desktop-shell components like panels, docks and overlays. It requires
+  /// `libgtk-layer-shell` at runtime and a compositor supporting the `wlr-layer-shell` protocol.
+  /// Building the window fails otherwise, and on X11.
+  fn with_layer_shell(self, layer: Layer, anchor: Anchor, exclusive_zone: i32) -> WindowBuilder;
+
+  /// Build window with a fixed scale factor, used instead of the one reported by GTK for
+  /// [`Window::scale_factor`] and for every conversion between logical and physical units.
+  ///
+  /// GTK 3 only supports integer scale factors: with fractional scaling (e.g. `1.25`), the
+  /// compositor reports the scale rounded up and downscales the rendered surface. On Wayland, with
+  /// the `wayland-protocols` feature, tao reads the true fractional scale with the
+  /// `wp_fractional_scale_v1` protocol when the compositor supports it. Otherwise the factor
+  /// reported by tao is the integer one, and this override is the way to use the fractional one.
+  ///
+  /// With the override, the scale factor never changes and `ScaleFactorChanged` is never emitted.
+  ///
+  /// The override replaces the integer scale of `GDK_SCALE`, it doesn't multiply it, and it
+  /// doesn't change how GTK renders the window. `GDK_DPI_SCALE` only scales text rendered by
+  /// GTK and is never reflected in the scale factor, with or without the override.
+  ///
+  /// ## Panics
+  ///
+  /// Panics if `scale_factor` isn't a positive, normal number.
+  ///
+  /// [`Window::scale_factor`]: crate::window::Window::scale_factor
+  fn with_scale_factor_override(self, scale_factor: f64) -> WindowBuilder;
+
+  /// Build window with a `HeaderBar` as its titlebar, accessible with
+  /// [`WindowExtUnix::header_bar`] to customize the titlebar and the placement of its buttons.
+  ///
+  /// A header bar is drawn by GTK as part of the window's client-side decorations, so it forces
+  /// client-side decorations even on environments that would otherwise use server-side ones.
+  /// Without it, the titlebar is drawn by the window manager or compositor and can't be
+  /// customized. The header bar is hidden along with the other decorations when they are
+  /// disabled with [`Window::set_decorations`].
+  ///
+  /// [`Window::set_decorations`]: crate::window::Window::set_decorations
+  fn with_header_bar(self, header_bar: bool) -> WindowBuilder;
 }
 
 impl WindowBuilderExtUnix for WindowBuilder {
@@ -44,6 +155,65 @@ impl WindowBuilderExtUnix for WindowBuilder {
     self.platform_specific.skip_taskbar = skip;
     self
   }
+
+  fn with_name(mut self, general: impl Into<String>, instance: impl Into<String>) -> WindowBuilder {
+    self.platform_specific.name = Some((general.into(), instance.into()));
+    self
+  }
+
+  fn with_override_redirect(mut self, override_redirect: bool) -> WindowBuilder {
+    self.platform_specific.override_redirect = override_redirect;
+    self
+  }
+
+  fn with_layer_shell(
+    mut self,
+    layer: Layer,
+    anchor: Anchor,
+    exclusive_zone: i32,
+  ) -> WindowBuilder {
+    self.platform_specific.layer_shell = Some((layer, anchor, exclusive_zone));
+    self
+  }
+
+  fn with_scale_factor_override(mut self, scale_factor: f64) -> WindowBuilder {
+    assert!(
+      crate::dpi::validate_scale_factor(scale_factor),
+      "`WindowBuilderExtUnix::with_scale_factor_override` received an invalid hidpi factor"
+    );
+    self.platform_specific.scale_factor_override = Some(scale_factor);
+    self
+  }
+
+  fn with_header_bar(mut self, header_bar: bool) -> WindowBuilder {
+    self.platform_specific.header_bar = header_bar;
+    self
+  }
+}
+
+/// The layer of a layer-shell surface, see [`WindowBuilderExtUnix::with_layer_shell`].
+///
+/// Layers are stacked from `Background` at the bottom to `Overlay` at the top, regular windows
+/// are between `Bottom` and `Top`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layer {
+  Background,
+  Bottom,
+  Top,
+  Overlay,
+}
+
+bitflags! {
+  /// The edges of the output a layer-shell surface is anchored to, see
+  /// [`WindowBuilderExtUnix::with_layer_shell`].
+  #[derive(Default)]
+  pub struct Anchor: u8 {
+    const TOP = 1 << 0;
+    const BOTTOM = 1 << 1;
+    const LEFT = 1 << 2;
+    const RIGHT = 1 << 3;
+  }
 }
 
 /// Additional methods on `EventLoop` that are specific to Unix.
@@ -57,16 +227,13 @@ pub trait EventLoopExtUnix {
     Self: Sized;
 }
 
-fn wrap_ev<T>(event_loop: UnixEventLoop<T>) -> EventLoop<T> {
-  EventLoop {
-    event_loop,
-    _marker: std::marker::PhantomData,
-  }
-}
-
 impl<T> EventLoopExtUnix for EventLoop<T> {
   #[inline]
   fn new_any_thread() -> Self {
-    wrap_ev(UnixEventLoop::new_any_thread())
+    EventLoop {
+      _active: ActiveGuard::acquire().expect("Failed to create the event loop"),
+      event_loop: UnixEventLoop::new_any_thread(),
+      _marker: std::marker::PhantomData,
+    }
   }
 }
@@ -1,18 +1,19 @@
 // Copyright 2019-2021 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#![cfg(target_os = "windows")]
+#![cfg(all(target_os = "windows", not(feature = "headless")))]
 
 use std::path::Path;
 
 pub use crate::platform_impl::hit_test;
 use crate::{
   dpi::PhysicalSize,
+  error::OsError,
   event::DeviceId,
-  event_loop::EventLoop,
+  event_loop::{ActiveGuard, EventLoop},
   monitor::MonitorHandle,
   platform_impl::{EventLoop as WindowsEventLoop, Parent, WinIcon},
-  window::{BadIcon, Icon, Theme, Window, WindowBuilder},
+  window::{BadIcon, Icon, Rect, Theme, Window, WindowBuilder},
 };
 use libc;
 use windows::Win32::{
@@ -20,6 +21,45 @@ use windows::Win32::{
   UI::{Input::KeyboardAndMouse::*, WindowsAndMessaging::*},
 };
 
+/// A color with red, green, blue and alpha components.
+pub type RGBA = (u8, u8, u8, u8);
+
+/// How the corners of a window are rounded on Windows 11.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CornerPreference {
+  /// Lets the system decide whether to round the corners.
+  Default = 0,
+  /// Never rounds the corners.
+  DoNotRound = 1,
+  /// Rounds the corners when appropriate.
+  Round = 2,
+  /// Rounds the corners with a small radius when appropriate.
+  RoundSmall = 3,
+}
+
+impl Default for CornerPreference {
+  fn default() -> Self {
+    CornerPreference::Default
+  }
+}
+
+/// The parts of a custom titlebar that behave like the native ones, see
+/// [`WindowExtWindows::set_nc_hit_regions`].
+///
+/// The rectangles are relative to the client area.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HitRegions {
+  /// Dragging these moves the window, and double-clicking them maximizes it.
+  pub caption: Vec<Rect>,
+  pub minimize_button: Option<Rect>,
+  /// Hovering it shows the snap layouts on Windows 11.
+  pub maximize_button: Option<Rect>,
+  pub close_button: Option<Rect>,
+  /// The width, in logical pixels, of the resize borders along the edges of the client area.
+  pub resize_border: Option<f64>,
+}
+
 /// Additional methods on `EventLoop` that are specific to Windows.
 pub trait EventLoopExtWindows {
   /// Creates an event loop off of the main thread.
@@ -51,6 +91,7 @@ impl<T> EventLoopExtWindows for EventLoop<T> {
   #[inline]
   fn new_any_thread() -> Self {
     EventLoop {
+      _active: ActiveGuard::acquire().expect("Failed to create the event loop"),
       event_loop: WindowsEventLoop::new_any_thread(),
       _marker: ::std::marker::PhantomData,
     }
@@ -59,6 +100,7 @@ impl<T> EventLoopExtWindows for EventLoop<T> {
   #[inline]
   fn new_dpi_unaware() -> Self {
     EventLoop {
+      _active: ActiveGuard::acquire().expect("Failed to create the event loop"),
       event_loop: WindowsEventLoop::new_dpi_unaware(),
       _marker: ::std::marker::PhantomData,
     }
@@ -67,6 +109,7 @@ impl<T> EventLoopExtWindows for EventLoop<T> {
   #[inline]
   fn new_dpi_unaware_any_thread() -> Self {
     EventLoop {
+      _active: ActiveGuard::acquire().expect("Failed to create the event loop"),
       event_loop: WindowsEventLoop::new_dpi_unaware_any_thread(),
       _marker: ::std::marker::PhantomData,
     }
@@ -97,7 +140,11 @@ pub trait WindowExtWindows {
   /// and <https://docs.microsoft.com/en-us/windows/win32/winmsg/window-features#disabled-windows>
   fn set_enable(&self, enabled: bool);
 
-  /// This sets `ICON_BIG`. A good ceiling here is 256x256.
+  /// This sets `ICON_BIG`, used by the taskbar and the Alt+Tab switcher, independently of the
+  /// `ICON_SMALL` set by the window icon. A good ceiling here is 256x256.
+  ///
+  /// Without a taskbar icon, Windows stretches the small window icon for the taskbar unless it
+  /// was created with `Icon::from_rgba_sizes`.
   fn set_taskbar_icon(&self, taskbar_icon: Option<Icon>);
 
   /// Returns the current window theme.
@@ -115,6 +162,82 @@ pub trait WindowExtWindows {
 
   /// Whether to show the window icon in the taskbar or not.
   fn set_skip_taskbar(&self, skip: bool);
+
+  /// Sets the background color of the title bar, `None` restores the default.
+  ///
+  /// Only supported on Windows 11, this does nothing on older versions. The alpha component is
+  /// ignored.
+  fn set_caption_color(&self, color: Option<RGBA>);
+
+  /// Sets the color of the title text, `None` restores the default.
+  ///
+  /// Only supported on Windows 11, this does nothing on older versions. The alpha component is
+  /// ignored.
+  fn set_title_text_color(&self, color: Option<RGBA>);
+
+  /// Sets the color of the window border, `None` restores the default.
+  ///
+  /// Only supported on Windows 11, this does nothing on older versions. The alpha component is
+  /// ignored.
+  fn set_border_color(&self, color: Option<RGBA>);
+
+  /// Sets how the corners of the window are rounded.
+  ///
+  /// Only supported on Windows 11, this does nothing on older versions.
+  fn set_corner_preference(&self, preference: CornerPreference);
+
+  /// Declares the parts of a custom titlebar, drawn in the client area, so that the window
+  /// manager treats them as the native caption, buttons and resize borders. This enables
+  /// aero-snap and, on Windows 11, the snap layouts when hovering the maximize button.
+  ///
+  /// These regions are non-client areas, so they don't receive mouse events like
+  /// [`WindowEvent::CursorMoved`]. Instead, for the buttons:
+  ///
+  /// 1. `WM_NCHITTEST` returns `HTMINBUTTON`, `HTMAXBUTTON` or `HTCLOSE`, hovering the maximize
+  ///    button shows the snap layouts.
+  /// 2. `WM_NCMOUSEMOVE` emits [`WindowEvent::CaptionButtonHovered`] with the button, and
+  ///    `WM_NCMOUSELEAVE` emits it with `None`. Draw the hover state from these.
+  /// 3. `WM_NCLBUTTONDOWN` emits [`WindowEvent::CaptionButtonInput`] with `Pressed`, the native
+  ///    button tracking is skipped.
+  /// 4. `WM_NCLBUTTONUP` emits it with `Released`, then minimizes, maximizes or restores the
+  ///    window, or emits [`WindowEvent::CloseRequested`].
+  ///
+  /// `None` removes the regions.
+  ///
+  /// [`WindowEvent::CaptionButtonHovered`]: crate::event::WindowEvent::CaptionButtonHovered
+  /// [`WindowEvent::CaptionButtonInput`]: crate::event::WindowEvent::CaptionButtonInput
+  /// [`WindowEvent::CloseRequested`]: crate::event::WindowEvent::CloseRequested
+  /// [`WindowEvent::CursorMoved`]: crate::event::WindowEvent::CursorMoved
+  fn set_nc_hit_regions(&self, regions: Option<HitRegions>);
+
+  /// Shows or hides the title bar of a decorated window.
+  ///
+  /// Unlike `Window::set_decorations(false)`, which removes the whole frame, hiding the title bar
+  /// only gives the caption to the client area: the window keeps its resize borders, including
+  /// the top one, its shadow and its snapping. The outer size of the window is kept, so the
+  /// client area only grows by the height of the caption.
+  ///
+  /// The caption buttons are hidden with the title bar, draw your own and declare them with
+  /// [`WindowExtWindows::set_nc_hit_regions`] to keep their native behavior. This does nothing
+  /// on a window without decorations.
+  fn set_titlebar_visible(&self, visible: bool);
+
+  /// Excludes a region of the client area from screen captures, see
+  /// [`Window::set_content_protection`] to exclude the whole window.
+  ///
+  /// Returns the `HWND` of a window created over the region and excluded from captures with
+  /// `WDA_EXCLUDEFROMCAPTURE`, or `WDA_MONITOR` before Windows 10 version 2004. Render the
+  /// sensitive content into it, e.g. with its own surface, when the window receives
+  /// [`Event::RedrawRequested`]: tao redraws the window whenever the protected one needs to be
+  /// painted. Its mouse input goes through to the window.
+  ///
+  /// The protected window is kept over the region, clipped to the client area, when the window
+  /// is moved, resized or its scale factor changes, and it's hidden along with the window.
+  /// Calling this again moves it to the new region, `None` destroys it.
+  ///
+  /// [`Event::RedrawRequested`]: crate::event::Event::RedrawRequested
+  /// [`Window::set_content_protection`]: crate::window::Window::set_content_protection
+  fn set_protected_region(&self, region: Option<Rect>) -> Result<Option<HWND>, OsError>;
 }
 
 impl WindowExtWindows for Window {
@@ -159,6 +282,41 @@ impl WindowExtWindows for Window {
   fn set_skip_taskbar(&self, skip: bool) {
     self.window.set_skip_taskbar(skip);
   }
+
+  #[inline]
+  fn set_caption_color(&self, color: Option<RGBA>) {
+    self.window.set_caption_color(color);
+  }
+
+  #[inline]
+  fn set_title_text_color(&self, color: Option<RGBA>) {
+    self.window.set_title_text_color(color);
+  }
+
+  #[inline]
+  fn set_border_color(&self, color: Option<RGBA>) {
+    self.window.set_border_color(color);
+  }
+
+  #[inline]
+  fn set_corner_preference(&self, preference: CornerPreference) {
+    self.window.set_corner_preference(preference);
+  }
+
+  #[inline]
+  fn set_nc_hit_regions(&self, regions: Option<HitRegions>) {
+    self.window.set_nc_hit_regions(regions);
+  }
+
+  #[inline]
+  fn set_titlebar_visible(&self, visible: bool) {
+    self.window.set_titlebar_visible(visible);
+  }
+
+  #[inline]
+  fn set_protected_region(&self, region: Option<Rect>) -> Result<Option<HWND>, OsError> {
+    self.window.set_protected_region(region)
+  }
 }
 
 /// Additional methods on `WindowBuilder` that are specific to Windows.
@@ -193,7 +351,11 @@ pub trait WindowBuilderExtWindows {
   /// If you use this, it is recommended that you combine it with `with_theme(Some(Theme::Light))` to avoid a jarring effect.
   fn with_menu(self, menu: HMENU) -> WindowBuilder;
 
-  /// This sets `ICON_BIG`. A good ceiling here is 256x256.
+  /// This sets `ICON_BIG`, used by the taskbar and the Alt+Tab switcher, independently of the
+  /// `ICON_SMALL` set by the window icon. A good ceiling here is 256x256.
+  ///
+  /// Without a taskbar icon, Windows stretches the small window icon for the taskbar unless it
+  /// was created with `Icon::from_rgba_sizes`.
   fn with_taskbar_icon(self, taskbar_icon: Option<Icon>) -> WindowBuilder;
 
   /// This sets `WS_EX_NOREDIRECTIONBITMAP`.
@@ -205,6 +367,10 @@ pub trait WindowBuilderExtWindows {
   /// COM API regardless of this option. Currently only fullscreen mode does that, but there may be more in the future.
   /// If you need COM API with `COINIT_MULTITHREADED` you must initialize it before calling any tao functions.
   /// See <https://docs.microsoft.com/en-us/windows/win32/api/objbase/nf-objbase-coinitialize#remarks> for more information.
+  ///
+  /// When disabled, no `IDropTarget` is registered for the window, so an embedded control such as
+  /// WebView2 can register its own to handle drag and drop. The window then won't emit
+  /// `WindowEvent::HoveredFile`, `WindowEvent::DroppedFile` or `WindowEvent::HoveredFileCancelled`.
   fn with_drag_and_drop(self, flag: bool) -> WindowBuilder;
 
   /// Forces a theme or uses the system settings if `None` was provided.
@@ -212,6 +378,20 @@ pub trait WindowBuilderExtWindows {
 
   /// Whether to create the window icon with the taskbar icon or not.
   fn with_skip_taskbar(self, skip: bool) -> WindowBuilder;
+
+  /// Sets how the corners of the window are rounded, see
+  /// [`WindowExtWindows::set_corner_preference`].
+  fn with_corner_preference(self, preference: CornerPreference) -> WindowBuilder;
+
+  /// Keeps the native drop shadow of the window when it has no decorations, see
+  /// [`WindowBuilder::with_decorations`].
+  ///
+  /// The window keeps its caption and resize frame styles, with the frame removed from view, so
+  /// a resizable window also keeps the resize borders and the snap layouts. Has no effect on a
+  /// decorated window.
+  ///
+  /// The default is `false`.
+  fn with_undecorated_shadow(self, shadow: bool) -> WindowBuilder;
 }
 
 impl WindowBuilderExtWindows for WindowBuilder {
@@ -262,6 +442,18 @@ impl WindowBuilderExtWindows for WindowBuilder {
     self.platform_specific.skip_taskbar = skip;
     self
   }
+
+  #[inline]
+  fn with_corner_preference(mut self, preference: CornerPreference) -> WindowBuilder {
+    self.platform_specific.corner_preference = Some(preference);
+    self
+  }
+
+  #[inline]
+  fn with_undecorated_shadow(mut self, shadow: bool) -> WindowBuilder {
+    self.platform_specific.undecorated_shadow = shadow;
+    self
+  }
 }
 
 /// Additional methods on `MonitorHandle` that are specific to Windows.
@@ -309,6 +501,9 @@ pub trait IconExtWindows: Sized {
   ///
   /// In cases where the specified size does not exist in the file, Windows may perform scaling
   /// to get an icon of the desired size.
+  ///
+  /// Unlike [`Icon::from_path`], only ICO files are supported. The inherent method takes
+  /// precedence, call this one as `<Icon as IconExtWindows>::from_path`.
   fn from_path<P: AsRef<Path>>(path: P, size: Option<PhysicalSize<u32>>) -> Result<Self, BadIcon>;
 
   /// Create an icon from a resource embedded in this executable or library.
@@ -328,7 +523,6 @@ impl IconExtWindows for Icon {
   }
 
   fn from_resource(ordinal: u16, size: Option<PhysicalSize<u32>>) -> Result<Self, BadIcon> {
-    let win_icon = WinIcon::from_resource(ordinal, size)?;
-    Ok(Icon { inner: win_icon })
+    Icon::from_resource(ordinal, size)
   }
 }
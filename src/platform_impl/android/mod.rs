@@ -4,7 +4,7 @@
 #![cfg(target_os = "android")]
 use crate::{
   accelerator::Accelerator,
-  dpi::{PhysicalPosition, PhysicalSize, Position, Size},
+  dpi::{LogicalSize, PhysicalPosition, PhysicalSize, Position, Size},
   error, event,
   event_loop::{self, ControlFlow},
   keyboard::{Key, KeyCode, KeyLocation, NativeKeyCode},
@@ -22,6 +22,8 @@ use raw_window_handle::{AndroidNdkHandle, RawWindowHandle};
 use std::{
   collections::VecDeque,
   convert::TryInto,
+  os::raw::c_void,
+  ptr,
   sync::{Arc, Mutex, RwLock},
   time::{Duration, Instant},
 };
@@ -116,6 +118,10 @@ pub struct EventLoop<T: 'static> {
   start_cause: event::StartCause,
   looper: ThreadLooper,
   running: bool,
+  /// Whether the `ANativeWindow` is valid, between `Resumed` and `Suspended`
+  has_window: bool,
+  /// The last height reported with `SoftInputChanged`
+  soft_input_height: u32,
 }
 
 macro_rules! call_event_handler {
@@ -129,20 +135,27 @@ macro_rules! call_event_handler {
 }
 
 impl<T: 'static> EventLoop<T> {
-  pub fn new() -> Self {
-    Self {
+  pub fn new() -> Result<Self, error::OsError> {
+    let looper = ThreadLooper::for_thread().ok_or_else(|| os_error!(OsError))?;
+    Ok(Self {
       window_target: event_loop::EventLoopWindowTarget {
         p: EventLoopWindowTarget {
           _marker: std::marker::PhantomData,
         },
+        control_flow: Default::default(),
+        scale_factor_rounding: Default::default(),
+        #[cfg(feature = "testing")]
+        injected_events: Default::default(),
         _marker: std::marker::PhantomData,
       },
       user_queue: Default::default(),
       first_event: None,
       start_cause: event::StartCause::Init,
-      looper: ThreadLooper::for_thread().unwrap(),
+      looper,
       running: false,
-    }
+      has_window: false,
+      soft_input_height: 0,
+    })
   }
 
   pub fn run<F>(mut self, event_handler: F) -> !
@@ -174,6 +187,7 @@ impl<T: 'static> EventLoop<T> {
       match self.first_event.take() {
         Some(EventSource::Callback) => match ndk_glue::poll_events().unwrap() {
           Event::WindowCreated => {
+            self.has_window = true;
             call_event_handler!(
               event_handler,
               self.window_target(),
@@ -184,6 +198,7 @@ impl<T: 'static> EventLoop<T> {
           Event::WindowResized => resized = true,
           Event::WindowRedrawNeeded => redraw = true,
           Event::WindowDestroyed => {
+            self.has_window = false;
             call_event_handler!(
               event_handler,
               self.window_target(),
@@ -193,10 +208,27 @@ impl<T: 'static> EventLoop<T> {
           }
           Event::Pause => self.running = false,
           Event::Resume => self.running = true,
+          Event::Destroy => {
+            call_event_handler!(
+              event_handler,
+              self.window_target(),
+              control_flow,
+              event::Event::WindowEvent {
+                window_id: window::WindowId(WindowId),
+                event: event::WindowEvent::Destroyed,
+              }
+            );
+            // The activity is gone, there is nothing left to run the event loop for
+            if !matches!(control_flow, ControlFlow::ExitWithCode(_)) {
+              control_flow = ControlFlow::ExitWithCode(0);
+            }
+          }
           Event::ConfigChanged => {
             let am = ndk_glue::native_activity().asset_manager();
             let config = Configuration::from_asset_manager(&am);
             let old_scale_factor = MonitorHandle.scale_factor();
+            let old_orientation = CONFIG.read().unwrap().orientation();
+            let orientation = config.orientation();
             *CONFIG.write().unwrap() = config;
             let scale_factor = MonitorHandle.scale_factor();
             if (scale_factor - old_scale_factor).abs() < f64::EPSILON {
@@ -210,6 +242,20 @@ impl<T: 'static> EventLoop<T> {
               };
               call_event_handler!(event_handler, self.window_target(), control_flow, event);
             }
+            if orientation != old_orientation {
+              let orientation = match orientation {
+                ndk::configuration::Orientation::Port => Some(window::Orientation::Portrait),
+                ndk::configuration::Orientation::Land => Some(window::Orientation::Landscape),
+                _ => None,
+              };
+              if let Some(orientation) = orientation {
+                let event = event::Event::WindowEvent {
+                  window_id: window::WindowId(WindowId),
+                  event: event::WindowEvent::OrientationChanged(orientation),
+                };
+                call_event_handler!(event_handler, self.window_target(), control_flow, event);
+              }
+            }
           }
           Event::WindowHasFocus => {
             call_event_handler!(
@@ -233,6 +279,22 @@ impl<T: 'static> EventLoop<T> {
               }
             );
           }
+          Event::ContentRectChanged => {
+            let rect = ndk_glue::content_rect();
+            let height = (MonitorHandle.size().height as i64 - rect.bottom as i64).max(0) as u32;
+            if height != self.soft_input_height {
+              self.soft_input_height = height;
+              call_event_handler!(
+                event_handler,
+                self.window_target(),
+                control_flow,
+                event::Event::WindowEvent {
+                  window_id: window::WindowId(WindowId),
+                  event: event::WindowEvent::SoftInputChanged { height },
+                }
+              );
+            }
+          }
           _ => {}
         },
         Some(EventSource::InputQueue) => {
@@ -358,7 +420,7 @@ impl<T: 'static> EventLoop<T> {
         event::Event::MainEventsCleared
       );
 
-      if resized && self.running {
+      if resized && self.running && self.has_window {
         let size = MonitorHandle.size();
         let event = event::Event::WindowEvent {
           window_id: window::WindowId(WindowId),
@@ -367,7 +429,7 @@ impl<T: 'static> EventLoop<T> {
         call_event_handler!(event_handler, self.window_target(), control_flow, event);
       }
 
-      if redraw && self.running {
+      if redraw && self.running && self.has_window {
         let event = event::Event::RedrawRequested(window::WindowId(WindowId));
         call_event_handler!(event_handler, self.window_target(), control_flow, event);
       }
@@ -378,6 +440,12 @@ impl<T: 'static> EventLoop<T> {
         control_flow,
         event::Event::RedrawEventsCleared
       );
+      call_event_handler!(
+        event_handler,
+        self.window_target(),
+        control_flow,
+        event::Event::AboutToWait
+      );
 
       match control_flow {
         ControlFlow::ExitWithCode(code) => {
@@ -391,6 +459,12 @@ impl<T: 'static> EventLoop<T> {
             start: Instant::now(),
             requested_resume: None,
           };
+          call_event_handler!(
+            event_handler,
+            self.window_target(),
+            control_flow,
+            event::Event::LoopDestroyed
+          );
           break 'event_loop code;
         }
         ControlFlow::Poll => {
@@ -403,9 +477,10 @@ impl<T: 'static> EventLoop<T> {
           self.start_cause = event::StartCause::Poll;
         }
         ControlFlow::Wait => {
+          let start = Instant::now();
           self.first_event = poll(self.looper.poll_all().unwrap());
           self.start_cause = event::StartCause::WaitCancelled {
-            start: Instant::now(),
+            start,
             requested_resume: None,
           }
         }
@@ -456,6 +531,11 @@ impl<T> EventLoopProxy<T> {
     self.looper.wake();
     Ok(())
   }
+
+  pub fn wake_up(&self) -> Result<(), event_loop::EventLoopClosed<()>> {
+    self.looper.wake();
+    Ok(())
+  }
 }
 
 impl<T> Clone for EventLoopProxy<T> {
@@ -484,6 +564,10 @@ impl<T: 'static> EventLoopWindowTarget<T> {
     v.push_back(MonitorHandle);
     v
   }
+
+  pub fn set_application_menu(&self, _menu: Menu) {}
+
+  pub fn listen_device_events(&self, _allowed: event_loop::DeviceEvents) {}
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -493,6 +577,14 @@ impl WindowId {
   pub fn dummy() -> Self {
     WindowId
   }
+
+  pub fn into_raw(self) -> u64 {
+    0
+  }
+
+  pub fn from_raw(raw: u64) -> Option<Self> {
+    (raw == 0).then(|| WindowId)
+  }
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -549,6 +641,16 @@ impl Window {
     // TODO
   }
 
+  pub fn pre_present_notify(&self) {}
+
+  pub fn request_redraw_rect(&self, _position: PhysicalPosition<i32>, _size: PhysicalSize<u32>) {
+    self.request_redraw();
+  }
+
+  pub fn redraw_rect(&self) -> Option<(PhysicalPosition<i32>, PhysicalSize<u32>)> {
+    None
+  }
+
   pub fn inner_position(&self) -> Result<PhysicalPosition<i32>, error::NotSupportedError> {
     Err(error::NotSupportedError::new())
   }
@@ -569,20 +671,73 @@ impl Window {
     warn!("Cannot set window size on Android");
   }
 
+  pub fn request_inner_size(&self, _size: Size) -> Option<PhysicalSize<u32>> {
+    warn!("Cannot set window size on Android");
+    None
+  }
+
   pub fn outer_size(&self) -> PhysicalSize<u32> {
     MonitorHandle.size()
   }
 
+  pub fn content_insets(&self) -> window::Insets {
+    window::Insets::default()
+  }
+
+  pub fn safe_area_insets(&self) -> window::Insets {
+    window::Insets::default()
+  }
+
+  pub fn set_orientation_lock(&self, orientation: Option<window::Orientation>) {
+    // `ActivityInfo.SCREEN_ORIENTATION_*` values
+    let requested_orientation = match orientation {
+      None => -1,
+      Some(window::Orientation::Landscape) => 0,
+      Some(window::Orientation::Portrait) => 1,
+      Some(window::Orientation::LandscapeFlipped) => 8,
+      Some(window::Orientation::PortraitFlipped) => 9,
+    };
+    let activity = ndk_glue::native_activity();
+    if let Err(e) = unsafe {
+      call_activity_method(
+        activity.vm(),
+        activity.activity(),
+        b"setRequestedOrientation\0",
+        b"(I)V\0",
+        requested_orientation,
+      )
+    } {
+      warn!("Failed to set the requested orientation: {}", e);
+    }
+  }
+
+  pub fn set_soft_input_visible(&self, visible: bool) {
+    let activity = ndk_glue::native_activity();
+    if visible {
+      activity.show_soft_input(false);
+    } else {
+      activity.hide_soft_input(false);
+    }
+  }
+
   pub fn set_min_inner_size(&self, _: Option<Size>) {}
 
   pub fn set_max_inner_size(&self, _: Option<Size>) {}
 
+  pub fn set_aspect_ratio(&self, _: Option<(u32, u32)>) {}
+
   pub fn set_title(&self, _title: &str) {}
 
+  pub fn title(&self) -> String {
+    String::new()
+  }
+
   pub fn set_menu(&self, _menu: Option<Menu>) {}
 
   pub fn set_visible(&self, _visibility: bool) {}
 
+  pub fn set_visible_with_animation(&self, _visibility: bool, _animate: bool) {}
+
   pub fn set_focus(&self) {
     //FIXME: implementation goes here
     warn!("set_focus not yet implemented on Android");
@@ -590,6 +745,8 @@ impl Window {
 
   pub fn set_resizable(&self, _resizeable: bool) {}
 
+  pub fn set_enabled_buttons(&self, _buttons: window::WindowButtons) {}
+
   pub fn set_minimized(&self, _minimized: bool) {}
 
   pub fn set_maximized(&self, _maximized: bool) {}
@@ -603,11 +760,20 @@ impl Window {
     false
   }
 
+  pub fn theme(&self) -> window::Theme {
+    window::Theme::Light
+  }
+
   pub fn is_resizable(&self) -> bool {
     warn!("`Window::is_resizable` is ignored on android");
     false
   }
 
+  pub fn enabled_buttons(&self) -> window::WindowButtons {
+    warn!("`Window::enabled_buttons` is ignored on android");
+    window::WindowButtons::all()
+  }
+
   pub fn is_decorated(&self) -> bool {
     warn!("`Window::is_decorated` is ignored on Android");
     false
@@ -623,12 +789,16 @@ impl Window {
 
   pub fn set_decorations(&self, _decorations: bool) {}
 
+  pub fn set_content_protection(&self, _enabled: bool) {}
+
   pub fn set_always_on_top(&self, _always_on_top: bool) {}
 
   pub fn set_window_icon(&self, _window_icon: Option<crate::icon::Icon>) {}
 
   pub fn set_ime_position(&self, _position: Position) {}
 
+  pub fn set_ime_cursor_area(&self, _position: Position, _size: Size) {}
+
   pub fn request_user_attention(&self, _request_type: Option<window::UserAttentionType>) {}
 
   pub fn hide_menu(&self) {}
@@ -642,6 +812,8 @@ impl Window {
 
   pub fn set_cursor_icon(&self, _: window::CursorIcon) {}
 
+  pub fn set_custom_cursor(&self, _: window::CustomCursor) {}
+
   pub fn set_cursor_position(&self, _: Position) -> Result<(), error::ExternalError> {
     Err(error::ExternalError::NotSupported(
       error::NotSupportedError::new(),
@@ -654,6 +826,14 @@ impl Window {
     ))
   }
 
+  pub fn set_transparent(&self, _: bool) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn set_blur(&self, _blur: bool) {}
+
   pub fn set_cursor_visible(&self, _: bool) {}
 
   pub fn drag_window(&self) -> Result<(), error::ExternalError> {
@@ -662,6 +842,21 @@ impl Window {
     ))
   }
 
+  pub fn set_cursor_hittest_region(
+    &self,
+    _region: Option<Vec<window::Rect>>,
+  ) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn set_shape(&self, _shape: Option<Vec<window::Rect>>) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
   pub fn raw_window_handle(&self) -> RawWindowHandle {
     let mut handle = AndroidNdkHandle::empty();
     if let Some(native_window) = ndk_glue::native_window().as_ref() {
@@ -707,7 +902,15 @@ impl MonitorHandle {
       let height = native_window.height() as _;
       PhysicalSize::new(width, height)
     } else {
-      PhysicalSize::new(0, 0)
+      // Before the native window is created, use the screen size of the current configuration,
+      // which is given in density-independent pixels
+      let config = CONFIG.read().unwrap();
+      match (config.screen_width_dp(), config.screen_height_dp()) {
+        (Some(width), Some(height)) => {
+          LogicalSize::new(width as f64, height as f64).to_physical(self.scale_factor())
+        }
+        _ => PhysicalSize::new(0, 0),
+      }
     }
   }
 
@@ -768,6 +971,62 @@ impl VideoMode {
   }
 }
 
+/// Calls the `void` method `name`, with the JNI `signature` and a single `int` argument, on
+/// `activity`. Both strings must be nul-terminated.
+///
+/// The current thread is attached to the VM for the call if it isn't already.
+unsafe fn call_activity_method(
+  vm: *mut jni_sys::JavaVM,
+  activity: jni_sys::jobject,
+  name: &[u8],
+  signature: &[u8],
+  argument: jni_sys::jint,
+) -> Result<(), &'static str> {
+  let invoke = &**vm;
+  let mut env: *mut jni_sys::JNIEnv = ptr::null_mut();
+  let mut attached = false;
+  if (invoke.GetEnv.unwrap())(
+    vm,
+    &mut env as *mut _ as *mut *mut c_void,
+    jni_sys::JNI_VERSION_1_6,
+  ) != jni_sys::JNI_OK
+  {
+    if (invoke.AttachCurrentThread.unwrap())(
+      vm,
+      &mut env as *mut _ as *mut *mut c_void,
+      ptr::null_mut(),
+    ) != jni_sys::JNI_OK
+    {
+      return Err("the thread couldn't be attached to the Java VM");
+    }
+    attached = true;
+  }
+
+  let jni = &**env;
+  let class = (jni.GetObjectClass.unwrap())(env, activity);
+  let method = (jni.GetMethodID.unwrap())(
+    env,
+    class,
+    name.as_ptr() as *const _,
+    signature.as_ptr() as *const _,
+  );
+  if !method.is_null() {
+    (jni.CallVoidMethod.unwrap())(env, activity, method, argument);
+  }
+  let result = if (jni.ExceptionCheck.unwrap())(env) == jni_sys::JNI_TRUE {
+    (jni.ExceptionClear.unwrap())(env);
+    Err("the method threw an exception")
+  } else {
+    Ok(())
+  };
+  (jni.DeleteLocalRef.unwrap())(env, class);
+
+  if attached {
+    (invoke.DetachCurrentThread.unwrap())(vm);
+  }
+  result
+}
+
 fn keycode_to_logical(keycode: ndk::event::Keycode, native: NativeKeyCode) -> Key<'static> {
   use ndk::event::Keycode::*;
 
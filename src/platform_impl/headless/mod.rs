@@ -0,0 +1,619 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! An in-memory backend that doesn't talk to any windowing system, used for testing.
+
+#![cfg(feature = "headless")]
+
+use crate::{
+  accelerator::{Accelerator, AcceleratorId},
+  dpi::{PhysicalPosition, PhysicalSize},
+  error, event,
+  event_loop::{self, ControlFlow},
+  global_shortcut::{GlobalShortcut as RootGlobalShortcut, ShortcutManagerError},
+  keyboard::{Key, KeyCode},
+  menu::{CustomMenuItem, MenuId, MenuItem, MenuType},
+  monitor,
+  platform::headless::HeadlessMonitor,
+};
+use std::{
+  cell::RefCell,
+  collections::VecDeque,
+  fmt::{self, Display, Formatter},
+  rc::Rc,
+  sync::{Arc, Condvar, Mutex, RwLock},
+  time::Instant,
+};
+
+mod window;
+pub use window::{PlatformSpecificWindowBuilderAttributes, Window, WindowId};
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct KeyEventExtra {
+  pub text_with_all_modifiers: Option<&'static str>,
+  pub key_without_modifiers: Key<'static>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MenuItemAttributes;
+
+#[derive(Debug, Clone)]
+pub struct Menu;
+
+impl Default for Menu {
+  fn default() -> Self {
+    Menu::new()
+  }
+}
+
+impl Menu {
+  pub fn new() -> Self {
+    Menu {}
+  }
+  pub fn new_popup_menu() -> Self {
+    Self::new()
+  }
+  pub fn add_item(
+    &mut self,
+    _menu_id: MenuId,
+    _title: &str,
+    _accelerator: Option<Accelerator>,
+    _enabled: bool,
+    _selected: bool,
+    _menu_type: MenuType,
+  ) -> CustomMenuItem {
+    CustomMenuItem(MenuItemAttributes {})
+  }
+  pub fn add_submenu(&mut self, _title: &str, _enabled: bool, _submenu: Menu) {}
+  pub fn add_native_item(
+    &mut self,
+    _item: MenuItem,
+    _menu_type: MenuType,
+  ) -> Option<CustomMenuItem> {
+    None
+  }
+}
+
+impl MenuItemAttributes {
+  pub fn id(self) -> MenuId {
+    MenuId::EMPTY
+  }
+  pub fn title(&self) -> String {
+    "".to_owned()
+  }
+  pub fn set_enabled(&mut self, _is_enabled: bool) {}
+  pub fn set_title(&mut self, _title: &str) {}
+  pub fn set_selected(&mut self, _is_selected: bool) {}
+  pub fn set_icon(&mut self, _icon: Vec<u8>) {}
+}
+
+/// Events produced by the windows themselves, e.g. `Resized` after `set_inner_size`.
+///
+/// Windows aren't generic over the user event type, so they can't push into the injected queue.
+#[derive(Default)]
+struct Pending {
+  window_events: VecDeque<(WindowId, event::WindowEvent<'static>)>,
+  redraws: Vec<WindowId>,
+  focused: Option<WindowId>,
+  woken: bool,
+}
+
+pub(crate) struct Shared {
+  pending: Mutex<Pending>,
+  condvar: Condvar,
+  /// Configured with `set_monitors`, the handles keep a reference to them.
+  monitors: Arc<RwLock<Vec<HeadlessMonitor>>>,
+}
+
+impl Default for Shared {
+  fn default() -> Self {
+    Shared {
+      pending: Default::default(),
+      condvar: Default::default(),
+      monitors: Arc::new(RwLock::new(vec![HeadlessMonitor::default()])),
+    }
+  }
+}
+
+impl Shared {
+  fn push_window_event(&self, window_id: WindowId, event: event::WindowEvent<'static>) {
+    let mut pending = self.pending.lock().unwrap();
+    pending.window_events.push_back((window_id, event));
+    self.condvar.notify_one();
+  }
+
+  fn request_redraw(&self, window_id: WindowId) {
+    let mut pending = self.pending.lock().unwrap();
+    if !pending.redraws.contains(&window_id) {
+      pending.redraws.push(window_id);
+    }
+    self.condvar.notify_one();
+  }
+
+  fn monitor(&self, index: usize) -> MonitorHandle {
+    MonitorHandle {
+      index,
+      monitors: self.monitors.clone(),
+    }
+  }
+
+  fn primary_monitor(&self) -> Option<monitor::MonitorHandle> {
+    if self.monitors.read().unwrap().is_empty() {
+      None
+    } else {
+      Some(monitor::MonitorHandle {
+        inner: self.monitor(0),
+      })
+    }
+  }
+
+  fn available_monitors(&self) -> VecDeque<MonitorHandle> {
+    (0..self.monitors.read().unwrap().len())
+      .map(|index| self.monitor(index))
+      .collect()
+  }
+
+  fn wake_up(&self) {
+    self.pending.lock().unwrap().woken = true;
+    self.condvar.notify_one();
+  }
+
+  /// Blocks until something is pending or `deadline` is reached, returns `false` on timeout.
+  fn wait(&self, deadline: Option<Instant>) -> bool {
+    let mut pending = self.pending.lock().unwrap();
+    loop {
+      if pending.woken || !pending.window_events.is_empty() || !pending.redraws.is_empty() {
+        pending.woken = false;
+        return true;
+      }
+      match deadline {
+        Some(deadline) => {
+          let now = Instant::now();
+          if now >= deadline {
+            return false;
+          }
+          pending = self
+            .condvar
+            .wait_timeout(pending, deadline - now)
+            .unwrap()
+            .0;
+        }
+        None => pending = self.condvar.wait(pending).unwrap(),
+      }
+    }
+  }
+}
+
+pub struct EventLoop<T: 'static> {
+  window_target: event_loop::EventLoopWindowTarget<T>,
+  user_queue: Arc<Mutex<VecDeque<T>>>,
+}
+
+macro_rules! call_event_handler {
+  ( $event_handler:expr, $window_target:expr, $cf:expr, $event:expr ) => {{
+    if let ControlFlow::ExitWithCode(code) = $cf {
+      $event_handler($event, $window_target, &mut ControlFlow::ExitWithCode(code));
+    } else {
+      $event_handler($event, $window_target, &mut $cf);
+    }
+  }};
+}
+
+impl<T: 'static> EventLoop<T> {
+  pub fn new() -> Result<Self, error::OsError> {
+    Ok(Self {
+      window_target: event_loop::EventLoopWindowTarget {
+        p: EventLoopWindowTarget {
+          shared: Default::default(),
+          injected: Default::default(),
+        },
+        control_flow: Default::default(),
+        scale_factor_rounding: Default::default(),
+        #[cfg(feature = "testing")]
+        injected_events: Default::default(),
+        _marker: std::marker::PhantomData,
+      },
+      user_queue: Default::default(),
+    })
+  }
+
+  pub fn run<F>(mut self, event_handler: F) -> !
+  where
+    F:
+      'static + FnMut(event::Event<'_, T>, &event_loop::EventLoopWindowTarget<T>, &mut ControlFlow),
+  {
+    let exit_code = self.run_return(event_handler);
+    ::std::process::exit(exit_code);
+  }
+
+  pub fn run_return<F>(&mut self, mut event_handler: F) -> i32
+  where
+    F: FnMut(event::Event<'_, T>, &event_loop::EventLoopWindowTarget<T>, &mut ControlFlow),
+  {
+    let mut control_flow = ControlFlow::default();
+    let mut start_cause = event::StartCause::Init;
+    let shared = self.window_target.p.shared.clone();
+
+    loop {
+      call_event_handler!(
+        event_handler,
+        self.window_target(),
+        control_flow,
+        event::Event::NewEvents(start_cause)
+      );
+
+      let injected = std::mem::take(&mut *self.window_target.p.injected.borrow_mut());
+      for event in injected {
+        call_event_handler!(event_handler, self.window_target(), control_flow, event);
+      }
+
+      let window_events = std::mem::take(&mut shared.pending.lock().unwrap().window_events);
+      for (window_id, event) in window_events {
+        call_event_handler!(
+          event_handler,
+          self.window_target(),
+          control_flow,
+          event::Event::WindowEvent {
+            window_id: crate::window::WindowId(window_id),
+            event,
+          }
+        );
+      }
+
+      let user_events = std::mem::take(&mut *self.user_queue.lock().unwrap());
+      for event in user_events {
+        call_event_handler!(
+          event_handler,
+          self.window_target(),
+          control_flow,
+          event::Event::UserEvent(event)
+        );
+      }
+
+      call_event_handler!(
+        event_handler,
+        self.window_target(),
+        control_flow,
+        event::Event::MainEventsCleared
+      );
+
+      let redraws = std::mem::take(&mut shared.pending.lock().unwrap().redraws);
+      for window_id in redraws {
+        call_event_handler!(
+          event_handler,
+          self.window_target(),
+          control_flow,
+          event::Event::RedrawRequested(crate::window::WindowId(window_id))
+        );
+      }
+
+      call_event_handler!(
+        event_handler,
+        self.window_target(),
+        control_flow,
+        event::Event::RedrawEventsCleared
+      );
+      call_event_handler!(
+        event_handler,
+        self.window_target(),
+        control_flow,
+        event::Event::AboutToWait
+      );
+
+      let has_injected = !self.window_target.p.injected.borrow().is_empty();
+      match control_flow {
+        ControlFlow::ExitWithCode(code) => {
+          call_event_handler!(
+            event_handler,
+            self.window_target(),
+            control_flow,
+            event::Event::LoopDestroyed
+          );
+          return code;
+        }
+        ControlFlow::Poll => start_cause = event::StartCause::Poll,
+        ControlFlow::Wait => {
+          let start = Instant::now();
+          if !has_injected {
+            shared.wait(None);
+          }
+          start_cause = event::StartCause::WaitCancelled {
+            start,
+            requested_resume: None,
+          };
+        }
+        ControlFlow::WaitUntil(requested_resume) => {
+          let start = Instant::now();
+          start_cause = if has_injected || shared.wait(Some(requested_resume)) {
+            event::StartCause::WaitCancelled {
+              start,
+              requested_resume: Some(requested_resume),
+            }
+          } else {
+            event::StartCause::ResumeTimeReached {
+              start,
+              requested_resume,
+            }
+          };
+        }
+      }
+    }
+  }
+
+  pub fn window_target(&self) -> &event_loop::EventLoopWindowTarget<T> {
+    &self.window_target
+  }
+
+  pub fn create_proxy(&self) -> EventLoopProxy<T> {
+    EventLoopProxy {
+      queue: self.user_queue.clone(),
+      shared: self.window_target.p.shared.clone(),
+    }
+  }
+}
+
+pub struct EventLoopProxy<T: 'static> {
+  queue: Arc<Mutex<VecDeque<T>>>,
+  shared: Arc<Shared>,
+}
+
+impl<T> EventLoopProxy<T> {
+  pub fn send_event(&self, event: T) -> Result<(), event_loop::EventLoopClosed<T>> {
+    self.queue.lock().unwrap().push_back(event);
+    self.shared.wake_up();
+    Ok(())
+  }
+
+  pub fn wake_up(&self) -> Result<(), event_loop::EventLoopClosed<()>> {
+    self.shared.wake_up();
+    Ok(())
+  }
+}
+
+impl<T> Clone for EventLoopProxy<T> {
+  fn clone(&self) -> Self {
+    EventLoopProxy {
+      queue: self.queue.clone(),
+      shared: self.shared.clone(),
+    }
+  }
+}
+
+pub struct EventLoopWindowTarget<T: 'static> {
+  pub(crate) shared: Arc<Shared>,
+  injected: Rc<RefCell<VecDeque<event::Event<'static, T>>>>,
+}
+
+impl<T> Clone for EventLoopWindowTarget<T> {
+  fn clone(&self) -> Self {
+    EventLoopWindowTarget {
+      shared: self.shared.clone(),
+      injected: self.injected.clone(),
+    }
+  }
+}
+
+impl<T: 'static> EventLoopWindowTarget<T> {
+  pub fn primary_monitor(&self) -> Option<monitor::MonitorHandle> {
+    self.shared.primary_monitor()
+  }
+
+  pub fn available_monitors(&self) -> VecDeque<MonitorHandle> {
+    self.shared.available_monitors()
+  }
+
+  pub fn set_application_menu(&self, _menu: Menu) {}
+
+  pub fn listen_device_events(&self, _allowed: event_loop::DeviceEvents) {}
+
+  pub(crate) fn inject_event(&self, event: event::Event<'static, T>) {
+    self.injected.borrow_mut().push_back(event);
+  }
+
+  pub(crate) fn set_monitors(&self, monitors: Vec<HeadlessMonitor>) {
+    *self.shared.monitors.write().unwrap() = monitors;
+  }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DeviceId;
+
+impl DeviceId {
+  pub fn dummy() -> Self {
+    DeviceId
+  }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct OsError;
+
+impl Display for OsError {
+  fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+    write!(fmt, "Headless OS Error")
+  }
+}
+
+pub(crate) use crate::icon::NoIcon as PlatformIcon;
+
+/// An index into the monitors configured on an event loop, a handle left dangling by a later
+/// `set_monitors` call reports the default monitor.
+#[derive(Clone, Debug)]
+pub struct MonitorHandle {
+  index: usize,
+  monitors: Arc<RwLock<Vec<HeadlessMonitor>>>,
+}
+
+impl MonitorHandle {
+  /// Handles are equal when they have the same index into the monitors of the same event loop.
+  fn key(&self) -> (usize, usize) {
+    (Arc::as_ptr(&self.monitors) as usize, self.index)
+  }
+
+  fn get(&self) -> HeadlessMonitor {
+    self
+      .monitors
+      .read()
+      .unwrap()
+      .get(self.index)
+      .cloned()
+      .unwrap_or_default()
+  }
+
+  pub fn name(&self) -> Option<String> {
+    Some(self.get().name)
+  }
+
+  pub fn size(&self) -> PhysicalSize<u32> {
+    self.get().size
+  }
+
+  pub fn position(&self) -> PhysicalPosition<i32> {
+    self.get().position
+  }
+
+  pub fn scale_factor(&self) -> f64 {
+    self.get().scale_factor
+  }
+
+  pub fn video_modes(&self) -> impl Iterator<Item = monitor::VideoMode> {
+    let monitor = self.get();
+    std::iter::once(monitor::VideoMode {
+      video_mode: VideoMode {
+        size: monitor.size.into(),
+        bit_depth: 32,
+        refresh_rate: monitor.refresh_rate,
+        monitor: self.clone(),
+      },
+    })
+  }
+}
+
+impl PartialEq for MonitorHandle {
+  fn eq(&self, other: &Self) -> bool {
+    self.key() == other.key()
+  }
+}
+
+impl Eq for MonitorHandle {}
+
+impl PartialOrd for MonitorHandle {
+  fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for MonitorHandle {
+  fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    self.key().cmp(&other.key())
+  }
+}
+
+impl std::hash::Hash for MonitorHandle {
+  fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    self.key().hash(state);
+  }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct VideoMode {
+  size: (u32, u32),
+  bit_depth: u16,
+  refresh_rate: u16,
+  monitor: MonitorHandle,
+}
+
+impl VideoMode {
+  pub fn size(&self) -> PhysicalSize<u32> {
+    self.size.into()
+  }
+
+  pub fn bit_depth(&self) -> u16 {
+    self.bit_depth
+  }
+
+  pub fn refresh_rate(&self) -> u16 {
+    self.refresh_rate
+  }
+
+  pub fn monitor(&self) -> monitor::MonitorHandle {
+    monitor::MonitorHandle {
+      inner: self.monitor.clone(),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct ShortcutManager {
+  shortcuts: Vec<Accelerator>,
+}
+
+impl ShortcutManager {
+  pub(crate) fn new<T>(_window_target: &event_loop::EventLoopWindowTarget<T>) -> Self {
+    ShortcutManager {
+      shortcuts: Vec::new(),
+    }
+  }
+
+  pub(crate) fn register(
+    &mut self,
+    accelerator: Accelerator,
+    _report_release: bool,
+  ) -> Result<RootGlobalShortcut, ShortcutManagerError> {
+    self.shortcuts.push(accelerator.clone());
+    Ok(RootGlobalShortcut(GlobalShortcut { accelerator }))
+  }
+
+  pub(crate) fn unregister_all(&mut self) -> Result<(), ShortcutManagerError> {
+    self.shortcuts.clear();
+    Ok(())
+  }
+
+  pub(crate) fn unregister(
+    &mut self,
+    shortcut: RootGlobalShortcut,
+  ) -> Result<(), ShortcutManagerError> {
+    let len = self.shortcuts.len();
+    self
+      .shortcuts
+      .retain(|accelerator| accelerator.clone().id() != shortcut.0.id());
+    if self.shortcuts.len() == len {
+      return Err(ShortcutManagerError::AcceleratorNotRegistered(
+        shortcut.0.accelerator,
+      ));
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobalShortcut {
+  pub(crate) accelerator: Accelerator,
+}
+
+impl GlobalShortcut {
+  pub fn id(&self) -> AcceleratorId {
+    self.accelerator.clone().id()
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Clipboard {
+  text: Option<String>,
+}
+
+impl Clipboard {
+  pub(crate) fn write_text(&mut self, s: impl AsRef<str>) {
+    self.text = Some(s.as_ref().to_owned());
+  }
+  pub(crate) fn read_text(&self) -> Option<String> {
+    self.text.clone()
+  }
+}
+
+pub fn keycode_to_scancode(_code: KeyCode) -> Option<u32> {
+  None
+}
+
+pub fn keycode_from_scancode(_scancode: u32) -> KeyCode {
+  KeyCode::Unidentified(crate::keyboard::NativeKeyCode::Unidentified)
+}
@@ -0,0 +1,403 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  collections::VecDeque,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+  },
+};
+
+use raw_window_handle::{RawWindowHandle, WebHandle};
+
+use crate::{
+  dpi::{PhysicalPosition, PhysicalSize, Position, Size},
+  error::{ExternalError, NotSupportedError, OsError as RootOsError},
+  event::WindowEvent,
+  icon::Icon,
+  monitor::MonitorHandle as RootMonitorHandle,
+  window::{
+    CursorIcon, CustomCursor, Fullscreen, Insets, Orientation, Rect, Theme, UserAttentionType,
+    WindowAttributes, WindowButtons,
+  },
+};
+
+use super::{EventLoopWindowTarget, Menu, MonitorHandle, Shared};
+
+static NEXT_WINDOW_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct WindowId(u64);
+
+impl WindowId {
+  pub fn dummy() -> Self {
+    WindowId(0)
+  }
+
+  pub fn into_raw(self) -> u64 {
+    self.0
+  }
+
+  pub fn from_raw(raw: u64) -> Option<Self> {
+    Some(WindowId(raw))
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PlatformSpecificWindowBuilderAttributes;
+
+struct WindowState {
+  title: String,
+  position: PhysicalPosition<i32>,
+  size: PhysicalSize<u32>,
+  min_size: Option<Size>,
+  max_size: Option<Size>,
+  visible: bool,
+  resizable: bool,
+  enabled_buttons: WindowButtons,
+  decorated: bool,
+  maximized: bool,
+  fullscreen: Option<Fullscreen>,
+  menu_visible: bool,
+}
+
+/// A window that only exists in memory.
+///
+/// Setters update the state right away and queue the matching `WindowEvent`, e.g. `Resized`,
+/// so that the event loop delivers them on its next iteration.
+pub struct Window {
+  window_id: WindowId,
+  shared: Arc<Shared>,
+  state: Mutex<WindowState>,
+}
+
+impl Window {
+  pub fn new<T: 'static>(
+    event_loop_window_target: &EventLoopWindowTarget<T>,
+    attributes: WindowAttributes,
+    _pl_attribs: PlatformSpecificWindowBuilderAttributes,
+  ) -> Result<Self, RootOsError> {
+    let window_id = WindowId(NEXT_WINDOW_ID.fetch_add(1, Ordering::Relaxed));
+    let scale_factor = event_loop_window_target.shared.monitor(0).scale_factor();
+    let size = attributes
+      .inner_size
+      .map(|size| size.to_physical(scale_factor))
+      .unwrap_or_else(|| PhysicalSize::new(800, 600));
+    let position = attributes
+      .position
+      .map(|position| position.to_physical(scale_factor))
+      .unwrap_or_default();
+
+    let window = Window {
+      window_id,
+      shared: event_loop_window_target.shared.clone(),
+      state: Mutex::new(WindowState {
+        title: attributes.title,
+        position,
+        size,
+        min_size: attributes.min_inner_size,
+        max_size: attributes.max_inner_size,
+        visible: attributes.visible,
+        resizable: attributes.resizable,
+        enabled_buttons: attributes.enabled_buttons,
+        decorated: attributes.decorations,
+        maximized: attributes.maximized,
+        fullscreen: attributes.fullscreen,
+        menu_visible: true,
+      }),
+    };
+
+    if attributes.visible && attributes.focused {
+      window.set_focus();
+    }
+
+    Ok(window)
+  }
+
+  pub fn id(&self) -> WindowId {
+    self.window_id
+  }
+
+  pub fn scale_factor(&self) -> f64 {
+    self.monitor().scale_factor()
+  }
+
+  pub fn request_redraw(&self) {
+    self.shared.request_redraw(self.window_id);
+  }
+
+  pub fn pre_present_notify(&self) {}
+
+  pub fn request_redraw_rect(&self, _position: PhysicalPosition<i32>, _size: PhysicalSize<u32>) {
+    self.request_redraw();
+  }
+
+  pub fn redraw_rect(&self) -> Option<(PhysicalPosition<i32>, PhysicalSize<u32>)> {
+    None
+  }
+
+  pub fn inner_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
+    Ok(self.state.lock().unwrap().position)
+  }
+
+  pub fn outer_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
+    Ok(self.state.lock().unwrap().position)
+  }
+
+  pub fn set_outer_position<P: Into<Position>>(&self, position: P) {
+    let position = position.into().to_physical(self.scale_factor());
+    let mut state = self.state.lock().unwrap();
+    if state.position != position {
+      state.position = position;
+      self
+        .shared
+        .push_window_event(self.window_id, WindowEvent::Moved(position));
+    }
+  }
+
+  pub fn inner_size(&self) -> PhysicalSize<u32> {
+    self.state.lock().unwrap().size
+  }
+
+  pub fn set_inner_size<S: Into<Size>>(&self, size: S) {
+    let scale_factor = self.scale_factor();
+    let mut state = self.state.lock().unwrap();
+    let mut size: PhysicalSize<u32> = size.into().to_physical(scale_factor);
+    if let Some(min_size) = state.min_size {
+      let min_size: PhysicalSize<u32> = min_size.to_physical(scale_factor);
+      size.width = size.width.max(min_size.width);
+      size.height = size.height.max(min_size.height);
+    }
+    if let Some(max_size) = state.max_size {
+      let max_size: PhysicalSize<u32> = max_size.to_physical(scale_factor);
+      size.width = size.width.min(max_size.width);
+      size.height = size.height.min(max_size.height);
+    }
+    if state.size != size {
+      state.size = size;
+      self
+        .shared
+        .push_window_event(self.window_id, WindowEvent::Resized(size));
+    }
+  }
+
+  pub fn request_inner_size<S: Into<Size>>(&self, size: S) -> Option<PhysicalSize<u32>> {
+    self.set_inner_size(size);
+    Some(self.inner_size())
+  }
+
+  pub fn outer_size(&self) -> PhysicalSize<u32> {
+    self.inner_size()
+  }
+
+  pub fn content_insets(&self) -> Insets {
+    Insets::default()
+  }
+
+  pub fn safe_area_insets(&self) -> Insets {
+    Insets::default()
+  }
+
+  pub fn set_orientation_lock(&self, _orientation: Option<Orientation>) {}
+
+  pub fn set_soft_input_visible(&self, _visible: bool) {}
+
+  pub fn set_min_inner_size<S: Into<Size>>(&self, min_size: Option<S>) {
+    self.state.lock().unwrap().min_size = min_size.map(Into::into);
+  }
+
+  pub fn set_max_inner_size<S: Into<Size>>(&self, max_size: Option<S>) {
+    self.state.lock().unwrap().max_size = max_size.map(Into::into);
+  }
+
+  pub fn set_aspect_ratio(&self, _aspect_ratio: Option<(u32, u32)>) {}
+
+  pub fn set_title(&self, title: &str) {
+    self.state.lock().unwrap().title = title.to_owned();
+  }
+
+  pub fn title(&self) -> String {
+    self.state.lock().unwrap().title.clone()
+  }
+
+  pub fn set_menu(&self, _menu: Option<Menu>) {}
+
+  pub fn set_visible(&self, visible: bool) {
+    self.state.lock().unwrap().visible = visible;
+  }
+
+  pub fn set_visible_with_animation(&self, visible: bool, _animate: bool) {
+    self.set_visible(visible);
+  }
+
+  pub fn set_transparent(&self, _transparent: bool) -> Result<(), ExternalError> {
+    Ok(())
+  }
+
+  pub fn set_blur(&self, _blur: bool) {}
+
+  pub fn set_focus(&self) {
+    let mut pending = self.shared.pending.lock().unwrap();
+    if pending.focused == Some(self.window_id) {
+      return;
+    }
+    if let Some(previous) = pending.focused.replace(self.window_id) {
+      pending
+        .window_events
+        .push_back((previous, WindowEvent::Focused(false)));
+    }
+    pending
+      .window_events
+      .push_back((self.window_id, WindowEvent::Focused(true)));
+    self.shared.condvar.notify_one();
+  }
+
+  pub fn set_resizable(&self, resizable: bool) {
+    self.state.lock().unwrap().resizable = resizable;
+  }
+
+  pub fn set_enabled_buttons(&self, buttons: WindowButtons) {
+    self.state.lock().unwrap().enabled_buttons = buttons;
+  }
+
+  pub fn set_minimized(&self, _minimized: bool) {}
+
+  pub fn set_maximized(&self, maximized: bool) {
+    self.state.lock().unwrap().maximized = maximized;
+  }
+
+  pub fn is_maximized(&self) -> bool {
+    self.state.lock().unwrap().maximized
+  }
+
+  pub fn is_resizable(&self) -> bool {
+    self.state.lock().unwrap().resizable
+  }
+
+  pub fn enabled_buttons(&self) -> WindowButtons {
+    self.state.lock().unwrap().enabled_buttons
+  }
+
+  pub fn is_decorated(&self) -> bool {
+    self.state.lock().unwrap().decorated
+  }
+
+  pub fn is_visible(&self) -> bool {
+    self.state.lock().unwrap().visible
+  }
+
+  pub fn theme(&self) -> Theme {
+    Theme::Light
+  }
+
+  pub fn set_cursor_hittest_region(&self, _region: Option<Vec<Rect>>) -> Result<(), ExternalError> {
+    Ok(())
+  }
+
+  pub fn set_shape(&self, _shape: Option<Vec<Rect>>) -> Result<(), ExternalError> {
+    Ok(())
+  }
+
+  pub fn drag_window(&self) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+    self.state.lock().unwrap().fullscreen = fullscreen;
+  }
+
+  pub fn fullscreen(&self) -> Option<Fullscreen> {
+    self.state.lock().unwrap().fullscreen.clone()
+  }
+
+  pub fn set_decorations(&self, decorations: bool) {
+    self.state.lock().unwrap().decorated = decorations;
+  }
+
+  pub fn set_content_protection(&self, _enabled: bool) {}
+
+  pub fn set_always_on_top(&self, _always_on_top: bool) {}
+
+  pub fn set_window_icon(&self, _window_icon: Option<Icon>) {}
+
+  pub fn set_ime_position<P: Into<Position>>(&self, _position: P) {}
+
+  pub fn set_ime_cursor_area<P: Into<Position>, S: Into<Size>>(&self, _position: P, _size: S) {}
+
+  pub fn request_user_attention(&self, _request_type: Option<UserAttentionType>) {}
+
+  pub fn hide_menu(&self) {
+    self.state.lock().unwrap().menu_visible = false;
+  }
+
+  pub fn show_menu(&self) {
+    self.state.lock().unwrap().menu_visible = true;
+  }
+
+  pub fn is_menu_visible(&self) -> bool {
+    self.state.lock().unwrap().menu_visible
+  }
+
+  pub fn set_cursor_icon(&self, _cursor: CursorIcon) {}
+
+  pub fn set_custom_cursor(&self, _cursor: CustomCursor) {}
+
+  pub fn set_cursor_position<P: Into<Position>>(&self, _position: P) -> Result<(), ExternalError> {
+    Ok(())
+  }
+
+  pub fn set_cursor_grab(&self, _grab: bool) -> Result<(), ExternalError> {
+    Ok(())
+  }
+
+  pub fn set_cursor_visible(&self, _visible: bool) {}
+
+  /// The monitor containing the window's top-left corner, or the primary one.
+  fn monitor(&self) -> MonitorHandle {
+    let position = self.state.lock().unwrap().position;
+    let monitors = self.shared.monitors.read().unwrap();
+    let index = monitors.iter().position(|monitor| {
+      position.x >= monitor.position.x
+        && position.y >= monitor.position.y
+        && position.x < monitor.position.x + monitor.size.width as i32
+        && position.y < monitor.position.y + monitor.size.height as i32
+    });
+    self.shared.monitor(index.unwrap_or(0))
+  }
+
+  pub fn current_monitor(&self) -> Option<RootMonitorHandle> {
+    Some(RootMonitorHandle {
+      inner: self.monitor(),
+    })
+  }
+
+  pub fn available_monitors(&self) -> VecDeque<MonitorHandle> {
+    self.shared.available_monitors()
+  }
+
+  pub fn primary_monitor(&self) -> Option<RootMonitorHandle> {
+    self.shared.primary_monitor()
+  }
+
+  /// There's no native window, the handle is empty.
+  pub fn raw_window_handle(&self) -> RawWindowHandle {
+    RawWindowHandle::Web(WebHandle::empty())
+  }
+}
+
+impl Drop for Window {
+  fn drop(&mut self) {
+    let mut pending = self.shared.pending.lock().unwrap();
+    if pending.focused == Some(self.window_id) {
+      pending.focused = None;
+    }
+    pending
+      .redraws
+      .retain(|window_id| *window_id != self.window_id);
+    pending
+      .window_events
+      .push_back((self.window_id, WindowEvent::Destroyed));
+    self.shared.condvar.notify_one();
+  }
+}
@@ -808,9 +808,8 @@ pub unsafe fn handle_main_events_cleared() {
     .map(|window| EventWrapper::StaticEvent(Event::RedrawRequested(RootWindowId(window.into()))))
     .collect();
 
-  if !redraw_events.is_empty() {
-    redraw_events.push(EventWrapper::StaticEvent(Event::RedrawEventsCleared));
-  }
+  redraw_events.push(EventWrapper::StaticEvent(Event::RedrawEventsCleared));
+  redraw_events.push(EventWrapper::StaticEvent(Event::AboutToWait));
   drop(this);
 
   handle_nonuser_events(redraw_events);
@@ -7,14 +7,18 @@ use std::{
   fmt::{self, Debug},
   marker::PhantomData,
   mem, ptr,
+  sync::{Arc, Weak},
 };
 
 use crossbeam_channel::{self as channel, Receiver, Sender};
 
 use crate::{
   dpi::LogicalSize,
+  error::OsError as RootOsError,
   event::Event,
-  event_loop::{ControlFlow, EventLoopClosed, EventLoopWindowTarget as RootEventLoopWindowTarget},
+  event_loop::{
+    ControlFlow, DeviceEvents, EventLoopClosed, EventLoopWindowTarget as RootEventLoopWindowTarget,
+  },
   monitor::MonitorHandle as RootMonitorHandle,
   platform::ios::Idiom,
 };
@@ -29,7 +33,7 @@ use crate::platform_impl::platform::{
     CFRunLoopSourceInvalidate, CFRunLoopSourceRef, CFRunLoopSourceSignal, CFRunLoopWakeUp,
     NSStringRust, UIApplicationMain, UIUserInterfaceIdiom,
   },
-  monitor, view, MonitorHandle,
+  monitor, view, Menu, MonitorHandle,
 };
 
 #[non_exhaustive]
@@ -51,7 +55,8 @@ pub enum EventProxy {
 
 #[derive(Clone)]
 pub struct EventLoopWindowTarget<T: 'static> {
-  receiver: Receiver<T>,
+  /// Shared, so the proxies can tell when it's dropped.
+  receiver: Arc<Receiver<T>>,
   sender_to_clone: Sender<T>,
 }
 
@@ -67,6 +72,10 @@ impl<T: 'static> EventLoopWindowTarget<T> {
 
     Some(RootMonitorHandle { inner: monitor })
   }
+
+  pub fn set_application_menu(&self, _menu: Menu) {}
+
+  pub fn listen_device_events(&self, _allowed: DeviceEvents) {}
 }
 
 pub struct EventLoop<T: 'static> {
@@ -74,7 +83,7 @@ pub struct EventLoop<T: 'static> {
 }
 
 impl<T: 'static> EventLoop<T> {
-  pub fn new() -> EventLoop<T> {
+  pub fn new() -> Result<EventLoop<T>, RootOsError> {
     static mut SINGLETON_INIT: bool = false;
     unsafe {
       assert_main_thread!("`EventLoop` can only be created on the main thread on iOS");
@@ -92,15 +101,19 @@ impl<T: 'static> EventLoop<T> {
     // this line sets up the main run loop before `UIApplicationMain`
     setup_control_flow_observers();
 
-    EventLoop {
+    Ok(EventLoop {
       window_target: RootEventLoopWindowTarget {
         p: EventLoopWindowTarget {
-          receiver,
+          receiver: Arc::new(receiver),
           sender_to_clone,
         },
+        control_flow: Default::default(),
+        scale_factor_rounding: Default::default(),
+        #[cfg(feature = "testing")]
+        injected_events: Default::default(),
         _marker: PhantomData,
       },
-    }
+    })
   }
 
   pub fn run<F>(self, event_handler: F) -> !
@@ -132,7 +145,10 @@ impl<T: 'static> EventLoop<T> {
   }
 
   pub fn create_proxy(&self) -> EventLoopProxy<T> {
-    EventLoopProxy::new(self.window_target.p.sender_to_clone.clone())
+    EventLoopProxy::new(
+      self.window_target.p.sender_to_clone.clone(),
+      Arc::downgrade(&self.window_target.p.receiver),
+    )
   }
 
   pub fn window_target(&self) -> &RootEventLoopWindowTarget<T> {
@@ -150,6 +166,7 @@ impl<T: 'static> EventLoop<T> {
 
 pub struct EventLoopProxy<T> {
   sender: Sender<T>,
+  receiver: Weak<Receiver<T>>,
   source: CFRunLoopSourceRef,
 }
 
@@ -157,7 +174,7 @@ unsafe impl<T: Send> Send for EventLoopProxy<T> {}
 
 impl<T> Clone for EventLoopProxy<T> {
   fn clone(&self) -> EventLoopProxy<T> {
-    EventLoopProxy::new(self.sender.clone())
+    EventLoopProxy::new(self.sender.clone(), self.receiver.clone())
   }
 }
 
@@ -171,7 +188,7 @@ impl<T> Drop for EventLoopProxy<T> {
 }
 
 impl<T> EventLoopProxy<T> {
-  fn new(sender: Sender<T>) -> EventLoopProxy<T> {
+  fn new(sender: Sender<T>, receiver: Weak<Receiver<T>>) -> EventLoopProxy<T> {
     unsafe {
       // just wake up the eventloop
       extern "C" fn event_loop_proxy_handler(_: *mut c_void) {}
@@ -186,7 +203,11 @@ impl<T> EventLoopProxy<T> {
       CFRunLoopAddSource(rl, source, kCFRunLoopCommonModes);
       CFRunLoopWakeUp(rl);
 
-      EventLoopProxy { sender, source }
+      EventLoopProxy {
+        sender,
+        receiver,
+        source,
+      }
     }
   }
 
@@ -195,13 +216,26 @@ impl<T> EventLoopProxy<T> {
       .sender
       .send(event)
       .map_err(|channel::SendError(x)| EventLoopClosed(x))?;
+    self.signal();
+    Ok(())
+  }
+
+  pub fn wake_up(&self) -> Result<(), EventLoopClosed<()>> {
+    if self.receiver.strong_count() == 0 {
+      return Err(EventLoopClosed(()));
+    }
+    self.signal();
+    Ok(())
+  }
+
+  fn signal(&self) {
     unsafe {
-      // let the main thread know there's a new event
+      // let the main thread know there's a new event, signals are coalesced until the source
+      // is performed
       CFRunLoopSourceSignal(self.source);
       let rl = CFRunLoopGetMain();
       CFRunLoopWakeUp(rl);
     }
-    Ok(())
   }
 }
 
@@ -10,6 +10,7 @@ use objc::{runtime::Object, Encode, Encoding};
 use crate::{
   dpi::LogicalSize,
   platform::ios::{Idiom, ScreenEdge, ValidOrientations},
+  window::Orientation,
 };
 
 pub type id = *mut Object;
@@ -211,6 +212,45 @@ impl UIInterfaceOrientationMask {
   }
 }
 
+impl From<Orientation> for UIInterfaceOrientationMask {
+  fn from(orientation: Orientation) -> UIInterfaceOrientationMask {
+    match orientation {
+      Orientation::Portrait => UIInterfaceOrientationMask::Portrait,
+      Orientation::PortraitFlipped => UIInterfaceOrientationMask::PortraitUpsideDown,
+      Orientation::Landscape => UIInterfaceOrientationMask::LandscapeRight,
+      Orientation::LandscapeFlipped => UIInterfaceOrientationMask::LandscapeLeft,
+    }
+  }
+}
+
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UIInterfaceOrientation(pub NSInteger);
+
+unsafe impl Encode for UIInterfaceOrientation {
+  fn encode() -> Encoding {
+    NSInteger::encode()
+  }
+}
+
+impl UIInterfaceOrientation {
+  pub const Unknown: UIInterfaceOrientation = UIInterfaceOrientation(0);
+  pub const Portrait: UIInterfaceOrientation = UIInterfaceOrientation(1);
+  pub const PortraitUpsideDown: UIInterfaceOrientation = UIInterfaceOrientation(2);
+  pub const LandscapeRight: UIInterfaceOrientation = UIInterfaceOrientation(3);
+  pub const LandscapeLeft: UIInterfaceOrientation = UIInterfaceOrientation(4);
+
+  pub fn to_orientation(self) -> Option<Orientation> {
+    match self {
+      UIInterfaceOrientation::Portrait => Some(Orientation::Portrait),
+      UIInterfaceOrientation::PortraitUpsideDown => Some(Orientation::PortraitFlipped),
+      UIInterfaceOrientation::LandscapeRight => Some(Orientation::Landscape),
+      UIInterfaceOrientation::LandscapeLeft => Some(Orientation::LandscapeFlipped),
+      _ => None,
+    }
+  }
+}
+
 #[repr(transparent)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct UIRectEdge(NSUInteger);
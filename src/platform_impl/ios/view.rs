@@ -1,26 +1,29 @@
 // Copyright 2019-2021 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::HashMap;
+use std::{collections::HashMap, ffi::CStr};
 
 use objc::{
   declare::ClassDecl,
-  runtime::{Class, Object, Sel, BOOL, NO, YES},
+  runtime::{Class, Object, Protocol, Sel, BOOL, NO, YES},
 };
 
 use crate::{
   dpi::PhysicalPosition,
-  event::{DeviceId as RootDeviceId, Event, Force, Touch, TouchPhase, WindowEvent},
+  event::{
+    DeviceId as RootDeviceId, ElementState, Event, Force, KeyEvent, Touch, TouchPhase, WindowEvent,
+  },
+  keyboard::{Key, KeyCode, KeyLocation},
   platform::ios::MonitorHandleExtIOS,
   platform_impl::platform::{
     app_state::{self, OSCapabilities},
     event_loop::{self, EventProxy, EventWrapper},
     ffi::{
-      id, nil, CGFloat, CGPoint, CGRect, UIForceTouchCapability, UIInterfaceOrientationMask,
-      UIRectEdge, UITouchPhase, UITouchType,
+      id, nil, CGFloat, CGPoint, CGRect, NSInteger, NSStringRust, UIForceTouchCapability,
+      UIInterfaceOrientation, UIInterfaceOrientationMask, UIRectEdge, UITouchPhase, UITouchType,
     },
-    window::PlatformSpecificWindowBuilderAttributes,
-    DeviceId,
+    window::{safe_area_insets, PlatformSpecificWindowBuilderAttributes},
+    DeviceId, KeyEventExtra,
   },
   window::{Fullscreen, WindowAttributes, WindowId as RootWindowId},
 };
@@ -119,7 +122,7 @@ unsafe fn get_view_class(root_view_class: &'static Class) -> &'static Class {
       }
     }
 
-    extern "C" fn layout_subviews(object: &Object, _: Sel) {
+    extern "C" fn layout_subviews(object: &mut Object, _: Sel) {
       unsafe {
         let superclass: &'static Class = msg_send![object, superclass];
         let () = msg_send![super(object, superclass), layoutSubviews];
@@ -150,6 +153,124 @@ unsafe fn get_view_class(root_view_class: &'static Class) -> &'static Class {
           window_id: RootWindowId(window.into()),
           event: WindowEvent::Resized(size),
         }));
+
+        // The view is laid out again when the interface rotates
+        let app: id = msg_send![class!(UIApplication), sharedApplication];
+        let orientation: UIInterfaceOrientation = msg_send![app, statusBarOrientation];
+        let last_orientation: NSInteger = *object.get_ivar("_taoInterfaceOrientation");
+        if orientation.0 != last_orientation {
+          object.set_ivar::<NSInteger>("_taoInterfaceOrientation", orientation.0);
+          if let Some(orientation) = orientation.to_orientation() {
+            app_state::handle_nonuser_event(EventWrapper::StaticEvent(Event::WindowEvent {
+              window_id: RootWindowId(window.into()),
+              event: WindowEvent::OrientationChanged(orientation),
+            }));
+          }
+        }
+      }
+    }
+
+    extern "C" fn safe_area_insets_did_change(object: &Object, _: Sel) {
+      unsafe {
+        let superclass: &'static Class = msg_send![object, superclass];
+        let () = msg_send![super(object, superclass), safeAreaInsetsDidChange];
+
+        let window: id = msg_send![object, window];
+        if window.is_null() {
+          return;
+        }
+        let insets = safe_area_insets(object as *const Object as id);
+        app_state::handle_nonuser_event(EventWrapper::StaticEvent(Event::WindowEvent {
+          window_id: RootWindowId(window.into()),
+          event: WindowEvent::SafeAreaChanged(insets),
+        }));
+      }
+    }
+
+    extern "C" fn can_become_first_responder(_: &Object, _: Sel) -> BOOL {
+      YES
+    }
+
+    // `UIKeyInput` methods, used for the on-screen keyboard
+
+    extern "C" fn has_text(_: &Object, _: Sel) -> BOOL {
+      NO
+    }
+
+    extern "C" fn insert_text(object: &Object, _: Sel, text: id) {
+      unsafe {
+        let window: id = msg_send![object, window];
+        if window.is_null() || text.is_null() {
+          return;
+        }
+        let text = CStr::from_ptr(text.UTF8String())
+          .to_string_lossy()
+          .into_owned();
+        app_state::handle_nonuser_event(EventWrapper::StaticEvent(Event::WindowEvent {
+          window_id: RootWindowId(window.into()),
+          event: WindowEvent::ReceivedImeText(text),
+        }));
+      }
+    }
+
+    extern "C" fn delete_backward(object: &Object, _: Sel) {
+      unsafe {
+        let window: id = msg_send![object, window];
+        if window.is_null() {
+          return;
+        }
+        let uiscreen: id = msg_send![window, screen];
+        let events = [ElementState::Pressed, ElementState::Released]
+          .iter()
+          .map(|&state| {
+            EventWrapper::StaticEvent(Event::WindowEvent {
+              window_id: RootWindowId(window.into()),
+              event: WindowEvent::KeyboardInput {
+                device_id: RootDeviceId(DeviceId { uiscreen }),
+                event: KeyEvent {
+                  physical_key: KeyCode::Backspace,
+                  logical_key: Key::Backspace,
+                  text: None,
+                  location: KeyLocation::Standard,
+                  state,
+                  repeat: false,
+                  platform_specific: KeyEventExtra {},
+                },
+                is_synthetic: false,
+              },
+            })
+          })
+          .collect::<Vec<_>>();
+        app_state::handle_nonuser_events(events);
+      }
+    }
+
+    extern "C" fn keyboard_will_change_frame(object: &Object, _: Sel, notification: id) {
+      unsafe {
+        let window: id = msg_send![object, window];
+        if window.is_null() {
+          return;
+        }
+        let user_info: id = msg_send![notification, userInfo];
+        let key = NSStringRust::alloc(nil).init_str("UIKeyboardFrameEndUserInfoKey");
+        let value: id = msg_send![user_info, objectForKey: key];
+        let () = msg_send![key, release];
+        if value.is_null() {
+          return;
+        }
+        // The keyboard frame is in the coordinate space of the screen
+        let keyboard_frame: CGRect = msg_send![value, CGRectValue];
+        let bounds: CGRect = msg_send![object, bounds];
+        let screen: id = msg_send![window, screen];
+        let screen_space: id = msg_send![screen, coordinateSpace];
+        let frame: CGRect = msg_send![object, convertRect:bounds toCoordinateSpace:screen_space];
+        let covered = frame.origin.y + frame.size.height - keyboard_frame.origin.y;
+        let scale_factor: CGFloat = msg_send![object, contentScaleFactor];
+        let height = (covered.max(0.0).min(frame.size.height) * scale_factor).round() as u32;
+        app_state::handle_nonuser_event(EventWrapper::StaticEvent(Event::WindowEvent {
+          window_id: RootWindowId(window.into()),
+          event: WindowEvent::SoftInputChanged { height },
+        }));
       }
     }
 
@@ -285,13 +406,41 @@ unsafe fn get_view_class(root_view_class: &'static Class) -> &'static Class {
     let mut decl = ClassDecl::new(&format!("TaoUIView{}", ID), root_view_class)
       .expect("Failed to declare class `TaoUIView`");
     ID += 1;
+    decl.add_ivar::<NSInteger>("_taoInterfaceOrientation");
     decl.add_method(
       sel!(drawRect:),
       draw_rect as extern "C" fn(&Object, Sel, CGRect),
     );
     decl.add_method(
       sel!(layoutSubviews),
-      layout_subviews as extern "C" fn(&Object, Sel),
+      layout_subviews as extern "C" fn(&mut Object, Sel),
+    );
+    decl.add_method(
+      sel!(safeAreaInsetsDidChange),
+      safe_area_insets_did_change as extern "C" fn(&Object, Sel),
+    );
+    decl.add_method(
+      sel!(canBecomeFirstResponder),
+      can_become_first_responder as extern "C" fn(&Object, Sel) -> BOOL,
+    );
+    decl.add_method(
+      sel!(hasText),
+      has_text as extern "C" fn(&Object, Sel) -> BOOL,
+    );
+    decl.add_method(
+      sel!(insertText:),
+      insert_text as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(deleteBackward),
+      delete_backward as extern "C" fn(&Object, Sel),
+    );
+    if let Some(protocol) = Protocol::get("UIKeyInput") {
+      decl.add_protocol(protocol);
+    }
+    decl.add_method(
+      sel!(keyboardWillChangeFrame:),
+      keyboard_will_change_frame as extern "C" fn(&Object, Sel, id),
     );
     decl.add_method(
       sel!(setContentScaleFactor:),
@@ -447,6 +596,18 @@ pub unsafe fn create_view(
     let () = msg_send![view, setContentScaleFactor: scale_factor as CGFloat];
   }
 
+  // Selector-based observers don't need to be removed since iOS 9
+  let notification_center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+  let name = NSStringRust::alloc(nil).init_str("UIKeyboardWillChangeFrameNotification");
+  let () = msg_send![
+    notification_center,
+    addObserver: view
+    selector: sel!(keyboardWillChangeFrame:)
+    name: name
+    object: nil
+  ];
+  let () = msg_send![name, release];
+
   view
 }
 
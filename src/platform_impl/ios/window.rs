@@ -25,7 +25,10 @@ use crate::{
     },
     monitor, view, EventLoopWindowTarget, Menu, MonitorHandle,
   },
-  window::{CursorIcon, Fullscreen, UserAttentionType, WindowAttributes, WindowId as RootWindowId},
+  window::{
+    CursorIcon, CustomCursor, Fullscreen, Insets, Orientation, Rect, Theme, UserAttentionType,
+    WindowAttributes, WindowButtons, WindowId as RootWindowId,
+  },
 };
 
 pub struct Inner {
@@ -50,6 +53,10 @@ impl Inner {
     debug!("`Window::set_title` is ignored on iOS")
   }
 
+  pub fn title(&self) -> String {
+    String::new()
+  }
+
   pub fn set_menu(&self, _menu: Option<Menu>) {
     debug!("`Window::set_menu` is ignored on iOS")
   }
@@ -65,6 +72,10 @@ impl Inner {
     }
   }
 
+  pub fn set_visible_with_animation(&self, visible: bool, _animate: bool) {
+    self.set_visible(visible);
+  }
+
   pub fn set_focus(&self) {
     //FIXME: implementation goes here
     warn!("set_focus not yet implemented on iOS");
@@ -88,6 +99,16 @@ impl Inner {
     }
   }
 
+  pub fn pre_present_notify(&self) {}
+
+  pub fn request_redraw_rect(&self, _position: PhysicalPosition<i32>, _size: PhysicalSize<u32>) {
+    self.request_redraw();
+  }
+
+  pub fn redraw_rect(&self) -> Option<(PhysicalPosition<i32>, PhysicalSize<u32>)> {
+    None
+  }
+
   pub fn inner_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
     unsafe {
       let safe_area = self.safe_area_screen_space();
@@ -153,10 +174,24 @@ impl Inner {
     }
   }
 
+  pub fn content_insets(&self) -> Insets {
+    warn!("`Window::content_insets` is ignored on iOS");
+    Insets::default()
+  }
+
+  pub fn safe_area_insets(&self) -> Insets {
+    unsafe { safe_area_insets(self.view) }
+  }
+
   pub fn set_inner_size(&self, _size: Size) {
     warn!("not clear what `Window::set_inner_size` means on iOS");
   }
 
+  pub fn request_inner_size(&self, _size: Size) -> Option<PhysicalSize<u32>> {
+    warn!("not clear what `Window::request_inner_size` means on iOS");
+    None
+  }
+
   pub fn set_min_inner_size(&self, _dimensions: Option<Size>) {
     warn!("`Window::set_min_inner_size` is ignored on iOS")
   }
@@ -165,10 +200,18 @@ impl Inner {
     warn!("`Window::set_max_inner_size` is ignored on iOS")
   }
 
+  pub fn set_aspect_ratio(&self, _aspect_ratio: Option<(u32, u32)>) {
+    warn!("`Window::set_aspect_ratio` is ignored on iOS")
+  }
+
   pub fn set_resizable(&self, _resizable: bool) {
     warn!("`Window::set_resizable` is ignored on iOS")
   }
 
+  pub fn set_enabled_buttons(&self, _buttons: WindowButtons) {
+    warn!("`Window::set_enabled_buttons` is ignored on iOS")
+  }
+
   pub fn scale_factor(&self) -> f64 {
     unsafe {
       let hidpi: CGFloat = msg_send![self.view, contentScaleFactor];
@@ -180,6 +223,10 @@ impl Inner {
     debug!("`Window::set_cursor_icon` ignored on iOS")
   }
 
+  pub fn set_custom_cursor(&self, _cursor: CustomCursor) {
+    debug!("`Window::set_custom_cursor` ignored on iOS")
+  }
+
   pub fn set_cursor_position(&self, _position: Position) -> Result<(), ExternalError> {
     Err(ExternalError::NotSupported(NotSupportedError::new()))
   }
@@ -188,6 +235,12 @@ impl Inner {
     Err(ExternalError::NotSupported(NotSupportedError::new()))
   }
 
+  pub fn set_transparent(&self, _transparent: bool) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_blur(&self, _blur: bool) {}
+
   pub fn set_cursor_visible(&self, _visible: bool) {
     debug!("`Window::set_cursor_visible` is ignored on iOS")
   }
@@ -196,6 +249,14 @@ impl Inner {
     Err(ExternalError::NotSupported(NotSupportedError::new()))
   }
 
+  pub fn set_cursor_hittest_region(&self, _region: Option<Vec<Rect>>) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_shape(&self, _shape: Option<Vec<Rect>>) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
   pub fn set_minimized(&self, _minimized: bool) {
     warn!("`Window::set_minimized` is ignored on iOS")
   }
@@ -214,11 +275,20 @@ impl Inner {
     false
   }
 
+  pub fn theme(&self) -> Theme {
+    Theme::Light
+  }
+
   pub fn is_resizable(&self) -> bool {
     warn!("`Window::is_resizable` is ignored on iOS");
     false
   }
 
+  pub fn enabled_buttons(&self) -> WindowButtons {
+    warn!("`Window::enabled_buttons` is ignored on iOS");
+    WindowButtons::all()
+  }
+
   pub fn is_decorated(&self) -> bool {
     warn!("`Window::is_decorated` is ignored on iOS");
     false
@@ -284,6 +354,10 @@ impl Inner {
     warn!("`Window::set_decorations` is ignored on iOS")
   }
 
+  pub fn set_content_protection(&self, _enabled: bool) {
+    warn!("`Window::set_content_protection` is ignored on iOS")
+  }
+
   pub fn set_always_on_top(&self, _always_on_top: bool) {
     warn!("`Window::set_always_on_top` is ignored on iOS")
   }
@@ -296,6 +370,10 @@ impl Inner {
     warn!("`Window::set_ime_position` is ignored on iOS")
   }
 
+  pub fn set_ime_cursor_area(&self, _position: Position, _size: Size) {
+    warn!("`Window::set_ime_cursor_area` is ignored on iOS")
+  }
+
   pub fn request_user_attention(&self, _request_type: Option<UserAttentionType>) {
     warn!("`Window::request_user_attention` is ignored on iOS")
   }
@@ -525,6 +603,32 @@ impl Inner {
     }
   }
 
+  pub fn set_orientation_lock(&self, orientation: Option<Orientation>) {
+    unsafe {
+      let supported_orientations = match orientation {
+        Some(orientation) => orientation.into(),
+        None => UIInterfaceOrientationMask::from_valid_orientations_idiom(
+          ValidOrientations::LandscapeAndPortrait,
+          event_loop::get_idiom(),
+        ),
+      };
+      msg_send![
+        self.view_controller,
+        setSupportedInterfaceOrientations: supported_orientations
+      ]
+    }
+  }
+
+  pub fn set_soft_input_visible(&self, visible: bool) {
+    unsafe {
+      if visible {
+        let _: BOOL = msg_send![self.view, becomeFirstResponder];
+      } else {
+        let _: BOOL = msg_send![self.view, resignFirstResponder];
+      }
+    }
+  }
+
   pub fn set_prefers_home_indicator_hidden(&self, hidden: bool) {
     unsafe {
       let prefers_home_indicator_hidden = if hidden { YES } else { NO };
@@ -643,6 +747,16 @@ impl WindowId {
       window: std::ptr::null_mut(),
     }
   }
+
+  pub fn into_raw(self) -> u64 {
+    self.window as usize as u64
+  }
+
+  pub fn from_raw(raw: u64) -> Option<Self> {
+    usize::try_from(raw)
+      .ok()
+      .map(|raw| WindowId { window: raw as id })
+  }
 }
 
 unsafe impl Send for WindowId {}
@@ -670,6 +784,22 @@ impl From<id> for WindowId {
   }
 }
 
+// requires main thread
+pub(crate) unsafe fn safe_area_insets(view: id) -> Insets {
+  if !app_state::os_capabilities().safe_area {
+    return Insets::default();
+  }
+  let insets: UIEdgeInsets = msg_send![view, safeAreaInsets];
+  // Rounded up, so the insets cover the whole obscured space
+  let points = |inset: CGFloat| inset.max(0.0).ceil() as u32;
+  Insets {
+    top: points(insets.top),
+    left: points(insets.left),
+    bottom: points(insets.bottom),
+    right: points(insets.right),
+  }
+}
+
 #[derive(Clone)]
 pub struct PlatformSpecificWindowBuilderAttributes {
   pub root_view_class: &'static Class,
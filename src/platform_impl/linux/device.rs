@@ -0,0 +1,284 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{os::raw::c_int, ptr, rc::Rc};
+
+use gtk::prelude::*;
+use x11_dl::{
+  xinput2::{self, XIEventMask, XIRawEvent, XInput2},
+  xlib::{self, Xlib},
+};
+
+use crate::{
+  event::{DeviceEvent, DeviceId as RootDeviceId, ElementState, MouseScrollDelta, RawKeyEvent},
+  event_loop::DeviceEvents,
+};
+
+use super::{keycode::keycode_from_scancode, wayland::RelativePointer, DeviceId};
+
+/// Delivers the raw XInput2 events of the X11 root window as `DeviceEvent`s.
+///
+/// GDK doesn't report raw events, they're read by a filter on the GDK display, which runs
+/// before GDK translates the X events.
+///
+/// On Wayland, only the relative pointer motion is delivered, with `zwp_relative_pointer_v1`, and
+/// only with the `wayland-protocols` feature.
+pub(crate) struct RawInputListener {
+  display: gdk::Display,
+  app: gtk::Application,
+  send_event: Rc<dyn Fn(RootDeviceId, DeviceEvent)>,
+  /// The installed filter and its data, `None` while the raw events aren't selected.
+  filter: Option<Box<FilterData>>,
+  /// The relative pointer on Wayland, `None` while the motion isn't listened to.
+  relative_pointer: Option<Box<RelativePointer>>,
+}
+
+struct FilterData {
+  xlib: Xlib,
+  xinput2: XInput2,
+  xdisplay: *mut xlib::Display,
+  /// The major opcode of the XInput extension.
+  opcode: c_int,
+  allowed: DeviceEvents,
+  app: gtk::Application,
+  send_event: Rc<dyn Fn(RootDeviceId, DeviceEvent)>,
+}
+
+impl RawInputListener {
+  pub fn new(
+    display: gdk::Display,
+    app: gtk::Application,
+    send_event: Rc<dyn Fn(RootDeviceId, DeviceEvent)>,
+  ) -> Self {
+    Self {
+      display,
+      app,
+      send_event,
+      filter: None,
+      relative_pointer: None,
+    }
+  }
+
+  pub fn listen(&mut self, allowed: DeviceEvents) {
+    if allowed == DeviceEvents::Never {
+      self.stop();
+      return;
+    }
+
+    if let Some(filter) = &mut self.filter {
+      filter.allowed = allowed;
+      return;
+    }
+    if self.relative_pointer.is_some() {
+      return;
+    }
+
+    if self.display.type_().name() == "GdkWaylandDisplay" {
+      // The compositor only sends the motion while the pointer is over a window of the
+      // application, whatever `allowed` is.
+      let send_event = self.send_event.clone();
+      self.relative_pointer = RelativePointer::new(
+        &self.display,
+        Box::new(move |device, delta| {
+          use glib::translate::ToGlibPtr;
+
+          let device: *mut gdk_sys::GdkDevice = device.to_glib_none().0;
+          send_event(
+            RootDeviceId(DeviceId(device as usize)),
+            DeviceEvent::MouseMotion { delta },
+          );
+        }),
+      );
+      if self.relative_pointer.is_none() {
+        log::warn!(
+          "The compositor doesn't support relative pointers, device events are unavailable"
+        );
+      }
+      return;
+    }
+    if self.display.type_().name() != "GdkX11Display" {
+      log::warn!("Device events are only supported on X11 and Wayland");
+      return;
+    }
+    let (xlib, xinput2) = match (Xlib::open(), XInput2::open()) {
+      (Ok(xlib), Ok(xinput2)) => (xlib, xinput2),
+      _ => {
+        log::warn!("Failed to load Xlib or XInput2, device events are unavailable");
+        return;
+      }
+    };
+
+    unsafe {
+      use glib::translate::ToGlibPtr;
+
+      let gdk_display: *mut gdk_sys::GdkDisplay = self.display.to_glib_none().0;
+      let xdisplay =
+        gdk_x11_sys::gdk_x11_display_get_xdisplay(gdk_display as *mut _) as *mut xlib::Display;
+
+      let mut opcode = 0;
+      let (mut event, mut error) = (0, 0);
+      if (xlib.XQueryExtension)(
+        xdisplay,
+        b"XInputExtension\0".as_ptr() as *const _,
+        &mut opcode,
+        &mut event,
+        &mut error,
+      ) == xlib::False
+      {
+        log::warn!("The X server doesn't support XInput, device events are unavailable");
+        return;
+      }
+
+      let filter = Box::new(FilterData {
+        xlib,
+        xinput2,
+        xdisplay,
+        opcode,
+        allowed,
+        app: self.app.clone(),
+        send_event: self.send_event.clone(),
+      });
+      filter.select_raw_events(true);
+      gdk_sys::gdk_window_add_filter(
+        ptr::null_mut(),
+        Some(raw_input_filter),
+        &*filter as *const FilterData as *mut _,
+      );
+      self.filter = Some(filter);
+    }
+  }
+
+  fn stop(&mut self) {
+    self.relative_pointer = None;
+    if let Some(filter) = self.filter.take() {
+      unsafe {
+        gdk_sys::gdk_window_remove_filter(
+          ptr::null_mut(),
+          Some(raw_input_filter),
+          &*filter as *const FilterData as *mut _,
+        );
+      }
+      filter.select_raw_events(false);
+    }
+  }
+}
+
+impl Drop for RawInputListener {
+  fn drop(&mut self) {
+    self.stop();
+  }
+}
+
+impl FilterData {
+  fn select_raw_events(&self, select: bool) {
+    let mut mask = [0u8; 4];
+    if select {
+      for event in &[
+        xinput2::XI_RawMotion,
+        xinput2::XI_RawButtonPress,
+        xinput2::XI_RawButtonRelease,
+        xinput2::XI_RawKeyPress,
+        xinput2::XI_RawKeyRelease,
+      ] {
+        xinput2::XISetMask(&mut mask, *event);
+      }
+    }
+    let mut event_mask = XIEventMask {
+      deviceid: xinput2::XIAllMasterDevices,
+      mask_len: mask.len() as c_int,
+      mask: mask.as_mut_ptr(),
+    };
+    unsafe {
+      let root = (self.xlib.XDefaultRootWindow)(self.xdisplay);
+      (self.xinput2.XISelectEvents)(self.xdisplay, root, &mut event_mask, 1);
+      (self.xlib.XFlush)(self.xdisplay);
+    }
+  }
+}
+
+/// GDK has already fetched the data of the generic events when the filters run.
+unsafe extern "C" fn raw_input_filter(
+  xevent: *mut gdk_sys::GdkXEvent,
+  _event: *mut gdk_sys::GdkEvent,
+  data: glib_sys::gpointer,
+) -> gdk_sys::GdkFilterReturn {
+  let data = &*(data as *const FilterData);
+  let cookie = &(*(xevent as *const xlib::XEvent)).generic_event_cookie;
+  if cookie.type_ != xlib::GenericEvent || cookie.extension != data.opcode || cookie.data.is_null()
+  {
+    return gdk_sys::GDK_FILTER_CONTINUE;
+  }
+  if data.allowed == DeviceEvents::WhenFocused
+    && !data.app.windows().iter().any(|window| window.is_active())
+  {
+    return gdk_sys::GDK_FILTER_CONTINUE;
+  }
+
+  let raw = &*(cookie.data as *const XIRawEvent);
+  let device_id = RootDeviceId(DeviceId(raw.sourceid as usize));
+  let send = |event| (data.send_event)(device_id, event);
+
+  match cookie.evtype {
+    xinput2::XI_RawMotion => {
+      let mask = std::slice::from_raw_parts(raw.valuators.mask, raw.valuators.mask_len as usize);
+      let mut values = raw.raw_values;
+      let mut delta = (0.0, 0.0);
+      for axis in 0..raw.valuators.mask_len * 8 {
+        if !xinput2::XIMaskIsSet(mask, axis) {
+          continue;
+        }
+        let value = *values;
+        values = values.add(1);
+        match axis {
+          0 => delta.0 = value,
+          1 => delta.1 = value,
+          _ => (),
+        }
+        send(DeviceEvent::Motion {
+          axis: axis as u32,
+          value,
+        });
+      }
+      if delta != (0.0, 0.0) {
+        send(DeviceEvent::MouseMotion { delta });
+      }
+    }
+    xinput2::XI_RawButtonPress | xinput2::XI_RawButtonRelease => {
+      let state = match cookie.evtype {
+        xinput2::XI_RawButtonPress => ElementState::Pressed,
+        _ => ElementState::Released,
+      };
+      // X11 reports each step of a wheel as a press and a release of the buttons 4 to 7
+      let delta = match raw.detail {
+        4 => Some((0.0, 1.0)),
+        5 => Some((0.0, -1.0)),
+        6 => Some((-1.0, 0.0)),
+        7 => Some((1.0, 0.0)),
+        _ => None,
+      };
+      match delta {
+        Some((x, y)) if state == ElementState::Pressed => send(DeviceEvent::MouseWheel {
+          delta: MouseScrollDelta::LineDelta(x, y),
+        }),
+        Some(_) => (),
+        None => send(DeviceEvent::Button {
+          button: raw.detail as u32,
+          state,
+        }),
+      }
+    }
+    xinput2::XI_RawKeyPress | xinput2::XI_RawKeyRelease => {
+      let state = match cookie.evtype {
+        xinput2::XI_RawKeyPress => ElementState::Pressed,
+        _ => ElementState::Released,
+      };
+      send(DeviceEvent::Key(RawKeyEvent {
+        physical_key: keycode_from_scancode(raw.detail as u32),
+        state,
+      }));
+    }
+    _ => (),
+  }
+
+  gdk_sys::GDK_FILTER_CONTINUE
+}
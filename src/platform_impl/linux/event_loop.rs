@@ -2,16 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-  cell::RefCell,
-  collections::{HashSet, VecDeque},
+  cell::{Cell, RefCell},
+  collections::{HashMap, HashSet, VecDeque},
   error::Error,
   process,
   rc::Rc,
-  sync::mpsc::SendError,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::SendError,
+    Arc, Weak,
+  },
   time::Instant,
 };
 
-use gdk::{Cursor, CursorType, EventKey, EventMask, WindowEdge, WindowState};
+use gdk::{Cursor, CursorType, EventKey, EventMask, ScrollDirection, WindowEdge, WindowState};
 use gio::{prelude::*, Cancellable};
 use glib::{source::Priority, Continue, MainContext};
 use gtk::{builders::AboutDialogBuilder, prelude::*, Inhibit};
@@ -19,19 +23,25 @@ use gtk::{builders::AboutDialogBuilder, prelude::*, Inhibit};
 use crate::{
   accelerator::AcceleratorId,
   dpi::{LogicalPosition, LogicalSize},
-  event::{ElementState, Event, MouseButton, StartCause, WindowEvent},
-  event_loop::{ControlFlow, EventLoopClosed, EventLoopWindowTarget as RootELW},
+  error::OsError as RootOsError,
+  event::{
+    ElementState, Event, MouseButton, MouseScrollDelta, StartCause, TouchPhase, WindowEvent,
+  },
+  event_loop::{ControlFlow, DeviceEvents, EventLoopClosed, EventLoopWindowTarget as RootELW},
   keyboard::ModifiersState,
   menu::{MenuItem, MenuType},
   monitor::MonitorHandle as RootMonitorHandle,
-  platform_impl::platform::{window::hit_test, DEVICE_ID},
+  platform_impl::platform::{device_id, window::hit_test},
   window::{CursorIcon, Fullscreen, WindowId as RootWindowId},
 };
 
 use super::{
-  keyboard,
-  monitor::MonitorHandle,
-  window::{WindowId, WindowRequest},
+  device::RawInputListener,
+  keyboard, menu,
+  monitor::{self, MonitorHandle},
+  wayland,
+  window::{self, current_user_time, WindowId, WindowRequest},
+  OsError,
 };
 
 #[derive(Clone)]
@@ -44,22 +54,17 @@ pub struct EventLoopWindowTarget<T> {
   pub(crate) windows: Rc<RefCell<HashSet<WindowId>>>,
   /// Window requests sender
   pub(crate) window_requests_tx: glib::Sender<(WindowId, WindowRequest)>,
+  /// Raw XInput2 events, see `listen_device_events`
+  raw_input: Rc<RefCell<RawInputListener>>,
+  /// Startup notification token of the process, taken by the first window
+  pub(crate) startup_id: Rc<RefCell<Option<String>>>,
   _marker: std::marker::PhantomData<T>,
 }
 
 impl<T> EventLoopWindowTarget<T> {
   #[inline]
   pub fn available_monitors(&self) -> VecDeque<MonitorHandle> {
-    let mut handles = VecDeque::new();
-    let display = &self.display;
-    let numbers = display.n_monitors();
-
-    for i in 0..numbers {
-      let monitor = MonitorHandle::new(display, i);
-      handles.push_back(monitor);
-    }
-
-    handles
+    monitor::available_monitors(&self.display)
   }
 
   #[inline]
@@ -70,6 +75,14 @@ impl<T> EventLoopWindowTarget<T> {
     let handle = MonitorHandle::new(&self.display, number);
     Some(RootMonitorHandle { inner: handle })
   }
+
+  #[inline]
+  pub fn set_application_menu(&self, _menu: menu::Menu) {}
+
+  #[inline]
+  pub fn listen_device_events(&self, allowed: DeviceEvents) {
+    self.raw_input.borrow_mut().listen(allowed);
+  }
 }
 
 pub struct EventLoop<T: 'static> {
@@ -77,52 +90,85 @@ pub struct EventLoop<T: 'static> {
   window_target: RootELW<T>,
   /// User event sender for EventLoopProxy
   user_event_tx: glib::Sender<T>,
+  /// Set by `EventLoopProxy::wake_up`, until the next iteration
+  wake_up: Arc<AtomicBool>,
   /// Event queue of EventLoop
   events: crossbeam_channel::Receiver<Event<'static, T>>,
   /// Draw queue of EventLoop
   draws: crossbeam_channel::Receiver<WindowId>,
+  /// Scale factor changes, dispatched by the loop as `ScaleFactorChanged` borrows the new size
+  scale_factor_changes: crossbeam_channel::Receiver<(WindowId, f64)>,
+}
+
+/// Frame pacing of a window presenting with `Window::pre_present_notify`.
+#[derive(Default)]
+struct FramePacing {
+  /// Whether the frame clock `update` signal is connected
+  connected: bool,
+  /// Whether a frame was presented and the frame clock didn't start the next one yet
+  presenting: bool,
+  /// Whether a redraw was requested while `presenting`
+  redraw_requested: bool,
 }
 
 impl<T: 'static> EventLoop<T> {
-  pub fn new() -> EventLoop<T> {
+  pub fn new() -> Result<EventLoop<T>, RootOsError> {
     assert_is_main_thread("new_any_thread");
-    EventLoop::new_any_thread()
+    EventLoop::try_new_any_thread()
   }
 
   pub fn new_any_thread() -> EventLoop<T> {
+    EventLoop::try_new_any_thread().expect("Failed to initialize gtk backend!")
+  }
+
+  fn try_new_any_thread() -> Result<EventLoop<T>, RootOsError> {
     let context = MainContext::default();
     context
-      .with_thread_default(|| EventLoop::new_gtk().expect("Failed to initialize gtk backend!"))
-      .expect("Failed to initialize gtk backend!")
+      .with_thread_default(EventLoop::new_gtk)
+      .map_err(|e| e.to_string())
+      .and_then(|event_loop| event_loop.map_err(|e| e.to_string()))
+      .map_err(|e| os_error!(OsError::GtkInitError(e)))
   }
 
   fn new_gtk() -> Result<EventLoop<T>, Box<dyn Error>> {
+    // Fails instead of aborting when no display can be opened.
+    gtk::init()?;
+
     let context = MainContext::default();
     let app = gtk::Application::new(None, gio::ApplicationFlags::empty());
     let app_ = app.clone();
     let cancellable: Option<&Cancellable> = None;
     app.register(cancellable)?;
 
-    // Send StartCause::Init event
     let (event_tx, event_rx) = crossbeam_channel::unbounded();
     let (draw_tx, draw_rx) = crossbeam_channel::unbounded();
-    let event_tx_ = event_tx.clone();
-    app.connect_activate(move |_| {
-      if let Err(e) = event_tx_.send(Event::NewEvents(StartCause::Init)) {
-        log::warn!("Failed to send init event to event channel: {}", e);
-      }
-    });
+    let (scale_factor_tx, scale_factor_rx) = crossbeam_channel::unbounded();
+    // The application is activated by `run`, which sends `StartCause::Init` itself so that it
+    // always comes first.
+    app.connect_activate(|_| {});
 
     // Create event loop window target.
     let (window_requests_tx, window_requests_rx) = glib::MainContext::channel(Priority::default());
     let window_requests_tx_ = window_requests_tx.clone();
     let display = gdk::Display::default()
       .expect("GdkDisplay not found. This usually means `gkt_init` hasn't called yet.");
+    let device_event_tx = event_tx.clone();
+    let raw_input = RawInputListener::new(
+      display.clone(),
+      app.clone(),
+      Rc::new(move |device_id, event| {
+        if let Err(e) = device_event_tx.send(Event::DeviceEvent { device_id, event }) {
+          log::warn!("Failed to send device event to event channel: {}", e);
+        }
+      }),
+    );
     let window_target = EventLoopWindowTarget {
       display,
       app,
       windows: Rc::new(RefCell::new(HashSet::new())),
       window_requests_tx,
+      raw_input: Rc::new(RefCell::new(raw_input)),
+      startup_id: Rc::new(RefCell::new(window::startup_id_from_env())),
       _marker: std::marker::PhantomData,
     };
 
@@ -136,53 +182,24 @@ impl<T: 'static> EventLoop<T> {
       Continue(true)
     });
 
+    // IME contexts of the windows, to update them on request
+    let im_contexts: Rc<RefCell<HashMap<WindowId, gtk::IMContextSimple>>> = Default::default();
+    // Windows that requested a cursor grab. The grab is only held while they have the focus.
+    let cursor_grabs: Rc<RefCell<HashSet<WindowId>>> = Default::default();
+    // Geometries of the fullscreen windows, restored when they leave fullscreen
+    let saved_geometries: Rc<RefCell<HashMap<WindowId, window::SavedGeometry>>> =
+      Default::default();
+    // Windows that presented a frame, see `Window::pre_present_notify`
+    let frame_pacing: Rc<RefCell<HashMap<WindowId, FramePacing>>> = Default::default();
+
     // Window Request
     window_requests_rx.attach(Some(&context), move |(id, request)| {
       if let Some(window) = app_.window_by_id(id.0) {
         match request {
           WindowRequest::Title(title) => window.set_title(&title),
           WindowRequest::Position((x, y)) => window.move_(x, y),
-          WindowRequest::Size((w, h)) => window.resize(w, h),
-          WindowRequest::MinSize((min_width, min_height)) => {
-            let picky_none: Option<&gtk::Window> = None;
-            window.set_geometry_hints(
-              picky_none,
-              Some(&gdk::Geometry::new(
-                min_width,
-                min_height,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0f64,
-                0f64,
-                gdk::Gravity::Center,
-              )),
-              gdk::WindowHints::MIN_SIZE,
-            )
-          }
-          WindowRequest::MaxSize((max_width, max_height)) => {
-            let picky_none: Option<&gtk::Window> = None;
-            window.set_geometry_hints(
-              picky_none,
-              Some(&gdk::Geometry::new(
-                0,
-                0,
-                max_width,
-                max_height,
-                0,
-                0,
-                0,
-                0,
-                0f64,
-                0f64,
-                gdk::Gravity::Center,
-              )),
-              gdk::WindowHints::MAX_SIZE,
-            )
-          }
+          WindowRequest::Size((w, h)) => window::set_content_size(&window, w, h),
+          WindowRequest::GeometryHints(hints) => window::set_geometry_hints(&window, hints),
           WindowRequest::Visible(visible) => {
             if visible {
               window.show_all();
@@ -191,9 +208,10 @@ impl<T: 'static> EventLoop<T> {
             }
           }
           WindowRequest::Focus => {
-            window.present_with_time(gdk_sys::GDK_CURRENT_TIME as _);
+            window.present_with_time(current_user_time(&window));
           }
           WindowRequest::Resizable(resizable) => window.set_resizable(resizable),
+          WindowRequest::EnabledButtons(buttons) => window::set_enabled_buttons(&window, buttons),
           WindowRequest::Minimized(minimized) => {
             if minimized {
               window.iconify();
@@ -220,6 +238,15 @@ impl<T: 'static> EventLoop<T> {
           }
           WindowRequest::Fullscreen(fullscreen) => match fullscreen {
             Some(f) => {
+              // Moving a fullscreen window to another monitor keeps the geometry it left
+              let fullscreen = window
+                .window()
+                .map_or(false, |w| w.state().contains(WindowState::FULLSCREEN));
+              if !fullscreen || !saved_geometries.borrow().contains_key(&id) {
+                saved_geometries
+                  .borrow_mut()
+                  .insert(id, window::SavedGeometry::of(&window));
+              }
               if let Fullscreen::Borderless(m) = f {
                 if let Some(monitor) = m {
                   let number = monitor.inner.number;
@@ -230,13 +257,30 @@ impl<T: 'static> EventLoop<T> {
                 }
               }
             }
-            None => window.unfullscreen(),
+            None => {
+              let geometry = saved_geometries.borrow_mut().remove(&id);
+              if let Some(geometry) = geometry {
+                window::restore_after_fullscreen(&window, geometry);
+              }
+              window.unfullscreen();
+            }
           },
-          WindowRequest::Decorations(decorations) => window.set_decorated(decorations),
+          WindowRequest::Decorations(decorations) => {
+            // The size of a GTK window excludes its decorations, including a client-side title
+            // bar, so restoring it keeps the content area size.
+            let (width, height) = window.size();
+            window.set_decorated(decorations);
+            let fullscreen = window
+              .window()
+              .map_or(false, |w| w.state().contains(WindowState::FULLSCREEN));
+            if !window.is_maximized() && !fullscreen {
+              window.resize(width, height);
+            }
+          }
           WindowRequest::AlwaysOnTop(always_on_top) => window.set_keep_above(always_on_top),
           WindowRequest::WindowIcon(window_icon) => {
             if let Some(icon) = window_icon {
-              window.set_icon(Some(&icon.inner.into()));
+              window.set_icon_list(&icon.inner.into_pixbufs());
             }
           }
           WindowRequest::UserAttention(request_type) => {
@@ -246,7 +290,22 @@ impl<T: 'static> EventLoop<T> {
             window.set_skip_taskbar_hint(skip);
             window.set_skip_pager_hint(skip)
           }
+          WindowRequest::StartupId(startup_id) => window.set_startup_id(&startup_id),
+          WindowRequest::CursorGrab(grab) => {
+            if grab {
+              cursor_grabs.borrow_mut().insert(id);
+            } else {
+              cursor_grabs.borrow_mut().remove(&id);
+            }
+            if !grab || window.has_toplevel_focus() {
+              grab_pointer(&window, grab);
+            }
+          }
+          WindowRequest::PointerConstraint(constraint) => {
+            wayland::constrain_pointer(&window, constraint)
+          }
           WindowRequest::CursorIcon(cursor) => {
+            window::stop_cursor_animation(&window);
             if let Some(gdk_window) = window.window() {
               let display = window.display();
               match cursor {
@@ -256,7 +315,7 @@ impl<T: 'static> EventLoop<T> {
                     match cr {
                       CursorIcon::Crosshair => "crosshair",
                       CursorIcon::Hand => "pointer",
-                      CursorIcon::Arrow => "crosshair",
+                      CursorIcon::Arrow => "default",
                       CursorIcon::Move => "move",
                       CursorIcon::Text => "text",
                       CursorIcon::Wait => "wait",
@@ -291,6 +350,8 @@ impl<T: 'static> EventLoop<T> {
                       CursorIcon::Default => "default",
                     },
                   )
+                  // The cursor theme might not provide every named cursor.
+                  .or_else(|| Cursor::from_name(&display, "default"))
                   .as_ref(),
                 ),
                 None => gdk_window
@@ -298,6 +359,14 @@ impl<T: 'static> EventLoop<T> {
               }
             };
           }
+          WindowRequest::CustomCursor(cursor) => window::set_custom_cursor(&window, &cursor),
+          WindowRequest::CursorHittestRegion(region) => {
+            window.input_shape_combine_region(cairo_region(region).as_ref());
+          }
+          WindowRequest::Shape(shape) => {
+            // Also shapes the input of the window
+            window.shape_combine_region(cairo_region(shape).as_ref());
+          }
           WindowRequest::CursorPosition((x, y)) => {
             if let Some(cursor) = window
               .display()
@@ -309,11 +378,23 @@ impl<T: 'static> EventLoop<T> {
               }
             }
           }
+          WindowRequest::ImeCursorArea((x, y, width, height)) => {
+            if let Some(ime) = im_contexts.borrow().get(&id) {
+              ime.set_cursor_location(&gdk::Rectangle::new(x, y, width, height));
+            }
+          }
+          WindowRequest::Transparent(transparent) => {
+            // GTK paints the opaque theme background unless the application paints the window.
+            window.set_app_paintable(transparent);
+            window.queue_draw();
+          }
           WindowRequest::WireUpEvents => {
             window.add_events(
               EventMask::POINTER_MOTION_MASK
                 | EventMask::BUTTON1_MOTION_MASK
                 | EventMask::BUTTON_PRESS_MASK
+                | EventMask::SCROLL_MASK
+                | EventMask::SMOOTH_SCROLL_MASK
                 | EventMask::TOUCH_MASK
                 | EventMask::STRUCTURE_MASK
                 | EventMask::FOCUS_CHANGE_MASK,
@@ -392,6 +473,22 @@ impl<T: 'static> EventLoop<T> {
               Inhibit(false)
             });
 
+            // The scale factor can change with the integer one of GTK or the fractional one of the
+            // Wayland compositor, a change is reported once the effective one differs.
+            let reported_scale_factor = Cell::new(window::scale_factor(&window));
+            let scale_factor_tx = scale_factor_tx.clone();
+            let scale_factor_changed = Rc::new(move |window: &gtk::Window| {
+              let scale_factor = window::scale_factor(window);
+              if scale_factor != reported_scale_factor.replace(scale_factor) {
+                if let Err(e) = scale_factor_tx.send((id, scale_factor)) {
+                  log::warn!("Failed to send scale factor change to event channel: {}", e);
+                }
+              }
+            });
+            let scale_factor_changed_ = scale_factor_changed.clone();
+            window.connect_scale_factor_notify(move |window| scale_factor_changed_(window));
+            wayland::wire_up_surface(&window, move |window| scale_factor_changed(window));
+
             let tx_clone = event_tx.clone();
             window.connect_delete_event(move |_, _| {
               if let Err(e) = tx_clone.send(Event::WindowEvent {
@@ -405,35 +502,75 @@ impl<T: 'static> EventLoop<T> {
 
             let tx_clone = event_tx.clone();
             window.connect_configure_event(move |window, event| {
-              let scale_factor = window.scale_factor();
+              let scale_factor = window::scale_factor(window);
 
               let (x, y) = event.position();
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
-                event: WindowEvent::Moved(
-                  LogicalPosition::new(x, y).to_physical(scale_factor as f64),
-                ),
+                event: WindowEvent::Moved(LogicalPosition::new(x, y).to_physical(scale_factor)),
               }) {
                 log::warn!("Failed to send window moved event to event channel: {}", e);
               }
 
-              let (w, h) = event.size();
-              if let Err(e) = tx_clone.send(Event::WindowEvent {
-                window_id: RootWindowId(id),
-                event: WindowEvent::Resized(
-                  LogicalSize::new(w, h).to_physical(scale_factor as f64),
-                ),
-              }) {
-                log::warn!(
-                  "Failed to send window resized event to event channel: {}",
-                  e
-                );
-              }
               false
             });
 
+            // `Resized` reports the content area, which is allocated after the window is
+            // configured
+            if let Some(window_box) = window.child() {
+              let tx_clone = event_tx.clone();
+              let last_size = Cell::new(None);
+              let last_insets = Cell::new(None);
+              window_box.connect_size_allocate(move |window_box, _| {
+                let window = match window_box
+                  .toplevel()
+                  .and_then(|window| window.downcast::<gtk::Window>().ok())
+                {
+                  Some(window) => window,
+                  None => return,
+                };
+                if let Some(insets) = window::content_insets(&window) {
+                  // The first allocation isn't a change
+                  let changed = last_insets
+                    .replace(Some(insets))
+                    .map_or(false, |last_insets| last_insets != insets);
+                  if changed {
+                    if let Err(e) = tx_clone.send(Event::WindowEvent {
+                      window_id: RootWindowId(id),
+                      event: WindowEvent::DecorationsChanged(insets),
+                    }) {
+                      log::warn!(
+                        "Failed to send decorations changed event to event channel: {}",
+                        e
+                      );
+                    }
+                  }
+                }
+                let size = match window::content_size(&window) {
+                  Some(size) if last_size.get() != Some(size) => size,
+                  _ => return,
+                };
+                last_size.set(Some(size));
+                if let Err(e) = tx_clone.send(Event::WindowEvent {
+                  window_id: RootWindowId(id),
+                  event: WindowEvent::Resized(
+                    LogicalSize::new(size.0, size.1).to_physical(window::scale_factor(&window)),
+                  ),
+                }) {
+                  log::warn!(
+                    "Failed to send window resized event to event channel: {}",
+                    e
+                  );
+                }
+              });
+            }
+
             let tx_clone = event_tx.clone();
-            window.connect_focus_in_event(move |_, _| {
+            let cursor_grabs_ = cursor_grabs.clone();
+            window.connect_focus_in_event(move |window, _| {
+              if cursor_grabs_.borrow().contains(&id) {
+                grab_pointer(window, true);
+              }
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
                 event: WindowEvent::Focused(true),
@@ -447,7 +584,11 @@ impl<T: 'static> EventLoop<T> {
             });
 
             let tx_clone = event_tx.clone();
-            window.connect_focus_out_event(move |_, _| {
+            let cursor_grabs_ = cursor_grabs.clone();
+            window.connect_focus_out_event(move |window, _| {
+              if cursor_grabs_.borrow().contains(&id) {
+                grab_pointer(window, false);
+              }
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
                 event: WindowEvent::Focused(false),
@@ -474,11 +615,11 @@ impl<T: 'static> EventLoop<T> {
             });
 
             let tx_clone = event_tx.clone();
-            window.connect_enter_notify_event(move |_, _| {
+            window.connect_enter_notify_event(move |_, event| {
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
                 event: WindowEvent::CursorEntered {
-                  device_id: DEVICE_ID,
+                  device_id: device_id(event),
                 },
               }) {
                 log::warn!(
@@ -492,13 +633,13 @@ impl<T: 'static> EventLoop<T> {
             let tx_clone = event_tx.clone();
             window.connect_motion_notify_event(move |window, motion| {
               if let Some(cursor) = motion.device() {
-                let scale_factor = window.scale_factor();
+                let scale_factor = window::scale_factor(window);
                 let (_, x, y) = cursor.window_at_position();
                 if let Err(e) = tx_clone.send(Event::WindowEvent {
                   window_id: RootWindowId(id),
                   event: WindowEvent::CursorMoved {
-                    position: LogicalPosition::new(x, y).to_physical(scale_factor as f64),
-                    device_id: DEVICE_ID,
+                    position: LogicalPosition::new(x, y).to_physical(scale_factor),
+                    device_id: device_id(motion),
                     // this field is depracted so it is fine to pass empty state
                     modifiers: ModifiersState::empty(),
                   },
@@ -510,11 +651,11 @@ impl<T: 'static> EventLoop<T> {
             });
 
             let tx_clone = event_tx.clone();
-            window.connect_leave_notify_event(move |_, _| {
+            window.connect_leave_notify_event(move |_, event| {
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
                 event: WindowEvent::CursorLeft {
-                  device_id: DEVICE_ID,
+                  device_id: device_id(event),
                 },
               }) {
                 log::warn!("Failed to send cursor left event to event channel: {}", e);
@@ -532,10 +673,12 @@ impl<T: 'static> EventLoop<T> {
                     1 => MouseButton::Left,
                     2 => MouseButton::Middle,
                     3 => MouseButton::Right,
+                    8 => MouseButton::Back,
+                    9 => MouseButton::Forward,
                     _ => MouseButton::Other(button as u16),
                   },
                   state: ElementState::Pressed,
-                  device_id: DEVICE_ID,
+                  device_id: device_id(event),
                   // this field is depracted so it is fine to pass empty state
                   modifiers: ModifiersState::empty(),
                 },
@@ -558,10 +701,12 @@ impl<T: 'static> EventLoop<T> {
                     1 => MouseButton::Left,
                     2 => MouseButton::Middle,
                     3 => MouseButton::Right,
+                    8 => MouseButton::Back,
+                    9 => MouseButton::Forward,
                     _ => MouseButton::Other(button as u16),
                   },
                   state: ElementState::Released,
-                  device_id: DEVICE_ID,
+                  device_id: device_id(event),
                   // this field is depracted so it is fine to pass empty state
                   modifiers: ModifiersState::empty(),
                 },
@@ -575,6 +720,43 @@ impl<T: 'static> EventLoop<T> {
             });
 
             let tx_clone = event_tx.clone();
+            window.connect_scroll_event(move |_, event| {
+              // GDK scrolls down and right for positive deltas, tao scrolls up and right.
+              let (x, y) = match event.direction() {
+                ScrollDirection::Up => (0.0, 1.0),
+                ScrollDirection::Down => (0.0, -1.0),
+                ScrollDirection::Left => (-1.0, 0.0),
+                ScrollDirection::Right => (1.0, 0.0),
+                _ => {
+                  let (x, y) = event.delta();
+                  (x, -y)
+                }
+              };
+              let phase = if event.is_scroll_stop_event() {
+                TouchPhase::Ended
+              } else {
+                TouchPhase::Moved
+              };
+              if let Err(e) = tx_clone.send(Event::WindowEvent {
+                window_id: RootWindowId(id),
+                event: WindowEvent::MouseWheel {
+                  device_id: device_id(event),
+                  delta: MouseScrollDelta::LineDelta(x as f32, y as f32),
+                  phase,
+                  momentum_phase: None,
+                  // this field is depracted so it is fine to pass empty state
+                  modifiers: ModifiersState::empty(),
+                },
+              }) {
+                log::warn!("Failed to send mouse wheel event to event channel: {}", e);
+              }
+              Inhibit(false)
+            });
+
+            let tx_clone = event_tx.clone();
+            // GDK doesn't flag auto-repeat, a key pressed again without being released is one.
+            let pressed_keys = Rc::new(RefCell::new(HashSet::new()));
+            let pressed_keys_clone = pressed_keys.clone();
             let keyboard_handler = Rc::new(move |event_key: EventKey, element_state| {
               // if we have a modifier lets send it
               let mut mods = keyboard::get_modifiers(event_key.clone());
@@ -599,14 +781,24 @@ impl<T: 'static> EventLoop<T> {
                 }
               }
 
-              // todo: implement repeat?
-              let event = keyboard::make_key_event(&event_key, false, None, element_state);
+              let is_repeat = {
+                let mut pressed_keys = pressed_keys_clone.borrow_mut();
+                let scancode = event_key.hardware_keycode();
+                match element_state {
+                  ElementState::Pressed => !pressed_keys.insert(scancode),
+                  _ => {
+                    pressed_keys.remove(&scancode);
+                    false
+                  }
+                }
+              };
+              let event = keyboard::make_key_event(&event_key, is_repeat, None, element_state);
 
               if let Some(event) = event {
                 if let Err(e) = tx_clone.send(Event::WindowEvent {
                   window_id: RootWindowId(id),
                   event: WindowEvent::KeyboardInput {
-                    device_id: DEVICE_ID,
+                    device_id: device_id(&event_key),
                     event,
                     is_synthetic: false,
                   },
@@ -622,6 +814,15 @@ impl<T: 'static> EventLoop<T> {
             let ime = gtk::IMContextSimple::default();
             ime.set_client_window(window.window().as_ref());
             ime.focus_in();
+            im_contexts.borrow_mut().insert(id, ime.clone());
+            let im_contexts_ = im_contexts.clone();
+            let cursor_grabs_ = cursor_grabs.clone();
+            let saved_geometries_ = saved_geometries.clone();
+            window.connect_destroy(move |_| {
+              im_contexts_.borrow_mut().remove(&id);
+              cursor_grabs_.borrow_mut().remove(&id);
+              saved_geometries_.borrow_mut().remove(&id);
+            });
             ime.connect_commit(move |_, s| {
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
@@ -648,28 +849,37 @@ impl<T: 'static> EventLoop<T> {
               Inhibit(false)
             });
 
+            // Releases are not delivered to unfocused windows.
+            window.connect_focus_out_event(move |_, _| {
+              pressed_keys.borrow_mut().clear();
+              Inhibit(false)
+            });
+
             let tx_clone = event_tx.clone();
+            let saved_geometries_ = saved_geometries.clone();
             window.connect_window_state_event(move |window, event| {
               let state = event.changed_mask();
+              // The window manager may leave fullscreen by itself, then it restores the geometry
+              if state.contains(WindowState::FULLSCREEN)
+                && !event.new_window_state().contains(WindowState::FULLSCREEN)
+              {
+                saved_geometries_.borrow_mut().remove(&id);
+              }
               if state.contains(WindowState::ICONIFIED) || state.contains(WindowState::MAXIMIZED) {
-                let scale_factor = window.scale_factor();
+                let scale_factor = window::scale_factor(window);
 
                 let (x, y) = window.position();
                 if let Err(e) = tx_clone.send(Event::WindowEvent {
                   window_id: RootWindowId(id),
-                  event: WindowEvent::Moved(
-                    LogicalPosition::new(x, y).to_physical(scale_factor as f64),
-                  ),
+                  event: WindowEvent::Moved(LogicalPosition::new(x, y).to_physical(scale_factor)),
                 }) {
                   log::warn!("Failed to send window moved event to event channel: {}", e);
                 }
 
-                let (w, h) = window.size();
+                let (w, h) = window::content_size(window).unwrap_or_else(|| window.size());
                 if let Err(e) = tx_clone.send(Event::WindowEvent {
                   window_id: RootWindowId(id),
-                  event: WindowEvent::Resized(
-                    LogicalSize::new(w, h).to_physical(scale_factor as f64),
-                  ),
+                  event: WindowEvent::Resized(LogicalSize::new(w, h).to_physical(scale_factor)),
                 }) {
                   log::warn!(
                     "Failed to send window resized event to event channel: {}",
@@ -680,12 +890,52 @@ impl<T: 'static> EventLoop<T> {
               Inhibit(false)
             });
           }
-          WindowRequest::Redraw => {
-            if let Err(e) = draw_tx.send(id) {
-              log::warn!("Failed to send redraw event to event channel: {}", e);
+          WindowRequest::Redraw(area) => {
+            // Held back until the frame clock starts the next frame after a presented one
+            let held_back = frame_pacing
+              .borrow_mut()
+              .get_mut(&id)
+              .map_or(false, |pacing| {
+                pacing.redraw_requested |= pacing.presenting;
+                pacing.presenting
+              });
+            if !held_back {
+              if let Err(e) = draw_tx.send(id) {
+                log::warn!("Failed to send redraw event to event channel: {}", e);
+              }
             }
 
-            window.queue_draw();
+            match area {
+              Some(area) => window::queue_draw_content_area(&window, area),
+              None => window.queue_draw(),
+            }
+          }
+          WindowRequest::PrePresentNotify => {
+            if let Some(frame_clock) = window.frame_clock() {
+              let mut pacings = frame_pacing.borrow_mut();
+              let pacing = pacings.entry(id).or_default();
+              if !pacing.connected {
+                pacing.connected = true;
+                let frame_pacing_ = frame_pacing.clone();
+                let draw_tx_ = draw_tx.clone();
+                frame_clock.connect_update(move |_| {
+                  if let Some(pacing) = frame_pacing_.borrow_mut().get_mut(&id) {
+                    pacing.presenting = false;
+                    if std::mem::take(&mut pacing.redraw_requested) {
+                      if let Err(e) = draw_tx_.send(id) {
+                        log::warn!("Failed to send redraw event to event channel: {}", e);
+                      }
+                    }
+                  }
+                });
+                let frame_pacing_ = frame_pacing.clone();
+                window.connect_destroy(move |_| {
+                  frame_pacing_.borrow_mut().remove(&id);
+                });
+              }
+              pacing.presenting = true;
+              frame_clock.request_phase(gdk::FrameClockPhase::UPDATE);
+            }
           }
           WindowRequest::Menu(m) => match m {
             (None, Some(menu_id)) => {
@@ -693,6 +943,7 @@ impl<T: 'static> EventLoop<T> {
                 window_id: Some(RootWindowId(id)),
                 menu_id,
                 origin: MenuType::MenuBar,
+                tray_id: None,
               }) {
                 log::warn!("Failed to send menu event to event channel: {}", e);
               }
@@ -751,19 +1002,24 @@ impl<T: 'static> EventLoop<T> {
             _ => {}
           },
           WindowRequest::SetMenu((window_menu, accel_group, mut menubar)) => {
+            // remove all existing elements as we overwrite
+            // but we keep same menubar reference
+            for i in menubar.children() {
+              menubar.remove(&i);
+            }
             if let Some(window_menu) = window_menu {
-              // remove all existing elements as we overwrite
-              // but we keep same menubar reference
-              for i in menubar.children() {
-                menubar.remove(&i);
-              }
               // create all new elements
               window_menu.generate_menu(&mut menubar, &window_requests_tx_, &accel_group, id);
               // make sure all newly added elements are visible
               menubar.show_all();
+            } else {
+              // a hidden menubar takes no space in the window box, the content reclaims it
+              menubar.hide();
             }
           }
-          WindowRequest::GlobalHotKey(_hotkey_id) => {}
+          WindowRequest::GlobalHotKey(_)
+          | WindowRequest::GlobalHotKeyReleased(_)
+          | WindowRequest::TrayMenu(_) => {}
         }
       } else if id == WindowId::dummy() {
         match request {
@@ -772,11 +1028,27 @@ impl<T: 'static> EventLoop<T> {
               log::warn!("Failed to send global hotkey event to event channel: {}", e);
             }
           }
+          WindowRequest::GlobalHotKeyReleased(hotkey_id) => {
+            if let Err(e) = event_tx.send(Event::GlobalShortcutReleased(AcceleratorId(hotkey_id))) {
+              log::warn!("Failed to send global hotkey event to event channel: {}", e);
+            }
+          }
           WindowRequest::Menu((None, Some(menu_id))) => {
             if let Err(e) = event_tx.send(Event::MenuEvent {
               window_id: None,
               menu_id,
               origin: MenuType::ContextMenu,
+              tray_id: None,
+            }) {
+              log::warn!("Failed to send status bar event to event channel: {}", e);
+            }
+          }
+          WindowRequest::TrayMenu((tray_id, menu_id)) => {
+            if let Err(e) = event_tx.send(Event::MenuEvent {
+              window_id: None,
+              menu_id,
+              origin: MenuType::ContextMenu,
+              tray_id: Some(tray_id),
             }) {
               log::warn!("Failed to send status bar event to event channel: {}", e);
             }
@@ -791,11 +1063,17 @@ impl<T: 'static> EventLoop<T> {
     let event_loop = Self {
       window_target: RootELW {
         p: window_target,
+        control_flow: Default::default(),
+        scale_factor_rounding: Default::default(),
+        #[cfg(feature = "testing")]
+        injected_events: Default::default(),
         _marker: std::marker::PhantomData,
       },
       user_event_tx,
+      wake_up: Default::default(),
       events: event_rx,
       draws: draw_rx,
+      scale_factor_changes: scale_factor_rx,
     };
 
     Ok(event_loop)
@@ -806,10 +1084,17 @@ impl<T: 'static> EventLoop<T> {
   where
     F: FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow) + 'static,
   {
-    let exit_code = self.run_return(callback);
+    let exit_code = self.run_inner(callback, true);
     process::exit(exit_code)
   }
 
+  pub(crate) fn run_return<F>(&mut self, callback: F) -> i32
+  where
+    F: FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
+  {
+    self.run_inner(callback, false)
+  }
+
   /// This is the core event loop logic. It basically loops on `gtk_main_iteration` and processes one
   /// event along with that iteration. Depends on current control flow and what it should do, an
   /// event state is defined. The whole state flow chart runs like following:
@@ -838,8 +1123,13 @@ impl<T: 'static> EventLoop<T> {
   /// - On `NewStart` to `EventQueue`, a `NewEvents` with corresponding `StartCause` depends on
   /// current control flow is sent.
   /// - On `EventQueue` to `DrawQueue`, a `MainEventsCleared` event is sent.
-  /// - On `DrawQueue` back to `NewStart`, a `RedrawEventsCleared` event is sent.
-  pub(crate) fn run_return<F>(&mut self, mut callback: F) -> i32
+  /// - On `DrawQueue` back to `NewStart`, a `RedrawEventsCleared` event and an `AboutToWait`
+  /// event are sent, even when there was nothing to draw.
+  ///
+  /// Before `LoopDestroyed`, events still queued are delivered so that `Destroyed` always comes
+  /// first. When `exiting` is set the process is about to exit and the remaining windows are
+  /// destroyed as well.
+  fn run_inner<F>(&mut self, mut callback: F, exiting: bool) -> i32
   where
     F: FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
   {
@@ -856,72 +1146,99 @@ impl<T: 'static> EventLoop<T> {
         let window_target = &self.window_target;
         let events = &self.events;
         let draws = &self.draws;
+        let scale_factor_changes = &self.scale_factor_changes;
+        let wake_up = &self.wake_up;
 
         window_target.p.app.activate();
-
-        let mut state = EventState::NewStart;
+        callback(
+          Event::NewEvents(StartCause::Init),
+          window_target,
+          &mut control_flow,
+        );
+
+        let mut state = EventState::EventQueue;
+        // When the loop started waiting, reported in `StartCause`.
+        let mut wait_start = None;
         let exit_code = loop {
           let mut blocking = false;
+          // Wakes the loop up once the `WaitUntil` deadline is reached.
+          let mut resume_timer = None;
           match state {
-            EventState::NewStart => match control_flow {
-              ControlFlow::ExitWithCode(code) => {
-                callback(Event::LoopDestroyed, window_target, &mut control_flow);
-                break code;
-              }
-              ControlFlow::Wait => {
-                if !events.is_empty() || !draws.is_empty() {
-                  callback(
-                    Event::NewEvents(StartCause::WaitCancelled {
-                      start: Instant::now(),
-                      requested_resume: None,
-                    }),
-                    window_target,
-                    &mut control_flow,
-                  );
-                  state = EventState::EventQueue;
-                } else {
-                  blocking = true;
-                }
-              }
-              ControlFlow::WaitUntil(requested_resume) => {
-                let start = Instant::now();
-                if start >= requested_resume {
-                  callback(
-                    Event::NewEvents(StartCause::ResumeTimeReached {
+            EventState::NewStart => {
+              let start = *wait_start.get_or_insert_with(Instant::now);
+              let has_events = wake_up.swap(false, Ordering::AcqRel)
+                || !events.is_empty()
+                || !draws.is_empty()
+                || !scale_factor_changes.is_empty();
+              let cause = match control_flow {
+                ControlFlow::ExitWithCode(code) => break code,
+                ControlFlow::Poll => Some(StartCause::Poll),
+                ControlFlow::Wait if has_events => Some(StartCause::WaitCancelled {
+                  start,
+                  requested_resume: None,
+                }),
+                ControlFlow::Wait => None,
+                ControlFlow::WaitUntil(requested_resume) => {
+                  let now = Instant::now();
+                  if now >= requested_resume {
+                    Some(StartCause::ResumeTimeReached {
                       start,
                       requested_resume,
-                    }),
-                    window_target,
-                    &mut control_flow,
-                  );
-                  state = EventState::EventQueue;
-                } else if !events.is_empty() {
-                  callback(
-                    Event::NewEvents(StartCause::WaitCancelled {
+                    })
+                  } else if has_events {
+                    Some(StartCause::WaitCancelled {
                       start,
                       requested_resume: Some(requested_resume),
-                    }),
-                    window_target,
-                    &mut control_flow,
-                  );
+                    })
+                  } else {
+                    let source = glib::timeout_source_new(
+                      requested_resume - now,
+                      None,
+                      Priority::default(),
+                      || Continue(false),
+                    );
+                    source.attach(Some(&context));
+                    resume_timer = Some(source);
+                    None
+                  }
+                }
+              };
+
+              match cause {
+                Some(cause) => {
+                  wait_start = None;
+                  callback(Event::NewEvents(cause), window_target, &mut control_flow);
                   state = EventState::EventQueue;
-                } else {
-                  blocking = true;
                 }
+                None => blocking = true,
               }
-              ControlFlow::Poll => {
-                callback(
-                  Event::NewEvents(StartCause::Poll),
-                  window_target,
-                  &mut control_flow,
-                );
-                state = EventState::EventQueue;
-              }
-            },
+            }
             EventState::EventQueue => match control_flow {
-              ControlFlow::ExitWithCode(code) => {
-                callback(Event::LoopDestroyed, window_target, &mut control_flow);
-                break (code);
+              ControlFlow::ExitWithCode(code) => break code,
+              _ if !scale_factor_changes.is_empty() => {
+                if let Ok((id, scale_factor)) = scale_factor_changes.try_recv() {
+                  if let Some(window) = window_target.p.app.window_by_id(id.0) {
+                    let (width, height) =
+                      window::content_size(&window).unwrap_or_else(|| window.size());
+                    let size = LogicalSize::new(width, height).to_physical(scale_factor);
+                    let mut new_inner_size = size;
+                    callback(
+                      Event::WindowEvent {
+                        window_id: RootWindowId(id),
+                        event: WindowEvent::ScaleFactorChanged {
+                          scale_factor,
+                          new_inner_size: &mut new_inner_size,
+                        },
+                      },
+                      window_target,
+                      &mut control_flow,
+                    );
+                    if new_inner_size != size {
+                      let (width, height) = new_inner_size.to_logical::<i32>(scale_factor).into();
+                      window::set_content_size(&window, width, height);
+                    }
+                  }
+                }
               }
               _ => match events.try_recv() {
                 Ok(event) => match event {
@@ -930,19 +1247,12 @@ impl<T: 'static> EventLoop<T> {
                 },
                 Err(_) => {
                   callback(Event::MainEventsCleared, window_target, &mut control_flow);
-                  if draws.is_empty() {
-                    state = EventState::NewStart;
-                  } else {
-                    state = EventState::DrawQueue;
-                  }
+                  state = EventState::DrawQueue;
                 }
               },
             },
             EventState::DrawQueue => match control_flow {
-              ControlFlow::ExitWithCode(code) => {
-                callback(Event::LoopDestroyed, window_target, &mut control_flow);
-                break code;
-              }
+              ControlFlow::ExitWithCode(code) => break code,
               _ => match draws.try_recv() {
                 Ok(id) => callback(
                   Event::RedrawRequested(RootWindowId(id)),
@@ -951,13 +1261,33 @@ impl<T: 'static> EventLoop<T> {
                 ),
                 Err(_) => {
                   callback(Event::RedrawEventsCleared, window_target, &mut control_flow);
+                  callback(Event::AboutToWait, window_target, &mut control_flow);
                   state = EventState::NewStart;
                 }
               },
             },
           }
           gtk::main_iteration_do(blocking);
+          if let Some(source) = resume_timer {
+            source.destroy();
+          }
         };
+
+        if exiting {
+          for window in window_target.p.app.windows() {
+            unsafe { window.destroy() };
+          }
+        }
+        // Let GTK process pending destroy requests and emit their `destroy` signals.
+        while gtk::events_pending() {
+          gtk::main_iteration_do(false);
+        }
+        while let Ok(event) = events.try_recv() {
+          if !matches!(event, Event::LoopDestroyed) {
+            callback(event, window_target, &mut control_flow);
+          }
+        }
+        callback(Event::LoopDestroyed, window_target, &mut control_flow);
         exit_code
       })
       .unwrap_or(1)
@@ -972,6 +1302,7 @@ impl<T: 'static> EventLoop<T> {
   pub fn create_proxy(&self) -> EventLoopProxy<T> {
     EventLoopProxy {
       user_event_tx: self.user_event_tx.clone(),
+      wake_up: Arc::downgrade(&self.wake_up),
     }
   }
 }
@@ -980,12 +1311,14 @@ impl<T: 'static> EventLoop<T> {
 #[derive(Debug)]
 pub struct EventLoopProxy<T: 'static> {
   user_event_tx: glib::Sender<T>,
+  wake_up: Weak<AtomicBool>,
 }
 
 impl<T: 'static> Clone for EventLoopProxy<T> {
   fn clone(&self) -> Self {
     Self {
       user_event_tx: self.user_event_tx.clone(),
+      wake_up: self.wake_up.clone(),
     }
   }
 }
@@ -1002,6 +1335,54 @@ impl<T: 'static> EventLoopProxy<T> {
       .send(event)
       .map_err(|SendError(error)| EventLoopClosed(error))
   }
+
+  pub fn wake_up(&self) -> Result<(), EventLoopClosed<()>> {
+    let wake_up = self.wake_up.upgrade().ok_or(EventLoopClosed(()))?;
+    // Wake-ups until the next iteration are coalesced
+    if !wake_up.swap(true, Ordering::AcqRel) {
+      MainContext::default().wakeup();
+    }
+    Ok(())
+  }
+}
+
+/// Grabs the pointer of the default seat for `window`, or releases it.
+fn grab_pointer(window: &gtk::Window, grab: bool) {
+  let seat = match window.display().default_seat() {
+    Some(seat) => seat,
+    None => return,
+  };
+  if !grab {
+    seat.ungrab();
+  } else if let Some(gdk_window) = window.window() {
+    let status = seat.grab(
+      &gdk_window,
+      gdk::SeatCapabilities::POINTER,
+      true,
+      None,
+      None,
+      None,
+    );
+    if status != gdk::GrabStatus::Success {
+      log::warn!("Failed to grab the cursor: {:?}", status);
+    }
+  }
+}
+
+/// Creates the region of the union of `rects`, given as `(x, y, width, height)`.
+fn cairo_region(rects: Option<Vec<(i32, i32, i32, i32)>>) -> Option<cairo::Region> {
+  rects.map(|rects| {
+    let rects: Vec<_> = rects
+      .into_iter()
+      .map(|(x, y, width, height)| cairo::RectangleInt {
+        x,
+        y,
+        width,
+        height,
+      })
+      .collect();
+    cairo::Region::create_rectangles(&rects)
+  })
 }
 
 fn assert_is_main_thread(suggested_method: &str) {
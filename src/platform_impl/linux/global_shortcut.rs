@@ -7,7 +7,7 @@ use crate::{
 };
 use crossbeam_channel::{self as channel, Receiver, Sender, TryRecvError};
 use std::{
-  collections::HashMap,
+  collections::{HashMap, HashSet},
   ptr,
   sync::{Arc, Mutex},
 };
@@ -52,22 +52,39 @@ impl ShortcutManager {
         let mut supported_rtrn: i32 = std::mem::MaybeUninit::uninit().assume_init();
         (xlib.XkbSetDetectableAutoRepeat)(display, 1, &mut supported_rtrn);
 
-        (xlib.XSelectInput)(display, root, xlib::KeyReleaseMask);
+        (xlib.XSelectInput)(display, root, xlib::KeyPressMask | xlib::KeyReleaseMask);
         #[allow(clippy::uninit_assumed_init)]
         let mut event: xlib::XEvent = std::mem::MaybeUninit::uninit().assume_init();
+        // The held shortcuts that report their release, the auto-repeated presses are dropped
+        let mut pressed = HashSet::new();
 
         loop {
           let event_loop_channel = event_loop_channel.clone();
           if (xlib.XPending)(display) > 0 {
             (xlib.XNextEvent)(display, &mut event);
-            if let xlib::KeyRelease = event.get_type() {
+            let event_type = event.get_type();
+            if event_type == xlib::KeyPress || event_type == xlib::KeyRelease {
               let keycode = event.key.keycode;
               let modifiers = event.key.state;
-              if let Some(hotkey_id) = hotkey_map.lock().unwrap().get(&(keycode as i32, modifiers))
+              let listener_id = (keycode as i32, modifiers);
+              if let Some(&(hotkey_id, report_release)) =
+                hotkey_map.lock().unwrap().get(&listener_id)
               {
-                event_loop_channel
-                  .send((window_id, WindowRequest::GlobalHotKey(*hotkey_id as u16)))
-                  .unwrap();
+                // Shortcuts that don't report their release trigger on release, as they always
+                // did, the others on press
+                let request = match (event_type, report_release) {
+                  (xlib::KeyPress, true) if pressed.insert(listener_id) => {
+                    Some(WindowRequest::GlobalHotKey(hotkey_id as u16))
+                  }
+                  (xlib::KeyRelease, true) if pressed.remove(&listener_id) => {
+                    Some(WindowRequest::GlobalHotKeyReleased(hotkey_id as u16))
+                  }
+                  (xlib::KeyRelease, false) => Some(WindowRequest::GlobalHotKey(hotkey_id as u16)),
+                  _ => None,
+                };
+                if let Some(request) = request {
+                  event_loop_channel.send((window_id, request)).unwrap();
+                }
               }
             }
           }
@@ -105,6 +122,7 @@ impl ShortcutManager {
               }
             }
             Ok(HotkeyMessage::UnregisterHotkey(id)) => {
+              pressed.remove(&id);
               let result = (xlib.XUngrabKey)(display, id.0, id.1, root);
               if result == 0 {
                 if let Err(err) = thread_sender
@@ -153,6 +171,7 @@ impl ShortcutManager {
   pub(crate) fn register(
     &mut self,
     accelerator: Accelerator,
+    report_release: bool,
   ) -> Result<RootGlobalShortcut, ShortcutManagerError> {
     let keycode = get_x11_scancode_from_hotkey(accelerator.key);
 
@@ -188,7 +207,7 @@ impl ShortcutManager {
             .shortcuts
             .lock()
             .unwrap()
-            .insert(id, accelerator.clone().id().0 as u32);
+            .insert(id, (accelerator.clone().id().0 as u32, report_release));
           let shortcut = GlobalShortcut { accelerator };
           return Ok(RootGlobalShortcut(shortcut));
         }
@@ -206,14 +225,34 @@ impl ShortcutManager {
   }
 
   pub(crate) fn unregister_all(&mut self) -> Result<(), ShortcutManagerError> {
-    for (found_id, _) in self.shortcuts.lock().unwrap().iter() {
+    // Only the keys grabbed by this manager are ungrabbed, on its own X connection
+    let ids: Vec<ListenerId> = self
+      .shortcuts
+      .lock()
+      .unwrap()
+      .drain()
+      .map(|(id, _)| id)
+      .collect();
+    let mut result = Ok(());
+    for id in ids {
       self
         .method_sender
-        .send(HotkeyMessage::UnregisterHotkey(*found_id))
+        .send(HotkeyMessage::UnregisterHotkey(id))
         .map_err(|_| ShortcutManagerError::InvalidAccelerator("Channel error".into()))?;
+      // Every request is answered, the answers must be read so that the next `register` gets
+      // its own
+      let unregistered = match self.method_receiver.recv() {
+        Ok(HotkeyMessage::UnregisterHotkeyResult(unregistered)) => unregistered,
+        Err(err) => Err(ShortcutManagerError::InvalidAccelerator(err.to_string())),
+        _ => Err(ShortcutManagerError::InvalidAccelerator(
+          "Unknown error".into(),
+        )),
+      };
+      if result.is_ok() {
+        result = unregistered;
+      }
     }
-    self.shortcuts = ListenerMap::default();
-    Ok(())
+    result
   }
 
   pub(crate) fn unregister(
@@ -221,7 +260,7 @@ impl ShortcutManager {
     shortcut: RootGlobalShortcut,
   ) -> Result<(), ShortcutManagerError> {
     let mut found_id = (-1, 0);
-    for (id, shortcut_id) in self.shortcuts.lock().unwrap().iter() {
+    for (id, (shortcut_id, _)) in self.shortcuts.lock().unwrap().iter() {
       if *shortcut_id == shortcut.0.id().0 as u32 {
         found_id = *id;
         break;
@@ -265,7 +304,8 @@ pub struct GlobalShortcut {
   pub(crate) accelerator: Accelerator,
 }
 type ListenerId = (i32, u32);
-type ListenerMap = Arc<Mutex<HashMap<ListenerId, u32>>>;
+/// The accelerator id of each grabbed key, and whether it reports its release.
+type ListenerMap = Arc<Mutex<HashMap<ListenerId, (u32, bool)>>>;
 
 impl GlobalShortcut {
   pub fn id(&self) -> AcceleratorId {
@@ -167,30 +167,32 @@ pub(crate) fn make_key_event(
   // a keycode (scancode in Windows) is a code that refers to a physical keyboard key.
   let scancode = key.hardware_keycode();
   // a keyval (keysym in X) is a "logical" key name, such as GDK_Enter, GDK_a, GDK_space, etc.
-  let keyval_without_modifiers = key.keyval();
-  let keyval_with_modifiers =
-    hardware_keycode_to_keyval(scancode).unwrap_or_else(|| keyval_without_modifiers.clone());
+  // GDK reports it with the active layout and modifiers applied.
+  let keyval_with_modifiers = key.keyval();
+  let keyval_without_modifiers =
+    hardware_keycode_to_keyval(scancode).unwrap_or_else(|| keyval_with_modifiers.clone());
   // get unicode value, with and without modifiers
-  let text_without_modifiers = keyval_with_modifiers.to_unicode();
-  let text_with_modifiers = keyval_without_modifiers.to_unicode();
+  let text_with_modifiers = keyval_with_modifiers.to_unicode();
+  let text_without_modifiers = keyval_without_modifiers.to_unicode();
   // get physical key from the scancode (keycode)
   let physical_key = key_override.unwrap_or_else(|| KeyCode::from_scancode(scancode as u32));
 
   // extract key without modifier
-  let key_without_modifiers = raw_key_to_key(keyval_with_modifiers.clone()).unwrap_or_else(|| {
-    if let Some(key) = text_without_modifiers {
-      if key >= ' ' && key != '\x7f' {
-        Key::Character(insert_or_get_key_str(key.to_string()))
+  let key_without_modifiers =
+    raw_key_to_key(keyval_without_modifiers.clone()).unwrap_or_else(|| {
+      if let Some(key) = text_without_modifiers {
+        if key >= ' ' && key != '\x7f' {
+          Key::Character(insert_or_get_key_str(key.to_string()))
+        } else {
+          Key::Unidentified(NativeKeyCode::Gtk(scancode))
+        }
       } else {
         Key::Unidentified(NativeKeyCode::Gtk(scancode))
       }
-    } else {
-      Key::Unidentified(NativeKeyCode::Gtk(scancode))
-    }
-  });
+    });
 
   // extract the logical key
-  let logical_key = raw_key_to_key(keyval_without_modifiers).unwrap_or_else(|| {
+  let logical_key = raw_key_to_key(keyval_with_modifiers).unwrap_or_else(|| {
     if let Some(key) = text_with_modifiers {
       if key >= ' ' && key != '\x7f' {
         Key::Character(insert_or_get_key_str(key.to_string()))
@@ -204,16 +206,20 @@ pub(crate) fn make_key_event(
 
   // make sure we have a valid key
   if !matches!(key_without_modifiers, Key::Unidentified(_)) {
-    let location = raw_key_to_location(keyval_with_modifiers);
+    let location = raw_key_to_location(keyval_without_modifiers);
     let text_with_all_modifiers =
-      text_without_modifiers.map(|text| insert_or_get_key_str(text.to_string()));
+      text_with_modifiers.map(|text| insert_or_get_key_str(text.to_string()));
     return Some(KeyEvent {
       location,
       logical_key,
       physical_key,
       repeat: is_repeat,
+      // only key presses produce text
+      text: match state {
+        ElementState::Pressed => text_with_all_modifiers,
+        ElementState::Released => None,
+      },
       state,
-      text: text_with_all_modifiers,
       platform_specific: KeyEventExtra {
         text_with_all_modifiers,
         key_without_modifiers,
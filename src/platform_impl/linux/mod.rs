@@ -18,6 +18,7 @@ compile_error!("`gtk-tray` and `ayatana-tray` Cargo features cannot be enabled a
 compile_error!("You must enable one of `gtk-tray` or `ayatana-tray` Cargo features");
 
 mod clipboard;
+mod device;
 mod event_loop;
 mod global_shortcut;
 mod keyboard;
@@ -30,6 +31,11 @@ mod monitor;
   not(all(feature = "gtk-tray", feature = "ayatana-tray"))
 ))]
 mod system_tray;
+#[cfg(feature = "wayland-protocols")]
+mod wayland;
+#[cfg(not(feature = "wayland-protocols"))]
+#[path = "wayland_stub.rs"]
+mod wayland;
 mod window;
 
 #[cfg(all(
@@ -48,7 +54,11 @@ pub use event_loop::{EventLoop, EventLoopProxy, EventLoopWindowTarget};
 pub use monitor::{MonitorHandle, VideoMode};
 pub use window::{hit_test, PlatformIcon, Window, WindowId};
 
-use crate::{event::DeviceId as RootDeviceId, keyboard::Key};
+use crate::{
+  event::DeviceId as RootDeviceId,
+  keyboard::Key,
+  platform::unix::{Anchor, Layer},
+};
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct KeyEventExtra {
@@ -59,17 +69,30 @@ pub struct KeyEventExtra {
 #[derive(Clone, Default)]
 pub struct PlatformSpecificWindowBuilderAttributes {
   pub skip_taskbar: bool,
+  /// The `(general, instance)` names used for `WM_CLASS` and the Wayland `app_id`.
+  pub name: Option<(String, String)>,
+  pub override_redirect: bool,
+  pub layer_shell: Option<(Layer, Anchor, i32)>,
+  pub scale_factor_override: Option<f64>,
+  pub header_bar: bool,
 }
 
 unsafe impl Send for PlatformSpecificWindowBuilderAttributes {}
 unsafe impl Sync for PlatformSpecificWindowBuilderAttributes {}
 
+#[non_exhaustive]
 #[derive(Debug, Clone)]
-pub struct OsError;
+pub enum OsError {
+  GtkInitError(String),
+  LayerShellError(&'static str),
+}
 
 impl std::fmt::Display for OsError {
-  fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-    Ok(())
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+    match self {
+      OsError::GtkInitError(e) => f.pad(&format!("failed to initialize gtk: {}", e)),
+      OsError::LayerShellError(e) => f.pad(&format!("failed to create layer surface: {}", e)),
+    }
   }
 }
 
@@ -82,5 +105,22 @@ impl DeviceId {
   }
 }
 
-// FIXME: currently we use a dummy device id, find if we can get device id from gtk
-pub(crate) const DEVICE_ID: RootDeviceId = RootDeviceId(DeviceId(0));
+/// Returns the id of the physical device that produced `event`.
+///
+/// On X11 this is the XInput2 device id. Other backends don't expose one, the address of the
+/// `GdkDevice` is used instead, which is stable for as long as the device is plugged in.
+pub(crate) fn device_id(event: &gdk::Event) -> RootDeviceId {
+  use glib::{translate::ToGlibPtr, ObjectExt};
+
+  let device = match event.source_device().or_else(|| event.device()) {
+    Some(device) => device,
+    None => return RootDeviceId(DeviceId(0)),
+  };
+  let device_ptr: *mut gdk_sys::GdkDevice = device.to_glib_none().0;
+  if device.display().type_().name() == "GdkX11Display" {
+    let id = unsafe { gdk_x11_sys::gdk_x11_device_get_id(device_ptr as *mut _) };
+    RootDeviceId(DeviceId(id as usize))
+  } else {
+    RootDeviceId(DeviceId(device_ptr as usize))
+  }
+}
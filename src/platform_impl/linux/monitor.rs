@@ -1,6 +1,8 @@
 // Copyright 2019-2021 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
+use std::{cell::RefCell, collections::VecDeque};
+
 use crate::{
   dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize},
   monitor::{MonitorHandle as RootMonitorHandle, VideoMode as RootVideoMode},
@@ -57,6 +59,51 @@ impl MonitorHandle {
   }
 }
 
+struct MonitorCache {
+  display: gdk::Display,
+  monitors: Option<VecDeque<MonitorHandle>>,
+}
+
+thread_local! {
+  // Monitors enumerated by the last `available_monitors` call, until one is added or removed.
+  // A `gdk::Monitor` reports its current geometry, so other display changes keep them valid.
+  static MONITORS: RefCell<Option<MonitorCache>> = RefCell::new(None);
+}
+
+pub fn available_monitors(display: &gdk::Display) -> VecDeque<MonitorHandle> {
+  MONITORS.with(|cache| {
+    let mut cache = cache.borrow_mut();
+    if cache
+      .as_ref()
+      .map_or(true, |cache| &cache.display != display)
+    {
+      let invalidate = |_: &gdk::Display, _: &gdk::Monitor| {
+        MONITORS.with(|cache| {
+          if let Some(cache) = &mut *cache.borrow_mut() {
+            cache.monitors = None;
+          }
+        })
+      };
+      display.connect_monitor_added(invalidate);
+      display.connect_monitor_removed(invalidate);
+      *cache = Some(MonitorCache {
+        display: display.clone(),
+        monitors: None,
+      });
+    }
+
+    let cache = cache.as_mut().unwrap();
+    cache
+      .monitors
+      .get_or_insert_with(|| {
+        (0..display.n_monitors())
+          .map(|i| MonitorHandle::new(display, i))
+          .collect()
+      })
+      .clone()
+  })
+}
+
 unsafe impl Send for MonitorHandle {}
 unsafe impl Sync for MonitorHandle {}
 
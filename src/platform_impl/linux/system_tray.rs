@@ -2,11 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-  error::OsError, event_loop::EventLoopWindowTarget, system_tray::SystemTray as RootSystemTray,
+  error::OsError, event::TrayId, event_loop::EventLoopWindowTarget,
+  system_tray::SystemTray as RootSystemTray,
 };
 
 use glib::Sender;
-use std::path::PathBuf;
+use std::{
+  path::PathBuf,
+  sync::atomic::{AtomicUsize, Ordering},
+};
 
 use gtk::{prelude::WidgetExt, AccelGroup};
 #[cfg(feature = "gtk-tray")]
@@ -17,55 +21,86 @@ use libayatana_appindicator::{AppIndicator, AppIndicatorStatus};
 use super::{menu::Menu, window::WindowRequest, WindowId};
 
 pub struct SystemTrayBuilder {
+  pub(crate) id: TrayId,
+  icon: PathBuf,
   tray_menu: Option<Menu>,
-  app_indicator: AppIndicator,
 }
 
 impl SystemTrayBuilder {
   #[inline]
   pub fn new(icon: PathBuf, tray_menu: Option<Menu>) -> Self {
-    let path = icon.parent().expect("Invalid icon");
-    let app_indicator = AppIndicator::with_path(
-      "tao application",
-      &icon.to_string_lossy(),
-      &path.to_string_lossy(),
-    );
-
     Self {
+      id: TrayId::EMPTY,
+      icon,
       tray_menu,
-      app_indicator,
     }
   }
 
   #[inline]
   pub fn build<T: 'static>(
-    mut self,
+    self,
     window_target: &EventLoopWindowTarget<T>,
   ) -> Result<RootSystemTray, OsError> {
-    let sender = window_target.p.window_requests_tx.clone();
+    // Every indicator needs its own name, otherwise they replace each other on the status bar.
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let indicator_name = format!(
+      "tao application {}",
+      COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+
+    let path = self.icon.parent().expect("Invalid icon");
+    let mut app_indicator = AppIndicator::with_path(
+      &indicator_name,
+      &self.icon.to_string_lossy(),
+      &path.to_string_lossy(),
+    );
+
+    // Menu items are only tied to a window, tag the clicks on this tray's menu with its id.
+    let (sender, menu_requests) = glib::MainContext::channel(glib::Priority::default());
+    let window_requests_tx = window_target.p.window_requests_tx.clone();
+    let tray_id = self.id;
+    menu_requests.attach(
+      Some(&glib::MainContext::default()),
+      move |(window_id, request)| {
+        let request = match request {
+          WindowRequest::Menu((None, Some(menu_id))) => WindowRequest::TrayMenu((tray_id, menu_id)),
+          request => request,
+        };
+        if let Err(e) = window_requests_tx.send((window_id, request)) {
+          log::warn!("Failed to send tray menu request to event loop: {}", e);
+        }
+        glib::Continue(true)
+      },
+    );
 
     if let Some(tray_menu) = self.tray_menu.clone() {
       let menu = &mut tray_menu.into_gtkmenu(&sender, &AccelGroup::new(), WindowId::dummy());
 
-      self.app_indicator.set_menu(menu);
+      app_indicator.set_menu(menu);
       menu.show_all();
     }
 
-    self.app_indicator.set_status(AppIndicatorStatus::Active);
+    app_indicator.set_status(AppIndicatorStatus::Active);
 
     Ok(RootSystemTray(SystemTray {
-      app_indicator: self.app_indicator,
+      id: self.id,
+      app_indicator,
       sender,
     }))
   }
 }
 
 pub struct SystemTray {
+  id: TrayId,
   app_indicator: AppIndicator,
   sender: Sender<(WindowId, WindowRequest)>,
 }
 
 impl SystemTray {
+  pub fn id(&self) -> TrayId {
+    self.id
+  }
+
   pub fn set_icon(&mut self, icon: PathBuf) {
     let path = icon.parent().expect("Invalid icon");
     self
@@ -74,6 +109,18 @@ impl SystemTray {
     self.app_indicator.set_icon(&icon.to_string_lossy())
   }
 
+  pub fn set_title(&mut self, title: &str) {
+    self.app_indicator.set_label(title, "");
+  }
+
+  pub fn set_visible(&mut self, visible: bool) {
+    self.app_indicator.set_status(if visible {
+      AppIndicatorStatus::Active
+    } else {
+      AppIndicatorStatus::Passive
+    });
+  }
+
   pub fn set_menu(&mut self, tray_menu: &Menu) {
     let mut menu =
       tray_menu
@@ -0,0 +1,997 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Wayland protocols GTK 3 doesn't bind, used through `libwayland-client`.
+//!
+//! `libwayland-client` is already loaded by GDK on Wayland, so its functions are looked up at
+//! runtime like the other optional system libraries. The protocol interfaces are defined here as
+//! they aren't part of `libwayland-client`, they're checked against the protocol XML files by the
+//! tests at the end of this file. The module is only built with the `wayland-protocols` feature,
+//! `wayland_stub.rs` replaces it otherwise.
+
+use std::{
+  cell::RefCell,
+  ffi::CStr,
+  mem,
+  os::raw::{c_char, c_int, c_void},
+  ptr,
+  rc::Rc,
+};
+
+use glib::translate::ToGlibPtr;
+use gtk::prelude::*;
+
+macro_rules! c_str {
+  ($s:expr) => {
+    concat!($s, "\0").as_ptr() as *const c_char
+  };
+}
+
+macro_rules! message {
+  ($name:expr, $signature:expr, $types:expr) => {
+    WlMessage {
+      name: c_str!($name),
+      signature: c_str!($signature),
+      types: $types.as_ptr(),
+    }
+  };
+}
+
+macro_rules! interface {
+  ($name:expr, $version:expr, $methods:expr, $events:expr) => {
+    WlInterface {
+      name: c_str!($name),
+      version: $version,
+      method_count: $methods.len() as c_int,
+      methods: $methods.as_ptr(),
+      event_count: $events.len() as c_int,
+      events: $events.as_ptr(),
+    }
+  };
+}
+
+#[repr(C)]
+struct WlProxy {
+  _private: [u8; 0],
+}
+
+#[repr(C)]
+struct WlEventQueue {
+  _private: [u8; 0],
+}
+
+/// `struct wl_message`
+#[repr(C)]
+struct WlMessage {
+  name: *const c_char,
+  signature: *const c_char,
+  types: *const Interface,
+}
+
+/// `struct wl_interface`
+#[repr(C)]
+struct WlInterface {
+  name: *const c_char,
+  version: c_int,
+  method_count: c_int,
+  methods: *const WlMessage,
+  event_count: c_int,
+  events: *const WlMessage,
+}
+
+/// The interface of a `new_id` argument in `WlMessage::types`, null for other arguments.
+#[repr(transparent)]
+struct Interface(*const WlInterface);
+
+// The interfaces are immutable statics
+unsafe impl Sync for WlMessage {}
+unsafe impl Sync for WlInterface {}
+unsafe impl Sync for Interface {}
+
+/// The types of messages without `new_id` arguments.
+static NO_INTERFACES: [Interface; 6] = [
+  Interface(ptr::null()),
+  Interface(ptr::null()),
+  Interface(ptr::null()),
+  Interface(ptr::null()),
+  Interface(ptr::null()),
+  Interface(ptr::null()),
+];
+static NO_MESSAGES: [WlMessage; 0] = [];
+
+static WP_FRACTIONAL_SCALE_MANAGER_V1_REQUESTS: [WlMessage; 2] = [
+  message!("destroy", "", NO_INTERFACES),
+  message!("get_fractional_scale", "no", GET_FRACTIONAL_SCALE_TYPES),
+];
+static GET_FRACTIONAL_SCALE_TYPES: [Interface; 2] = [
+  Interface(&WP_FRACTIONAL_SCALE_V1 as *const _),
+  Interface(ptr::null()),
+];
+static WP_FRACTIONAL_SCALE_MANAGER_V1: WlInterface = interface!(
+  "wp_fractional_scale_manager_v1",
+  1,
+  WP_FRACTIONAL_SCALE_MANAGER_V1_REQUESTS,
+  NO_MESSAGES
+);
+
+static WP_FRACTIONAL_SCALE_V1_REQUESTS: [WlMessage; 1] = [message!("destroy", "", NO_INTERFACES)];
+static WP_FRACTIONAL_SCALE_V1_EVENTS: [WlMessage; 1] =
+  [message!("preferred_scale", "u", NO_INTERFACES)];
+static WP_FRACTIONAL_SCALE_V1: WlInterface = interface!(
+  "wp_fractional_scale_v1",
+  1,
+  WP_FRACTIONAL_SCALE_V1_REQUESTS,
+  WP_FRACTIONAL_SCALE_V1_EVENTS
+);
+
+static ZWP_POINTER_CONSTRAINTS_V1_REQUESTS: [WlMessage; 3] = [
+  message!("destroy", "", NO_INTERFACES),
+  message!("lock_pointer", "noo?ou", LOCK_POINTER_TYPES),
+  message!("confine_pointer", "noo?ou", CONFINE_POINTER_TYPES),
+];
+static LOCK_POINTER_TYPES: [Interface; 5] = [
+  Interface(&ZWP_LOCKED_POINTER_V1 as *const _),
+  Interface(ptr::null()),
+  Interface(ptr::null()),
+  Interface(ptr::null()),
+  Interface(ptr::null()),
+];
+static CONFINE_POINTER_TYPES: [Interface; 5] = [
+  Interface(&ZWP_CONFINED_POINTER_V1 as *const _),
+  Interface(ptr::null()),
+  Interface(ptr::null()),
+  Interface(ptr::null()),
+  Interface(ptr::null()),
+];
+static ZWP_POINTER_CONSTRAINTS_V1: WlInterface = interface!(
+  "zwp_pointer_constraints_v1",
+  1,
+  ZWP_POINTER_CONSTRAINTS_V1_REQUESTS,
+  NO_MESSAGES
+);
+
+static ZWP_LOCKED_POINTER_V1_REQUESTS: [WlMessage; 3] = [
+  message!("destroy", "", NO_INTERFACES),
+  message!("set_cursor_position_hint", "ff", NO_INTERFACES),
+  message!("set_region", "?o", NO_INTERFACES),
+];
+static ZWP_LOCKED_POINTER_V1_EVENTS: [WlMessage; 2] = [
+  message!("locked", "", NO_INTERFACES),
+  message!("unlocked", "", NO_INTERFACES),
+];
+static ZWP_LOCKED_POINTER_V1: WlInterface = interface!(
+  "zwp_locked_pointer_v1",
+  1,
+  ZWP_LOCKED_POINTER_V1_REQUESTS,
+  ZWP_LOCKED_POINTER_V1_EVENTS
+);
+
+static ZWP_CONFINED_POINTER_V1_REQUESTS: [WlMessage; 2] = [
+  message!("destroy", "", NO_INTERFACES),
+  message!("set_region", "?o", NO_INTERFACES),
+];
+static ZWP_CONFINED_POINTER_V1_EVENTS: [WlMessage; 2] = [
+  message!("confined", "", NO_INTERFACES),
+  message!("unconfined", "", NO_INTERFACES),
+];
+static ZWP_CONFINED_POINTER_V1: WlInterface = interface!(
+  "zwp_confined_pointer_v1",
+  1,
+  ZWP_CONFINED_POINTER_V1_REQUESTS,
+  ZWP_CONFINED_POINTER_V1_EVENTS
+);
+
+static ZWP_RELATIVE_POINTER_MANAGER_V1_REQUESTS: [WlMessage; 2] = [
+  message!("destroy", "", NO_INTERFACES),
+  message!("get_relative_pointer", "no", GET_RELATIVE_POINTER_TYPES),
+];
+static GET_RELATIVE_POINTER_TYPES: [Interface; 2] = [
+  Interface(&ZWP_RELATIVE_POINTER_V1 as *const _),
+  Interface(ptr::null()),
+];
+static ZWP_RELATIVE_POINTER_MANAGER_V1: WlInterface = interface!(
+  "zwp_relative_pointer_manager_v1",
+  1,
+  ZWP_RELATIVE_POINTER_MANAGER_V1_REQUESTS,
+  NO_MESSAGES
+);
+
+static ZWP_RELATIVE_POINTER_V1_REQUESTS: [WlMessage; 1] = [message!("destroy", "", NO_INTERFACES)];
+static ZWP_RELATIVE_POINTER_V1_EVENTS: [WlMessage; 1] =
+  [message!("relative_motion", "uuffff", NO_INTERFACES)];
+static ZWP_RELATIVE_POINTER_V1: WlInterface = interface!(
+  "zwp_relative_pointer_v1",
+  1,
+  ZWP_RELATIVE_POINTER_V1_REQUESTS,
+  ZWP_RELATIVE_POINTER_V1_EVENTS
+);
+
+// The opcodes of the requests sent, their index in the `methods` of their interface
+const WL_DISPLAY_GET_REGISTRY: u32 = 1;
+const WL_REGISTRY_BIND: u32 = 0;
+const WP_FRACTIONAL_SCALE_MANAGER_V1_GET_FRACTIONAL_SCALE: u32 = 1;
+const WP_FRACTIONAL_SCALE_V1_DESTROY: u32 = 0;
+const ZWP_POINTER_CONSTRAINTS_V1_LOCK_POINTER: u32 = 1;
+const ZWP_POINTER_CONSTRAINTS_V1_CONFINE_POINTER: u32 = 2;
+const ZWP_LOCKED_POINTER_V1_DESTROY: u32 = 0;
+const ZWP_CONFINED_POINTER_V1_DESTROY: u32 = 0;
+const ZWP_RELATIVE_POINTER_MANAGER_V1_GET_RELATIVE_POINTER: u32 = 1;
+const ZWP_RELATIVE_POINTER_V1_DESTROY: u32 = 0;
+
+/// The `persistent` value of `zwp_pointer_constraints_v1::lifetime`.
+const LIFETIME_PERSISTENT: u32 = 2;
+
+type Marshal = unsafe extern "C" fn(*mut WlProxy, u32, ...);
+type MarshalConstructor =
+  unsafe extern "C" fn(*mut WlProxy, u32, *const WlInterface, ...) -> *mut WlProxy;
+type MarshalConstructorVersioned =
+  unsafe extern "C" fn(*mut WlProxy, u32, *const WlInterface, u32, ...) -> *mut WlProxy;
+type AddListener = unsafe extern "C" fn(*mut WlProxy, *const c_void, *mut c_void) -> c_int;
+type Destroy = unsafe extern "C" fn(*mut WlProxy);
+type CreateWrapper = unsafe extern "C" fn(*mut WlProxy) -> *mut WlProxy;
+type SetQueue = unsafe extern "C" fn(*mut WlProxy, *mut WlEventQueue);
+type CreateQueue = unsafe extern "C" fn(*mut WlProxy) -> *mut WlEventQueue;
+type DestroyQueue = unsafe extern "C" fn(*mut WlEventQueue);
+type RoundtripQueue = unsafe extern "C" fn(*mut WlProxy, *mut WlEventQueue) -> c_int;
+type GetWlDisplay = unsafe extern "C" fn(*mut gdk_sys::GdkDisplay) -> *mut WlProxy;
+type GetWlSurface = unsafe extern "C" fn(*mut gdk_sys::GdkWindow) -> *mut WlProxy;
+type GetWlPointer = unsafe extern "C" fn(*mut gdk_sys::GdkDevice) -> *mut WlProxy;
+
+/// Looks up the symbol `name`, a nul-terminated string, as `T`, which must be a pointer type.
+unsafe fn symbol<T: Copy>(name: &[u8]) -> Option<T> {
+  let symbol = libc::dlsym(libc::RTLD_DEFAULT, name.as_ptr() as *const _);
+  if symbol.is_null() {
+    None
+  } else {
+    Some(mem::transmute_copy(&symbol))
+  }
+}
+
+/// The `libwayland-client` and GDK Wayland functions.
+struct Client {
+  marshal: Marshal,
+  marshal_constructor: MarshalConstructor,
+  marshal_constructor_versioned: MarshalConstructorVersioned,
+  add_listener: AddListener,
+  destroy: Destroy,
+  create_wrapper: CreateWrapper,
+  wrapper_destroy: Destroy,
+  set_queue: SetQueue,
+  create_queue: CreateQueue,
+  destroy_queue: DestroyQueue,
+  roundtrip_queue: RoundtripQueue,
+  registry_interface: *const WlInterface,
+  get_wl_display: GetWlDisplay,
+  get_wl_surface: GetWlSurface,
+  get_wl_pointer: GetWlPointer,
+}
+
+impl Client {
+  fn load() -> Option<Self> {
+    unsafe {
+      Some(Self {
+        marshal: symbol(b"wl_proxy_marshal\0")?,
+        marshal_constructor: symbol(b"wl_proxy_marshal_constructor\0")?,
+        marshal_constructor_versioned: symbol(b"wl_proxy_marshal_constructor_versioned\0")?,
+        add_listener: symbol(b"wl_proxy_add_listener\0")?,
+        destroy: symbol(b"wl_proxy_destroy\0")?,
+        create_wrapper: symbol(b"wl_proxy_create_wrapper\0")?,
+        wrapper_destroy: symbol(b"wl_proxy_wrapper_destroy\0")?,
+        set_queue: symbol(b"wl_proxy_set_queue\0")?,
+        create_queue: symbol(b"wl_display_create_queue\0")?,
+        destroy_queue: symbol(b"wl_event_queue_destroy\0")?,
+        roundtrip_queue: symbol(b"wl_display_roundtrip_queue\0")?,
+        registry_interface: symbol(b"wl_registry_interface\0")?,
+        get_wl_display: symbol(b"gdk_wayland_display_get_wl_display\0")?,
+        get_wl_surface: symbol(b"gdk_wayland_window_get_wl_surface\0")?,
+        get_wl_pointer: symbol(b"gdk_wayland_device_get_wl_pointer\0")?,
+      })
+    }
+  }
+
+  /// Sends the destructor request `opcode` of `proxy` and destroys it.
+  unsafe fn destroy_proxy(&self, proxy: *mut WlProxy, opcode: u32) {
+    (self.marshal)(proxy, opcode);
+    (self.destroy)(proxy);
+  }
+}
+
+/// The globals of a Wayland display used by tao, null when the compositor doesn't support them.
+pub(crate) struct Globals {
+  client: Client,
+  fractional_scale_manager: *mut WlProxy,
+  pointer_constraints: *mut WlProxy,
+  relative_pointer_manager: *mut WlProxy,
+}
+
+/// `struct wl_registry_listener`
+#[repr(C)]
+struct RegistryListener {
+  global: unsafe extern "C" fn(*mut c_void, *mut WlProxy, u32, *const c_char, u32),
+  global_remove: unsafe extern "C" fn(*mut c_void, *mut WlProxy, u32),
+}
+
+/// A global advertised by the registry: its name, interface and version.
+type Advertised = Vec<(u32, String, u32)>;
+
+unsafe extern "C" fn registry_global(
+  data: *mut c_void,
+  _: *mut WlProxy,
+  name: u32,
+  interface: *const c_char,
+  version: u32,
+) {
+  let advertised = &mut *(data as *mut Advertised);
+  let interface = CStr::from_ptr(interface).to_string_lossy().into_owned();
+  advertised.push((name, interface, version));
+}
+
+unsafe extern "C" fn registry_global_remove(_: *mut c_void, _: *mut WlProxy, _: u32) {}
+
+static REGISTRY_LISTENER: RegistryListener = RegistryListener {
+  global: registry_global,
+  global_remove: registry_global_remove,
+};
+
+impl Globals {
+  fn new(display: &gdk::Display) -> Option<Self> {
+    let client = Client::load()?;
+    unsafe {
+      let wl_display = (client.get_wl_display)(display.to_glib_none().0);
+      if wl_display.is_null() {
+        return None;
+      }
+
+      // The registry is read on a private queue, so GDK doesn't dispatch its events
+      let queue = (client.create_queue)(wl_display);
+      let wrapper = (client.create_wrapper)(wl_display);
+      (client.set_queue)(wrapper, queue);
+      let registry = (client.marshal_constructor)(
+        wrapper,
+        WL_DISPLAY_GET_REGISTRY,
+        client.registry_interface,
+        ptr::null_mut::<c_void>(),
+      );
+      (client.wrapper_destroy)(wrapper);
+
+      let mut advertised = Advertised::new();
+      (client.add_listener)(
+        registry,
+        &REGISTRY_LISTENER as *const _ as *const c_void,
+        &mut advertised as *mut _ as *mut c_void,
+      );
+      (client.roundtrip_queue)(wl_display, queue);
+
+      let bind = |interface: &'static WlInterface| {
+        let name = CStr::from_ptr(interface.name).to_str().unwrap_or_default();
+        advertised
+          .iter()
+          .find(|(_, advertised, _)| advertised == name)
+          .map_or(ptr::null_mut(), |&(global, _, version)| {
+            let version = version.min(interface.version as u32);
+            let proxy = (client.marshal_constructor_versioned)(
+              registry,
+              WL_REGISTRY_BIND,
+              interface,
+              version,
+              global,
+              interface.name,
+              version,
+              ptr::null_mut::<c_void>(),
+            );
+            // The objects created from the global use its queue, the one GDK dispatches
+            (client.set_queue)(proxy, ptr::null_mut());
+            proxy
+          })
+      };
+      let fractional_scale_manager = bind(&WP_FRACTIONAL_SCALE_MANAGER_V1);
+      let pointer_constraints = bind(&ZWP_POINTER_CONSTRAINTS_V1);
+      let relative_pointer_manager = bind(&ZWP_RELATIVE_POINTER_MANAGER_V1);
+
+      (client.destroy)(registry);
+      (client.destroy_queue)(queue);
+
+      Some(Self {
+        client,
+        fractional_scale_manager,
+        pointer_constraints,
+        relative_pointer_manager,
+      })
+    }
+  }
+}
+
+thread_local! {
+  // The globals of the last display they were requested for, `None` if it isn't a Wayland one.
+  static GLOBALS: RefCell<Option<(gdk::Display, Option<Rc<Globals>>)>> = RefCell::new(None);
+}
+
+/// Returns the globals of `display`, `None` if it isn't a Wayland display.
+pub(crate) fn globals(display: &gdk::Display) -> Option<Rc<Globals>> {
+  GLOBALS.with(|cache| {
+    let mut cache = cache.borrow_mut();
+    match &*cache {
+      Some((cached, globals)) if cached == display => globals.clone(),
+      _ => {
+        let globals = if display.type_().name() == "GdkWaylandDisplay" {
+          Globals::new(display).map(Rc::new)
+        } else {
+          None
+        };
+        *cache = Some((display.clone(), globals.clone()));
+        globals
+      }
+    }
+  })
+}
+
+/// `struct wp_fractional_scale_v1_listener`
+#[repr(C)]
+struct FractionalScaleListener {
+  preferred_scale: unsafe extern "C" fn(*mut c_void, *mut WlProxy, u32),
+}
+
+unsafe extern "C" fn fractional_scale_preferred_scale(
+  data: *mut c_void,
+  _: *mut WlProxy,
+  scale: u32,
+) {
+  let fractional_scale = &*(data as *const FractionalScale);
+  // The scale is sent as a numerator over 120
+  (fractional_scale.on_change)(scale as f64 / 120.0);
+}
+
+static FRACTIONAL_SCALE_LISTENER: FractionalScaleListener = FractionalScaleListener {
+  preferred_scale: fractional_scale_preferred_scale,
+};
+
+/// A `wp_fractional_scale_v1` object, receiving the preferred scale of a surface.
+struct FractionalScale {
+  globals: Rc<Globals>,
+  proxy: *mut WlProxy,
+  on_change: Box<dyn Fn(f64)>,
+}
+
+impl FractionalScale {
+  fn new(window: &gdk::Window, on_change: Box<dyn Fn(f64)>) -> Option<Box<Self>> {
+    let globals = globals(&window.display())?;
+    if globals.fractional_scale_manager.is_null() {
+      return None;
+    }
+    unsafe {
+      let surface = (globals.client.get_wl_surface)(window.to_glib_none().0);
+      if surface.is_null() {
+        return None;
+      }
+      let proxy = (globals.client.marshal_constructor)(
+        globals.fractional_scale_manager,
+        WP_FRACTIONAL_SCALE_MANAGER_V1_GET_FRACTIONAL_SCALE,
+        &WP_FRACTIONAL_SCALE_V1,
+        ptr::null_mut::<c_void>(),
+        surface,
+      );
+      if proxy.is_null() {
+        return None;
+      }
+      let fractional_scale = Box::new(Self {
+        globals: globals.clone(),
+        proxy,
+        on_change,
+      });
+      (globals.client.add_listener)(
+        proxy,
+        &FRACTIONAL_SCALE_LISTENER as *const _ as *const c_void,
+        &*fractional_scale as *const _ as *mut c_void,
+      );
+      Some(fractional_scale)
+    }
+  }
+}
+
+impl Drop for FractionalScale {
+  fn drop(&mut self) {
+    unsafe {
+      self
+        .globals
+        .client
+        .destroy_proxy(self.proxy, WP_FRACTIONAL_SCALE_V1_DESTROY)
+    };
+  }
+}
+
+const FRACTIONAL_SCALE_KEY: &str = "tao-wayland-fractional-scale";
+const FRACTIONAL_SCALE_OBJECT_KEY: &str = "tao-wayland-fractional-scale-object";
+
+/// Returns the preferred fractional scale of `window` sent by the compositor, if any.
+pub(crate) fn fractional_scale(window: &gtk::Window) -> Option<f64> {
+  unsafe {
+    window
+      .data::<f64>(FRACTIONAL_SCALE_KEY)
+      .map(|scale| *scale.as_ref())
+  }
+}
+
+/// Manages the Wayland objects tied to the surface of `window`, it must be called for every
+/// window.
+///
+/// The preferred fractional scale of `window` is tracked with `wp_fractional_scale_v1` when the
+/// compositor supports it, `on_scale_change` is called when the compositor changes it. The
+/// objects are destroyed when the window is unrealized.
+pub(crate) fn wire_up_surface<F: Fn(&gtk::Window) + 'static>(
+  window: &gtk::Window,
+  on_scale_change: F,
+) {
+  let on_scale_change = Rc::new(on_scale_change);
+  let attach = move |window: &gtk::Window| {
+    let gdk_window = match window.window() {
+      Some(gdk_window) => gdk_window,
+      None => return,
+    };
+    let weak_window = window.downgrade();
+    let on_scale_change = on_scale_change.clone();
+    let fractional_scale = FractionalScale::new(
+      &gdk_window,
+      Box::new(move |scale| {
+        if let Some(window) = weak_window.upgrade() {
+          unsafe { window.set_data(FRACTIONAL_SCALE_KEY, scale) };
+          on_scale_change(&window);
+        }
+      }),
+    );
+    if let Some(fractional_scale) = fractional_scale {
+      unsafe { window.set_data(FRACTIONAL_SCALE_OBJECT_KEY, fractional_scale) };
+    }
+  };
+
+  if window.is_realized() {
+    attach(window);
+  }
+  window.connect_realize(attach);
+  // The objects must be destroyed before the surface, which GDK destroys after this signal
+  window.connect_unrealize(|window| unsafe {
+    window.steal_data::<Box<FractionalScale>>(FRACTIONAL_SCALE_OBJECT_KEY);
+    window.steal_data::<ConstrainedPointer>(CONSTRAINED_POINTER_KEY);
+  });
+}
+
+/// Returns the `wl_pointer` of the pointer of the default seat of `display`.
+fn seat_pointer(globals: &Globals, display: &gdk::Display) -> Option<(gdk::Device, *mut WlProxy)> {
+  let device = display.default_seat()?.pointer()?;
+  let pointer = unsafe { (globals.client.get_wl_pointer)(device.to_glib_none().0) };
+  if pointer.is_null() {
+    None
+  } else {
+    Some((device, pointer))
+  }
+}
+
+/// How the pointer is constrained to a window, see `constrain_pointer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerConstraint {
+  /// The pointer can't leave the window, with `zwp_confined_pointer_v1`.
+  Confined,
+  /// The pointer doesn't move, with `zwp_locked_pointer_v1`.
+  Locked,
+}
+
+/// Returns whether the compositor of `display` supports the pointer-constraints protocol.
+pub(crate) fn supports_pointer_constraints(display: &gdk::Display) -> bool {
+  globals(display).map_or(false, |globals| !globals.pointer_constraints.is_null())
+}
+
+/// A `zwp_locked_pointer_v1` or `zwp_confined_pointer_v1` object.
+struct ConstrainedPointer {
+  globals: Rc<Globals>,
+  proxy: *mut WlProxy,
+  constraint: PointerConstraint,
+}
+
+impl Drop for ConstrainedPointer {
+  fn drop(&mut self) {
+    let opcode = match self.constraint {
+      PointerConstraint::Locked => ZWP_LOCKED_POINTER_V1_DESTROY,
+      PointerConstraint::Confined => ZWP_CONFINED_POINTER_V1_DESTROY,
+    };
+    unsafe { self.globals.client.destroy_proxy(self.proxy, opcode) };
+  }
+}
+
+const CONSTRAINED_POINTER_KEY: &str = "tao-wayland-constrained-pointer";
+
+/// Constrains the pointer of the default seat to `window`, replacing the previous constraint,
+/// or releases it.
+///
+/// The constraint is persistent: the compositor activates it whenever the pointer is over the
+/// window while it has the focus, until it's released or the window is unrealized, see
+/// `wire_up_surface`.
+pub(crate) fn constrain_pointer(window: &gtk::Window, constraint: Option<PointerConstraint>) {
+  unsafe {
+    window.steal_data::<ConstrainedPointer>(CONSTRAINED_POINTER_KEY);
+  }
+  let constraint = match constraint {
+    Some(constraint) => constraint,
+    None => return,
+  };
+  let globals = match globals(&window.display()) {
+    Some(globals) if !globals.pointer_constraints.is_null() => globals,
+    _ => return,
+  };
+  let (gdk_window, (_, pointer)) =
+    match (window.window(), seat_pointer(&globals, &window.display())) {
+      (Some(gdk_window), Some(pointer)) => (gdk_window, pointer),
+      _ => {
+        log::warn!(
+          "Failed to constrain the pointer, the window isn't realized or there's no pointer"
+        );
+        return;
+      }
+    };
+  unsafe {
+    let surface = (globals.client.get_wl_surface)(gdk_window.to_glib_none().0);
+    if surface.is_null() {
+      return;
+    }
+    let (opcode, interface): (u32, &'static WlInterface) = match constraint {
+      PointerConstraint::Locked => (
+        ZWP_POINTER_CONSTRAINTS_V1_LOCK_POINTER,
+        &ZWP_LOCKED_POINTER_V1,
+      ),
+      PointerConstraint::Confined => (
+        ZWP_POINTER_CONSTRAINTS_V1_CONFINE_POINTER,
+        &ZWP_CONFINED_POINTER_V1,
+      ),
+    };
+    // On the whole surface
+    let proxy = (globals.client.marshal_constructor)(
+      globals.pointer_constraints,
+      opcode,
+      interface,
+      ptr::null_mut::<c_void>(),
+      surface,
+      pointer,
+      ptr::null_mut::<WlProxy>(),
+      LIFETIME_PERSISTENT,
+    );
+    if proxy.is_null() {
+      return;
+    }
+    window.set_data(
+      CONSTRAINED_POINTER_KEY,
+      ConstrainedPointer {
+        globals: globals.clone(),
+        proxy,
+        constraint,
+      },
+    );
+  }
+}
+
+/// `struct zwp_relative_pointer_v1_listener`
+#[repr(C)]
+struct RelativePointerListener {
+  relative_motion: unsafe extern "C" fn(*mut c_void, *mut WlProxy, u32, u32, i32, i32, i32, i32),
+}
+
+unsafe extern "C" fn relative_pointer_relative_motion(
+  data: *mut c_void,
+  _: *mut WlProxy,
+  _utime_hi: u32,
+  _utime_lo: u32,
+  _dx: i32,
+  _dy: i32,
+  dx_unaccel: i32,
+  dy_unaccel: i32,
+) {
+  let relative_pointer = &*(data as *const RelativePointer);
+  // `wl_fixed_t` values, with 8 bits of fractional part
+  (relative_pointer.on_motion)(
+    &relative_pointer.device,
+    (dx_unaccel as f64 / 256.0, dy_unaccel as f64 / 256.0),
+  );
+}
+
+static RELATIVE_POINTER_LISTENER: RelativePointerListener = RelativePointerListener {
+  relative_motion: relative_pointer_relative_motion,
+};
+
+/// A `zwp_relative_pointer_v1` object, receiving the unaccelerated motion of the pointer of the
+/// default seat, even while it's locked.
+pub(crate) struct RelativePointer {
+  globals: Rc<Globals>,
+  proxy: *mut WlProxy,
+  device: gdk::Device,
+  on_motion: Box<dyn Fn(&gdk::Device, (f64, f64))>,
+}
+
+impl RelativePointer {
+  /// Returns `None` if `display` isn't a Wayland display or its compositor doesn't support the
+  /// relative-pointer protocol.
+  pub fn new(
+    display: &gdk::Display,
+    on_motion: Box<dyn Fn(&gdk::Device, (f64, f64))>,
+  ) -> Option<Box<Self>> {
+    let globals = globals(display)?;
+    if globals.relative_pointer_manager.is_null() {
+      return None;
+    }
+    let (device, pointer) = seat_pointer(&globals, display)?;
+    unsafe {
+      let proxy = (globals.client.marshal_constructor)(
+        globals.relative_pointer_manager,
+        ZWP_RELATIVE_POINTER_MANAGER_V1_GET_RELATIVE_POINTER,
+        &ZWP_RELATIVE_POINTER_V1,
+        ptr::null_mut::<c_void>(),
+        pointer,
+      );
+      if proxy.is_null() {
+        return None;
+      }
+      let relative_pointer = Box::new(Self {
+        globals: globals.clone(),
+        proxy,
+        device,
+        on_motion,
+      });
+      (globals.client.add_listener)(
+        proxy,
+        &RELATIVE_POINTER_LISTENER as *const _ as *const c_void,
+        &*relative_pointer as *const _ as *mut c_void,
+      );
+      Some(relative_pointer)
+    }
+  }
+}
+
+impl Drop for RelativePointer {
+  fn drop(&mut self) {
+    unsafe {
+      self
+        .globals
+        .client
+        .destroy_proxy(self.proxy, ZWP_RELATIVE_POINTER_V1_DESTROY)
+    };
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A message as declared in the protocol XML: its name, signature and the interfaces of its
+  /// `new_id` arguments.
+  ///
+  /// The interfaces of `object` arguments are left null, `libwayland-client` only uses them to
+  /// check the objects of received events.
+  type Message = (&'static str, &'static str, &'static [Option<&'static str>]);
+
+  fn string(s: *const c_char) -> &'static str {
+    unsafe { CStr::from_ptr(s) }.to_str().unwrap()
+  }
+
+  /// The number of arguments of `signature`, without the `?` of nullable arguments and the
+  /// version it's available since.
+  fn argument_count(signature: &str) -> usize {
+    signature
+      .chars()
+      .filter(|c| !c.is_ascii_digit() && *c != '?')
+      .count()
+  }
+
+  fn check_messages(
+    interface: &str,
+    messages: *const WlMessage,
+    count: c_int,
+    expected: &[Message],
+  ) {
+    assert_eq!(
+      count as usize,
+      expected.len(),
+      "message count of {}",
+      interface
+    );
+    for (opcode, &(name, signature, types)) in expected.iter().enumerate() {
+      let message = unsafe { &*messages.add(opcode) };
+      assert_eq!(
+        string(message.name),
+        name,
+        "opcode {} of {}",
+        opcode,
+        interface
+      );
+      assert_eq!(
+        string(message.signature),
+        signature,
+        "{}::{}",
+        interface,
+        name
+      );
+      let count = argument_count(signature);
+      for argument in 0..count {
+        let actual = unsafe { &*message.types.add(argument) }.0;
+        let actual = unsafe { actual.as_ref() }.map(|interface| string(interface.name));
+        let expected = types.get(argument).copied().flatten();
+        assert_eq!(
+          actual, expected,
+          "argument {} of {}::{}",
+          argument, interface, name
+        );
+      }
+    }
+  }
+
+  fn check_interface(
+    interface: &WlInterface,
+    name: &str,
+    version: c_int,
+    requests: &[Message],
+    events: &[Message],
+  ) {
+    assert_eq!(string(interface.name), name);
+    assert_eq!(interface.version, version, "version of {}", name);
+    check_messages(name, interface.methods, interface.method_count, requests);
+    check_messages(name, interface.events, interface.event_count, events);
+  }
+
+  fn opcode(interface: &WlInterface, opcode: u32) -> &'static str {
+    assert!((opcode as c_int) < interface.method_count);
+    string(unsafe { &*interface.methods.add(opcode as usize) }.name)
+  }
+
+  #[test]
+  fn fractional_scale_interfaces() {
+    // fractional-scale-v1.xml
+    check_interface(
+      &WP_FRACTIONAL_SCALE_MANAGER_V1,
+      "wp_fractional_scale_manager_v1",
+      1,
+      &[
+        ("destroy", "", &[]),
+        (
+          "get_fractional_scale",
+          "no",
+          &[Some("wp_fractional_scale_v1"), None],
+        ),
+      ],
+      &[],
+    );
+    check_interface(
+      &WP_FRACTIONAL_SCALE_V1,
+      "wp_fractional_scale_v1",
+      1,
+      &[("destroy", "", &[])],
+      &[("preferred_scale", "u", &[None])],
+    );
+    assert_eq!(
+      opcode(
+        &WP_FRACTIONAL_SCALE_MANAGER_V1,
+        WP_FRACTIONAL_SCALE_MANAGER_V1_GET_FRACTIONAL_SCALE
+      ),
+      "get_fractional_scale"
+    );
+    assert_eq!(
+      opcode(&WP_FRACTIONAL_SCALE_V1, WP_FRACTIONAL_SCALE_V1_DESTROY),
+      "destroy"
+    );
+  }
+
+  #[test]
+  fn core_opcodes() {
+    // wayland.xml: `wl_display` requests `sync` and `get_registry`, `wl_registry` requests `bind`
+    assert_eq!(WL_DISPLAY_GET_REGISTRY, 1);
+    assert_eq!(WL_REGISTRY_BIND, 0);
+  }
+
+  #[test]
+  fn pointer_constraints_interfaces() {
+    // pointer-constraints-unstable-v1.xml
+    check_interface(
+      &ZWP_POINTER_CONSTRAINTS_V1,
+      "zwp_pointer_constraints_v1",
+      1,
+      &[
+        ("destroy", "", &[]),
+        (
+          "lock_pointer",
+          "noo?ou",
+          &[Some("zwp_locked_pointer_v1"), None, None, None, None],
+        ),
+        (
+          "confine_pointer",
+          "noo?ou",
+          &[Some("zwp_confined_pointer_v1"), None, None, None, None],
+        ),
+      ],
+      &[],
+    );
+    check_interface(
+      &ZWP_LOCKED_POINTER_V1,
+      "zwp_locked_pointer_v1",
+      1,
+      &[
+        ("destroy", "", &[]),
+        ("set_cursor_position_hint", "ff", &[None, None]),
+        ("set_region", "?o", &[None]),
+      ],
+      &[("locked", "", &[]), ("unlocked", "", &[])],
+    );
+    check_interface(
+      &ZWP_CONFINED_POINTER_V1,
+      "zwp_confined_pointer_v1",
+      1,
+      &[("destroy", "", &[]), ("set_region", "?o", &[None])],
+      &[("confined", "", &[]), ("unconfined", "", &[])],
+    );
+    assert_eq!(
+      opcode(
+        &ZWP_POINTER_CONSTRAINTS_V1,
+        ZWP_POINTER_CONSTRAINTS_V1_LOCK_POINTER
+      ),
+      "lock_pointer"
+    );
+    assert_eq!(
+      opcode(
+        &ZWP_POINTER_CONSTRAINTS_V1,
+        ZWP_POINTER_CONSTRAINTS_V1_CONFINE_POINTER
+      ),
+      "confine_pointer"
+    );
+    assert_eq!(
+      opcode(&ZWP_LOCKED_POINTER_V1, ZWP_LOCKED_POINTER_V1_DESTROY),
+      "destroy"
+    );
+    assert_eq!(
+      opcode(&ZWP_CONFINED_POINTER_V1, ZWP_CONFINED_POINTER_V1_DESTROY),
+      "destroy"
+    );
+    // `oneshot` is 1 and `persistent` is 2
+    assert_eq!(LIFETIME_PERSISTENT, 2);
+  }
+
+  #[test]
+  fn relative_pointer_interfaces() {
+    // relative-pointer-unstable-v1.xml
+    check_interface(
+      &ZWP_RELATIVE_POINTER_MANAGER_V1,
+      "zwp_relative_pointer_manager_v1",
+      1,
+      &[
+        ("destroy", "", &[]),
+        (
+          "get_relative_pointer",
+          "no",
+          &[Some("zwp_relative_pointer_v1"), None],
+        ),
+      ],
+      &[],
+    );
+    check_interface(
+      &ZWP_RELATIVE_POINTER_V1,
+      "zwp_relative_pointer_v1",
+      1,
+      &[("destroy", "", &[])],
+      &[(
+        "relative_motion",
+        "uuffff",
+        &[None, None, None, None, None, None],
+      )],
+    );
+    assert_eq!(
+      opcode(
+        &ZWP_RELATIVE_POINTER_MANAGER_V1,
+        ZWP_RELATIVE_POINTER_MANAGER_V1_GET_RELATIVE_POINTER
+      ),
+      "get_relative_pointer"
+    );
+    assert_eq!(
+      opcode(&ZWP_RELATIVE_POINTER_V1, ZWP_RELATIVE_POINTER_V1_DESTROY),
+      "destroy"
+    );
+  }
+
+  #[test]
+  fn no_interfaces_covers_every_argument() {
+    // The messages without `new_id` arguments share `NO_INTERFACES`
+    assert!(NO_INTERFACES.len() >= argument_count("uuffff"));
+    assert!(NO_INTERFACES.iter().all(|interface| interface.0.is_null()));
+  }
+}
@@ -0,0 +1,44 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replaces `wayland.rs` when the `wayland-protocols` feature is disabled: the protocols GTK 3
+//! doesn't bind aren't used, windows get the integer scale of GTK and the pointer can't be
+//! constrained on Wayland.
+
+use std::marker::PhantomData;
+
+/// How the pointer is constrained to a window, see `constrain_pointer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerConstraint {
+  Confined,
+  Locked,
+}
+
+pub(crate) fn fractional_scale(_window: &gtk::Window) -> Option<f64> {
+  None
+}
+
+pub(crate) fn wire_up_surface<F: Fn(&gtk::Window) + 'static>(
+  _window: &gtk::Window,
+  _on_scale_change: F,
+) {
+}
+
+pub(crate) fn supports_pointer_constraints(_display: &gdk::Display) -> bool {
+  false
+}
+
+pub(crate) fn constrain_pointer(_window: &gtk::Window, _constraint: Option<PointerConstraint>) {}
+
+pub(crate) struct RelativePointer {
+  _private: PhantomData<*mut ()>,
+}
+
+impl RelativePointer {
+  pub fn new(
+    _display: &gdk::Display,
+    _on_motion: Box<dyn Fn(&gdk::Device, (f64, f64))>,
+  ) -> Option<Box<Self>> {
+    None
+  }
+}
@@ -2,29 +2,40 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-  cell::RefCell,
+  cell::{Cell, RefCell},
   collections::VecDeque,
+  os::raw::c_int,
   rc::Rc,
   sync::atomic::{AtomicBool, AtomicI32, Ordering},
+  time::Duration,
 };
 
 use gdk::{WindowEdge, WindowState};
 use gdk_pixbuf::{Colorspace, Pixbuf};
+use glib::translate::IntoGlib;
 use gtk::{prelude::*, AccelGroup, Orientation};
 use raw_window_handle::{RawWindowHandle, XlibHandle};
 
 use crate::{
   dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Position, Size},
   error::{ExternalError, NotSupportedError, OsError as RootOsError},
-  icon::{BadIcon, Icon},
+  event::TrayId,
+  icon::{BadIcon, Icon, RgbaIcon},
   menu::{MenuId, MenuItem},
   monitor::MonitorHandle as RootMonitorHandle,
-  window::{CursorIcon, Fullscreen, UserAttentionType, WindowAttributes, BORDERLESS_RESIZE_INSET},
+  platform::unix::{Anchor, Layer},
+  window::{
+    CursorIcon, CustomCursor, Fullscreen, Insets, Rect, Theme, UserAttentionType, WindowAttributes,
+    WindowButtons, BORDERLESS_RESIZE_INSET,
+  },
 };
 
 use super::{
-  event_loop::EventLoopWindowTarget, menu, monitor::MonitorHandle,
-  PlatformSpecificWindowBuilderAttributes,
+  event_loop::EventLoopWindowTarget,
+  menu,
+  monitor::{self, MonitorHandle},
+  wayland::{self, PointerConstraint},
+  OsError, PlatformSpecificWindowBuilderAttributes,
 };
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -34,46 +45,84 @@ impl WindowId {
   pub fn dummy() -> Self {
     WindowId(u32::MAX)
   }
+
+  pub fn into_raw(self) -> u64 {
+    self.0 as u64
+  }
+
+  pub fn from_raw(raw: u64) -> Option<Self> {
+    u32::try_from(raw).ok().map(WindowId)
+  }
 }
 
 /// An icon used for the window titlebar, taskbar, etc.
 #[derive(Debug, Clone)]
 pub struct PlatformIcon {
+  /// The same icon in several sizes, GTK picks the best one.
+  images: Vec<IconImage>,
+}
+
+#[derive(Debug, Clone)]
+struct IconImage {
   raw: Vec<u8>,
   width: i32,
   height: i32,
   row_stride: i32,
 }
 
-impl From<PlatformIcon> for Pixbuf {
-  fn from(icon: PlatformIcon) -> Self {
+impl From<IconImage> for Pixbuf {
+  fn from(image: IconImage) -> Self {
     Pixbuf::from_mut_slice(
-      icon.raw,
+      image.raw,
       gdk_pixbuf::Colorspace::Rgb,
       true,
       8,
-      icon.width,
-      icon.height,
-      icon.row_stride,
+      image.width,
+      image.height,
+      image.row_stride,
     )
   }
 }
 
+impl IconImage {
+  fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Self {
+    let row_stride =
+      Pixbuf::calculate_rowstride(Colorspace::Rgb, true, 8, width as i32, height as i32);
+    Self {
+      raw: rgba,
+      width: width as i32,
+      height: height as i32,
+      row_stride,
+    }
+  }
+}
+
 impl PlatformIcon {
   /// Creates an `Icon` from 32bpp RGBA data.
   ///
   /// The length of `rgba` must be divisible by 4, and `width * height` must equal
   /// `rgba.len() / 4`. Otherwise, this will return a `BadIcon` error.
   pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, BadIcon> {
-    let row_stride =
-      Pixbuf::calculate_rowstride(Colorspace::Rgb, true, 8, width as i32, height as i32);
     Ok(Self {
-      raw: rgba,
-      width: width as i32,
-      height: height as i32,
-      row_stride,
+      images: vec![IconImage::from_rgba(rgba, width, height)],
     })
   }
+
+  pub fn from_rgba_sizes(sizes: Vec<(Vec<u8>, u32, u32)>) -> Result<Self, BadIcon> {
+    let images = sizes
+      .into_iter()
+      .map(|(rgba, width, height)| {
+        let icon = RgbaIcon::from_rgba(rgba, width, height)?;
+        Ok(IconImage::from_rgba(icon.rgba, icon.width, icon.height))
+      })
+      .collect::<Result<_, BadIcon>>()?;
+    Ok(Self { images })
+  }
+
+  /// The images to pass to `gtk_window_set_icon_list`.
+  pub(crate) fn into_pixbufs(self) -> Vec<Pixbuf> {
+    self.images.into_iter().map(Into::into).collect()
+  }
 }
 
 pub struct Window {
@@ -87,12 +136,21 @@ pub struct Window {
   pub(crate) accel_group: AccelGroup,
   // Gtk MenuBar allocation -- always available
   menu_bar: gtk::MenuBar,
-  scale_factor: Rc<AtomicI32>,
+  /// The client-side titlebar, see `with_header_bar`
+  header_bar: Option<gtk::HeaderBar>,
   position: Rc<(AtomicI32, AtomicI32)>,
   size: Rc<(AtomicI32, AtomicI32)>,
+  /// Size of the content area, see `content_size`
+  inner_size: Rc<(AtomicI32, AtomicI32)>,
+  content_insets: Rc<Cell<Insets>>,
   maximized: Rc<AtomicBool>,
   minimized: Rc<AtomicBool>,
   fullscreen: RefCell<Option<Fullscreen>>,
+  geometry_hints: Cell<GeometryHints>,
+  enabled_buttons: Rc<Cell<WindowButtons>>,
+  transparent: Rc<AtomicBool>,
+  /// Whether the window was created with a visual that supports transparency.
+  rgba_visual: bool,
 }
 
 impl Window {
@@ -113,11 +171,66 @@ impl Window {
     let accel_group = AccelGroup::new();
     window.add_accel_group(&accel_group);
 
+    // The layer surface must be set up before the window is realized
+    if let Some((layer, anchor, exclusive_zone)) = pl_attribs.layer_shell {
+      if let Err(e) = init_layer_shell(window.upcast_ref(), layer, anchor, exclusive_zone) {
+        // The window was added to the application, it would stay there without its `Window`
+        event_loop_window_target
+          .windows
+          .borrow_mut()
+          .remove(&window_id);
+        unsafe { window.destroy() };
+        return Err(os_error!(OsError::LayerShellError(e)));
+      }
+    }
+
+    // GDK already sets `WM_CLIENT_MACHINE` and `_NET_WM_PID` when realizing X11 toplevels. It
+    // leaves out the pid inside a sandbox on purpose, since it would be the one of another pid
+    // namespace there.
+
+    // Set WM_CLASS and app_id, both must be set before the window is realized
+    if let Some((general, instance)) = &pl_attribs.name {
+      #[allow(deprecated)] // There is no replacement to set the X11 `WM_CLASS`
+      window.set_wmclass(instance, general);
+
+      let app_id = general.clone();
+      window.connect_realize(move |window| {
+        if let Some(gdk_window) = window.window() {
+          set_wayland_application_id(&gdk_window, &app_id);
+        }
+      });
+    }
+
+    // Override-redirect must be set on the `GdkWindow` before it's mapped
+    if pl_attribs.override_redirect {
+      window.connect_realize(|window| {
+        if let Some(gdk_window) = window.window() {
+          gdk_window.set_override_redirect(true);
+        }
+      });
+    }
+
+    // The titlebar must be set before the window is realized
+    let header_bar = if pl_attribs.header_bar {
+      let header_bar = gtk::HeaderBar::new();
+      header_bar.set_show_close_button(true);
+      header_bar.show();
+      window.set_titlebar(Some(&header_bar));
+      Some(header_bar)
+    } else {
+      None
+    };
+
+    // Must be set before any logical <-> physical conversion, including the ones below
+    if let Some(scale_factor) = pl_attribs.scale_factor_override {
+      unsafe { window.set_data(SCALE_FACTOR_OVERRIDE_KEY, scale_factor) };
+    }
+
     // Set Width/Height & Resizable
-    let win_scale_factor = window.scale_factor();
+    let win_scale_factor = scale_factor(&window);
     let (width, height) = attributes
       .inner_size
-      .map(|size| size.to_logical::<f64>(win_scale_factor as f64).into())
+      .map(|size| size.to_logical::<f64>(win_scale_factor).into())
       .unwrap_or((800, 600));
     window.set_resizable(attributes.resizable);
     if attributes.resizable {
@@ -126,65 +239,68 @@ impl Window {
       window.set_size_request(width, height);
     }
 
-    // Set Min/Max Size
-    let geom_mask = (if attributes.min_inner_size.is_some() {
-      gdk::WindowHints::MIN_SIZE
-    } else {
-      gdk::WindowHints::empty()
-    }) | (if attributes.max_inner_size.is_some() {
-      gdk::WindowHints::MAX_SIZE
-    } else {
-      gdk::WindowHints::empty()
+    // Set Enabled Buttons, the WM functions are only known to GDK once the window is realized
+    let enabled_buttons = Rc::new(Cell::new(attributes.enabled_buttons));
+    window.set_deletable(attributes.enabled_buttons.contains(WindowButtons::CLOSE));
+    let enabled_buttons_clone = enabled_buttons.clone();
+    window.connect_realize(move |window| {
+      set_enabled_buttons(window, enabled_buttons_clone.get());
     });
-    let (min_width, min_height) = attributes
-      .min_inner_size
-      .map(|size| size.to_logical::<f64>(win_scale_factor as f64).into())
-      .unwrap_or_default();
-    let (max_width, max_height) = attributes
-      .max_inner_size
-      .map(|size| size.to_logical::<f64>(win_scale_factor as f64).into())
-      .unwrap_or_default();
-    let picky_none: Option<&gtk::Window> = None;
-    window.set_geometry_hints(
-      picky_none,
-      Some(&gdk::Geometry::new(
-        min_width,
-        min_height,
-        max_width,
-        max_height,
-        0,
-        0,
-        0,
-        0,
-        0f64,
-        0f64,
-        gdk::Gravity::Center,
-      )),
-      geom_mask,
-    );
+
+    // Set Min/Max Size and Aspect Ratio
+    let geometry_hints = GeometryHints {
+      min_size: attributes
+        .min_inner_size
+        .map(|size| size.to_logical::<i32>(win_scale_factor).into()),
+      max_size: attributes
+        .max_inner_size
+        .map(|size| size.to_logical::<i32>(win_scale_factor).into()),
+      aspect_ratio: attributes.aspect_ratio,
+    };
+    set_geometry_hints(&window, geometry_hints);
 
     // Set Position
     if let Some(position) = attributes.position {
-      let (x, y): (i32, i32) = position.to_logical::<i32>(win_scale_factor as f64).into();
+      let (x, y): (i32, i32) = position.to_logical::<i32>(win_scale_factor).into();
       window.move_(x, y);
     }
 
     // Set Transparent
+    let transparent = Rc::new(AtomicBool::new(false));
+    let mut rgba_visual = false;
     if attributes.transparent {
-      if let Some(screen) = window.screen() {
-        if let Some(visual) = screen.rgba_visual() {
-          window.set_visual(Some(&visual));
-        }
+      // The alpha channel is only used with a 32-bit ARGB visual, set before the window is
+      // realized, and is only composited against the desktop by a running compositor. Otherwise
+      // the cleared background would be painted black, so the window stays opaque.
+      let screen = window.screen();
+      let visual = screen
+        .as_ref()
+        .and_then(|screen| screen.rgba_visual())
+        .filter(|visual| visual.depth() == 32);
+      if let Some(visual) = visual {
+        window.set_visual(Some(&visual));
+        rgba_visual = true;
+      }
+      let composited = screen.map_or(false, |screen| screen.is_composited());
+      if rgba_visual && composited {
+        transparent.store(true, Ordering::Release);
+      } else if !rgba_visual {
+        log::warn!("No 32-bit ARGB visual is available, the window is opaque");
+      } else {
+        log::warn!("No compositor is running, the window is opaque");
       }
 
-      window.connect_draw(|_, cr| {
-        cr.set_source_rgba(0., 0., 0., 0.);
-        cr.set_operator(cairo::Operator::Source);
-        let _ = cr.paint();
-        cr.set_operator(cairo::Operator::Over);
+      let transparent_clone = transparent.clone();
+      window.connect_draw(move |_, cr| {
+        if transparent_clone.load(Ordering::Acquire) {
+          cr.set_source_rgba(0., 0., 0., 0.);
+          cr.set_operator(cairo::Operator::Source);
+          let _ = cr.paint();
+          cr.set_operator(cairo::Operator::Over);
+        }
         Inhibit(false)
       });
-      window.set_app_paintable(true);
+      window.set_app_paintable(transparent.load(Ordering::Acquire));
     }
 
     // We always create a box and allocate menubar, so if they set_menu after creation
@@ -217,11 +333,23 @@ impl Window {
 
     window.set_keep_above(attributes.always_on_top);
     if let Some(icon) = attributes.window_icon {
-      window.set_icon(Some(&icon.inner.into()));
+      window.set_icon_list(&icon.inner.into_pixbufs());
+    }
+
+    // Hand the startup notification token we were launched with to the compositor so the window
+    // is allowed to take focus.
+    if let Some(startup_id) = event_loop_window_target.startup_id.borrow_mut().take() {
+      window.set_startup_id(&startup_id);
     }
 
+    window.set_focus_on_map(attributes.focused);
     if attributes.visible {
       window.show_all();
+      if attributes.focused {
+        // Focus stealing prevention keeps windows presented without a valid timestamp in the
+        // background.
+        window.present_with_time(current_user_time(window.upcast_ref()));
+      }
     } else {
       window.hide();
     }
@@ -234,6 +362,24 @@ impl Window {
     let size: Rc<(AtomicI32, AtomicI32)> = Rc::new((w_size.0.into(), w_size.1.into()));
     let size_clone = size.clone();
 
+    let inner_size: Rc<(AtomicI32, AtomicI32)> = Rc::new((width.into(), height.into()));
+    let inner_size_clone = inner_size.clone();
+    let insets = Rc::new(Cell::new(Insets::default()));
+    let insets_clone = insets.clone();
+    window_box.connect_size_allocate(move |window_box, _| {
+      if let Some(window) = window_box.toplevel() {
+        if let Ok(window) = window.downcast::<gtk::Window>() {
+          if let Some((w, h)) = content_size(&window) {
+            inner_size_clone.0.store(w, Ordering::Release);
+            inner_size_clone.1.store(h, Ordering::Release);
+          }
+          if let Some(insets) = content_insets(&window) {
+            insets_clone.set(insets);
+          }
+        }
+      }
+    });
+
     window.connect_configure_event(move |_, event| {
       let (x, y) = event.position();
       position_clone.0.store(x, Ordering::Release);
@@ -259,17 +405,11 @@ impl Window {
       Inhibit(false)
     });
 
-    let scale_factor: Rc<AtomicI32> = Rc::new(win_scale_factor.into());
-    let scale_factor_clone = scale_factor.clone();
-    window.connect_scale_factor_notify(move |window| {
-      scale_factor_clone.store(window.scale_factor(), Ordering::Release);
-    });
-
     if let Err(e) = window_requests_tx.send((window_id, WindowRequest::WireUpEvents)) {
       log::warn!("Fail to send wire up events request: {}", e);
     }
 
-    if let Err(e) = window_requests_tx.send((window_id, WindowRequest::Redraw)) {
+    if let Err(e) = window_requests_tx.send((window_id, WindowRequest::Redraw(None))) {
       log::warn!("Fail to send redraw request: {}", e);
     }
 
@@ -279,12 +419,18 @@ impl Window {
       window_requests_tx,
       accel_group,
       menu_bar,
-      scale_factor,
+      header_bar,
       position,
       size,
+      inner_size,
+      content_insets: insets,
       maximized,
       minimized,
       fullscreen: RefCell::new(attributes.fullscreen),
+      geometry_hints: Cell::new(geometry_hints),
+      enabled_buttons,
+      transparent,
+      rgba_visual,
     };
 
     win.set_skip_taskbar(pl_attribs.skip_taskbar);
@@ -296,24 +442,53 @@ impl Window {
     self.window_id
   }
 
+  pub fn header_bar(&self) -> Option<&gtk::HeaderBar> {
+    self.header_bar.as_ref()
+  }
+
   pub fn scale_factor(&self) -> f64 {
-    self.scale_factor.load(Ordering::Acquire) as f64
+    scale_factor(&self.window)
   }
 
   pub fn request_redraw(&self) {
     if let Err(e) = self
       .window_requests_tx
-      .send((self.window_id, WindowRequest::Redraw))
+      .send((self.window_id, WindowRequest::Redraw(None)))
+    {
+      log::warn!("Fail to send redraw request: {}", e);
+    }
+  }
+
+  pub fn pre_present_notify(&self) {
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::PrePresentNotify))
     {
+      log::warn!("Fail to send pre present notify request: {}", e);
+    }
+  }
+
+  pub fn request_redraw_rect(&self, position: PhysicalPosition<i32>, size: PhysicalSize<u32>) {
+    let scale_factor = self.scale_factor();
+    let (x, y) = position.to_logical::<i32>(scale_factor).into();
+    let (width, height) = size.to_logical::<i32>(scale_factor).into();
+    if let Err(e) = self.window_requests_tx.send((
+      self.window_id,
+      WindowRequest::Redraw(Some((x, y, width, height))),
+    )) {
       log::warn!("Fail to send redraw request: {}", e);
     }
   }
 
+  pub fn redraw_rect(&self) -> Option<(PhysicalPosition<i32>, PhysicalSize<u32>)> {
+    None
+  }
+
   pub fn inner_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
     let (x, y) = &*self.position;
     Ok(
       LogicalPosition::new(x.load(Ordering::Acquire), y.load(Ordering::Acquire))
-        .to_physical(self.scale_factor.load(Ordering::Acquire) as f64),
+        .to_physical(self.scale_factor()),
     )
   }
 
@@ -321,7 +496,7 @@ impl Window {
     let (x, y) = &*self.position;
     Ok(
       LogicalPosition::new(x.load(Ordering::Acquire), y.load(Ordering::Acquire))
-        .to_physical(self.scale_factor.load(Ordering::Acquire) as f64),
+        .to_physical(self.scale_factor()),
     )
   }
 
@@ -340,13 +515,13 @@ impl Window {
   }
 
   pub fn inner_size(&self) -> PhysicalSize<u32> {
-    let (width, height) = &*self.size;
+    let (width, height) = &*self.inner_size;
 
     LogicalSize::new(
       width.load(Ordering::Acquire) as u32,
       height.load(Ordering::Acquire) as u32,
     )
-    .to_physical(self.scale_factor.load(Ordering::Acquire) as f64)
+    .to_physical(self.scale_factor())
   }
 
   pub fn set_inner_size<S: Into<Size>>(&self, size: S) {
@@ -360,6 +535,12 @@ impl Window {
     }
   }
 
+  pub fn request_inner_size<S: Into<Size>>(&self, size: S) -> Option<PhysicalSize<u32>> {
+    // The resize is applied by the event loop, and then by the window manager.
+    self.set_inner_size(size);
+    None
+  }
+
   pub fn outer_size(&self) -> PhysicalSize<u32> {
     let (width, height) = &*self.size;
 
@@ -367,31 +548,45 @@ impl Window {
       width.load(Ordering::Acquire) as u32,
       height.load(Ordering::Acquire) as u32,
     )
-    .to_physical(self.scale_factor.load(Ordering::Acquire) as f64)
+    .to_physical(self.scale_factor())
+  }
+
+  pub fn content_insets(&self) -> Insets {
+    self.content_insets.get()
   }
 
+  pub fn safe_area_insets(&self) -> Insets {
+    Insets::default()
+  }
+
+  pub fn set_orientation_lock(&self, _orientation: Option<crate::window::Orientation>) {}
+
+  pub fn set_soft_input_visible(&self, _visible: bool) {}
+
   pub fn set_min_inner_size<S: Into<Size>>(&self, min_size: Option<S>) {
-    if let Some(size) = min_size {
-      let (min_width, min_height) = size.into().to_logical::<i32>(self.scale_factor()).into();
-
-      if let Err(e) = self.window_requests_tx.send((
-        self.window_id,
-        WindowRequest::MinSize((min_width, min_height)),
-      )) {
-        log::warn!("Fail to send min size request: {}", e);
-      }
-    }
+    let mut hints = self.geometry_hints.get();
+    hints.min_size = min_size.map(|size| size.into().to_logical::<i32>(self.scale_factor()).into());
+    self.set_geometry_hints(hints);
   }
   pub fn set_max_inner_size<S: Into<Size>>(&self, max_size: Option<S>) {
-    if let Some(size) = max_size {
-      let (max_width, max_height) = size.into().to_logical::<i32>(self.scale_factor()).into();
-
-      if let Err(e) = self.window_requests_tx.send((
-        self.window_id,
-        WindowRequest::MaxSize((max_width, max_height)),
-      )) {
-        log::warn!("Fail to send max size request: {}", e);
-      }
+    let mut hints = self.geometry_hints.get();
+    hints.max_size = max_size.map(|size| size.into().to_logical::<i32>(self.scale_factor()).into());
+    self.set_geometry_hints(hints);
+  }
+
+  pub fn set_aspect_ratio(&self, aspect_ratio: Option<(u32, u32)>) {
+    let mut hints = self.geometry_hints.get();
+    hints.aspect_ratio = aspect_ratio.map(|(width, height)| width as f64 / height as f64);
+    self.set_geometry_hints(hints);
+  }
+
+  fn set_geometry_hints(&self, hints: GeometryHints) {
+    self.geometry_hints.set(hints);
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::GeometryHints(hints)))
+    {
+      log::warn!("Fail to send geometry hints request: {}", e);
     }
   }
 
@@ -404,6 +599,14 @@ impl Window {
     }
   }
 
+  pub fn title(&self) -> String {
+    self
+      .window
+      .title()
+      .map(|t| t.as_str().to_string())
+      .unwrap_or_default()
+  }
+
   pub fn set_menu(&self, menu: Option<menu::Menu>) {
     if let Err(e) = self.window_requests_tx.send((
       self.window_id,
@@ -422,6 +625,28 @@ impl Window {
     }
   }
 
+  pub fn set_visible_with_animation(&self, visible: bool, _animate: bool) {
+    self.set_visible(visible);
+  }
+
+  pub fn set_transparent(&self, transparent: bool) -> Result<(), ExternalError> {
+    // The visual can't be changed once the window is realized.
+    if transparent && !self.rgba_visual {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
+
+    self.transparent.store(transparent, Ordering::Release);
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::Transparent(transparent)))
+    {
+      log::warn!("Fail to send transparent request: {}", e);
+    }
+    Ok(())
+  }
+
+  pub fn set_blur(&self, _blur: bool) {}
+
   pub fn set_focus(&self) {
     if !self.minimized.load(Ordering::Acquire) && self.window.get_visible() {
       if let Err(e) = self
@@ -442,6 +667,16 @@ impl Window {
     }
   }
 
+  pub fn set_enabled_buttons(&self, buttons: WindowButtons) {
+    self.enabled_buttons.set(buttons);
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::EnabledButtons(buttons)))
+    {
+      log::warn!("Fail to send enabled buttons request: {}", e);
+    }
+  }
+
   pub fn set_minimized(&self, minimized: bool) {
     if let Err(e) = self
       .window_requests_tx
@@ -468,13 +703,67 @@ impl Window {
     self.window.is_resizable()
   }
 
+  pub fn enabled_buttons(&self) -> WindowButtons {
+    self.enabled_buttons.get()
+  }
+
   pub fn is_decorated(&self) -> bool {
     self.window.is_decorated()
   }
 
   #[inline]
   pub fn is_visible(&self) -> bool {
-    self.window.is_visible()
+    self.window.is_visible() && self.window.is_mapped() && !self.minimized.load(Ordering::Acquire)
+  }
+
+  pub fn theme(&self) -> Theme {
+    // The prelude can't export it, `gio::prelude::SettingsExt` has the same name
+    use gtk::traits::SettingsExt;
+
+    if let Some(settings) = gtk::Settings::default() {
+      let prefer_dark = settings.is_gtk_application_prefer_dark_theme();
+      let theme_name = settings.gtk_theme_name().map(|name| name.to_lowercase());
+      if prefer_dark || theme_name.map_or(false, |name| name.ends_with("-dark")) {
+        return Theme::Dark;
+      }
+    }
+    Theme::Light
+  }
+
+  pub fn set_cursor_hittest_region(&self, region: Option<Vec<Rect>>) -> Result<(), ExternalError> {
+    let region = self.logical_rects(region);
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::CursorHittestRegion(region)))
+    {
+      log::warn!("Fail to send cursor hittest region request: {}", e);
+    }
+    Ok(())
+  }
+
+  pub fn set_shape(&self, shape: Option<Vec<Rect>>) -> Result<(), ExternalError> {
+    let shape = self.logical_rects(shape);
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::Shape(shape)))
+    {
+      log::warn!("Fail to send shape request: {}", e);
+    }
+    Ok(())
+  }
+
+  fn logical_rects(&self, rects: Option<Vec<Rect>>) -> Option<Vec<(i32, i32, i32, i32)>> {
+    let scale_factor = self.scale_factor();
+    rects.map(|rects| {
+      rects
+        .into_iter()
+        .map(|rect| {
+          let (x, y) = rect.position.to_logical::<i32>(scale_factor).into();
+          let (width, height) = rect.size.to_logical::<i32>(scale_factor).into();
+          (x, y, width, height)
+        })
+        .collect()
+    })
   }
 
   pub fn drag_window(&self) -> Result<(), ExternalError> {
@@ -510,6 +799,8 @@ impl Window {
     }
   }
 
+  pub fn set_content_protection(&self, _enabled: bool) {}
+
   pub fn set_always_on_top(&self, always_on_top: bool) {
     if let Err(e) = self
       .window_requests_tx
@@ -528,8 +819,21 @@ impl Window {
     }
   }
 
-  pub fn set_ime_position<P: Into<Position>>(&self, _position: P) {
-    //TODO
+  pub fn set_ime_position<P: Into<Position>>(&self, position: P) {
+    self.set_ime_cursor_area(position, LogicalSize::new(0, 0));
+  }
+
+  pub fn set_ime_cursor_area<P: Into<Position>, S: Into<Size>>(&self, position: P, size: S) {
+    let scale_factor = self.scale_factor();
+    let (x, y): (i32, i32) = position.into().to_logical::<i32>(scale_factor).into();
+    let (width, height): (i32, i32) = size.into().to_logical::<i32>(scale_factor).into();
+
+    if let Err(e) = self.window_requests_tx.send((
+      self.window_id,
+      WindowRequest::ImeCursorArea((x, y, width, height)),
+    )) {
+      log::warn!("Fail to send IME cursor area request: {}", e);
+    }
   }
 
   pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
@@ -562,6 +866,15 @@ impl Window {
     }
   }
 
+  pub fn set_custom_cursor(&self, cursor: CustomCursor) {
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::CustomCursor(cursor)))
+    {
+      log::warn!("Fail to send custom cursor request: {}", e);
+    }
+  }
+
   pub fn set_cursor_position<P: Into<Position>>(&self, position: P) -> Result<(), ExternalError> {
     let inner_pos = self.inner_position().unwrap_or_default();
     let (x, y): (i32, i32) = position
@@ -579,7 +892,40 @@ impl Window {
     Ok(())
   }
 
-  pub fn set_cursor_grab(&self, _grab: bool) -> Result<(), ExternalError> {
+  pub fn set_cursor_grab(&self, grab: bool) -> Result<(), ExternalError> {
+    // A seat grab only redirects the input on Wayland, the pointer still leaves the window
+    if self.window.display().type_().name() == "GdkWaylandDisplay" {
+      return self.set_pointer_constraint(grab.then(|| PointerConstraint::Confined));
+    }
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::CursorGrab(grab)))
+    {
+      log::warn!("Fail to send cursor grab request: {}", e);
+    }
+    Ok(())
+  }
+
+  pub fn set_cursor_lock(&self, lock: bool) -> Result<(), ExternalError> {
+    if self.window.display().type_().name() != "GdkWaylandDisplay" {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
+    self.set_pointer_constraint(lock.then(|| PointerConstraint::Locked))
+  }
+
+  fn set_pointer_constraint(
+    &self,
+    constraint: Option<PointerConstraint>,
+  ) -> Result<(), ExternalError> {
+    if constraint.is_some() && !wayland::supports_pointer_constraints(&self.window.display()) {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::PointerConstraint(constraint)))
+    {
+      log::warn!("Fail to send pointer constraint request: {}", e);
+    }
     Ok(())
   }
 
@@ -608,16 +954,7 @@ impl Window {
 
   #[inline]
   pub fn available_monitors(&self) -> VecDeque<MonitorHandle> {
-    let mut handles = VecDeque::new();
-    let display = self.window.display();
-    let numbers = display.n_monitors();
-
-    for i in 0..numbers {
-      let monitor = MonitorHandle::new(&display, i);
-      handles.push_back(monitor);
-    }
-
-    handles
+    monitor::available_monitors(&self.window.display())
   }
 
   pub fn primary_monitor(&self) -> Option<RootMonitorHandle> {
@@ -642,6 +979,15 @@ impl Window {
     RawWindowHandle::Xlib(handle)
   }
 
+  pub(crate) fn set_startup_id(&self, startup_id: &str) {
+    if let Err(e) = self.window_requests_tx.send((
+      self.window_id,
+      WindowRequest::StartupId(startup_id.to_string()),
+    )) {
+      log::warn!("Fail to send startup id request: {}", e);
+    }
+  }
+
   pub(crate) fn set_skip_taskbar(&self, skip: bool) {
     if let Err(e) = self
       .window_requests_tx
@@ -662,11 +1008,11 @@ pub enum WindowRequest {
   Title(String),
   Position((i32, i32)),
   Size((i32, i32)),
-  MinSize((i32, i32)),
-  MaxSize((i32, i32)),
+  GeometryHints(GeometryHints),
   Visible(bool),
   Focus,
   Resizable(bool),
+  EnabledButtons(WindowButtons),
   Minimized(bool),
   Maximized(bool),
   DragWindow,
@@ -676,13 +1022,446 @@ pub enum WindowRequest {
   WindowIcon(Option<Icon>),
   UserAttention(Option<UserAttentionType>),
   SetSkipTaskbar(bool),
+  StartupId(String),
+  CursorGrab(bool),
+  PointerConstraint(Option<PointerConstraint>),
   CursorIcon(Option<CursorIcon>),
+  CustomCursor(CustomCursor),
   CursorPosition((i32, i32)),
+  ImeCursorArea((i32, i32, i32, i32)),
+  CursorHittestRegion(Option<Vec<(i32, i32, i32, i32)>>),
+  Shape(Option<Vec<(i32, i32, i32, i32)>>),
+  Transparent(bool),
   WireUpEvents,
-  Redraw,
+  /// Redraws the `(x, y, width, height)` area of the content, or the whole window.
+  Redraw(Option<(i32, i32, i32, i32)>),
+  PrePresentNotify,
   Menu((Option<MenuItem>, Option<MenuId>)),
+  TrayMenu((TrayId, MenuId)),
   SetMenu((Option<menu::Menu>, AccelGroup, gtk::MenuBar)),
   GlobalHotKey(u16),
+  GlobalHotKeyReleased(u16),
+}
+
+/// Size constraints of a window, in logical pixels.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GeometryHints {
+  pub min_size: Option<(i32, i32)>,
+  pub max_size: Option<(i32, i32)>,
+  /// Width to height ratio of the window content.
+  pub aspect_ratio: Option<f64>,
+}
+
+/// Applies all of `hints` to `window`, since each call replaces the hints that were set before.
+pub(crate) fn set_geometry_hints<W: IsA<gtk::Window>>(window: &W, hints: GeometryHints) {
+  let mut mask = gdk::WindowHints::empty();
+  let (min_width, min_height) = hints.min_size.unwrap_or_default();
+  let (max_width, max_height) = hints.max_size.unwrap_or_default();
+  let aspect_ratio = hints.aspect_ratio.unwrap_or_default();
+  if hints.min_size.is_some() {
+    mask |= gdk::WindowHints::MIN_SIZE;
+  }
+  if hints.max_size.is_some() {
+    mask |= gdk::WindowHints::MAX_SIZE;
+  }
+  if hints.aspect_ratio.is_some() {
+    mask |= gdk::WindowHints::ASPECT;
+  }
+
+  let picky_none: Option<&gtk::Window> = None;
+  window.set_geometry_hints(
+    picky_none,
+    Some(&gdk::Geometry::new(
+      min_width,
+      min_height,
+      max_width,
+      max_height,
+      0,
+      0,
+      0,
+      0,
+      aspect_ratio,
+      aspect_ratio,
+      gdk::Gravity::Center,
+    )),
+    mask,
+  );
+}
+
+/// Hints the window manager about the title bar buttons of `window` that are enabled.
+///
+/// The close button maps to `deletable`, the other ones to the WM functions of the realized
+/// `GdkWindow`, which GTK resets when `deletable` changes.
+pub(crate) fn set_enabled_buttons<W: IsA<gtk::Window>>(window: &W, buttons: WindowButtons) {
+  window.set_deletable(buttons.contains(WindowButtons::CLOSE));
+  if let Some(gdk_window) = window.upcast_ref::<gtk::Window>().window() {
+    // With `ALL` set, the other functions are the ones to disable
+    let mut functions = gdk::WMFunction::ALL;
+    if !buttons.contains(WindowButtons::CLOSE) {
+      functions |= gdk::WMFunction::CLOSE;
+    }
+    if !buttons.contains(WindowButtons::MINIMIZE) {
+      functions |= gdk::WMFunction::MINIMIZE;
+    }
+    if !buttons.contains(WindowButtons::MAXIMIZE) {
+      functions |= gdk::WMFunction::MAXIMIZE;
+    }
+    gdk_window.set_functions(functions);
+  }
+}
+
+/// Returns the position and size of the content area of `window`, relative to its `GdkWindow`,
+/// or `None` before the content is allocated.
+///
+/// The content area excludes the client-side decorations and the menu bar.
+fn content_area<W: IsA<gtk::Window>>(window: &W) -> Option<gtk::Allocation> {
+  let window_box = window.upcast_ref::<gtk::Window>().child()?;
+  if !window_box.is_realized() {
+    return None;
+  }
+  let window_box = window_box.downcast::<gtk::Box>().ok()?;
+  // The menu bar is always the first child, see `Window::new`
+  let menu_height = window_box
+    .children()
+    .first()
+    .filter(|menu_bar| menu_bar.is_visible())
+    .map(|menu_bar| menu_bar.allocated_height())
+    .unwrap_or(0);
+  let allocation = window_box.allocation();
+  Some(gtk::Allocation::new(
+    allocation.x(),
+    allocation.y() + menu_height,
+    allocation.width(),
+    allocation.height() - menu_height,
+  ))
+}
+
+/// Queues a redraw of the `(x, y, width, height)` area of the content area of `window`, in
+/// logical units.
+pub(crate) fn queue_draw_content_area<W: IsA<gtk::Window>>(
+  window: &W,
+  (x, y, width, height): (i32, i32, i32, i32),
+) {
+  let (left, top) = content_area(window)
+    .map(|area| (area.x(), area.y()))
+    .unwrap_or_default();
+  window
+    .upcast_ref::<gtk::Window>()
+    .queue_draw_area(left + x, top + y, width, height);
+}
+
+/// The geometry of a window before it entered fullscreen, see `restore_after_fullscreen`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SavedGeometry {
+  position: (i32, i32),
+  size: (i32, i32),
+  maximized: bool,
+}
+
+impl SavedGeometry {
+  pub(crate) fn of(window: &gtk::Window) -> Self {
+    Self {
+      position: window.position(),
+      size: window.size(),
+      maximized: window.is_maximized(),
+    }
+  }
+}
+
+/// Moves and resizes `window` back to `geometry` once it left fullscreen. The window is centered
+/// on the primary monitor instead when the saved position isn't on any monitor anymore.
+///
+/// A maximized window is left to the window manager, which restores it maximized by itself.
+pub(crate) fn restore_after_fullscreen(window: &gtk::Window, geometry: SavedGeometry) {
+  if geometry.maximized {
+    return;
+  }
+
+  // The window manager ignores the geometry of fullscreen windows, so wait for the state change
+  let handler = Rc::new(Cell::new(None));
+  let handler_ = handler.clone();
+  let id = window.connect_window_state_event(move |window, event| {
+    if event.changed_mask().contains(WindowState::FULLSCREEN)
+      && !event.new_window_state().contains(WindowState::FULLSCREEN)
+    {
+      let (x, y) = on_screen_position(window, geometry);
+      window.move_(x, y);
+      window.resize(geometry.size.0, geometry.size.1);
+      if let Some(id) = handler_.take() {
+        window.disconnect(id);
+      }
+    }
+    Inhibit(false)
+  });
+  handler.set(Some(id));
+}
+
+fn on_screen_position(window: &gtk::Window, geometry: SavedGeometry) -> (i32, i32) {
+  let ((x, y), (width, height)) = (geometry.position, geometry.size);
+  let display = window.display();
+  let on_screen = (0..display.n_monitors())
+    .filter_map(|i| display.monitor(i))
+    .map(|monitor| monitor.geometry())
+    .any(|rect| {
+      x < rect.x() + rect.width()
+        && x + width > rect.x()
+        && y < rect.y() + rect.height()
+        && y + height > rect.y()
+    });
+  if on_screen {
+    return (x, y);
+  }
+
+  match display.primary_monitor().or_else(|| display.monitor(0)) {
+    Some(monitor) => {
+      let area = monitor.workarea();
+      (
+        area.x() + ((area.width() - width) / 2).max(0),
+        area.y() + ((area.height() - height) / 2).max(0),
+      )
+    }
+    None => (x, y),
+  }
+}
+
+const CURSOR_ANIMATION_KEY: &str = "tao-cursor-animation";
+
+/// Sets `cursor` on `window`, cycling its frames with a timer when it's animated.
+pub(crate) fn set_custom_cursor(window: &gtk::Window, cursor: &CustomCursor) {
+  stop_cursor_animation(window);
+  let gdk_window = match window.window() {
+    Some(gdk_window) => gdk_window,
+    None => return,
+  };
+  let display = window.display();
+  let frames: Vec<(gdk::Cursor, Duration)> = cursor
+    .frames
+    .iter()
+    .map(|frame| {
+      let pixbuf: Pixbuf =
+        IconImage::from_rgba(frame.rgba.clone(), cursor.width, cursor.height).into();
+      let gdk_cursor = gdk::Cursor::from_pixbuf(
+        &display,
+        &pixbuf,
+        cursor.hotspot.x as i32,
+        cursor.hotspot.y as i32,
+      );
+      (gdk_cursor, frame.duration)
+    })
+    .collect();
+  gdk_window.set_cursor(Some(&frames[0].0));
+
+  if cursor.is_animated() {
+    let running = Rc::new(Cell::new(true));
+    unsafe { window.set_data(CURSOR_ANIMATION_KEY, running.clone()) };
+    animate_cursor(gdk_window, Rc::new(frames), 0, running);
+  }
+}
+
+/// Stops the animation of the cursor of `window`, if it has one.
+pub(crate) fn stop_cursor_animation(window: &gtk::Window) {
+  if let Some(running) = unsafe { window.steal_data::<Rc<Cell<bool>>>(CURSOR_ANIMATION_KEY) } {
+    running.set(false);
+  }
+}
+
+fn animate_cursor(
+  gdk_window: gdk::Window,
+  frames: Rc<Vec<(gdk::Cursor, Duration)>>,
+  frame: usize,
+  running: Rc<Cell<bool>>,
+) {
+  glib::timeout_add_local_once(frames[frame].1, move || {
+    if !running.get() || gdk_window.is_destroyed() {
+      return;
+    }
+    let next = (frame + 1) % frames.len();
+    gdk_window.set_cursor(Some(&frames[next].0));
+    animate_cursor(gdk_window, frames, next, running);
+  });
+}
+
+const SCALE_FACTOR_OVERRIDE_KEY: &str = "tao-scale-factor-override";
+
+/// Returns the scale factor of `window`: the one set with `with_scale_factor_override` if any,
+/// then the fractional one preferred by a Wayland compositor, then the integer one of GTK.
+pub(crate) fn scale_factor<W: IsA<gtk::Window>>(window: &W) -> f64 {
+  let window = window.upcast_ref::<gtk::Window>();
+  match unsafe { window.data::<f64>(SCALE_FACTOR_OVERRIDE_KEY) } {
+    Some(scale_factor) => unsafe { *scale_factor.as_ref() },
+    None => wayland::fractional_scale(window).unwrap_or_else(|| window.scale_factor() as f64),
+  }
+}
+
+/// Returns the logical size of the content area of `window`, see `content_area`.
+pub(crate) fn content_size<W: IsA<gtk::Window>>(window: &W) -> Option<(i32, i32)> {
+  content_area(window).map(|area| (area.width(), area.height()))
+}
+
+/// Returns the physical space between the edges of the `GdkWindow` of `window` and its content
+/// area, see `content_area`.
+pub(crate) fn content_insets<W: IsA<gtk::Window>>(window: &W) -> Option<Insets> {
+  let area = content_area(window)?;
+  let window = window.upcast_ref::<gtk::Window>();
+  let gdk_window = window.window()?;
+  let scale_factor = scale_factor(window);
+  let physical = |inset: i32| (inset.max(0) as f64 * scale_factor).round() as u32;
+  Some(Insets {
+    top: physical(area.y()),
+    left: physical(area.x()),
+    bottom: physical(gdk_window.height() - area.y() - area.height()),
+    right: physical(gdk_window.width() - area.x() - area.width()),
+  })
+}
+
+/// Resizes `window` so that its content area, see `content_size`, gets the given size.
+///
+/// `gtk_window_resize` sets the size of the whole window, which includes the header bar of the
+/// client-side decorations and the menu bar.
+pub(crate) fn set_content_size<W: IsA<gtk::Window>>(window: &W, width: i32, height: i32) {
+  let (window_width, window_height) = window.size();
+  let (delta_width, delta_height) = content_size(window)
+    .map(|(w, h)| (window_width - w, window_height - h))
+    .unwrap_or_default();
+  window.resize(width + delta_width, height + delta_height);
+}
+
+/// Returns a timestamp window managers accept to give the focus to `window`.
+pub(crate) fn current_user_time(window: &gtk::Window) -> u32 {
+  use glib::translate::ToGlibPtr;
+
+  let time = gtk::current_event_time();
+  if time != gdk_sys::GDK_CURRENT_TIME as u32 {
+    return time;
+  }
+
+  // Outside of an event handler, ask the X server for its time. Wayland uses activation tokens
+  // instead of timestamps.
+  match window.window() {
+    Some(gdk_window) if gdk_window.display().type_().name() == "GdkX11Display" => unsafe {
+      let gdk_window: *mut gdk_sys::GdkWindow = gdk_window.to_glib_none().0;
+      gdk_x11_sys::gdk_x11_get_server_time(gdk_window as *mut _)
+    },
+    _ => gdk_sys::GDK_CURRENT_TIME as u32,
+  }
+}
+
+/// Returns the startup notification token set by the launcher, if any.
+///
+/// The environment is left untouched, removing variables isn't sound while other threads may
+/// read it. The event loop hands the token to its first window instead.
+pub(crate) fn startup_id_from_env() -> Option<String> {
+  // Wayland (xdg-activation) first, then X11 startup notification.
+  ["XDG_ACTIVATION_TOKEN", "DESKTOP_STARTUP_ID"]
+    .iter()
+    .filter_map(|var| std::env::var(var).ok())
+    .find(|value| !value.is_empty())
+}
+
+/// Turns the window into a layer-shell surface using `libgtk-layer-shell`, which is loaded at
+/// runtime as it's an optional system library.
+fn init_layer_shell(
+  window: &gtk::Window,
+  layer: Layer,
+  anchor: Anchor,
+  exclusive_zone: i32,
+) -> Result<(), &'static str> {
+  if window.display().type_().name() != "GdkWaylandDisplay" {
+    return Err("layer-shell is only supported on Wayland");
+  }
+
+  type IsSupported = unsafe extern "C" fn() -> glib_sys::gboolean;
+  type InitForWindow = unsafe extern "C" fn(*mut gtk::ffi::GtkWindow);
+  type SetLayer = unsafe extern "C" fn(*mut gtk::ffi::GtkWindow, c_int);
+  type SetAnchor = unsafe extern "C" fn(*mut gtk::ffi::GtkWindow, c_int, glib_sys::gboolean);
+  type SetExclusiveZone = unsafe extern "C" fn(*mut gtk::ffi::GtkWindow, c_int);
+  unsafe {
+    let lib = libc::dlopen(
+      b"libgtk-layer-shell.so.0\0".as_ptr() as *const _,
+      libc::RTLD_NOW | libc::RTLD_GLOBAL,
+    );
+    if lib.is_null() {
+      return Err("libgtk-layer-shell.so.0 couldn't be loaded");
+    }
+    let symbol = |name: &[u8]| libc::dlsym(lib, name.as_ptr() as *const _);
+
+    // `gtk_layer_is_supported` was added in 0.6, assume support with older versions
+    let is_supported = symbol(b"gtk_layer_is_supported\0");
+    if !is_supported.is_null() {
+      let is_supported: IsSupported = std::mem::transmute(is_supported);
+      if is_supported() == glib_sys::GFALSE {
+        return Err("the compositor doesn't support the wlr-layer-shell protocol");
+      }
+    }
+
+    let init_for_window = symbol(b"gtk_layer_init_for_window\0");
+    let set_layer = symbol(b"gtk_layer_set_layer\0");
+    let set_anchor = symbol(b"gtk_layer_set_anchor\0");
+    let set_exclusive_zone = symbol(b"gtk_layer_set_exclusive_zone\0");
+    if init_for_window.is_null()
+      || set_layer.is_null()
+      || set_anchor.is_null()
+      || set_exclusive_zone.is_null()
+    {
+      return Err("libgtk-layer-shell.so.0 is missing symbols");
+    }
+    let init_for_window: InitForWindow = std::mem::transmute(init_for_window);
+    let set_layer: SetLayer = std::mem::transmute(set_layer);
+    let set_anchor: SetAnchor = std::mem::transmute(set_anchor);
+    let set_exclusive_zone: SetExclusiveZone = std::mem::transmute(set_exclusive_zone);
+
+    let window = window.as_ptr();
+    init_for_window(window);
+    // `GtkLayerShellLayer` values
+    set_layer(
+      window,
+      match layer {
+        Layer::Background => 0,
+        Layer::Bottom => 1,
+        Layer::Top => 2,
+        Layer::Overlay => 3,
+      },
+    );
+    // `GtkLayerShellEdge` values
+    for (edge, flag) in [
+      (0, Anchor::LEFT),
+      (1, Anchor::RIGHT),
+      (2, Anchor::TOP),
+      (3, Anchor::BOTTOM),
+    ] {
+      set_anchor(window, edge, anchor.contains(flag).into_glib());
+    }
+    set_exclusive_zone(window, exclusive_zone);
+  }
+  Ok(())
+}
+
+/// Sets the `app_id` of a Wayland toplevel. This is a no-op on other backends.
+fn set_wayland_application_id(window: &gdk::Window, app_id: &str) {
+  if window.display().type_().name() != "GdkWaylandDisplay" {
+    return;
+  }
+
+  // `gdk_wayland_window_set_application_id` is only available in GTK 3.24.22+ and isn't part of
+  // the `gdk-sys` bindings, so we look it up at runtime.
+  type SetApplicationId = unsafe extern "C" fn(
+    *mut gdk_sys::GdkWindow,
+    *const std::os::raw::c_char,
+  ) -> glib_sys::gboolean;
+  unsafe {
+    let symbol = libc::dlsym(
+      libc::RTLD_DEFAULT,
+      b"gdk_wayland_window_set_application_id\0".as_ptr() as *const _,
+    );
+    if symbol.is_null() {
+      log::warn!("Setting the Wayland app_id requires GTK 3.24.22 or later");
+      return;
+    }
+    let set_application_id: SetApplicationId = std::mem::transmute(symbol);
+    if let Ok(app_id) = std::ffi::CString::new(app_id) {
+      set_application_id(window.as_ptr(), app_id.as_ptr());
+    }
+  }
 }
 
 pub fn hit_test(window: &gdk::Window, cx: f64, cy: f64) -> WindowEdge {
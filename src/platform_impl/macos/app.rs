@@ -1,10 +1,11 @@
 // Copyright 2019-2021 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::VecDeque;
+use std::{collections::VecDeque, sync::Mutex};
 
+use block::ConcreteBlock;
 use cocoa::{
-  appkit::{self, NSEvent},
+  appkit::{self, NSEvent, NSEventMask},
   base::id,
 };
 use objc::{
@@ -13,12 +14,31 @@ use objc::{
 };
 
 use super::{app_state::AppState, event::EventWrapper, util, DEVICE_ID};
-use crate::event::{DeviceEvent, ElementState, Event};
+use crate::{
+  event::{DeviceEvent, ElementState, Event},
+  event_loop::DeviceEvents,
+};
 
 pub struct AppClass(pub *const Class);
 unsafe impl Send for AppClass {}
 unsafe impl Sync for AppClass {}
 
+/// The global event monitor installed for `DeviceEvents::Always`.
+struct GlobalMonitor(id);
+unsafe impl Send for GlobalMonitor {}
+
+struct DeviceEventsState {
+  allowed: DeviceEvents,
+  global_monitor: Option<GlobalMonitor>,
+}
+
+lazy_static! {
+  static ref DEVICE_EVENTS: Mutex<DeviceEventsState> = Mutex::new(DeviceEventsState {
+    allowed: DeviceEvents::WhenFocused,
+    global_monitor: None,
+  });
+}
+
 lazy_static! {
   pub static ref APP_CLASS: AppClass = unsafe {
     let superclass = class!(NSApplication);
@@ -59,7 +79,47 @@ extern "C" fn send_event(this: &Object, _sel: Sel, event: id) {
   }
 }
 
+/// `sendEvent:` only sees the events of the focused application, the mouse events that happen
+/// while another application is focused are reported by a global monitor instead.
+pub fn listen_device_events(allowed: DeviceEvents) {
+  let mut state = DEVICE_EVENTS.lock().unwrap();
+  state.allowed = allowed;
+  unsafe {
+    if allowed == DeviceEvents::Always {
+      if state.global_monitor.is_none() {
+        let mask = NSEventMask::NSMouseMovedMask
+          | NSEventMask::NSLeftMouseDraggedMask
+          | NSEventMask::NSRightMouseDraggedMask
+          | NSEventMask::NSOtherMouseDraggedMask
+          | NSEventMask::NSLeftMouseDownMask
+          | NSEventMask::NSRightMouseDownMask
+          | NSEventMask::NSOtherMouseDownMask
+          | NSEventMask::NSLeftMouseUpMask
+          | NSEventMask::NSRightMouseUpMask
+          | NSEventMask::NSOtherMouseUpMask;
+        let handler = ConcreteBlock::new(|event: id| dispatch_device_event(event)).copy();
+        let monitor: id = msg_send![
+          class!(NSEvent),
+          addGlobalMonitorForEventsMatchingMask: mask.bits()
+          handler: &*handler
+        ];
+        let monitor: id = msg_send![monitor, retain];
+        state.global_monitor = Some(GlobalMonitor(monitor));
+      }
+    } else if let Some(GlobalMonitor(monitor)) = state.global_monitor.take() {
+      let _: () = msg_send![class!(NSEvent), removeMonitor: monitor];
+      let _: () = msg_send![monitor, release];
+    }
+  }
+}
+
 unsafe fn maybe_dispatch_device_event(event: id) {
+  if DEVICE_EVENTS.lock().unwrap().allowed != DeviceEvents::Never {
+    dispatch_device_event(event);
+  }
+}
+
+unsafe fn dispatch_device_event(event: id) {
   let event_type = event.eventType();
   match event_type {
     appkit::NSMouseMoved
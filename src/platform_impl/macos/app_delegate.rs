@@ -1,7 +1,10 @@
 // Copyright 2019-2021 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{platform::macos::ActivationPolicy, platform_impl::platform::app_state::AppState};
+use crate::{
+  platform::macos::ActivationPolicy,
+  platform_impl::platform::{app_state::AppState, event_loop::catch_panic},
+};
 
 use cocoa::base::id;
 use objc::{
@@ -11,6 +14,8 @@ use objc::{
 use std::{
   cell::{RefCell, RefMut},
   os::raw::c_void,
+  panic::AssertUnwindSafe,
+  process,
 };
 
 static AUX_DELEGATE_STATE_NAME: &str = "auxState";
@@ -83,12 +88,16 @@ extern "C" fn dealloc(this: &Object, _: Sel) {
 
 extern "C" fn did_finish_launching(this: &Object, _: Sel, _: id) {
   trace!("Triggered `applicationDidFinishLaunching`");
-  AppState::launched(this);
+  catch_panic(AssertUnwindSafe(|| AppState::launched(this)));
   trace!("Completed `applicationDidFinishLaunching`");
 }
 
 extern "C" fn application_will_terminate(_: &Object, _: Sel, _: id) {
   trace!("Triggered `applicationWillTerminate`");
-  AppState::exit();
+  // AppKit exits the process after this, so the panic can't be resumed. It has already been
+  // reported by the panic hook, only the exit code is left to match a regular panic.
+  if catch_panic(|| AppState::exit(true)).is_none() {
+    process::exit(101);
+  }
   trace!("Completed `applicationWillTerminate`");
 }
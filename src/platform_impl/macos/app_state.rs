@@ -18,7 +18,7 @@ use std::{
 use cocoa::{
   appkit::{NSApp, NSApplication, NSWindow},
   base::{id, nil},
-  foundation::{NSAutoreleasePool, NSSize},
+  foundation::{NSArray, NSAutoreleasePool, NSSize},
 };
 use objc::runtime::{Object, BOOL, NO, YES};
 
@@ -271,8 +271,24 @@ impl AppState {
     }));
   }
 
-  pub fn exit() -> i32 {
+  /// Emits `LoopDestroyed`. When `close_windows` is set the process is about to exit, so the
+  /// remaining windows are closed first to deliver their `Destroyed` events.
+  pub fn exit(close_windows: bool) -> i32 {
     HANDLER.set_in_callback(true);
+    if close_windows {
+      unsafe {
+        // `windows` returns a copy, so closing doesn't mutate the array we iterate.
+        let windows: id = msg_send![NSApp(), windows];
+        for i in 0..windows.count() {
+          let () = msg_send![windows.objectAtIndex(i), close];
+        }
+      }
+    }
+    // Deliver events queued since the last iteration, such as `Destroyed` from
+    // `windowWillClose:`, so they all precede `LoopDestroyed`.
+    for event in HANDLER.take_events() {
+      HANDLER.handle_nonuser_event(event);
+    }
     HANDLER.handle_nonuser_event(EventWrapper::StaticEvent(Event::LoopDestroyed));
     HANDLER.set_in_callback(false);
     HANDLER.callback.lock().unwrap().take();
@@ -347,6 +363,16 @@ impl AppState {
   }
 
   pub fn handle_redraw(window_id: WindowId) {
+    // AppKit can draw the window before the queued events are dispatched, e.g. on each step of a
+    // live resize, so they're delivered first to have `Resized` arrive before the redraw.
+    if !HANDLER.get_in_callback() {
+      HANDLER.set_in_callback(true);
+      for event in HANDLER.take_events() {
+        HANDLER.handle_nonuser_event(event);
+      }
+      HANDLER.set_in_callback(false);
+    }
+    HANDLER.redraw().retain(|id| *id != window_id);
     HANDLER.handle_nonuser_event(EventWrapper::StaticEvent(Event::RedrawRequested(window_id)));
   }
 
@@ -384,6 +410,7 @@ impl AppState {
         HANDLER.handle_nonuser_event(EventWrapper::StaticEvent(Event::RedrawRequested(window_id)));
       }
       HANDLER.handle_nonuser_event(EventWrapper::StaticEvent(Event::RedrawEventsCleared));
+      HANDLER.handle_nonuser_event(EventWrapper::StaticEvent(Event::AboutToWait));
       HANDLER.set_in_callback(false);
     }
     if HANDLER.should_exit() {
@@ -11,6 +11,7 @@ use std::{
   panic::{catch_unwind, resume_unwind, RefUnwindSafe, UnwindSafe},
   process, ptr,
   rc::{Rc, Weak},
+  sync::{self, Arc},
 };
 
 use cocoa::{
@@ -22,16 +23,21 @@ use crossbeam_channel::{self as channel, Receiver, Sender};
 use scopeguard::defer;
 
 use crate::{
+  error::OsError as RootOsError,
   event::Event,
-  event_loop::{ControlFlow, EventLoopClosed, EventLoopWindowTarget as RootWindowTarget},
+  event_loop::{
+    ControlFlow, DeviceEvents, EventLoopClosed, EventLoopWindowTarget as RootWindowTarget,
+  },
   monitor::MonitorHandle as RootMonitorHandle,
   platform_impl::platform::{
-    app::APP_CLASS,
+    app::{self, APP_CLASS},
     app_delegate::APP_DELEGATE_CLASS,
     app_state::AppState,
+    menu::{self, Menu},
     monitor::{self, MonitorHandle},
     observer::*,
     util::IdRef,
+    OsError,
   },
 };
 
@@ -63,16 +69,25 @@ impl PanicInfo {
   }
 }
 
+thread_local! {
+  /// The panic info of the event loop, for the Objective-C callbacks that can't be given it.
+  static PANIC_INFO: RefCell<Weak<PanicInfo>> = RefCell::new(Weak::new());
+}
+
 #[derive(Clone)]
 pub struct EventLoopWindowTarget<T: 'static> {
   pub sender: Sender<T>, // this is only here to be cloned elsewhere
-  pub receiver: Receiver<T>,
+  /// Shared, so the proxies can tell when it's dropped.
+  pub receiver: Arc<Receiver<T>>,
 }
 
 impl<T> Default for EventLoopWindowTarget<T> {
   fn default() -> Self {
     let (sender, receiver) = channel::unbounded();
-    EventLoopWindowTarget { sender, receiver }
+    EventLoopWindowTarget {
+      sender,
+      receiver: Arc::new(receiver),
+    }
   }
 }
 
@@ -87,6 +102,16 @@ impl<T: 'static> EventLoopWindowTarget<T> {
     let monitor = monitor::primary_monitor();
     Some(RootMonitorHandle { inner: monitor })
   }
+
+  #[inline]
+  pub fn set_application_menu(&self, menu: Menu) {
+    menu::initialize(menu);
+  }
+
+  #[inline]
+  pub fn listen_device_events(&self, allowed: DeviceEvents) {
+    app::listen_device_events(allowed);
+  }
 }
 
 pub struct EventLoop<T: 'static> {
@@ -105,7 +130,7 @@ pub struct EventLoop<T: 'static> {
 }
 
 impl<T> EventLoop<T> {
-  pub fn new() -> Self {
+  pub fn new() -> Result<Self, RootOsError> {
     let delegate = unsafe {
       let is_main_thread: BOOL = msg_send!(class!(NSThread), isMainThread);
       if is_main_thread == NO {
@@ -117,6 +142,11 @@ impl<T> EventLoop<T> {
       // with the wrong `NSApplication` class and the wrong thread could
       // be marked as main.
       let app: id = msg_send![APP_CLASS.0, sharedApplication];
+      if app == nil {
+        return Err(os_error!(OsError::CreationError(
+          "Unable to get the shared NSApplication"
+        )));
+      }
 
       let delegate = IdRef::new(msg_send![APP_DELEGATE_CLASS.0, new]);
       let pool = NSAutoreleasePool::new(nil);
@@ -126,15 +156,20 @@ impl<T> EventLoop<T> {
     };
     let panic_info: Rc<PanicInfo> = Default::default();
     setup_control_flow_observers(Rc::downgrade(&panic_info));
-    EventLoop {
+    PANIC_INFO.with(|info| *info.borrow_mut() = Rc::downgrade(&panic_info));
+    Ok(EventLoop {
       delegate,
       window_target: Rc::new(RootWindowTarget {
         p: Default::default(),
+        control_flow: Default::default(),
+        scale_factor_rounding: Default::default(),
+        #[cfg(feature = "testing")]
+        injected_events: Default::default(),
         _marker: PhantomData,
       }),
       panic_info,
       _callback: None,
-    }
+    })
   }
 
   pub fn window_target(&self) -> &RootWindowTarget<T> {
@@ -145,11 +180,18 @@ impl<T> EventLoop<T> {
   where
     F: 'static + FnMut(Event<'_, T>, &RootWindowTarget<T>, &mut ControlFlow),
   {
-    let exit_code = self.run_return(callback);
+    let exit_code = self.run_inner(callback, true);
     process::exit(exit_code);
   }
 
   pub fn run_return<F>(&mut self, callback: F) -> i32
+  where
+    F: FnMut(Event<'_, T>, &RootWindowTarget<T>, &mut ControlFlow),
+  {
+    self.run_inner(callback, false)
+  }
+
+  fn run_inner<F>(&mut self, callback: F, exiting: bool) -> i32
   where
     F: FnMut(Event<'_, T>, &RootWindowTarget<T>, &mut ControlFlow),
   {
@@ -184,7 +226,7 @@ impl<T> EventLoop<T> {
         drop(self._callback.take());
         resume_unwind(panic);
       }
-      AppState::exit()
+      AppState::exit(exiting)
     };
     drop(self._callback.take());
 
@@ -192,7 +234,10 @@ impl<T> EventLoop<T> {
   }
 
   pub fn create_proxy(&self) -> Proxy<T> {
-    Proxy::new(self.window_target.p.sender.clone())
+    Proxy::new(
+      self.window_target.p.sender.clone(),
+      Arc::downgrade(&self.window_target.p.receiver),
+    )
   }
 }
 
@@ -246,8 +291,20 @@ pub fn stop_app_on_panic<F: FnOnce() -> R + UnwindSafe, R>(
   }
 }
 
+/// Runs `f` from an Objective-C callback. A panic in `f` mustn't unwind into AppKit, so it's
+/// caught and the event loop is stopped, to resume the panic once `run_return` regains control.
+pub fn catch_panic<F: FnOnce() -> R + UnwindSafe, R>(f: F) -> Option<R> {
+  let panic_info = PANIC_INFO.with(|info| info.borrow().clone());
+  if panic_info.upgrade().is_none() {
+    // No event loop is alive to resume the panic
+    return Some(f());
+  }
+  stop_app_on_panic(panic_info, f)
+}
+
 pub struct Proxy<T> {
   sender: Sender<T>,
+  receiver: sync::Weak<Receiver<T>>,
   source: CFRunLoopSourceRef,
 }
 
@@ -264,12 +321,12 @@ impl<T> Drop for Proxy<T> {
 
 impl<T> Clone for Proxy<T> {
   fn clone(&self) -> Self {
-    Proxy::new(self.sender.clone())
+    Proxy::new(self.sender.clone(), self.receiver.clone())
   }
 }
 
 impl<T> Proxy<T> {
-  fn new(sender: Sender<T>) -> Self {
+  fn new(sender: Sender<T>, receiver: sync::Weak<Receiver<T>>) -> Self {
     unsafe {
       // just wake up the eventloop
       extern "C" fn event_loop_proxy_handler(_: *mut c_void) {}
@@ -283,7 +340,11 @@ impl<T> Proxy<T> {
       CFRunLoopAddSource(rl, source, kCFRunLoopCommonModes);
       CFRunLoopWakeUp(rl);
 
-      Proxy { sender, source }
+      Proxy {
+        sender,
+        receiver,
+        source,
+      }
     }
   }
 
@@ -292,12 +353,25 @@ impl<T> Proxy<T> {
       .sender
       .send(event)
       .map_err(|channel::SendError(x)| EventLoopClosed(x))?;
+    self.signal();
+    Ok(())
+  }
+
+  pub fn wake_up(&self) -> Result<(), EventLoopClosed<()>> {
+    if self.receiver.strong_count() == 0 {
+      return Err(EventLoopClosed(()));
+    }
+    self.signal();
+    Ok(())
+  }
+
+  fn signal(&self) {
     unsafe {
-      // let the main thread know there's a new event
+      // let the main thread know there's a new event, signals are coalesced until the source
+      // is performed
       CFRunLoopSourceSignal(self.source);
       let rl = CFRunLoopGetMain();
       CFRunLoopWakeUp(rl);
     }
-    Ok(())
   }
 }
@@ -168,6 +168,12 @@ pub const IO8BitOverlayPixels: &str = "O8";
 
 pub type CGWindowLevel = i32;
 pub type CGDisplayModeRef = *mut libc::c_void;
+pub type CGDisplayChangeSummaryFlags = u32;
+pub type CGDisplayReconfigurationCallBack = extern "C" fn(
+  display: CGDirectDisplayID,
+  flags: CGDisplayChangeSummaryFlags,
+  user_info: *mut libc::c_void,
+);
 
 // `CGDisplayCreateUUIDFromDisplayID` comes from the `ColorSync` framework.
 // However, that framework was only introduced "publicly" in macOS 10.13.
@@ -234,6 +240,10 @@ extern "C" {
   pub fn CGDisplayModeCopyPixelEncoding(mode: CGDisplayModeRef) -> CFStringRef;
   pub fn CGDisplayModeRetain(mode: CGDisplayModeRef);
   pub fn CGDisplayModeRelease(mode: CGDisplayModeRef);
+  pub fn CGDisplayRegisterReconfigurationCallback(
+    callback: CGDisplayReconfigurationCallBack,
+    user_info: *mut libc::c_void,
+  ) -> CGError;
 }
 
 #[repr(transparent)]
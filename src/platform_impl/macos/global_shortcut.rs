@@ -1,50 +1,103 @@
-use std::os::raw::{c_int, c_void};
+use std::{
+  cell::RefCell,
+  collections::HashSet,
+  fmt,
+  os::raw::{c_int, c_void},
+  ptr,
+  rc::Rc,
+  sync::{Arc, Mutex},
+};
+
+use core_foundation::{
+  base::TCFType,
+  runloop::{kCFRunLoopCommonModes, CFRunLoop, CFRunLoopSource},
+};
+use core_graphics::event::{
+  CGEvent, CGEventFlags, CGEventTap, CGEventTapLocation, CGEventTapOptions, CGEventTapPlacement,
+  CGEventType,
+};
 
 use crate::{
   accelerator::{Accelerator, AcceleratorId},
   event::Event,
   event_loop::EventLoopWindowTarget,
   global_shortcut::{GlobalShortcut as RootGlobalShortcut, ShortcutManagerError},
+  keyboard::{KeyCode, ModifiersState},
 };
 
 use super::{app_state::AppState, event::EventWrapper};
 
-type KeyCallback = unsafe extern "C" fn(c_int, *mut c_void);
+type KeyCallback = unsafe extern "C" fn(c_int, c_int, *mut c_void);
 #[derive(Debug, Clone)]
 pub struct ShortcutManager {
   shortcuts: Vec<GlobalShortcut>,
   event_handler: *mut c_void,
+  /// The ids of the Carbon hotkeys that report their release.
+  release_shortcuts: Arc<Mutex<HashSet<u16>>>,
+  modifier_shortcuts: Rc<ModifierShortcuts>,
 }
 
 impl ShortcutManager {
   pub(crate) fn new<T>(_window_target: &EventLoopWindowTarget<T>) -> Self {
-    let saved_callback = Box::into_raw(Box::new(global_accelerator_handler));
+    let release_shortcuts = Arc::new(Mutex::new(HashSet::new()));
+    let handler_release_shortcuts = release_shortcuts.clone();
+    let saved_callback = Box::into_raw(Box::new(move |item_id, pressed| {
+      global_accelerator_handler(&handler_release_shortcuts, item_id, pressed)
+    }));
     let event_handler = make_accelerator_callback(saved_callback);
     ShortcutManager {
       event_handler,
       shortcuts: Vec::new(),
+      release_shortcuts,
+      modifier_shortcuts: Default::default(),
     }
   }
 
-  pub(crate) fn unregister_all(&self) -> Result<(), ShortcutManagerError> {
-    for shortcut in &self.shortcuts {
+  pub(crate) fn unregister_all(&mut self) -> Result<(), ShortcutManagerError> {
+    for shortcut in self.shortcuts.drain(..) {
       shortcut.unregister();
     }
+    self.modifier_shortcuts.shortcuts.borrow_mut().clear();
+    self.release_shortcuts.lock().unwrap().clear();
     Ok(())
   }
 
   pub(crate) fn unregister(
-    &self,
+    &mut self,
     shortcut: RootGlobalShortcut,
   ) -> Result<(), ShortcutManagerError> {
     shortcut.0.unregister();
+    self
+      .shortcuts
+      .retain(|registered| registered.id() != shortcut.0.id());
+    self
+      .modifier_shortcuts
+      .shortcuts
+      .borrow_mut()
+      .retain(|(accelerator, _)| accelerator.clone().id() != shortcut.0.id());
+    self
+      .release_shortcuts
+      .lock()
+      .unwrap()
+      .remove(&shortcut.0.id().0);
     Ok(())
   }
 
   pub(crate) fn register(
     &mut self,
     accelerator: Accelerator,
+    report_release: bool,
   ) -> Result<RootGlobalShortcut, ShortcutManagerError> {
+    if modifier_key(accelerator.key).is_some() {
+      self.modifier_shortcuts.register(accelerator.clone())?;
+      let shortcut = GlobalShortcut {
+        accelerator,
+        carbon_ref: CarbonRef::new(ptr::null_mut()),
+      };
+      self.shortcuts.push(shortcut.clone());
+      return Ok(RootGlobalShortcut(shortcut));
+    }
+
     unsafe {
       let mut converted_modifiers: i32 = 0;
       if accelerator.mods.shift_key() {
@@ -71,6 +124,13 @@ impl ShortcutManager {
           converted_modifiers as i32,
           scan_code as i32,
         );
+        if report_release {
+          self
+            .release_shortcuts
+            .lock()
+            .unwrap()
+            .insert(accelerator.clone().id().0);
+        }
         let shortcut = GlobalShortcut {
           accelerator,
           carbon_ref: CarbonRef::new(handler_ref),
@@ -88,17 +148,17 @@ impl ShortcutManager {
   // connect_event_loop is not needed on macos
 }
 
-unsafe extern "C" fn trampoline<F>(result: c_int, user_data: *mut c_void)
+unsafe extern "C" fn trampoline<F>(result: c_int, pressed: c_int, user_data: *mut c_void)
 where
-  F: FnMut(c_int) + 'static,
+  F: FnMut(c_int, bool) + 'static,
 {
   let user_data = &mut *(user_data as *mut F);
-  user_data(result);
+  user_data(result, pressed != 0);
 }
 
 fn get_trampoline<F>() -> KeyCallback
 where
-  F: FnMut(c_int) + 'static,
+  F: FnMut(c_int, bool) + 'static,
 {
   trampoline::<F>
 }
@@ -121,6 +181,7 @@ impl CarbonRef {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct GlobalShortcut {
+  /// Null for the modifier-only shortcuts.
   pub(crate) carbon_ref: CarbonRef,
   pub(crate) accelerator: Accelerator,
 }
@@ -133,22 +194,157 @@ impl GlobalShortcut {
 
 impl GlobalShortcut {
   pub(crate) fn unregister(&self) {
-    unsafe { unregister_hotkey(self.carbon_ref.0) };
+    if !self.carbon_ref.0.is_null() {
+      unsafe { unregister_hotkey(self.carbon_ref.0) };
+    }
   }
 }
 
 fn make_accelerator_callback<F>(handler: *mut F) -> *mut c_void
 where
-  F: FnMut(i32) + 'static + Sync + Send,
+  F: FnMut(i32, bool) + 'static + Sync + Send,
 {
   let cb = get_trampoline::<F>();
   unsafe { install_event_handler(cb, handler as *mut c_void) }
 }
 
-fn global_accelerator_handler(item_id: i32) {
-  AppState::queue_event(EventWrapper::StaticEvent(Event::GlobalShortcutEvent(
-    AcceleratorId(item_id as u16),
-  )));
+fn global_accelerator_handler(
+  release_shortcuts: &Mutex<HashSet<u16>>,
+  item_id: i32,
+  pressed: bool,
+) {
+  let id = AcceleratorId(item_id as u16);
+  if pressed {
+    AppState::queue_event(EventWrapper::StaticEvent(Event::GlobalShortcutEvent(id)));
+  } else if release_shortcuts.lock().unwrap().contains(&id.0) {
+    AppState::queue_event(EventWrapper::StaticEvent(Event::GlobalShortcutReleased(id)));
+  }
+}
+
+/// Carbon hotkeys need a non-modifier key, modifier-only shortcuts are matched by an event tap
+/// watching the `flagsChanged` events instead, which also reports their release.
+#[derive(Default)]
+struct ModifierShortcuts {
+  /// The shortcuts and whether they're pressed.
+  shortcuts: Rc<RefCell<Vec<(Accelerator, bool)>>>,
+  /// Created with the first shortcut.
+  tap: RefCell<Option<(CGEventTap<'static>, CFRunLoopSource)>>,
+}
+
+impl fmt::Debug for ModifierShortcuts {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("ModifierShortcuts")
+      .field("shortcuts", &self.shortcuts)
+      .finish()
+  }
+}
+
+impl ModifierShortcuts {
+  fn register(&self, accelerator: Accelerator) -> Result<(), ShortcutManagerError> {
+    let mut tap = self.tap.borrow_mut();
+    if tap.is_none() {
+      *tap = Some(self.create_tap().ok_or_else(|| {
+        ShortcutManagerError::InvalidAccelerator(
+          "Failed to watch the modifier keys, modifier-only shortcuts need the accessibility permission".into(),
+        )
+      })?);
+    }
+    self.shortcuts.borrow_mut().push((accelerator, false));
+    Ok(())
+  }
+
+  fn create_tap(&self) -> Option<(CGEventTap<'static>, CFRunLoopSource)> {
+    let shortcuts = self.shortcuts.clone();
+    let tap = CGEventTap::new(
+      CGEventTapLocation::Session,
+      CGEventTapPlacement::HeadInsertEventTap,
+      CGEventTapOptions::ListenOnly,
+      vec![CGEventType::FlagsChanged],
+      move |_, _, event| {
+        handle_flags_changed(&mut shortcuts.borrow_mut(), event);
+        None
+      },
+    )
+    .ok()?;
+    let source = tap.mach_port.create_runloop_source(0).ok()?;
+    unsafe { CFRunLoop::get_main().add_source(&source, kCFRunLoopCommonModes) };
+    tap.enable();
+    Some((tap, source))
+  }
+}
+
+impl Drop for ModifierShortcuts {
+  fn drop(&mut self) {
+    if let Some((tap, source)) = self.tap.borrow_mut().take() {
+      unsafe {
+        CFRunLoop::get_main().remove_source(&source, kCFRunLoopCommonModes);
+        CFMachPortInvalidate(tap.mach_port.as_concrete_TypeRef() as *const c_void);
+      }
+    }
+  }
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+  fn CFMachPortInvalidate(port: *const c_void);
+}
+
+/// The device-dependent flag and the modifier of a modifier key.
+fn modifier_key(key: KeyCode) -> Option<(CGEventFlags, ModifiersState)> {
+  // The device-dependent flags from `IOLLEvent.h`, which tell left and right keys apart
+  let (flag, modifier) = match key {
+    KeyCode::ControlLeft => (0x0000_0001, ModifiersState::CONTROL),
+    KeyCode::ShiftLeft => (0x0000_0002, ModifiersState::SHIFT),
+    KeyCode::ShiftRight => (0x0000_0004, ModifiersState::SHIFT),
+    KeyCode::SuperLeft => (0x0000_0008, ModifiersState::SUPER),
+    KeyCode::SuperRight => (0x0000_0010, ModifiersState::SUPER),
+    KeyCode::AltLeft => (0x0000_0020, ModifiersState::ALT),
+    KeyCode::AltRight => (0x0000_0040, ModifiersState::ALT),
+    KeyCode::ControlRight => (0x0000_2000, ModifiersState::CONTROL),
+    _ => return None,
+  };
+  Some((CGEventFlags::from_bits_truncate(flag), modifier))
+}
+
+/// Compares the shortcuts to the modifiers held after each change, a shortcut is pressed when
+/// its key is held with exactly its modifiers.
+fn handle_flags_changed(shortcuts: &mut [(Accelerator, bool)], event: &CGEvent) {
+  let flags = event.get_flags();
+  let mut held = ModifiersState::empty();
+  held.set(
+    ModifiersState::SHIFT,
+    flags.contains(CGEventFlags::CGEventFlagShift),
+  );
+  held.set(
+    ModifiersState::CONTROL,
+    flags.contains(CGEventFlags::CGEventFlagControl),
+  );
+  held.set(
+    ModifiersState::ALT,
+    flags.contains(CGEventFlags::CGEventFlagAlternate),
+  );
+  held.set(
+    ModifiersState::SUPER,
+    flags.contains(CGEventFlags::CGEventFlagCommand),
+  );
+
+  for (accelerator, pressed) in shortcuts {
+    let (key_flag, modifier) = match modifier_key(accelerator.key) {
+      Some(key) => key,
+      None => continue,
+    };
+    let is_pressed = flags.contains(key_flag) && held == accelerator.mods | modifier;
+    if is_pressed == *pressed {
+      continue;
+    }
+    *pressed = is_pressed;
+    let id = accelerator.clone().id();
+    AppState::queue_event(EventWrapper::StaticEvent(if is_pressed {
+      Event::GlobalShortcutEvent(id)
+    } else {
+      Event::GlobalShortcutReleased(id)
+    }));
+  }
 }
 
 impl Drop for ShortcutManager {
@@ -451,10 +451,22 @@ fn send_event(this: &Object, origin: MenuType) {
     MenuType::ContextMenu => None,
   };
 
+  #[cfg(feature = "tray")]
+  let tray_id = match origin {
+    MenuType::MenuBar => None,
+    MenuType::ContextMenu => unsafe {
+      let menu: id = msg_send![this, menu];
+      super::system_tray::tray_id_of_menu(menu)
+    },
+  };
+  #[cfg(not(feature = "tray"))]
+  let tray_id = None;
+
   let event = Event::MenuEvent {
     window_id,
     menu_id: MenuId(*menu_id.0),
     origin,
+    tray_id,
   };
   AppState::queue_event(EventWrapper::StaticEvent(event));
 }
@@ -92,6 +92,10 @@ impl Window {
 
   #[inline]
   pub fn is_maximized(&self) -> bool {
+    // `isZoomed` is also true in fullscreen, report whether the window is zoomed once it leaves it
+    if let Some(maximized) = self.window.maximized_in_fullscreen() {
+      return maximized;
+    }
     let () = unsafe { msg_send![*self.delegate, markIsCheckingZoomedIn] };
     let f = self.window.is_zoomed();
     let () = unsafe { msg_send![*self.delegate, clearIsCheckingZoomedIn] };
@@ -1,7 +1,11 @@
 // Copyright 2019-2021 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::VecDeque, fmt};
+use std::{
+  collections::VecDeque,
+  fmt,
+  sync::{Mutex, Once},
+};
 
 use super::{ffi, util};
 use crate::{
@@ -145,16 +149,38 @@ impl std::hash::Hash for MonitorHandle {
   }
 }
 
+lazy_static! {
+  // Monitors enumerated by the last `available_monitors` call, until the displays are
+  // reconfigured.
+  static ref MONITORS: Mutex<Option<VecDeque<MonitorHandle>>> = Mutex::new(None);
+}
+
+extern "C" fn display_reconfigured(
+  _display: CGDirectDisplayID,
+  _flags: ffi::CGDisplayChangeSummaryFlags,
+  _user_info: *mut libc::c_void,
+) {
+  *MONITORS.lock().unwrap() = None;
+}
+
 pub fn available_monitors() -> VecDeque<MonitorHandle> {
-  if let Ok(displays) = CGDisplay::active_displays() {
-    let mut monitors = VecDeque::with_capacity(displays.len());
-    for display in displays {
-      monitors.push_back(MonitorHandle(display));
-    }
-    monitors
-  } else {
-    VecDeque::with_capacity(0)
+  static REGISTER_CALLBACK: Once = Once::new();
+  REGISTER_CALLBACK.call_once(|| unsafe {
+    ffi::CGDisplayRegisterReconfigurationCallback(display_reconfigured, std::ptr::null_mut());
+  });
+
+  let mut cache = MONITORS.lock().unwrap();
+  if let Some(monitors) = &*cache {
+    return monitors.clone();
   }
+
+  let displays = match CGDisplay::active_displays() {
+    Ok(displays) => displays,
+    Err(_) => return VecDeque::with_capacity(0),
+  };
+  let monitors: VecDeque<MonitorHandle> = displays.into_iter().map(MonitorHandle).collect();
+  *cache = Some(monitors.clone());
+  monitors
 }
 
 pub fn primary_monitor() -> MonitorHandle {
@@ -99,7 +99,17 @@ pub type CFRunLoopObserverCallBack =
   extern "C" fn(observer: CFRunLoopObserverRef, activity: CFRunLoopActivity, info: *mut c_void);
 pub type CFRunLoopTimerCallBack = extern "C" fn(timer: CFRunLoopTimerRef, info: *mut c_void);
 
-pub enum CFRunLoopTimerContext {}
+/// This mirrors the struct with the same name from Core Foundation.
+/// https://developer.apple.com/documentation/corefoundation/cfrunlooptimercontext?language=objc
+#[allow(non_snake_case)]
+#[repr(C)]
+pub struct CFRunLoopTimerContext {
+  pub version: CFIndex,
+  pub info: *mut c_void,
+  pub retain: Option<extern "C" fn(info: *const c_void) -> *const c_void>,
+  pub release: Option<extern "C" fn(info: *const c_void)>,
+  pub copyDescription: Option<extern "C" fn(info: *const c_void) -> CFStringRef>,
+}
 
 /// This mirrors the struct with the same name from Core Foundation.
 /// https://developer.apple.com/documentation/corefoundation/cfrunloopobservercontext?language=objc
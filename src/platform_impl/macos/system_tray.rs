@@ -10,17 +10,17 @@ use super::{
 use crate::{
   dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize},
   error::OsError,
-  event::{Event, Rectangle, TrayEvent},
+  event::{Event, Rectangle, TrayEvent, TrayId},
   event_loop::EventLoopWindowTarget,
   system_tray::SystemTray as RootSystemTray,
 };
 use cocoa::{
   appkit::{
-    NSButton, NSEventMask, NSEventModifierFlags, NSEventType, NSImage, NSSquareStatusItemLength,
-    NSStatusBar, NSStatusItem, NSWindow,
+    NSButton, NSCellImagePosition, NSEventMask, NSEventModifierFlags, NSEventType, NSImage,
+    NSSquareStatusItemLength, NSStatusBar, NSStatusItem, NSVariableStatusItemLength, NSWindow,
   },
-  base::{id, nil, NO, YES},
-  foundation::{NSAutoreleasePool, NSData, NSPoint, NSSize},
+  base::{id, nil, BOOL, NO, YES},
+  foundation::{NSAutoreleasePool, NSData, NSPoint, NSSize, NSString},
 };
 use objc::{
   declare::ClassDecl,
@@ -29,6 +29,7 @@ use objc::{
 use std::sync::Once;
 
 pub struct SystemTrayBuilder {
+  pub(crate) id: TrayId,
   pub(crate) system_tray: SystemTray,
 }
 
@@ -42,11 +43,15 @@ impl SystemTrayBuilder {
         .autorelease();
 
       Self {
+        id: TrayId::EMPTY,
         system_tray: SystemTray {
+          id: TrayId::EMPTY,
           icon_is_template: false,
           icon,
+          title: None,
           tray_menu,
           ns_status_bar,
+          tray_target: nil,
         },
       }
     }
@@ -55,9 +60,10 @@ impl SystemTrayBuilder {
   /// Builds the system tray.
   #[inline]
   pub fn build<T: 'static>(
-    self,
+    mut self,
     _window_target: &EventLoopWindowTarget<T>,
   ) -> Result<RootSystemTray, OsError> {
+    self.system_tray.id = self.id;
     unsafe {
       // use our existing status bar
       let status_bar = self.system_tray.ns_status_bar;
@@ -65,14 +71,21 @@ impl SystemTrayBuilder {
       // set our icon
       self.system_tray.create_button_with_icon();
 
+      // set our title
+      if let Some(title) = &self.system_tray.title {
+        self.system_tray.set_title(title);
+      }
+
       // attach click event to our button
       let button = status_bar.button();
       let tray_target: id = msg_send![make_tray_class(), alloc];
       let tray_target: id = msg_send![tray_target, init];
       (*tray_target).set_ivar("status_bar", status_bar);
       (*tray_target).set_ivar("menu", nil);
+      (*tray_target).set_ivar("tray_id", self.id.0);
       let _: () = msg_send![button, setAction: sel!(click:)];
       let _: () = msg_send![button, setTarget: tray_target];
+      self.system_tray.tray_target = tray_target;
       let _: () = msg_send![
         button,
         sendActionOn: NSEventMask::NSLeftMouseDownMask
@@ -97,26 +110,71 @@ impl SystemTrayBuilder {
 /// System tray is a status icon that can show popup menu. It is usually displayed on top right or bottom right of the screen.
 #[derive(Debug, Clone)]
 pub struct SystemTray {
+  pub(crate) id: TrayId,
   pub(crate) icon: Vec<u8>,
   pub(crate) icon_is_template: bool,
+  pub(crate) title: Option<String>,
   pub(crate) tray_menu: Option<Menu>,
   pub(crate) ns_status_bar: id,
+  /// The `TaoTrayHandler` of the button, also the delegate of the menu
+  pub(crate) tray_target: id,
 }
 
 impl SystemTray {
+  pub fn id(&self) -> TrayId {
+    self.id
+  }
+
   pub fn set_icon(&mut self, icon: Vec<u8>) {
     // update our icon
     self.icon = icon;
     self.create_button_with_icon();
   }
 
-  pub fn set_icon_as_template(mut self, is_template: bool) {
+  pub fn set_icon_as_template(&mut self, is_template: bool) {
     self.icon_is_template = is_template;
+    unsafe {
+      let button = self.ns_status_bar.button();
+      let nsimage: id = msg_send![button, image];
+      if nsimage != nil {
+        let _: () = msg_send![nsimage, setTemplate: if is_template { YES } else { NO }];
+        // force the button to redraw with the updated image
+        button.setImage_(nsimage);
+      }
+    }
+  }
+
+  pub fn set_title(&mut self, title: &str) {
+    self.title = Some(title.to_string());
+    unsafe {
+      let status_item = self.ns_status_bar;
+      let button = status_item.button();
+
+      // A square status item only has room for the icon, let it grow to fit the title.
+      let length = if title.is_empty() {
+        NSSquareStatusItemLength
+      } else {
+        NSVariableStatusItemLength
+      };
+      let _: () = msg_send![status_item, setLength: length];
+
+      let _: () = msg_send![button, setImagePosition: NSCellImagePosition::NSImageLeft];
+      let _: () = msg_send![button, setTitle: NSString::alloc(nil).init_str(title).autorelease()];
+    }
+  }
+
+  pub fn set_visible(&mut self, visible: bool) {
+    unsafe {
+      let _: () = msg_send![self.ns_status_bar, setVisible: if visible { YES } else { NO }];
+    }
   }
 
   pub fn set_menu(&mut self, tray_menu: &Menu) {
     unsafe {
-      self.ns_status_bar.setMenu_(tray_menu.menu);
+      // Like in `SystemTrayBuilder::build`, the menu is shown by the tray handler, which is also
+      // how a click on it is traced back to this tray.
+      (*self.tray_target).set_ivar("menu", tray_menu.menu);
+      let () = msg_send![tray_menu.menu, setDelegate: self.tray_target];
     }
   }
 
@@ -149,6 +207,31 @@ impl SystemTray {
   }
 }
 
+/// Returns the id of the tray whose menu contains `menu`, if any.
+///
+/// The top level menu of a tray has the tray handler as delegate, see `SystemTrayBuilder::build`.
+pub(crate) unsafe fn tray_id_of_menu(mut menu: id) -> Option<TrayId> {
+  while menu != nil {
+    let supermenu: id = msg_send![menu, supermenu];
+    if supermenu == nil {
+      break;
+    }
+    menu = supermenu;
+  }
+  if menu == nil {
+    return None;
+  }
+  let delegate: id = msg_send![menu, delegate];
+  if delegate == nil {
+    return None;
+  }
+  let is_tray: BOOL = msg_send![delegate, isKindOfClass: make_tray_class()];
+  if is_tray == NO {
+    return None;
+  }
+  Some(TrayId(*(*delegate).get_ivar::<u16>("tray_id")))
+}
+
 /// Create a `TrayHandler` Class that handle button click event and also menu opening and closing.
 ///
 /// We set the tray menu to tray_target instead of status bar, because setting directly to status bar
@@ -164,6 +247,7 @@ fn make_tray_class() -> *const Class {
     let mut decl = ClassDecl::new("TaoTrayHandler", superclass).unwrap();
     decl.add_ivar::<id>("status_bar");
     decl.add_ivar::<id>("menu");
+    decl.add_ivar::<u16>("tray_id");
     decl.add_method(
       sel!(click:),
       perform_tray_click as extern "C" fn(&mut Object, _, id),
@@ -220,6 +304,7 @@ extern "C" fn perform_tray_click(this: &mut Object, _: Sel, button: id) {
 
     if let Some(event) = click_type {
       let event = Event::TrayEvent {
+        id: TrayId(*this.get_ivar::<u16>("tray_id")),
         bounds: Rectangle { position, size },
         position: PhysicalPosition::new(
           mouse_location.x,
@@ -3,13 +3,15 @@
 
 use std::{
   ops::Deref,
-  sync::{Mutex, Weak},
+  sync::{Arc, Mutex, Weak},
 };
 
 use cocoa::{
-  appkit::{CGFloat, NSScreen, NSWindow, NSWindowStyleMask},
+  appkit::{
+    CGFloat, NSColor, NSScreen, NSWindow, NSWindowButton, NSWindowOrderingMode, NSWindowStyleMask,
+  },
   base::{id, nil},
-  foundation::{NSPoint, NSSize, NSString},
+  foundation::{NSInteger, NSPoint, NSRect, NSSize, NSString, NSUInteger},
 };
 use dispatch::Queue;
 use objc::{
@@ -20,6 +22,7 @@ use objc::{
 use crate::{
   dpi::LogicalSize,
   event::{Event, WindowEvent},
+  platform::macos::Vibrancy,
   platform_impl::platform::{
     app_state::AppState,
     event::EventWrapper,
@@ -27,9 +30,12 @@ use crate::{
     util::IdRef,
     window::{get_window_id, SharedState},
   },
-  window::WindowId,
+  window::{WindowButtons, WindowId},
 };
 
+// `NSWindowAnimationBehaviorNone`
+const NS_WINDOW_ANIMATION_BEHAVIOR_NONE: NSInteger = 2;
+
 // Unsafe wrapper type that allows us to dispatch things that aren't Send.
 // This should *only* be used to dispatch to the main queue.
 // While it is indeed not guaranteed that these types can safely be sent to
@@ -85,6 +91,18 @@ pub unsafe fn set_content_size_async(ns_window: id, size: LogicalSize<f64>) {
   });
 }
 
+pub unsafe fn set_content_size_sync(ns_window: id, size: LogicalSize<f64>) {
+  let is_main_thread: BOOL = msg_send!(class!(NSThread), isMainThread);
+  if is_main_thread != NO {
+    ns_window.setContentSize_(NSSize::new(size.width as CGFloat, size.height as CGFloat));
+  } else {
+    let ns_window = MainThreadSafe(ns_window);
+    Queue::main().exec_sync(move || {
+      ns_window.setContentSize_(NSSize::new(size.width as CGFloat, size.height as CGFloat));
+    })
+  }
+}
+
 // `setFrameTopLeftPoint:` isn't thread-safe, but fortunately has the courtesy
 // to log errors.
 pub unsafe fn set_frame_top_left_point_async(ns_window: id, point: NSPoint) {
@@ -146,6 +164,45 @@ pub unsafe fn restore_display_mode_async(ns_screen: u32) {
   });
 }
 
+unsafe fn set_maximized(
+  ns_window: id,
+  is_zoomed: bool,
+  maximized: bool,
+  shared_state: Weak<Mutex<SharedState>>,
+) {
+  if let Some(shared_state) = shared_state.upgrade() {
+    trace!("Locked shared state in `set_maximized`");
+    let mut shared_state_lock = shared_state.lock().unwrap();
+
+    // Save the standard frame sized if it is not zoomed
+    if !is_zoomed {
+      shared_state_lock.standard_frame = Some(NSWindow::frame(ns_window));
+    }
+
+    shared_state_lock.maximized = maximized;
+
+    let curr_mask = ns_window.styleMask();
+    if shared_state_lock.fullscreen.is_some() {
+      // Handle it in window_did_exit_fullscreen
+      return;
+    } else if curr_mask.contains(NSWindowStyleMask::NSResizableWindowMask) {
+      // Just use the native zoom if resizable
+      ns_window.zoom_(nil);
+    } else {
+      // if it's not resizable, we set the frame directly
+      let new_rect = if maximized {
+        let screen = NSScreen::mainScreen(nil);
+        NSScreen::visibleFrame(screen)
+      } else {
+        shared_state_lock.saved_standard_frame()
+      };
+      ns_window.setFrame_display_(new_rect, NO);
+    }
+
+    trace!("Unlocked shared state in `set_maximized`");
+  }
+}
+
 // `setMaximized` is not thread-safe
 pub unsafe fn set_maximized_async(
   ns_window: id,
@@ -156,36 +213,42 @@ pub unsafe fn set_maximized_async(
   let ns_window = MainThreadSafe(ns_window);
   let shared_state = MainThreadSafe(shared_state);
   Queue::main().exec_async(move || {
-    if let Some(shared_state) = shared_state.upgrade() {
-      trace!("Locked shared state in `set_maximized`");
-      let mut shared_state_lock = shared_state.lock().unwrap();
-
-      // Save the standard frame sized if it is not zoomed
-      if !is_zoomed {
-        shared_state_lock.standard_frame = Some(NSWindow::frame(*ns_window));
-      }
-
-      shared_state_lock.maximized = maximized;
+    let MainThreadSafe(shared_state) = shared_state;
+    set_maximized(*ns_window, is_zoomed, maximized, shared_state);
+  });
+}
 
-      let curr_mask = ns_window.styleMask();
-      if shared_state_lock.fullscreen.is_some() {
-        // Handle it in window_did_exit_fullscreen
-        return;
-      } else if curr_mask.contains(NSWindowStyleMask::NSResizableWindowMask) {
-        // Just use the native zoom if resizable
-        ns_window.zoom_(nil);
-      } else {
-        // if it's not resizable, we set the frame directly
-        let new_rect = if maximized {
-          let screen = NSScreen::mainScreen(nil);
-          NSScreen::visibleFrame(screen)
-        } else {
-          shared_state_lock.saved_standard_frame()
-        };
-        ns_window.setFrame_display_(new_rect, NO);
-      }
+// Unlike `set_maximized_async`, the new frame is applied once this returns, so the window
+// size can be read right after.
+pub unsafe fn set_maximized_sync(
+  ns_window: id,
+  is_zoomed: bool,
+  maximized: bool,
+  shared_state: Weak<Mutex<SharedState>>,
+) {
+  let is_main_thread: BOOL = msg_send!(class!(NSThread), isMainThread);
+  if is_main_thread != NO {
+    set_maximized(ns_window, is_zoomed, maximized, shared_state);
+  } else {
+    let ns_window = MainThreadSafe(ns_window);
+    let shared_state = MainThreadSafe(shared_state);
+    Queue::main().exec_sync(move || {
+      let MainThreadSafe(shared_state) = shared_state;
+      set_maximized(*ns_window, is_zoomed, maximized, shared_state);
+    })
+  }
+}
 
-      trace!("Unlocked shared state in `set_maximized`");
+// `setOpaque:` and `setBackgroundColor:` aren't thread-safe.
+pub unsafe fn set_transparent_async(ns_window: id, transparent: bool) {
+  let ns_window = MainThreadSafe(ns_window);
+  Queue::main().exec_async(move || {
+    if transparent {
+      ns_window.setOpaque_(NO);
+      ns_window.setBackgroundColor_(NSColor::clearColor(nil));
+    } else {
+      ns_window.setOpaque_(YES);
+      ns_window.setBackgroundColor_(msg_send![class!(NSColor), windowBackgroundColor]);
     }
   });
 }
@@ -208,6 +271,22 @@ pub unsafe fn make_key_and_order_front_async(ns_window: id) {
   });
 }
 
+// Shows or hides the window like `make_key_and_order_front_async` and `order_out_async`, without
+// the animation of its `animationBehavior`
+pub unsafe fn set_visible_without_animation_async(ns_window: id, visible: bool) {
+  let ns_window = MainThreadSafe(ns_window);
+  Queue::main().exec_async(move || {
+    let behavior: NSInteger = msg_send![*ns_window, animationBehavior];
+    let () = msg_send![*ns_window, setAnimationBehavior: NS_WINDOW_ANIMATION_BEHAVIOR_NONE];
+    if visible {
+      ns_window.makeKeyAndOrderFront_(nil);
+    } else {
+      ns_window.orderOut_(nil);
+    }
+    let () = msg_send![*ns_window, setAnimationBehavior: behavior];
+  });
+}
+
 // `setTitle:` isn't thread-safe. Calling it from another thread invalidates the
 // window drag regions, which throws an exception when not done in the main
 // thread
@@ -219,6 +298,87 @@ pub unsafe fn set_title_async(ns_window: id, title: String) {
   });
 }
 
+// The standard window buttons are views, and AppKit enables the zoom button again whenever
+// the resizable bit of the style mask changes, so this must follow every `set_style_mask`.
+// The zoom button stays disabled on a non-resizable window.
+pub unsafe fn set_enabled_buttons(ns_window: id, buttons: WindowButtons) {
+  let resizable = ns_window
+    .styleMask()
+    .contains(NSWindowStyleMask::NSResizableWindowMask);
+  for (titlebar_button, button, enabled) in &[
+    (
+      NSWindowButton::NSWindowCloseButton,
+      WindowButtons::CLOSE,
+      true,
+    ),
+    (
+      NSWindowButton::NSWindowMiniaturizeButton,
+      WindowButtons::MINIMIZE,
+      true,
+    ),
+    (
+      NSWindowButton::NSWindowZoomButton,
+      WindowButtons::MAXIMIZE,
+      resizable,
+    ),
+  ] {
+    let titlebar_button = ns_window.standardWindowButton_(*titlebar_button);
+    let enabled = if *enabled && buttons.contains(*button) {
+      YES
+    } else {
+      NO
+    };
+    let _: () = msg_send![titlebar_button, setEnabled: enabled];
+  }
+}
+
+pub unsafe fn set_enabled_buttons_async(ns_window: id, buttons: WindowButtons) {
+  let ns_window = MainThreadSafe(ns_window);
+  Queue::main().exec_async(move || {
+    set_enabled_buttons(*ns_window, buttons);
+  });
+}
+
+/// Adds an `NSVisualEffectView` behind `ns_view`, which blurs what is behind the window. A subview
+/// of `ns_view` would be drawn over its content, so it's a sibling placed below it.
+unsafe fn add_blur_view(ns_view: id, vibrancy: Vibrancy) -> IdRef {
+  let superview: id = msg_send![ns_view, superview];
+  let frame: NSRect = msg_send![ns_view, frame];
+  let blur_view: id = msg_send![class!(NSVisualEffectView), alloc];
+  let blur_view = IdRef::new(msg_send![blur_view, initWithFrame: frame]);
+  let _: () = msg_send![*blur_view, setMaterial: vibrancy.material as NSInteger];
+  let _: () = msg_send![*blur_view, setBlendingMode: vibrancy.blending_mode as NSInteger];
+  let _: () = msg_send![*blur_view, setState: vibrancy.state as NSInteger];
+  // NSViewWidthSizable | NSViewHeightSizable
+  let _: () = msg_send![*blur_view, setAutoresizingMask: (2 | 16) as NSUInteger];
+  let _: () = msg_send![
+    superview,
+    addSubview: *blur_view
+    positioned: NSWindowOrderingMode::NSWindowBelow
+    relativeTo: ns_view
+  ];
+  blur_view
+}
+
+// Views can only be added and removed on the main thread.
+pub unsafe fn set_blur_view_async(
+  ns_view: id,
+  blur_view: Arc<Mutex<Option<IdRef>>>,
+  vibrancy: Option<Vibrancy>,
+) {
+  let ns_view = MainThreadSafe(ns_view);
+  let blur_view = MainThreadSafe(blur_view);
+  Queue::main().exec_async(move || {
+    let mut blur_view = blur_view.lock().unwrap();
+    if let Some(view) = blur_view.take() {
+      let _: () = msg_send![*view, removeFromSuperview];
+    }
+    if let Some(vibrancy) = vibrancy {
+      *blur_view = Some(add_blur_view(*ns_view, vibrancy));
+    }
+  });
+}
+
 // `setFocus:` isn't thread-safe.
 pub unsafe fn set_focus(ns_window: id) {
   let ns_window = MainThreadSafe(ns_window);
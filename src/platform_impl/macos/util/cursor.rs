@@ -4,17 +4,20 @@
 use cocoa::{
   appkit::NSImage,
   base::{id, nil},
-  foundation::{NSDictionary, NSPoint, NSString},
+  foundation::{NSDictionary, NSInteger, NSPoint, NSSize, NSString, NSUInteger},
 };
-use objc::runtime::{Sel, NO};
-use std::cell::RefCell;
+use objc::runtime::{Sel, NO, YES};
+use std::{cell::RefCell, ptr};
 
-use crate::window::CursorIcon;
+use super::IdRef;
+use crate::{dpi::PhysicalPosition, window::CursorIcon};
 
 pub enum Cursor {
   Native(&'static str),
   Undocumented(&'static str),
   WebKit(&'static str),
+  /// A `NSCursor` made from an image, see `custom_cursor`.
+  Custom(IdRef),
 }
 
 impl From<CursorIcon> for Cursor {
@@ -96,27 +99,74 @@ impl Cursor {
         msg_send![class, performSelector: sel]
       }
       Cursor::WebKit(cursor_name) => load_webkit_cursor(cursor_name),
+      Cursor::Custom(cursor) => **cursor,
     }
   }
 }
 
+/// `NSBitmapFormatAlphaNonpremultiplied`
+const ALPHA_NONPREMULTIPLIED: NSUInteger = 1 << 1;
+
+/// Creates a `NSCursor` from 32bpp RGBA data, with one point per pixel.
+pub unsafe fn custom_cursor(
+  rgba: &[u8],
+  width: u32,
+  height: u32,
+  hotspot: PhysicalPosition<u32>,
+) -> IdRef {
+  let color_space = NSString::alloc(nil).init_str("NSDeviceRGBColorSpace");
+  let bitmap: id = msg_send![class!(NSBitmapImageRep), alloc];
+  let bitmap: id = msg_send![bitmap,
+      initWithBitmapDataPlanes:ptr::null_mut::<*mut u8>()
+      pixelsWide:width as NSInteger
+      pixelsHigh:height as NSInteger
+      bitsPerSample:8 as NSInteger
+      samplesPerPixel:4 as NSInteger
+      hasAlpha:YES
+      isPlanar:NO
+      colorSpaceName:color_space
+      bitmapFormat:ALPHA_NONPREMULTIPLIED
+      bytesPerRow:(width * 4) as NSInteger
+      bitsPerPixel:32 as NSInteger
+  ];
+  let _: () = msg_send![color_space, release];
+  let data: *mut u8 = msg_send![bitmap, bitmapData];
+  ptr::copy_nonoverlapping(rgba.as_ptr(), data, rgba.len());
+
+  let image = NSImage::alloc(nil).initWithSize_(NSSize::new(width as f64, height as f64));
+  image.addRepresentation_(bitmap);
+  let _: () = msg_send![bitmap, release];
+
+  let cursor: id = msg_send![class!(NSCursor), alloc];
+  let cursor: id = msg_send![cursor,
+      initWithImage:image
+      hotSpot:NSPoint::new(hotspot.x as f64, hotspot.y as f64)
+  ];
+  let _: () = msg_send![image, release];
+  IdRef::new(cursor)
+}
+
 // Note that loading `busybutclickable` with this code won't animate the frames;
 // instead you'll just get them all in a column.
 pub unsafe fn load_webkit_cursor(cursor_name: &str) -> id {
   static CURSOR_ROOT: &str = "/System/Library/Frameworks/ApplicationServices.framework/Versions/A/Frameworks/HIServices.framework/Versions/A/Resources/cursors";
   let cursor_root = NSString::alloc(nil).init_str(CURSOR_ROOT);
-  let cursor_name = NSString::alloc(nil).init_str(cursor_name);
+  let ns_cursor_name = NSString::alloc(nil).init_str(cursor_name);
   let cursor_pdf = NSString::alloc(nil).init_str("cursor.pdf");
   let cursor_plist = NSString::alloc(nil).init_str("info.plist");
   let key_x = NSString::alloc(nil).init_str("hotx");
   let key_y = NSString::alloc(nil).init_str("hoty");
 
-  let cursor_path: id = msg_send![cursor_root, stringByAppendingPathComponent: cursor_name];
+  let cursor_path: id = msg_send![cursor_root, stringByAppendingPathComponent: ns_cursor_name];
   let pdf_path: id = msg_send![cursor_path, stringByAppendingPathComponent: cursor_pdf];
   let info_path: id = msg_send![cursor_path, stringByAppendingPathComponent: cursor_plist];
 
   let image = NSImage::alloc(nil).initByReferencingFile_(pdf_path);
   let info = NSDictionary::dictionaryWithContentsOfFile_(nil, info_path);
+  if image == nil || info == nil {
+    warn!("Cursor `{}` could not be loaded from WebKit", cursor_name);
+    return msg_send![class!(NSCursor), arrowCursor];
+  }
   let x = info.valueForKey_(key_x);
   let y = info.valueForKey_(key_y);
   let point = NSPoint::new(msg_send![x, doubleValue], msg_send![y, doubleValue]);
@@ -7,6 +7,7 @@ use std::{
   os::raw::*,
   ptr, slice, str,
   sync::{Arc, Mutex, Weak},
+  time::Duration,
 };
 
 use cocoa::{
@@ -25,10 +26,17 @@ use crate::{
     DeviceEvent, ElementState, Event, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent,
   },
   keyboard::{KeyCode, ModifiersState},
+  platform::macos::OptionAsAlt,
   platform_impl::platform::{
     app_state::AppState,
     event::{code_to_key, create_key_event, event_mods, get_scancode, EventWrapper},
+    event_loop::catch_panic,
     ffi::*,
+    observer::{
+      kCFRunLoopCommonModes, CFAbsoluteTimeGetCurrent, CFRelease, CFRunLoopAddTimer,
+      CFRunLoopGetMain, CFRunLoopTimerContext, CFRunLoopTimerCreate, CFRunLoopTimerInvalidate,
+      CFRunLoopTimerRef, CFRunLoopTimerSetNextFireDate,
+    },
     util::{self, IdRef},
     window::get_window_id,
     DEVICE_ID,
@@ -39,6 +47,8 @@ use crate::{
 pub struct CursorState {
   pub visible: bool,
   pub cursor: util::Cursor,
+  /// Cycles the frames of an animated custom cursor, stopped when dropped.
+  pub animation: Option<CursorAnimation>,
 }
 
 impl Default for CursorState {
@@ -46,14 +56,104 @@ impl Default for CursorState {
     Self {
       visible: true,
       cursor: Default::default(),
+      animation: None,
     }
   }
 }
 
+/// A timer setting the frames of an animated custom cursor one after the other as the cursor of
+/// a view.
+pub struct CursorAnimation {
+  timer: CFRunLoopTimerRef,
+  frames: Vec<(IdRef, Duration)>,
+  current: usize,
+}
+
+/// The view animated by a `CursorAnimation`, owned by its timer.
+struct CursorAnimationTarget {
+  ns_view: id,
+  cursor_state: Weak<Mutex<CursorState>>,
+}
+
+impl CursorAnimation {
+  /// Starts cycling `frames` from the first one, which must already be the cursor of `ns_view`.
+  pub fn start(
+    ns_view: id,
+    cursor_state: Weak<Mutex<CursorState>>,
+    frames: Vec<(IdRef, Duration)>,
+  ) -> Self {
+    extern "C" fn release_target(info: *const c_void) {
+      drop(unsafe { Box::from_raw(info as *mut CursorAnimationTarget) });
+    }
+
+    let target = Box::new(CursorAnimationTarget {
+      ns_view,
+      cursor_state,
+    });
+    let mut context = CFRunLoopTimerContext {
+      version: 0,
+      info: Box::into_raw(target) as *mut c_void,
+      retain: None,
+      release: Some(release_target),
+      copyDescription: None,
+    };
+    unsafe {
+      // The fire date is set again after each frame, the interval only keeps the timer valid.
+      let timer = CFRunLoopTimerCreate(
+        ptr::null_mut(),
+        CFAbsoluteTimeGetCurrent() + frames[0].1.as_secs_f64(),
+        std::f64::MAX,
+        0,
+        0,
+        next_cursor_frame,
+        &mut context,
+      );
+      CFRunLoopAddTimer(CFRunLoopGetMain(), timer, kCFRunLoopCommonModes);
+      CursorAnimation {
+        timer,
+        frames,
+        current: 0,
+      }
+    }
+  }
+}
+
+impl Drop for CursorAnimation {
+  fn drop(&mut self) {
+    unsafe {
+      CFRunLoopTimerInvalidate(self.timer);
+      CFRelease(self.timer as _);
+    }
+  }
+}
+
+extern "C" fn next_cursor_frame(timer: CFRunLoopTimerRef, info: *mut c_void) {
+  let target = unsafe { &*(info as *const CursorAnimationTarget) };
+  let cursor_access = match target.cursor_state.upgrade() {
+    Some(cursor_access) => cursor_access,
+    None => return,
+  };
+  let mut cursor_state = cursor_access.lock().unwrap();
+  let animation = match cursor_state.animation.as_mut() {
+    Some(animation) if animation.timer == timer => animation,
+    _ => return,
+  };
+  animation.current = (animation.current + 1) % animation.frames.len();
+  let (cursor, duration) = animation.frames[animation.current].clone();
+  cursor_state.cursor = util::Cursor::Custom(cursor);
+  // `resetCursorRects` locks the cursor state
+  drop(cursor_state);
+  unsafe {
+    CFRunLoopTimerSetNextFireDate(timer, CFAbsoluteTimeGetCurrent() + duration.as_secs_f64());
+    let ns_window: id = msg_send![target.ns_view, window];
+    let _: () = msg_send![ns_window, invalidateCursorRectsForView: target.ns_view];
+  }
+}
+
 pub(super) struct ViewState {
   ns_window: id,
   pub cursor_state: Arc<Mutex<CursorState>>,
-  ime_spot: Option<(f64, f64)>,
+  ime_cursor_area: Option<NSRect>,
 
   /// This is true when we are currently modifying a marked text
   /// using ime. When the text gets commited, this is set to false.
@@ -69,6 +169,10 @@ pub(super) struct ViewState {
   pub(super) modifiers: ModifiersState,
   phys_modifiers: HashSet<KeyCode>,
   tracking_rect: Option<NSInteger>,
+  option_as_alt: OptionAsAlt,
+  accepts_first_mouse: bool,
+  /// The rectangles receiving mouse events, from the top-left corner of the view.
+  hittest_region: Option<Vec<NSRect>>,
 }
 
 impl ViewState {
@@ -77,19 +181,22 @@ impl ViewState {
   }
 }
 
-pub fn new_view(ns_window: id) -> (IdRef, Weak<Mutex<CursorState>>) {
+pub fn new_view(ns_window: id, accepts_first_mouse: bool) -> (IdRef, Weak<Mutex<CursorState>>) {
   let cursor_state = Default::default();
   let cursor_access = Arc::downgrade(&cursor_state);
   let state = ViewState {
     ns_window,
     cursor_state,
-    ime_spot: None,
+    ime_cursor_area: None,
     in_ime_preedit: false,
     key_triggered_ime: false,
     is_key_down: false,
     modifiers: Default::default(),
     phys_modifiers: Default::default(),
     tracking_rect: None,
+    option_as_alt: Default::default(),
+    accepts_first_mouse,
+    hittest_region: None,
   };
   unsafe {
     // This is free'd in `dealloc`
@@ -102,17 +209,58 @@ pub fn new_view(ns_window: id) -> (IdRef, Weak<Mutex<CursorState>>) {
   }
 }
 
-pub unsafe fn set_ime_position(ns_view: id, input_context: id, x: f64, y: f64) {
+pub unsafe fn set_ime_cursor_area(
+  ns_view: id,
+  input_context: id,
+  x: f64,
+  y: f64,
+  width: f64,
+  height: f64,
+) {
   let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
   let state = &mut *(state_ptr as *mut ViewState);
   let content_rect =
     NSWindow::contentRectForFrameRect_(state.ns_window, NSWindow::frame(state.ns_window));
   let base_x = content_rect.origin.x as f64;
   let base_y = (content_rect.origin.y + content_rect.size.height) as f64;
-  state.ime_spot = Some((base_x + x, base_y - y));
+  // Screen coordinates have their origin at the bottom left.
+  state.ime_cursor_area = Some(NSRect::new(
+    NSPoint::new(base_x + x, base_y - y - height),
+    NSSize::new(width, height),
+  ));
   let _: () = msg_send![input_context, invalidateCharacterCoordinates];
 }
 
+pub unsafe fn set_option_as_alt(ns_view: id, option_as_alt: OptionAsAlt) {
+  let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
+  let state = &mut *(state_ptr as *mut ViewState);
+  state.option_as_alt = option_as_alt;
+}
+
+pub unsafe fn option_as_alt(ns_view: id) -> OptionAsAlt {
+  let state_ptr: *mut c_void = *(*ns_view).get_ivar("taoState");
+  let state = &*(state_ptr as *const ViewState);
+  state.option_as_alt
+}
+
+pub unsafe fn set_hittest_region(ns_view: id, region: Option<Vec<NSRect>>) {
+  let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
+  let state = &mut *(state_ptr as *mut ViewState);
+  state.hittest_region = region;
+}
+
+/// Whether a held Option key is configured to act as Alt.
+fn is_option_as_alt(state: &ViewState) -> bool {
+  let left = state.phys_modifiers.contains(&KeyCode::AltLeft);
+  let right = state.phys_modifiers.contains(&KeyCode::AltRight);
+  match state.option_as_alt {
+    OptionAsAlt::None => false,
+    OptionAsAlt::OnlyLeft => left,
+    OptionAsAlt::OnlyRight => right,
+    OptionAsAlt::Both => left || right,
+  }
+}
+
 fn is_arrow_key(keycode: KeyCode) -> bool {
   matches!(
     keycode,
@@ -294,6 +442,10 @@ lazy_static! {
       sel!(acceptsFirstMouse:),
       accepts_first_mouse as extern "C" fn(&Object, Sel, id) -> BOOL,
     );
+    decl.add_method(
+      sel!(hitTest:),
+      hit_test as extern "C" fn(&Object, Sel, NSPoint) -> id,
+    );
     decl.add_ivar::<*mut c_void>("taoState");
     decl.add_ivar::<id>("markedText");
     let protocol = Protocol::get("NSTextInputClient").unwrap();
@@ -383,7 +535,8 @@ extern "C" fn draw_rect(this: &Object, _sel: Sel, rect: NSRect) {
     let state_ptr: *mut c_void = *this.get_ivar("taoState");
     let state = &mut *(state_ptr as *mut ViewState);
 
-    AppState::handle_redraw(WindowId(get_window_id(state.ns_window)));
+    let window_id = WindowId(get_window_id(state.ns_window));
+    catch_panic(|| AppState::handle_redraw(window_id));
 
     let superclass = util::superclass(this);
     let () = msg_send![super(this, superclass), drawRect: rect];
@@ -520,15 +673,15 @@ extern "C" fn first_rect_for_character_range(
     trace!("Triggered `firstRectForCharacterRange`");
     let state_ptr: *mut c_void = *this.get_ivar("taoState");
     let state = &mut *(state_ptr as *mut ViewState);
-    let (x, y) = state.ime_spot.unwrap_or_else(|| {
+    let rect = state.ime_cursor_area.unwrap_or_else(|| {
       let content_rect =
         NSWindow::contentRectForFrameRect_(state.ns_window, NSWindow::frame(state.ns_window));
       let x = content_rect.origin.x;
       let y = util::bottom_left_to_top_left(content_rect);
-      (x, y)
+      NSRect::new(NSPoint::new(x as _, y as _), NSSize::new(0.0, 0.0))
     });
     trace!("Completed `firstRectForCharacterRange`");
-    NSRect::new(NSPoint::new(x as _, y as _), NSSize::new(0.0, 0.0))
+    rect
   }
 }
 
@@ -695,7 +848,8 @@ extern "C" fn key_down(this: &mut Object, _sel: Sel, event: id) {
 
     update_potentially_stale_modifiers(state, event);
 
-    let pass_along = !is_repeat || !state.is_key_down;
+    // Option acting as Alt must not compose characters, so the text input system is skipped.
+    let pass_along = (!is_repeat || !state.is_key_down) && !is_option_as_alt(state);
     if pass_along {
       // See below for why we do this.
       clear_marked_text(this);
@@ -979,14 +1133,35 @@ extern "C" fn right_mouse_up(this: &Object, _sel: Sel, event: id) {
   mouse_click(this, event, MouseButton::Right, ElementState::Released);
 }
 
+/// Maps the `buttonNumber` of an `otherMouse` event, which is `2` for the middle button and
+/// `3`/`4` for the back/forward thumb buttons.
+fn other_mouse_button(event: id) -> MouseButton {
+  match unsafe { event.buttonNumber() } {
+    2 => MouseButton::Middle,
+    3 => MouseButton::Back,
+    4 => MouseButton::Forward,
+    button => MouseButton::Other(button as u16),
+  }
+}
+
 extern "C" fn other_mouse_down(this: &Object, _sel: Sel, event: id) {
   mouse_motion(this, event);
-  mouse_click(this, event, MouseButton::Middle, ElementState::Pressed);
+  mouse_click(
+    this,
+    event,
+    other_mouse_button(event),
+    ElementState::Pressed,
+  );
 }
 
 extern "C" fn other_mouse_up(this: &Object, _sel: Sel, event: id) {
   mouse_motion(this, event);
-  mouse_click(this, event, MouseButton::Middle, ElementState::Released);
+  mouse_click(
+    this,
+    event,
+    other_mouse_button(event),
+    ElementState::Released,
+  );
 }
 
 fn mouse_motion(this: &Object, event: id) {
@@ -1106,8 +1281,16 @@ extern "C" fn scroll_wheel(this: &Object, _sel: Sel, event: id) {
     let phase = match event.phase() {
       NSEventPhase::NSEventPhaseMayBegin | NSEventPhase::NSEventPhaseBegan => TouchPhase::Started,
       NSEventPhase::NSEventPhaseEnded => TouchPhase::Ended,
+      NSEventPhase::NSEventPhaseCancelled => TouchPhase::Cancelled,
       _ => TouchPhase::Moved,
     };
+    let momentum_phase = match event.momentumPhase() {
+      NSEventPhase::NSEventPhaseBegan => Some(TouchPhase::Started),
+      NSEventPhase::NSEventPhaseChanged => Some(TouchPhase::Moved),
+      NSEventPhase::NSEventPhaseEnded => Some(TouchPhase::Ended),
+      NSEventPhase::NSEventPhaseCancelled => Some(TouchPhase::Cancelled),
+      _ => None,
+    };
 
     let device_event = Event::DeviceEvent {
       device_id: DEVICE_ID,
@@ -1125,6 +1308,7 @@ extern "C" fn scroll_wheel(this: &Object, _sel: Sel, event: id) {
         device_id: DEVICE_ID,
         delta,
         phase,
+        momentum_phase,
         modifiers: event_mods(event),
       },
     };
@@ -1168,6 +1352,41 @@ extern "C" fn wants_key_down_for_event(_this: &Object, _sel: Sel, _event: id) ->
   YES
 }
 
-extern "C" fn accepts_first_mouse(_this: &Object, _sel: Sel, _event: id) -> BOOL {
-  YES
+extern "C" fn accepts_first_mouse(this: &Object, _sel: Sel, _event: id) -> BOOL {
+  unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &*(state_ptr as *const ViewState);
+    if state.accepts_first_mouse {
+      YES
+    } else {
+      NO
+    }
+  }
+}
+
+extern "C" fn hit_test(this: &Object, _sel: Sel, point: NSPoint) -> id {
+  unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &*(state_ptr as *const ViewState);
+
+    if let Some(region) = &state.hittest_region {
+      // `point` is in the coordinates of the superview, and the view isn't flipped.
+      let superview: id = msg_send![this, superview];
+      let point: NSPoint = msg_send![this, convertPoint: point fromView: superview];
+      let bounds: NSRect = msg_send![this, bounds];
+      let (x, y) = (point.x, bounds.size.height - point.y);
+      let inside = region.iter().any(|rect| {
+        x >= rect.origin.x
+          && x < rect.origin.x + rect.size.width
+          && y >= rect.origin.y
+          && y < rect.origin.y + rect.size.height
+      });
+      if !inside {
+        return nil;
+      }
+    }
+
+    let superclass = util::superclass(this);
+    msg_send![super(this, superclass), hitTest: point]
+  }
 }
@@ -11,6 +11,7 @@ use std::{
     atomic::{AtomicBool, Ordering},
     Arc, Mutex, Weak,
   },
+  time::Duration,
 };
 
 use crate::{
@@ -20,26 +21,29 @@ use crate::{
   error::{ExternalError, NotSupportedError, OsError as RootOsError},
   icon::Icon,
   monitor::{MonitorHandle as RootMonitorHandle, VideoMode as RootVideoMode},
-  platform::macos::WindowExtMacOS,
+  platform::macos::{OptionAsAlt, Vibrancy, WindowExtMacOS},
   platform_impl::platform::{
     app_state::{AppState, INTERRUPT_EVENT_LOOP_EXIT},
-    ffi, menu,
+    ffi,
     monitor::{self, MonitorHandle, VideoMode},
     util::{self, IdRef},
-    view::{self, new_view, CursorState},
+    view::{self, new_view, CursorAnimation, CursorState},
     window_delegate::new_delegate,
     OsError,
   },
-  window::{CursorIcon, Fullscreen, UserAttentionType, WindowAttributes, WindowId as RootWindowId},
+  window::{
+    CursorIcon, CustomCursor, Fullscreen, Insets, Orientation, Rect, Theme, UserAttentionType,
+    WindowAttributes, WindowButtons, WindowId as RootWindowId,
+  },
 };
 use cocoa::{
   appkit::{
     self, CGFloat, NSApp, NSApplication, NSApplicationPresentationOptions, NSColor,
-    NSRequestUserAttentionType, NSScreen, NSView, NSWindow, NSWindowButton, NSWindowOrderingMode,
-    NSWindowStyleMask,
+    NSRequestUserAttentionType, NSScreen, NSView, NSWindow, NSWindowButton,
+    NSWindowCollectionBehavior, NSWindowOrderingMode, NSWindowStyleMask,
   },
   base::{id, nil},
-  foundation::{NSAutoreleasePool, NSDictionary, NSPoint, NSRect, NSSize, NSUInteger},
+  foundation::{NSAutoreleasePool, NSDictionary, NSInteger, NSPoint, NSRect, NSSize, NSUInteger},
 };
 use core_graphics::display::{CGDisplay, CGDisplayMode};
 use objc::{
@@ -49,6 +53,10 @@ use objc::{
 
 use super::Menu;
 
+// `NSWindowSharingType` values
+const NS_WINDOW_SHARING_NONE: NSUInteger = 0;
+const NS_WINDOW_SHARING_READ_ONLY: NSUInteger = 1;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Id(pub usize);
 
@@ -56,6 +64,14 @@ impl Id {
   pub unsafe fn dummy() -> Self {
     Id(0)
   }
+
+  pub fn into_raw(self) -> u64 {
+    self.0 as u64
+  }
+
+  pub fn from_raw(raw: u64) -> Option<Self> {
+    usize::try_from(raw).ok().map(Id)
+  }
 }
 
 // Convert the `cocoa::base::id` associated with a window to a usize to use as a unique identifier
@@ -82,7 +98,11 @@ pub struct PlatformSpecificWindowBuilderAttributes {
   pub fullsize_content_view: bool,
   pub resize_increments: Option<LogicalSize<f64>>,
   pub disallow_hidpi: bool,
-  pub has_shadow: bool,
+  /// `None` means the default, which is no shadow for transparent windows.
+  pub has_shadow: Option<bool>,
+  pub accepts_first_mouse: bool,
+  pub vibrancy: Vibrancy,
+  pub fullscreen_auxiliary: bool,
 }
 
 impl Default for PlatformSpecificWindowBuilderAttributes {
@@ -98,7 +118,10 @@ impl Default for PlatformSpecificWindowBuilderAttributes {
       fullsize_content_view: false,
       resize_increments: None,
       disallow_hidpi: false,
-      has_shadow: true,
+      has_shadow: None,
+      accepts_first_mouse: true,
+      vibrancy: Default::default(),
+      fullscreen_auxiliary: false,
     }
   }
 }
@@ -107,7 +130,7 @@ unsafe fn create_view(
   ns_window: id,
   pl_attribs: &PlatformSpecificWindowBuilderAttributes,
 ) -> Option<(IdRef, Weak<Mutex<CursorState>>)> {
-  let (ns_view, cursor_state) = new_view(ns_window);
+  let (ns_view, cursor_state) = new_view(ns_window, pl_attribs.accepts_first_mouse);
   ns_view.non_nil().map(|ns_view| {
     if !pl_attribs.disallow_hidpi {
       ns_view.setWantsBestResolutionOpenGLSurface_(YES);
@@ -221,9 +244,15 @@ fn create_window(
           let _: () = msg_send![button, setHidden: YES];
         }
       }
+      if attrs.enabled_buttons != WindowButtons::all() {
+        util::set_enabled_buttons(*ns_window, attrs.enabled_buttons);
+      }
       if pl_attrs.movable_by_window_background {
         ns_window.setMovableByWindowBackground_(YES);
       }
+      if attrs.window_menu.is_some() {
+        warn!("`WindowBuilder::with_menu` is ignored on macOS, use `EventLoopWindowTarget::set_application_menu`");
+      }
 
       if attrs.always_on_top {
         let _: () = msg_send![
@@ -232,6 +261,10 @@ fn create_window(
         ];
       }
 
+      if attrs.content_protection {
+        let _: () = msg_send![*ns_window, setSharingType: NS_WINDOW_SHARING_NONE];
+      }
+
       if let Some(increments) = pl_attrs.resize_increments {
         let (x, y) = (increments.width, increments.height);
         if x >= 1.0 && y >= 1.0 {
@@ -244,15 +277,16 @@ fn create_window(
         let _: () = msg_send![parent as id, addChildWindow: *ns_window ordered: NSWindowOrderingMode::NSWindowAbove];
       }
 
-      if !pl_attrs.has_shadow {
+      // The shadow of a transparent window follows its bounds rather than its opaque pixels.
+      if !pl_attrs.has_shadow.unwrap_or(!attrs.transparent) {
         ns_window.setHasShadow_(NO);
       }
+      if pl_attrs.fullscreen_auxiliary {
+        set_fullscreen_auxiliary(*ns_window, true);
+      }
       if attrs.position.is_none() {
         ns_window.center();
       }
-      if let Some(window_menu) = attrs.window_menu.clone() {
-        menu::initialize(window_menu);
-      }
       ns_window
     });
     pool.drain();
@@ -283,6 +317,7 @@ lazy_static! {
 #[derive(Default)]
 pub struct SharedState {
   pub resizable: bool,
+  pub enabled_buttons: WindowButtons,
   pub fullscreen: Option<Fullscreen>,
   // This is true between windowWillEnterFullScreen and windowDidEnterFullScreen
   // or windowWillExitFullScreen and windowDidExitFullScreen.
@@ -295,6 +330,10 @@ pub struct SharedState {
   pub standard_frame: Option<NSRect>,
   is_simple_fullscreen: bool,
   pub saved_style: Option<NSWindowStyleMask>,
+  // Min/max content sizes requested by the user. AppKit drops them while the
+  // window is in native fullscreen, so they're re-applied on exit.
+  pub min_inner_size: Option<Size>,
+  pub max_inner_size: Option<Size>,
   /// Presentation options saved before entering `set_simple_fullscreen`, and
   /// restored upon exiting it. Also used when transitioning from Borderless to
   /// Exclusive fullscreen in `set_fullscreen` because we need to disable the menu
@@ -302,6 +341,10 @@ pub struct SharedState {
   /// transitioning back to borderless fullscreen.
   save_presentation_opts: Option<NSApplicationPresentationOptions>,
   pub saved_desktop_display_mode: Option<(CGDisplay, CGDisplayMode)>,
+  /// Whether the shadow was set explicitly, otherwise it follows the transparency.
+  shadow_set: bool,
+  /// Whether the cursor grab was requested. It's only applied while the window is key.
+  pub cursor_grab: bool,
 }
 
 impl SharedState {
@@ -316,6 +359,7 @@ impl From<WindowAttributes> for SharedState {
   fn from(attribs: WindowAttributes) -> Self {
     SharedState {
       resizable: attribs.resizable,
+      enabled_buttons: attribs.enabled_buttons,
       // This fullscreen field tracks the current state of the window
       // (as seen by `WindowDelegate`), and since the window hasn't
       // actually been fullscreened yet, we can't set it yet. This is
@@ -324,6 +368,8 @@ impl From<WindowAttributes> for SharedState {
       // identical, resulting in a no-op.
       fullscreen: None,
       maximized: attribs.maximized,
+      min_inner_size: attribs.min_inner_size,
+      max_inner_size: attribs.max_inner_size,
       ..Default::default()
     }
   }
@@ -337,6 +383,10 @@ pub struct UnownedWindow {
   decorations: AtomicBool,
   cursor_state: Weak<Mutex<CursorState>>,
   pub inner_rect: Option<PhysicalSize<u32>>,
+  // The look of the blur, and the `NSVisualEffectView` below the content while the window
+  // is blurred
+  vibrancy: Mutex<Vibrancy>,
+  blur_view: Arc<Mutex<Option<IdRef>>>,
 }
 
 unsafe impl Send for UnownedWindow {}
@@ -391,6 +441,9 @@ impl UnownedWindow {
         let logical_dim = dim.to_logical(scale_factor);
         set_max_inner_size(*ns_window, logical_dim)
       });
+      if let Some(ratio) = win_attribs.aspect_ratio {
+        let () = msg_send![*ns_window, setContentAspectRatio: NSSize::new(ratio, 1.0)];
+      }
 
       use cocoa::foundation::NSArray;
       // register for drag and drop operations.
@@ -408,7 +461,9 @@ impl UnownedWindow {
     let fullscreen = win_attribs.fullscreen.take();
     let maximized = win_attribs.maximized;
     let visible = win_attribs.visible;
+    let focused = win_attribs.focused;
     let decorations = win_attribs.decorations;
+    let blur = win_attribs.blur;
     let inner_rect = win_attribs
       .inner_size
       .map(|size| size.to_physical(scale_factor));
@@ -417,27 +472,38 @@ impl UnownedWindow {
       ns_view,
       ns_window,
       input_context,
-      shared_state: Arc::new(Mutex::new(win_attribs.into())),
+      shared_state: Arc::new(Mutex::new(SharedState {
+        shadow_set: pl_attribs.has_shadow.is_some(),
+        ..win_attribs.into()
+      })),
       decorations: AtomicBool::new(decorations),
       cursor_state,
       inner_rect,
+      vibrancy: Mutex::new(pl_attribs.vibrancy),
+      blur_view: Default::default(),
     });
 
+    if blur {
+      window.set_blur(true);
+    }
+
     let delegate = new_delegate(&window, fullscreen.is_some());
 
     // Set fullscreen mode after we setup everything
     window.set_fullscreen(fullscreen);
 
+    if maximized {
+      window.set_maximized(maximized);
+    }
+
     // Setting the window as key has to happen *after* we set the fullscreen
-    // state, since otherwise we'll briefly see the window at normal size
-    // before it transitions.
-    if visible {
+    // and maximized states, since otherwise we'll briefly see the window at
+    // normal size before it transitions.
+    if visible && focused {
       // Tightly linked with `app_state::window_activation_hack`
       unsafe { window.ns_window.makeKeyAndOrderFront_(nil) };
-    }
-
-    if maximized {
-      window.set_maximized(maximized);
+    } else if visible {
+      unsafe { window.ns_window.orderFront_(nil) };
     }
 
     unsafe { pool.drain() };
@@ -463,12 +529,15 @@ impl UnownedWindow {
     }
   }
 
-  pub fn set_menu(&self, menu: Option<Menu>) {
-    // TODO if None we should set an empty menu
-    // On windows we can remove it, in macOS we can't
-    if let Some(menu) = menu {
-      menu::initialize(menu);
-    }
+  pub fn title(&self) -> String {
+    unsafe { util::ns_string_to_rust(self.ns_window.title()) }
+  }
+
+  pub fn set_menu(&self, _menu: Option<Menu>) {
+    // The menu bar belongs to the application, see `set_application_menu`
+    warn!(
+      "`Window::set_menu` is ignored on macOS, use `EventLoopWindowTarget::set_application_menu`"
+    );
   }
 
   pub fn set_visible(&self, visible: bool) {
@@ -478,6 +547,15 @@ impl UnownedWindow {
     }
   }
 
+  #[inline]
+  pub fn set_visible_with_animation(&self, visible: bool, animate: bool) {
+    if animate {
+      self.set_visible(visible);
+    } else {
+      unsafe { util::set_visible_without_animation_async(*self.ns_window, visible) }
+    }
+  }
+
   #[inline]
   // Shortener for set_visible(true)
   pub fn set_focus(&self) {
@@ -493,6 +571,27 @@ impl UnownedWindow {
     AppState::queue_redraw(RootWindowId(self.id()));
   }
 
+  pub fn pre_present_notify(&self) {}
+
+  pub fn request_redraw_rect(&self, position: PhysicalPosition<i32>, size: PhysicalSize<u32>) {
+    let scale_factor = self.scale_factor();
+    let position = position.to_logical::<f64>(scale_factor);
+    let size = size.to_logical::<f64>(scale_factor);
+    unsafe {
+      // The view isn't flipped, its origin is at the bottom left
+      let bounds: NSRect = msg_send![*self.ns_view, bounds];
+      let rect = NSRect::new(
+        NSPoint::new(position.x, bounds.size.height - position.y - size.height),
+        NSSize::new(size.width, size.height),
+      );
+      let () = msg_send![*self.ns_view, setNeedsDisplayInRect: rect];
+    }
+  }
+
+  pub fn redraw_rect(&self) -> Option<(PhysicalPosition<i32>, PhysicalSize<u32>)> {
+    None
+  }
+
   pub fn outer_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
     let frame_rect = unsafe { NSWindow::frame(*self.ns_window) };
     let position = LogicalPosition::new(
@@ -541,6 +640,32 @@ impl UnownedWindow {
     logical.to_physical(scale_factor)
   }
 
+  #[inline]
+  pub fn content_insets(&self) -> Insets {
+    let scale_factor = self.scale_factor();
+    let (frame, content) = unsafe {
+      let frame = NSWindow::frame(*self.ns_window);
+      (frame, self.ns_window.contentRectForFrameRect_(frame))
+    };
+    // The coordinates are flipped, the origin is the bottom-left corner
+    let physical = |inset: CGFloat| (inset.max(0.0) as f64 * scale_factor).round() as u32;
+    Insets {
+      top: physical(frame.origin.y + frame.size.height - content.origin.y - content.size.height),
+      left: physical(content.origin.x - frame.origin.x),
+      bottom: physical(content.origin.y - frame.origin.y),
+      right: physical(frame.origin.x + frame.size.width - content.origin.x - content.size.width),
+    }
+  }
+
+  #[inline]
+  pub fn safe_area_insets(&self) -> Insets {
+    Insets::default()
+  }
+
+  pub fn set_orientation_lock(&self, _orientation: Option<Orientation>) {}
+
+  pub fn set_soft_input_visible(&self, _visible: bool) {}
+
   #[inline]
   pub fn set_inner_size(&self, size: Size) {
     unsafe {
@@ -549,7 +674,16 @@ impl UnownedWindow {
     }
   }
 
+  pub fn request_inner_size(&self, size: Size) -> Option<PhysicalSize<u32>> {
+    unsafe {
+      let scale_factor = self.scale_factor();
+      util::set_content_size_sync(*self.ns_window, size.to_logical(scale_factor));
+    }
+    Some(self.inner_size())
+  }
+
   pub fn set_min_inner_size(&self, dimensions: Option<Size>) {
+    self.shared_state.lock().unwrap().min_inner_size = dimensions;
     unsafe {
       let dimensions = dimensions.unwrap_or(Logical(LogicalSize {
         width: 0.0,
@@ -561,6 +695,7 @@ impl UnownedWindow {
   }
 
   pub fn set_max_inner_size(&self, dimensions: Option<Size>) {
+    self.shared_state.lock().unwrap().max_inner_size = dimensions;
     unsafe {
       let dimensions = dimensions.unwrap_or(Logical(LogicalSize {
         width: std::f32::MAX as f64,
@@ -571,30 +706,100 @@ impl UnownedWindow {
     }
   }
 
+  pub fn set_aspect_ratio(&self, aspect_ratio: Option<(u32, u32)>) {
+    unsafe {
+      match aspect_ratio {
+        Some((width, height)) => {
+          let () = msg_send![
+            *self.ns_window,
+            setContentAspectRatio: NSSize::new(width as f64, height as f64)
+          ];
+        }
+        // The aspect ratio and the resize increments are exclusive, resetting the increments
+        // removes the ratio.
+        None => {
+          let () = msg_send![*self.ns_window, setContentResizeIncrements: NSSize::new(1.0, 1.0)];
+        }
+      }
+    }
+  }
+
   #[inline]
   pub fn set_resizable(&self, resizable: bool) {
-    let fullscreen = {
+    let (fullscreen, enabled_buttons) = {
       trace!("Locked shared state in `set_resizable`");
       let mut shared_state_lock = self.shared_state.lock().unwrap();
       shared_state_lock.resizable = resizable;
       trace!("Unlocked shared state in `set_resizable`");
-      shared_state_lock.fullscreen.is_some()
+      (
+        self.is_fullscreen_or_transitioning(&shared_state_lock),
+        shared_state_lock.enabled_buttons,
+      )
     };
     if !fullscreen {
-      let mut mask = unsafe { self.ns_window.styleMask() };
-      if resizable {
-        mask |= NSWindowStyleMask::NSResizableWindowMask;
-      } else {
-        mask &= !NSWindowStyleMask::NSResizableWindowMask;
-      }
+      let mask = with_resizable(unsafe { self.ns_window.styleMask() }, resizable);
       self.set_style_mask_async(mask);
-    } // Otherwise, we don't change the mask until we exit fullscreen.
+      unsafe { util::set_enabled_buttons_async(*self.ns_window, enabled_buttons) };
+    } // Otherwise, we don't change the mask until `window_did_exit_fullscreen`.
+  }
+
+  /// Whether the style mask belongs to the fullscreen mode, which is the case during the
+  /// transitions too. `SharedState::fullscreen` is already `None` while exiting fullscreen.
+  fn is_fullscreen_or_transitioning(&self, shared_state: &SharedState) -> bool {
+    shared_state.fullscreen.is_some()
+      || shared_state.in_fullscreen_transition
+      || unsafe { self.ns_window.styleMask() }.contains(NSWindowStyleMask::NSFullScreenWindowMask)
+  }
+
+  #[inline]
+  pub fn set_enabled_buttons(&self, buttons: WindowButtons) {
+    trace!("Locked shared state in `set_enabled_buttons`");
+    self.shared_state.lock().unwrap().enabled_buttons = buttons;
+    trace!("Unlocked shared state in `set_enabled_buttons`");
+    unsafe { util::set_enabled_buttons_async(*self.ns_window, buttons) };
+  }
+
+  #[inline]
+  pub fn enabled_buttons(&self) -> WindowButtons {
+    self.shared_state.lock().unwrap().enabled_buttons
   }
 
   pub fn set_cursor_icon(&self, cursor: CursorIcon) {
     let cursor = util::Cursor::from(cursor);
     if let Some(cursor_access) = self.cursor_state.upgrade() {
-      cursor_access.lock().unwrap().cursor = cursor;
+      let mut cursor_state = cursor_access.lock().unwrap();
+      cursor_state.cursor = cursor;
+      cursor_state.animation = None;
+    }
+    unsafe {
+      let _: () = msg_send![*self.ns_window,
+          invalidateCursorRectsForView:*self.ns_view
+      ];
+    }
+  }
+
+  pub fn set_custom_cursor(&self, cursor: CustomCursor) {
+    let frames: Vec<(IdRef, Duration)> = cursor
+      .frames
+      .iter()
+      .map(|frame| {
+        let ns_cursor =
+          unsafe { util::custom_cursor(&frame.rgba, cursor.width, cursor.height, cursor.hotspot) };
+        (ns_cursor, frame.duration)
+      })
+      .collect();
+    if let Some(cursor_access) = self.cursor_state.upgrade() {
+      let mut cursor_state = cursor_access.lock().unwrap();
+      cursor_state.cursor = util::Cursor::Custom(frames[0].0.clone());
+      cursor_state.animation = if cursor.is_animated() {
+        Some(CursorAnimation::start(
+          *self.ns_view,
+          Arc::downgrade(&cursor_access),
+          frames,
+        ))
+      } else {
+        None
+      };
     }
     unsafe {
       let _: () = msg_send![*self.ns_window,
@@ -605,9 +810,13 @@ impl UnownedWindow {
 
   #[inline]
   pub fn set_cursor_grab(&self, grab: bool) -> Result<(), ExternalError> {
-    // TODO: Do this for real https://stackoverflow.com/a/40922095/5435443
-    CGDisplay::associate_mouse_and_mouse_cursor_position(!grab)
-      .map_err(|status| ExternalError::Os(os_error!(OsError::CGError(status))))
+    self.shared_state.lock().unwrap().cursor_grab = grab;
+    // The grab is applied again in `windowDidBecomeKey:`
+    let is_key: BOOL = unsafe { msg_send![*self.ns_window, isKeyWindow] };
+    if !grab || is_key == YES {
+      set_cursor_grab(grab)?;
+    }
+    Ok(())
   }
 
   #[inline]
@@ -649,6 +858,30 @@ impl UnownedWindow {
     Ok(())
   }
 
+  #[inline]
+  pub fn set_cursor_hittest_region(&self, region: Option<Vec<Rect>>) -> Result<(), ExternalError> {
+    let scale_factor = self.scale_factor();
+    let region = region.map(|rects| {
+      rects
+        .into_iter()
+        .map(|rect| {
+          let position: LogicalPosition<f64> = rect.position.to_logical(scale_factor);
+          let size: LogicalSize<f64> = rect.size.to_logical(scale_factor);
+          NSRect::new(
+            NSPoint::new(position.x, position.y),
+            NSSize::new(size.width, size.height),
+          )
+        })
+        .collect()
+    });
+    unsafe { view::set_hittest_region(*self.ns_view, region) };
+    Ok(())
+  }
+
+  pub fn set_shape(&self, _shape: Option<Vec<Rect>>) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
   #[inline]
   pub fn drag_window(&self) -> Result<(), ExternalError> {
     unsafe {
@@ -680,16 +913,21 @@ impl UnownedWindow {
     is_zoomed != NO
   }
 
+  /// Whether the window is zoomed once it exits fullscreen, `None` if it isn't fullscreen.
+  pub(crate) fn maximized_in_fullscreen(&self) -> Option<bool> {
+    let shared_state_lock = self.shared_state.lock().unwrap();
+    shared_state_lock
+      .fullscreen
+      .as_ref()
+      .map(|_| shared_state_lock.maximized)
+  }
+
   fn saved_style(&self, shared_state: &mut SharedState) -> NSWindowStyleMask {
     let base_mask = shared_state
       .saved_style
       .take()
       .unwrap_or_else(|| unsafe { self.ns_window.styleMask() });
-    if shared_state.resizable {
-      base_mask | NSWindowStyleMask::NSResizableWindowMask
-    } else {
-      base_mask & !NSWindowStyleMask::NSResizableWindowMask
-    }
+    with_resizable(base_mask, shared_state.resizable)
   }
 
   /// This is called when the window is exiting fullscreen, whether by the
@@ -702,13 +940,33 @@ impl UnownedWindow {
     shared_state_lock.fullscreen = None;
 
     let maximized = shared_state_lock.maximized;
-    let mask = self.saved_style(&mut *shared_state_lock);
+    let enabled_buttons = shared_state_lock.enabled_buttons;
+    let min_inner_size = shared_state_lock.min_inner_size;
+    let max_inner_size = shared_state_lock.max_inner_size;
+    let mask =
+      self.saved_style(&mut *shared_state_lock) & !NSWindowStyleMask::NSFullScreenWindowMask;
 
     drop(shared_state_lock);
     trace!("Unocked shared state in `restore_state_from_fullscreen`");
 
-    self.set_style_mask_async(mask);
-    self.set_maximized(maximized);
+    // We're called from the window delegate, so the mask can be applied right
+    // away; the zoom button and `zoom:` both depend on the resizable bit.
+    self.set_style_mask_sync(mask);
+    unsafe { util::set_enabled_buttons(*self.ns_window, enabled_buttons) };
+    self.set_min_inner_size(min_inner_size);
+    self.set_max_inner_size(max_inner_size);
+
+    let is_zoomed = self.is_zoomed();
+    if is_zoomed != maximized {
+      unsafe {
+        util::set_maximized_async(
+          *self.ns_window,
+          is_zoomed,
+          maximized,
+          Arc::downgrade(&self.shared_state),
+        );
+      }
+    }
   }
 
   #[inline]
@@ -732,12 +990,20 @@ impl UnownedWindow {
 
   #[inline]
   pub fn set_maximized(&self, maximized: bool) {
+    {
+      let mut shared_state_lock = self.shared_state.lock().unwrap();
+      if shared_state_lock.fullscreen.is_some() {
+        // The window is zoomed when it exits fullscreen, in `window_did_exit_fullscreen`
+        shared_state_lock.maximized = maximized;
+        return;
+      }
+    }
     let is_zoomed = self.is_zoomed();
     if is_zoomed == maximized {
       return;
     };
     unsafe {
-      util::set_maximized_async(
+      util::set_maximized_sync(
         *self.ns_window,
         is_zoomed,
         maximized,
@@ -758,8 +1024,27 @@ impl UnownedWindow {
     is_visible == YES
   }
 
+  pub fn theme(&self) -> Theme {
+    unsafe {
+      let appearance: id = msg_send![*self.ns_window, effectiveAppearance];
+      let name: id = msg_send![appearance, name];
+      // e.g. NSAppearanceNameDarkAqua or NSAppearanceNameAccessibilityHighContrastDarkAqua
+      if util::ns_string_to_rust(name).contains("Dark") {
+        Theme::Dark
+      } else {
+        Theme::Light
+      }
+    }
+  }
+
   #[inline]
   pub fn is_resizable(&self) -> bool {
+    let shared_state_lock = self.shared_state.lock().unwrap();
+    // Fullscreen windows are always resizable, report the state they'll be restored to
+    if self.is_fullscreen_or_transitioning(&shared_state_lock) {
+      return shared_state_lock.resizable;
+    }
+    drop(shared_state_lock);
     let is_resizable: BOOL = unsafe { msg_send![*self.ns_window, isResizable] };
     is_resizable == YES
   }
@@ -899,8 +1184,14 @@ impl UnownedWindow {
       }
     }
 
+    // Read before entering fullscreen, where `isZoomed` is always true
+    let is_zoomed = old_fullscreen.is_none() && self.is_zoomed();
+
     trace!("Locked shared state in `set_fullscreen`");
     let mut shared_state_lock = self.shared_state.lock().unwrap();
+    if old_fullscreen.is_none() {
+      shared_state_lock.maximized = is_zoomed;
+    }
     shared_state_lock.fullscreen = fullscreen.clone();
     trace!("Unlocked shared state in `set_fullscreen`");
 
@@ -985,13 +1276,14 @@ impl UnownedWindow {
     if decorations != self.decorations.load(Ordering::Acquire) {
       self.decorations.store(decorations, Ordering::Release);
 
-      let (fullscreen, resizable) = {
+      let (fullscreen, resizable, enabled_buttons) = {
         trace!("Locked shared state in `set_decorations`");
         let shared_state_lock = self.shared_state.lock().unwrap();
         trace!("Unlocked shared state in `set_decorations`");
         (
           shared_state_lock.fullscreen.is_some(),
           shared_state_lock.resizable,
+          shared_state_lock.enabled_buttons,
         )
       };
 
@@ -1015,10 +1307,45 @@ impl UnownedWindow {
         }
         new_mask
       };
+
+      // Changing the style mask keeps the frame, so the content view would grow or shrink by
+      // the size of the title bar. Restore the content size afterwards to avoid that.
+      let view_frame = unsafe { NSView::frame(*self.ns_view) };
+      let content_size =
+        LogicalSize::new(view_frame.size.width as f64, view_frame.size.height as f64);
+      let is_zoomed = self.is_zoomed();
+
       self.set_style_mask_async(new_mask);
+      unsafe { util::set_enabled_buttons_async(*self.ns_window, enabled_buttons) };
+      if !is_zoomed {
+        unsafe { util::set_content_size_async(*self.ns_window, content_size) };
+      }
     }
   }
 
+  #[inline]
+  pub fn set_transparent(&self, transparent: bool) -> Result<(), ExternalError> {
+    let shadow_set = self.shared_state.lock().unwrap().shadow_set;
+    unsafe {
+      util::set_transparent_async(*self.ns_window, transparent);
+      if !shadow_set {
+        self
+          .ns_window
+          .setHasShadow_(if transparent { NO } else { YES });
+      }
+    }
+    Ok(())
+  }
+
+  pub fn set_blur(&self, blur: bool) {
+    let vibrancy = *self.vibrancy.lock().unwrap();
+    self.set_blur_view(if blur { Some(vibrancy) } else { None });
+  }
+
+  fn set_blur_view(&self, vibrancy: Option<Vibrancy>) {
+    unsafe { util::set_blur_view_async(*self.ns_view, self.blur_view.clone(), vibrancy) };
+  }
+
   #[inline]
   pub fn set_always_on_top(&self, always_on_top: bool) {
     let level = if always_on_top {
@@ -1029,6 +1356,18 @@ impl UnownedWindow {
     unsafe { util::set_level_async(*self.ns_window, level) };
   }
 
+  #[inline]
+  pub fn set_content_protection(&self, enabled: bool) {
+    let sharing_type = if enabled {
+      NS_WINDOW_SHARING_NONE
+    } else {
+      NS_WINDOW_SHARING_READ_ONLY
+    };
+    unsafe {
+      let _: () = msg_send![*self.ns_window, setSharingType: sharing_type];
+    }
+  }
+
   #[inline]
   pub fn set_window_icon(&self, _icon: Option<Icon>) {
     // macOS doesn't have window icons. Though, there is
@@ -1043,14 +1382,22 @@ impl UnownedWindow {
 
   #[inline]
   pub fn set_ime_position(&self, spot: Position) {
+    self.set_ime_cursor_area(spot, LogicalSize::new(0.0, 0.0).into());
+  }
+
+  #[inline]
+  pub fn set_ime_cursor_area(&self, position: Position, size: Size) {
     let scale_factor = self.scale_factor();
-    let logical_spot = spot.to_logical(scale_factor);
+    let logical_position = position.to_logical::<f64>(scale_factor);
+    let logical_size = size.to_logical::<f64>(scale_factor);
     unsafe {
-      view::set_ime_position(
+      view::set_ime_cursor_area(
         *self.ns_view,
         *self.input_context,
-        logical_spot.x,
-        logical_spot.y,
+        logical_position.x,
+        logical_position.y,
+        logical_size.width,
+        logical_size.height,
       );
     }
   }
@@ -1227,12 +1574,43 @@ impl WindowExtMacOS for UnownedWindow {
 
   #[inline]
   fn set_has_shadow(&self, has_shadow: bool) {
+    self.shared_state.lock().unwrap().shadow_set = true;
     unsafe {
       self
         .ns_window
         .setHasShadow_(if has_shadow { YES } else { NO })
     }
   }
+
+  #[inline]
+  fn invalidate_shadow(&self) {
+    unsafe {
+      let () = msg_send![*self.ns_window, invalidateShadow];
+    }
+  }
+
+  #[inline]
+  fn set_option_as_alt(&self, option_as_alt: OptionAsAlt) {
+    unsafe { view::set_option_as_alt(*self.ns_view, option_as_alt) }
+  }
+
+  #[inline]
+  fn set_vibrancy(&self, vibrancy: Option<Vibrancy>) {
+    if let Some(vibrancy) = vibrancy {
+      *self.vibrancy.lock().unwrap() = vibrancy;
+    }
+    self.set_blur_view(vibrancy);
+  }
+
+  #[inline]
+  fn option_as_alt(&self) -> OptionAsAlt {
+    unsafe { view::option_as_alt(*self.ns_view) }
+  }
+
+  #[inline]
+  fn set_fullscreen_auxiliary(&self, fullscreen_auxiliary: bool) {
+    unsafe { set_fullscreen_auxiliary(*self.ns_window, fullscreen_auxiliary) }
+  }
 }
 
 impl Drop for UnownedWindow {
@@ -1245,6 +1623,21 @@ impl Drop for UnownedWindow {
   }
 }
 
+unsafe fn set_fullscreen_auxiliary(ns_window: id, fullscreen_auxiliary: bool) {
+  let mut behavior = ns_window.collectionBehavior();
+  behavior.set(
+    NSWindowCollectionBehavior::NSWindowCollectionBehaviorFullScreenAuxiliary,
+    fullscreen_auxiliary,
+  );
+  ns_window.setCollectionBehavior_(behavior);
+}
+
+pub(super) fn set_cursor_grab(grab: bool) -> Result<(), ExternalError> {
+  // TODO: Do this for real https://stackoverflow.com/a/40922095/5435443
+  CGDisplay::associate_mouse_and_mouse_cursor_position(!grab)
+    .map_err(|status| ExternalError::Os(os_error!(OsError::CGError(status))))
+}
+
 unsafe fn set_min_inner_size<V: NSWindow + Copy>(window: V, mut min_size: LogicalSize<f64>) {
   let mut current_rect = NSWindow::frame(window);
   let content_rect = NSWindow::contentRectForFrameRect_(window, NSWindow::frame(window));
@@ -1292,3 +1685,12 @@ unsafe fn set_max_inner_size<V: NSWindow + Copy>(window: V, mut max_size: Logica
     window.setFrame_display_(current_rect, NO)
   }
 }
+
+/// Returns `mask` with the resizable bit set to `resizable`.
+fn with_resizable(mask: NSWindowStyleMask, resizable: bool) -> NSWindowStyleMask {
+  if resizable {
+    mask | NSWindowStyleMask::NSResizableWindowMask
+  } else {
+    mask & !NSWindowStyleMask::NSResizableWindowMask
+  }
+}
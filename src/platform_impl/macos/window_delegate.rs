@@ -4,6 +4,7 @@
 use std::{
   f64,
   os::raw::c_void,
+  panic::AssertUnwindSafe,
   sync::{atomic::Ordering, Arc, Weak},
 };
 
@@ -24,9 +25,10 @@ use crate::{
   platform_impl::platform::{
     app_state::{AppState, INTERRUPT_EVENT_LOOP_EXIT},
     event::{EventProxy, EventWrapper},
+    event_loop::catch_panic,
     util::{self, IdRef},
     view::ViewState,
-    window::{get_window_id, UnownedWindow},
+    window::{get_window_id, set_cursor_grab, UnownedWindow},
   },
   window::{Fullscreen, WindowId},
 };
@@ -256,7 +258,7 @@ fn with_state<F: FnOnce(&mut WindowDelegateState) -> T, T>(this: &Object, callba
     let state_ptr: *mut c_void = *this.get_ivar("taoState");
     &mut *(state_ptr as *mut WindowDelegateState)
   };
-  callback(state_ptr);
+  catch_panic(AssertUnwindSafe(|| callback(state_ptr)));
 }
 
 extern "C" fn dealloc(this: &Object, _sel: Sel) {
@@ -317,6 +319,14 @@ extern "C" fn window_did_resize(this: &Object, _: Sel, _: id) {
     if !state.is_checking_zoomed_in {
       state.emit_resize_event();
       state.emit_move_event();
+
+      // Redraw on each step of a live resize, `drawRect:` emits the `RedrawRequested`.
+      unsafe {
+        let in_live_resize: BOOL = msg_send![*state.ns_window, inLiveResize];
+        if in_live_resize == YES {
+          let () = msg_send![*state.ns_view, setNeedsDisplay: YES];
+        }
+      }
     }
   });
   trace!("Completed `windowDidResize:`");
@@ -344,6 +354,11 @@ extern "C" fn window_did_become_key(this: &Object, _: Sel, _: id) {
   with_state(this, |state| {
     // TODO: center the cursor if the window had mouse grab when it
     // lost focus
+    state.with_window(|window| {
+      if window.shared_state.lock().unwrap().cursor_grab {
+        let _ = set_cursor_grab(true);
+      }
+    });
     state.emit_event(WindowEvent::Focused(true));
   });
   trace!("Completed `windowDidBecomeKey:`");
@@ -375,6 +390,13 @@ extern "C" fn window_did_resign_key(this: &Object, _: Sel, _: id) {
       state.emit_event(WindowEvent::ModifiersChanged(view_state.modifiers));
     }
 
+    // The cursor grab only lasts as long as the window is key
+    state.with_window(|window| {
+      if window.shared_state.lock().unwrap().cursor_grab {
+        let _ = set_cursor_grab(false);
+      }
+    });
+
     state.emit_event(WindowEvent::Focused(false));
   });
   trace!("Completed `windowDidResignKey:`");
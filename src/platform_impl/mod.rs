@@ -3,29 +3,36 @@
 
 pub use self::platform::*;
 
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", not(feature = "headless")))]
 #[path = "windows/mod.rs"]
 mod platform;
-#[cfg(any(
-  target_os = "linux",
-  target_os = "dragonfly",
-  target_os = "freebsd",
-  target_os = "netbsd",
-  target_os = "openbsd"
+#[cfg(all(
+  any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  ),
+  not(feature = "headless")
 ))]
 #[path = "linux/mod.rs"]
 mod platform;
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(feature = "headless")))]
 #[path = "macos/mod.rs"]
 mod platform;
-#[cfg(target_os = "android")]
+#[cfg(all(target_os = "android", not(feature = "headless")))]
 #[path = "android/mod.rs"]
 mod platform;
-#[cfg(target_os = "ios")]
+#[cfg(all(target_os = "ios", not(feature = "headless")))]
 #[path = "ios/mod.rs"]
 mod platform;
+#[cfg(feature = "headless")]
+#[path = "headless/mod.rs"]
+mod platform;
 
 #[cfg(all(
+  not(feature = "headless"),
   not(target_os = "ios"),
   not(target_os = "windows"),
   not(target_os = "linux"),
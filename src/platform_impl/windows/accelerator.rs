@@ -53,6 +53,11 @@ pub(crate) fn register_accel(hwnd: HWND, accel: &[ACCEL]) {
   table.insert(WindowHandle(hwnd.0), Arc::new(AccelTable::new(accel)));
 }
 
+pub(crate) fn unregister_accel(hwnd: HWND) {
+  let mut table = ACCEL_TABLES.lock().unwrap();
+  table.remove(&WindowHandle(hwnd.0));
+}
+
 impl Drop for AccelTable {
   fn drop(&mut self) {
     unsafe {
@@ -69,6 +69,11 @@ lazy_static! {
     static ref LIGHT_THEME_NAME: Vec<u16> = util::encode_wide("");
 }
 
+/// Windows 11 still reports itself as Windows 10, with a build number from 22000.
+pub fn is_windows_11() -> bool {
+  matches!(*WIN10_BUILD_VERSION, Some(v) if v >= 22000)
+}
+
 /// Attempt to set a theme on a window, if necessary.
 /// Returns the theme that was picked
 pub fn try_theme(hwnd: HWND, preferred_theme: Option<Theme>) -> Theme {
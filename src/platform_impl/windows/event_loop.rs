@@ -40,21 +40,31 @@ use windows::Win32::{
 use crate::{
   accelerator::AcceleratorId,
   dpi::{PhysicalPosition, PhysicalSize},
-  event::{DeviceEvent, Event, Force, RawKeyEvent, Touch, TouchPhase, WindowEvent},
-  event_loop::{ControlFlow, EventLoopClosed, EventLoopWindowTarget as RootELW},
+  error::OsError as RootOsError,
+  event::{
+    CaptionButton, DeviceEvent, ElementState, Event, Force, RawKeyEvent, Touch, TouchPhase,
+    WindowEvent,
+  },
+  event_loop::{ControlFlow, DeviceEvents, EventLoopClosed, EventLoopWindowTarget as RootELW},
   keyboard::{KeyCode, ModifiersState},
   monitor::MonitorHandle as RootMonitorHandle,
   platform_impl::platform::{
-    accelerator,
+    accelerator, button_device_id,
     dark_mode::try_theme,
     dpi::{become_dpi_aware, dpi_to_scale_factor, enable_non_client_dpi_scaling},
+    global_shortcut, icon, key_device_id,
     keyboard::is_msg_keyboard_related,
     keyboard_layout::LAYOUT_CACHE,
+    menu,
     minimal_ime::is_msg_ime_related,
     monitor::{self, MonitorHandle},
-    raw_input, util,
+    pointer_device_id, raw_input, set_key_device, set_mouse_device, util,
+    window::{
+      hidden_titlebar_hit_test, nc_hit_test, place_protected_window, restore_display_mode,
+      set_display_mode,
+    },
     window_state::{CursorFlags, WindowFlags, WindowState},
-    wrap_device_id, WindowId, DEVICE_ID,
+    wrap_device_id, OsError, WindowId, DEVICE_ID,
   },
   window::{Fullscreen, WindowId as RootWindowId},
 };
@@ -154,10 +164,11 @@ macro_rules! main_thread_check {
 }
 
 impl<T: 'static> EventLoop<T> {
-  pub fn new() -> EventLoop<T> {
+  pub fn new() -> Result<EventLoop<T>, RootOsError> {
     main_thread_check!("new_any_thread");
 
-    Self::new_any_thread()
+    become_dpi_aware();
+    Self::try_new_dpi_unaware_any_thread()
   }
 
   pub fn new_any_thread() -> EventLoop<T> {
@@ -172,9 +183,13 @@ impl<T: 'static> EventLoop<T> {
   }
 
   pub fn new_dpi_unaware_any_thread() -> EventLoop<T> {
+    Self::try_new_dpi_unaware_any_thread().expect("Failed to create the event loop")
+  }
+
+  fn try_new_dpi_unaware_any_thread() -> Result<EventLoop<T>, RootOsError> {
     let thread_id = unsafe { GetCurrentThreadId() };
 
-    let thread_msg_target = create_event_target_window();
+    let thread_msg_target = create_event_target_window()?;
 
     let send_thread_msg_target = thread_msg_target;
     thread::spawn(move || wait_thread(thread_id, send_thread_msg_target));
@@ -183,9 +198,12 @@ impl<T: 'static> EventLoop<T> {
     let runner_shared = Rc::new(EventLoopRunner::new(thread_msg_target, wait_thread_id));
 
     let thread_msg_sender = subclass_event_target_window(thread_msg_target, runner_shared.clone());
-    raw_input::register_all_mice_and_keyboards_for_raw_input(thread_msg_target);
+    raw_input::register_all_mice_and_keyboards_for_raw_input(
+      thread_msg_target,
+      DeviceEvents::Always,
+    );
 
-    EventLoop {
+    Ok(EventLoop {
       thread_msg_sender,
       window_target: RootELW {
         p: EventLoopWindowTarget {
@@ -193,9 +211,13 @@ impl<T: 'static> EventLoop<T> {
           thread_msg_target,
           runner_shared,
         },
+        control_flow: Default::default(),
+        scale_factor_rounding: Default::default(),
+        #[cfg(feature = "testing")]
+        injected_events: Default::default(),
         _marker: PhantomData,
       },
-    }
+    })
   }
 
   pub fn window_target(&self) -> &RootELW<T> {
@@ -206,11 +228,18 @@ impl<T: 'static> EventLoop<T> {
   where
     F: 'static + FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
   {
-    let exit_code = self.run_return(event_handler);
+    let exit_code = self.run_inner(event_handler, true);
     ::std::process::exit(exit_code);
   }
 
-  pub fn run_return<F>(&mut self, mut event_handler: F) -> i32
+  pub fn run_return<F>(&mut self, event_handler: F) -> i32
+  where
+    F: FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
+  {
+    self.run_inner(event_handler, false)
+  }
+
+  fn run_inner<F>(&mut self, mut event_handler: F, exiting: bool) -> i32
   where
     F: FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
   {
@@ -240,15 +269,24 @@ impl<T: 'static> EventLoop<T> {
         // global accelerator
         if msg.message == WM_HOTKEY {
           let event_loop_runner = self.window_target.p.runner_shared.clone();
+          global_shortcut::on_hotkey_pressed(msg.wParam.0 as u16);
           event_loop_runner.send_event(Event::GlobalShortcutEvent(AcceleratorId(
             msg.wParam.0 as u16,
           )));
+        } else if msg.message == *GLOBAL_SHORTCUT_RELEASED_MSG_ID {
+          let event_loop_runner = self.window_target.p.runner_shared.clone();
+          event_loop_runner.send_event(Event::GlobalShortcutReleased(AcceleratorId(
+            msg.wParam.0 as u16,
+          )));
         }
 
-        // window accelerator
-        let accels = accelerator::find_accels(GetAncestor(msg.hwnd, GA_ROOT));
+        // window accelerator, the table is kept apart from the menu so shortcuts also work
+        // while the menu bar is hidden. The `WM_COMMAND` goes to the top-level window, where
+        // the menu subclass is installed, even when a child window has the focus.
+        let root = GetAncestor(msg.hwnd, GA_ROOT);
+        let accels = accelerator::find_accels(root);
         let translated = accels.map_or(false, |it| {
-          TranslateAcceleratorW(msg.hwnd, it.handle(), &msg) != 0
+          TranslateAcceleratorW(root, it.handle(), &msg) != 0
         });
         if !translated {
           TranslateMessage(&msg);
@@ -269,8 +307,33 @@ impl<T: 'static> EventLoop<T> {
     };
 
     unsafe {
+      // Windows dropped during the last iteration have only posted their destroy request, and
+      // when the process is about to exit the remaining ones will never be dropped at all.
+      // Destroy them here so that every `Destroyed` event precedes `LoopDestroyed`.
+      let mut windows = Vec::new();
+      runner.owned_windows(|window| windows.push(window));
+      for window in windows {
+        let mut msg = MSG::default();
+        if exiting
+          || PeekMessageW(
+            &mut msg,
+            window,
+            *DESTROY_MSG_ID,
+            *DESTROY_MSG_ID,
+            PM_REMOVE,
+          )
+          .as_bool()
+        {
+          DestroyWindow(window);
+        }
+      }
       runner.loop_destroyed();
     }
+    // The handlers of the final events may have panicked as well
+    if let Err(payload) = runner.take_panic_error() {
+      runner.reset_runner();
+      panic::resume_unwind(payload);
+    }
     runner.reset_runner();
     exit_code
   }
@@ -301,6 +364,12 @@ impl<T> EventLoopWindowTarget<T> {
     let monitor = monitor::primary_monitor();
     Some(RootMonitorHandle { inner: monitor })
   }
+
+  pub fn set_application_menu(&self, _menu: menu::Menu) {}
+
+  pub fn listen_device_events(&self, allowed: DeviceEvents) {
+    raw_input::register_all_mice_and_keyboards_for_raw_input(self.thread_msg_target, allowed);
+  }
 }
 
 fn main_thread_id() -> u32 {
@@ -533,13 +602,32 @@ impl<T: 'static> EventLoopProxy<T> {
       }
     }
   }
+
+  pub fn wake_up(&self) -> Result<(), EventLoopClosed<()>> {
+    // The messages are all handled in the same iteration of the event loop, which coalesces
+    // the wake-ups.
+    unsafe {
+      if PostMessageW(self.target_window, *USER_EVENT_MSG_ID, WPARAM(1), LPARAM(0)).as_bool() {
+        Ok(())
+      } else {
+        Err(EventLoopClosed(()))
+      }
+    }
+  }
 }
 
 type WaitUntilInstantBox = Box<Instant>;
 
+/// Timer redrawing a window while it's being resized or moved, see `WM_ENTERSIZEMOVE`.
+const SIZE_MOVE_TIMER_ID: usize = 1;
+/// Roughly one frame at 60Hz, in milliseconds.
+const SIZE_MOVE_TIMER_INTERVAL: u32 = 16;
+/// Timer moving an animated custom cursor on to its next frame, see `Window::set_custom_cursor`.
+pub const CURSOR_ANIMATION_TIMER_ID: usize = 2;
+
 lazy_static! {
     // Message sent by the `EventLoopProxy` when we want to wake up the thread.
-    // WPARAM and LPARAM are unused.
+    // WPARAM is 1 when there is no user event to receive, LPARAM is unused.
     static ref USER_EVENT_MSG_ID: u32 = {
         unsafe {
             RegisterWindowMessageA("Tao::WakeupMsg")
@@ -588,6 +676,11 @@ lazy_static! {
     pub static ref SET_RETAIN_STATE_ON_SIZE_MSG_ID: u32 = unsafe {
         RegisterWindowMessageA("Tao::SetRetainMaximized")
     };
+    // Message posted to the thread when a global shortcut registered with
+    // `register_with_release` is released. WPARAM is the `AcceleratorId`, LPARAM is unused.
+    pub static ref GLOBAL_SHORTCUT_RELEASED_MSG_ID: u32 = unsafe {
+        RegisterWindowMessageA("Tao::GlobalShortcutReleased")
+    };
     static ref THREAD_EVENT_TARGET_WINDOW_CLASS: Vec<u16> = unsafe {
         let mut class_name= util::encode_wide("Tao Thread Event Target");
 
@@ -612,7 +705,7 @@ lazy_static! {
     };
 }
 
-fn create_event_target_window() -> HWND {
+fn create_event_target_window() -> Result<HWND, RootOsError> {
   let window = unsafe {
     CreateWindowExW(
       WS_EX_NOACTIVATE | WS_EX_TRANSPARENT | WS_EX_LAYERED,
@@ -629,6 +722,11 @@ fn create_event_target_window() -> HWND {
       ptr::null_mut(),
     )
   };
+  if window.is_invalid() {
+    return Err(os_error!(OsError::CreationError(
+      "Unable to create the event loop window"
+    )));
+  }
   util::SetWindowLongPtrW(
     window,
     GWL_STYLE,
@@ -637,7 +735,7 @@ fn create_event_target_window() -> HWND {
     // the LAYERED style.
     (WS_VISIBLE | WS_POPUP) as isize,
   );
-  window
+  Ok(window)
 }
 
 fn subclass_event_target_window<T>(
@@ -912,7 +1010,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: KeyboardInput {
-          device_id: DEVICE_ID,
+          device_id: key_device_id(event.event.physical_key),
           event: event.event,
           is_synthetic: event.is_synthetic,
         },
@@ -957,6 +1055,13 @@ unsafe fn public_window_callback_inner<T: 'static>(
         .window_state
         .lock()
         .set_window_flags_in_place(|f| f.insert(WindowFlags::MARKER_IN_SIZE_MOVE));
+      // The modal size/move loop doesn't dispatch our thread messages, the timer keeps the
+      // event loop iterating and the window repainted until it ends.
+      SetTimer(window, SIZE_MOVE_TIMER_ID, SIZE_MOVE_TIMER_INTERVAL, None);
+      subclass_input.send_event(Event::WindowEvent {
+        window_id: RootWindowId(WindowId(window.0)),
+        event: WindowEvent::MoveResizeStarted,
+      });
       result = ProcResult::Value(LRESULT(0));
     }
 
@@ -965,6 +1070,46 @@ unsafe fn public_window_callback_inner<T: 'static>(
         .window_state
         .lock()
         .set_window_flags_in_place(|f| f.remove(WindowFlags::MARKER_IN_SIZE_MOVE));
+      KillTimer(window, SIZE_MOVE_TIMER_ID);
+      subclass_input.send_event(Event::WindowEvent {
+        window_id: RootWindowId(WindowId(window.0)),
+        event: WindowEvent::MoveResizeEnded,
+      });
+      result = ProcResult::Value(LRESULT(0));
+    }
+
+    win32wm::WM_TIMER if wparam.0 == SIZE_MOVE_TIMER_ID => {
+      // Start a new iteration, the `WM_PAINT` that follows finishes it with `RedrawRequested`.
+      if !subclass_input.event_loop_runner.should_buffer() {
+        subclass_input.event_loop_runner.poll();
+      }
+      RedrawWindow(window, ptr::null(), HRGN::default(), RDW_INTERNALPAINT);
+      result = ProcResult::Value(LRESULT(0));
+    }
+
+    win32wm::WM_TIMER if wparam.0 == CURSOR_ANIMATION_TIMER_ID => {
+      let mut window_state = subclass_input.window_state.lock();
+      let in_window = window_state
+        .mouse
+        .cursor_flags()
+        .contains(CursorFlags::IN_WINDOW);
+      match window_state.mouse.custom_cursor.as_mut() {
+        Some(cursor) => {
+          cursor.next_frame();
+          SetTimer(
+            window,
+            CURSOR_ANIMATION_TIMER_ID,
+            cursor.frame_interval(),
+            None,
+          );
+          if in_window {
+            SetCursor(cursor.handle());
+          }
+        }
+        None => {
+          KillTimer(window, CURSOR_ANIMATION_TIMER_ID);
+        }
+      }
       result = ProcResult::Value(LRESULT(0));
     }
 
@@ -981,6 +1126,102 @@ unsafe fn public_window_callback_inner<T: 'static>(
         window_id: RootWindowId(WindowId(window.0)),
         event: DecorationsClick,
       });
+
+      // The buttons of a custom titlebar act on release, skip the tracking of native buttons
+      let region_hit = nc_hit_test(
+        window,
+        &subclass_input.window_state.lock(),
+        i32::from(util::GET_X_LPARAM(lparam)),
+        i32::from(util::GET_Y_LPARAM(lparam)),
+      );
+      if let Some(button) = caption_button(region_hit) {
+        subclass_input.send_event(Event::WindowEvent {
+          window_id: RootWindowId(WindowId(window.0)),
+          event: WindowEvent::CaptionButtonInput {
+            button,
+            state: ElementState::Pressed,
+          },
+        });
+        result = ProcResult::Value(LRESULT(0));
+      }
+    }
+
+    win32wm::WM_NCLBUTTONUP => {
+      let region_hit = nc_hit_test(
+        window,
+        &subclass_input.window_state.lock(),
+        i32::from(util::GET_X_LPARAM(lparam)),
+        i32::from(util::GET_Y_LPARAM(lparam)),
+      );
+      if let Some(button) = caption_button(region_hit) {
+        subclass_input.send_event(Event::WindowEvent {
+          window_id: RootWindowId(WindowId(window.0)),
+          event: WindowEvent::CaptionButtonInput {
+            button,
+            state: ElementState::Released,
+          },
+        });
+        match button {
+          CaptionButton::Minimize => {
+            ShowWindow(window, SW_MINIMIZE);
+          }
+          CaptionButton::Maximize => {
+            ShowWindow(
+              window,
+              if util::is_maximized(window) {
+                SW_RESTORE
+              } else {
+                SW_MAXIMIZE
+              },
+            );
+          }
+          CaptionButton::Close => {
+            PostMessageW(window, WM_CLOSE, WPARAM(0), LPARAM(0));
+          }
+        }
+        result = ProcResult::Value(LRESULT(0));
+      }
+    }
+
+    win32wm::WM_NCMOUSEMOVE => {
+      let mut window_state = subclass_input.window_state.lock();
+      let button = caption_button(nc_hit_test(
+        window,
+        &window_state,
+        i32::from(util::GET_X_LPARAM(lparam)),
+        i32::from(util::GET_Y_LPARAM(lparam)),
+      ));
+      if window_state.hovered_caption_button != button {
+        if window_state.hovered_caption_button.is_none() {
+          // Calling TrackMouseEvent in order to receive non-client mouse leave events.
+          TrackMouseEvent(&mut TRACKMOUSEEVENT {
+            cbSize: mem::size_of::<TRACKMOUSEEVENT>() as u32,
+            dwFlags: TME_LEAVE | TME_NONCLIENT,
+            hwndTrack: window,
+            dwHoverTime: HOVER_DEFAULT,
+          });
+        }
+        window_state.hovered_caption_button = button;
+        drop(window_state);
+        subclass_input.send_event(Event::WindowEvent {
+          window_id: RootWindowId(WindowId(window.0)),
+          event: WindowEvent::CaptionButtonHovered(button),
+        });
+      }
+    }
+
+    win32wm::WM_NCMOUSELEAVE => {
+      let hovered = subclass_input
+        .window_state
+        .lock()
+        .hovered_caption_button
+        .take();
+      if hovered.is_some() {
+        subclass_input.send_event(Event::WindowEvent {
+          window_id: RootWindowId(WindowId(window.0)),
+          event: WindowEvent::CaptionButtonHovered(None),
+        });
+      }
     }
 
     win32wm::WM_CLOSE => {
@@ -993,17 +1234,24 @@ unsafe fn public_window_callback_inner<T: 'static>(
     }
 
     win32wm::WM_DESTROY => {
-      use crate::event::WindowEvent::Destroyed;
       let _ = RevokeDragDrop(window);
-      subclass_input.send_event(Event::WindowEvent {
-        window_id: RootWindowId(WindowId(window.0)),
-        event: Destroyed,
-      });
-      subclass_input.event_loop_runner.remove_window(window);
+      let saved_display_mode = subclass_input.window_state.lock().saved_display_mode.take();
+      if let Some(mut saved) = saved_display_mode {
+        restore_display_mode(&mut saved);
+      }
       result = ProcResult::Value(LRESULT(0));
     }
 
     win32wm::WM_NCDESTROY => {
+      use crate::event::WindowEvent::Destroyed;
+      // `WM_NCDESTROY` is the last message a window receives, so child windows have already
+      // been torn down and the handle is still valid for the event's `WindowId`.
+      if subclass_input.event_loop_runner.remove_window(window) {
+        subclass_input.send_event(Event::WindowEvent {
+          window_id: RootWindowId(WindowId(window.0)),
+          event: Destroyed,
+        });
+      }
       remove_window_subclass::<T>(window);
       subclass_input.subclass_removed.set(true);
       result = ProcResult::Value(LRESULT(0));
@@ -1016,7 +1264,22 @@ unsafe fn public_window_callback_inner<T: 'static>(
         RedrawWindow(window, ptr::null(), HRGN::default(), RDW_INTERNALPAINT);
       } else {
         let managing_redraw = flush_paint_messages(Some(window), &subclass_input.event_loop_runner);
+        {
+          // `RDW_INTERNALPAINT` doesn't invalidate anything, so a full redraw is also needed when
+          // there is no update region.
+          let mut update_rect = RECT::default();
+          let has_update_rect = GetUpdateRect(window, &mut update_rect, false).as_bool();
+          let mut window_state = subclass_input.window_state.lock();
+          let redraw_full = std::mem::take(&mut window_state.redraw_full);
+          window_state.redraw_rect = if redraw_full || !has_update_rect {
+            None
+          } else {
+            Some(update_rect)
+          };
+        }
         subclass_input.send_event(Event::RedrawRequested(RootWindowId(WindowId(window.0))));
+        // The region is only valid while the event is handled
+        subclass_input.window_state.lock().redraw_rect = None;
         if managing_redraw {
           subclass_input.event_loop_runner.redraw_events_cleared();
           process_control_flow(&subclass_input.event_loop_runner);
@@ -1084,6 +1347,8 @@ unsafe fn public_window_callback_inner<T: 'static>(
         });
       }
 
+      place_protected_window(window, &subclass_input.window_state.lock());
+
       // This is necessary for us to still get sent WM_SIZE.
       result = ProcResult::DefSubclassProc;
     }
@@ -1154,7 +1419,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
         subclass_input.send_event(Event::WindowEvent {
           window_id: RootWindowId(WindowId(window.0)),
           event: CursorEntered {
-            device_id: DEVICE_ID,
+            device_id: pointer_device_id(),
           },
         });
 
@@ -1184,7 +1449,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
         subclass_input.send_event(Event::WindowEvent {
           window_id: RootWindowId(WindowId(window.0)),
           event: CursorMoved {
-            device_id: DEVICE_ID,
+            device_id: pointer_device_id(),
             position,
             modifiers,
           },
@@ -1206,7 +1471,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: CursorLeft {
-          device_id: DEVICE_ID,
+          device_id: pointer_device_id(),
         },
       });
 
@@ -1224,9 +1489,10 @@ unsafe fn public_window_callback_inner<T: 'static>(
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: WindowEvent::MouseWheel {
-          device_id: DEVICE_ID,
+          device_id: pointer_device_id(),
           delta: LineDelta(0.0, value),
           phase: TouchPhase::Moved,
+          momentum_phase: None,
           modifiers,
         },
       });
@@ -1245,9 +1511,10 @@ unsafe fn public_window_callback_inner<T: 'static>(
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: WindowEvent::MouseWheel {
-          device_id: DEVICE_ID,
+          device_id: pointer_device_id(),
           delta: LineDelta(value, 0.0),
           phase: TouchPhase::Moved,
+          momentum_phase: None,
           modifiers,
         },
       });
@@ -1271,7 +1538,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: MouseInput {
-          device_id: DEVICE_ID,
+          device_id: button_device_id(0),
           state: Pressed,
           button: Left,
           modifiers,
@@ -1290,7 +1557,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: MouseInput {
-          device_id: DEVICE_ID,
+          device_id: button_device_id(0),
           state: Released,
           button: Left,
           modifiers,
@@ -1309,7 +1576,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: MouseInput {
-          device_id: DEVICE_ID,
+          device_id: button_device_id(2),
           state: Pressed,
           button: Right,
           modifiers,
@@ -1328,7 +1595,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: MouseInput {
-          device_id: DEVICE_ID,
+          device_id: button_device_id(2),
           state: Released,
           button: Right,
           modifiers,
@@ -1347,7 +1614,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: MouseInput {
-          device_id: DEVICE_ID,
+          device_id: button_device_id(1),
           state: Pressed,
           button: Middle,
           modifiers,
@@ -1366,7 +1633,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: MouseInput {
-          device_id: DEVICE_ID,
+          device_id: button_device_id(1),
           state: Released,
           button: Middle,
           modifiers,
@@ -1376,8 +1643,16 @@ unsafe fn public_window_callback_inner<T: 'static>(
     }
 
     win32wm::WM_XBUTTONDOWN => {
-      use crate::event::{ElementState::Pressed, MouseButton::Other, WindowEvent::MouseInput};
-      let xbutton = util::GET_XBUTTON_WPARAM(wparam);
+      use crate::event::{
+        ElementState::Pressed,
+        MouseButton::{Back, Forward, Other},
+        WindowEvent::MouseInput,
+      };
+      let button = match util::GET_XBUTTON_WPARAM(wparam) {
+        1 => Back,    // XBUTTON1
+        2 => Forward, // XBUTTON2
+        xbutton => Other(xbutton),
+      };
 
       capture_mouse(window, &mut *subclass_input.window_state.lock());
 
@@ -1386,9 +1661,9 @@ unsafe fn public_window_callback_inner<T: 'static>(
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: MouseInput {
-          device_id: DEVICE_ID,
+          device_id: pointer_device_id(),
           state: Pressed,
-          button: Other(xbutton),
+          button,
           modifiers,
         },
       });
@@ -1396,8 +1671,16 @@ unsafe fn public_window_callback_inner<T: 'static>(
     }
 
     win32wm::WM_XBUTTONUP => {
-      use crate::event::{ElementState::Released, MouseButton::Other, WindowEvent::MouseInput};
-      let xbutton = util::GET_XBUTTON_WPARAM(wparam);
+      use crate::event::{
+        ElementState::Released,
+        MouseButton::{Back, Forward, Other},
+        WindowEvent::MouseInput,
+      };
+      let button = match util::GET_XBUTTON_WPARAM(wparam) {
+        1 => Back,    // XBUTTON1
+        2 => Forward, // XBUTTON2
+        xbutton => Other(xbutton),
+      };
 
       release_mouse(subclass_input.window_state.lock());
 
@@ -1406,9 +1689,9 @@ unsafe fn public_window_callback_inner<T: 'static>(
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: MouseInput {
-          device_id: DEVICE_ID,
+          device_id: pointer_device_id(),
           state: Released,
-          button: Other(xbutton),
+          button,
           modifiers,
         },
       });
@@ -1613,10 +1896,54 @@ unsafe fn public_window_callback_inner<T: 'static>(
       result = ProcResult::Value(LRESULT(0));
     }
 
+    // Like games do, give the desktop its video mode back while another application is
+    // active and switch to the exclusive fullscreen mode again once we are reactivated.
+    win32wm::WM_ACTIVATEAPP => {
+      let active = wparam.0 != 0;
+      let mut window_state = subclass_input.window_state.lock();
+      if let Some(Fullscreen::Exclusive(video_mode)) = window_state.fullscreen.clone() {
+        if active && window_state.saved_display_mode.is_none() {
+          drop(window_state);
+          if let Some(saved) = set_display_mode(&video_mode) {
+            subclass_input.window_state.lock().saved_display_mode = Some(saved);
+
+            if let Ok(monitor_info) =
+              monitor::get_monitor_info(video_mode.monitor().inner.hmonitor())
+            {
+              let rect = monitor_info.monitorInfo.rcMonitor;
+              SetWindowPos(
+                window,
+                HWND::default(),
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                SWP_NOZORDER | SWP_NOACTIVATE,
+              );
+            }
+          }
+        } else if !active {
+          if let Some(mut saved) = window_state.saved_display_mode.take() {
+            drop(window_state);
+            restore_display_mode(&mut saved);
+            ShowWindow(window, SW_MINIMIZE);
+          }
+        }
+      }
+    }
+
     win32wm::WM_SETFOCUS => {
       use crate::event::WindowEvent::Focused;
       update_modifiers(window, subclass_input);
 
+      // Re-apply the cursor grab that was released when the window lost focus.
+      subclass_input
+        .window_state
+        .lock()
+        .mouse
+        .set_cursor_flags(window, |_| ())
+        .ok();
+
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: Focused(true),
@@ -1628,7 +1955,14 @@ unsafe fn public_window_callback_inner<T: 'static>(
     win32wm::WM_KILLFOCUS => {
       use crate::event::WindowEvent::{Focused, ModifiersChanged};
 
-      subclass_input.window_state.lock().modifiers_state = ModifiersState::empty();
+      {
+        let mut w = subclass_input.window_state.lock();
+        w.modifiers_state = ModifiersState::empty();
+        // The cursor grab is focus-scoped, release it until the window is focused again.
+        if w.mouse.cursor_flags().contains(CursorFlags::GRABBED) {
+          util::set_cursor_clip(None).ok();
+        }
+      }
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: ModifiersChanged(ModifiersState::empty()),
@@ -1649,7 +1983,13 @@ unsafe fn public_window_callback_inner<T: 'static>(
         // `WM_MOUSEMOVE` seems to come after `WM_SETCURSOR` for a given cursor movement.
         let in_client_area = u32::from(util::LOWORD(lparam.0 as u32)) == HTCLIENT;
         if in_client_area {
-          Some(window_state.mouse.cursor)
+          Some(match &window_state.mouse.custom_cursor {
+            Some(cursor) => cursor.handle(),
+            None => LoadCursorW(
+              HINSTANCE::default(),
+              window_state.mouse.cursor.to_windows_cursor(),
+            ),
+          })
         } else {
           None
         }
@@ -1657,7 +1997,6 @@ unsafe fn public_window_callback_inner<T: 'static>(
 
       match set_cursor_to {
         Some(cursor) => {
-          let cursor = LoadCursorW(HINSTANCE::default(), cursor.to_windows_cursor());
           SetCursor(cursor);
           result = ProcResult::Value(LRESULT(0));
         }
@@ -1665,6 +2004,56 @@ unsafe fn public_window_callback_inner<T: 'static>(
       }
     }
 
+    win32wm::WM_SIZING => {
+      let window_state = subclass_input.window_state.lock();
+      let frame = util::get_window_rect(window).zip(util::get_client_rect(window).ok());
+
+      if let (Some(ratio), Some((window_rect, client_rect))) = (window_state.aspect_ratio, frame) {
+        let rect = &mut *(lparam.0 as *mut RECT);
+        // The ratio applies to the client area, excluding the frame.
+        let frame_width =
+          (window_rect.right - window_rect.left) - (client_rect.right - client_rect.left);
+        let frame_height =
+          (window_rect.bottom - window_rect.top) - (client_rect.bottom - client_rect.top);
+        let mut width = (rect.right - rect.left - frame_width) as f64;
+        let mut height = (rect.bottom - rect.top - frame_height) as f64;
+
+        let edge = wparam.0 as u32;
+        if edge == WMSZ_TOP || edge == WMSZ_BOTTOM {
+          width = height * ratio;
+        } else {
+          height = width / ratio;
+        }
+
+        // The min and max sizes take precedence over the ratio.
+        if let Some(min_size) = window_state.min_size {
+          let min_size: PhysicalSize<f64> = min_size.to_physical(window_state.scale_factor);
+          width = width.max(min_size.width);
+          height = height.max(min_size.height);
+        }
+        if let Some(max_size) = window_state.max_size {
+          let max_size: PhysicalSize<f64> = max_size.to_physical(window_state.scale_factor);
+          width = width.min(max_size.width);
+          height = height.min(max_size.height);
+        }
+
+        let width = width.round() as i32 + frame_width;
+        let height = height.round() as i32 + frame_height;
+        if edge == WMSZ_LEFT || edge == WMSZ_TOPLEFT || edge == WMSZ_BOTTOMLEFT {
+          rect.left = rect.right - width;
+        } else {
+          rect.right = rect.left + width;
+        }
+        if edge == WMSZ_TOP || edge == WMSZ_TOPLEFT || edge == WMSZ_TOPRIGHT {
+          rect.top = rect.bottom - height;
+        } else {
+          rect.bottom = rect.top + height;
+        }
+
+        result = ProcResult::Value(LRESULT(1));
+      }
+    }
+
     win32wm::WM_GETMINMAXINFO => {
       let mmi = lparam.0 as *mut MINMAXINFO;
 
@@ -1677,7 +2066,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
         if let Some(min_size) = window_state.min_size {
           let min_size = min_size.to_physical(window_state.scale_factor);
           let (width, height): (u32, u32) =
-            util::adjust_size(window, min_size, is_decorated).into();
+            util::client_to_window_size(window, min_size, is_decorated).into();
           (*mmi).ptMinTrackSize = POINT {
             x: width as i32,
             y: height as i32,
@@ -1686,7 +2075,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
         if let Some(max_size) = window_state.max_size {
           let max_size = max_size.to_physical(window_state.scale_factor);
           let (width, height): (u32, u32) =
-            util::adjust_size(window, max_size, is_decorated).into();
+            util::client_to_window_size(window, max_size, is_decorated).into();
           (*mmi).ptMaxTrackSize = POINT {
             x: width as i32,
             y: height as i32,
@@ -1710,7 +2099,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
       let new_scale_factor = dpi_to_scale_factor(new_dpi_x);
       let old_scale_factor: f64;
 
-      let (allow_resize, is_decorated) = {
+      let (allow_resize, is_decorated, window_icon, taskbar_icon) = {
         let mut window_state = subclass_input.window_state.lock();
         old_scale_factor = window_state.scale_factor;
         window_state.scale_factor = new_scale_factor;
@@ -1724,9 +2113,16 @@ unsafe fn public_window_callback_inner<T: 'static>(
         (
           window_state.fullscreen.is_none() && !window_flags.contains(WindowFlags::MAXIMIZED),
           window_flags.contains(WindowFlags::DECORATIONS),
+          window_state.window_icon.clone(),
+          window_state.taskbar_icon.clone(),
         )
       };
 
+      // Pick the icon sizes matching the new DPI
+      if window_icon.is_some() || taskbar_icon.is_some() {
+        icon::set_icons_for_window(window, window_icon.as_ref(), taskbar_icon.as_ref());
+      }
+
       let mut style = GetWindowLongW(window, GWL_STYLE) as WINDOW_STYLE;
       // if the window isn't decorated, remove `WS_SIZEBOX` and `WS_CAPTION` so
       // `AdjustWindowRect*` functions doesn't account for the hidden caption and borders and
@@ -1787,6 +2183,9 @@ unsafe fn public_window_callback_inner<T: 'static>(
         false => old_physical_inner_size,
       };
 
+      subclass_input.window_state.lock().dpi_changed_monitor = Some(MonitorHandle::new(
+        MonitorFromRect(&suggested_rect, MONITOR_DEFAULTTONEAREST),
+      ));
       let _ = subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: ScaleFactorChanged {
@@ -1794,6 +2193,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
           new_inner_size: &mut new_physical_inner_size,
         },
       });
+      subclass_input.window_state.lock().dpi_changed_monitor = None;
 
       let dragging_window: bool;
 
@@ -1943,36 +2343,59 @@ unsafe fn public_window_callback_inner<T: 'static>(
       let win_flags = subclass_input.window_state.lock().window_flags();
 
       if !win_flags.contains(WindowFlags::DECORATIONS) {
+        let params = &mut *(lparam.0 as *mut NCCALCSIZE_PARAMS);
         // adjust the maximized borderless window so it doesn't cover the taskbar
         if util::is_maximized(window) {
           let monitor = monitor::current_monitor(window);
           if let Ok(monitor_info) = monitor::get_monitor_info(monitor.hmonitor()) {
-            let params = &mut *(lparam.0 as *mut NCCALCSIZE_PARAMS);
             params.rgrc[0] = monitor_info.monitorInfo.rcWork;
           }
+        } else if win_flags.contains(WindowFlags::UNDECORATED_SHADOW) {
+          // leave the top pixel to the extended frame, otherwise DWM paints it white
+          params.rgrc[0].top += 1;
+          params.rgrc[0].bottom += 1;
         }
         result = ProcResult::Value(LRESULT(0)); // return 0 here to make the window borderless
+      } else if win_flags.contains(WindowFlags::TITLEBAR_HIDDEN) {
+        let params = &mut *(lparam.0 as *mut NCCALCSIZE_PARAMS);
+        let window_rect = params.rgrc[0];
+        let value = DefSubclassProc(window, msg, wparam, lparam);
+        // give the caption to the client area, leaving a top resize border as thick as the side
+        // ones. A maximized window is framed outside of the monitor, so this also removes the
+        // caption without covering the taskbar.
+        let frame = params.rgrc[0].left - window_rect.left;
+        params.rgrc[0].top = window_rect.top + frame;
+        result = ProcResult::Value(value);
       } else {
         result = ProcResult::DefSubclassProc;
       }
     }
 
     win32wm::WM_NCHITTEST => {
-      // Allow resizing unmaximized borderless window
-      if !util::is_maximized(window)
-        && !subclass_input
-          .window_state
-          .lock()
-          .window_flags()
-          .contains(WindowFlags::DECORATIONS)
-      {
-        // cursor location
-        let (cx, cy) = (
-          i32::from(util::GET_X_LPARAM(lparam)),
-          i32::from(util::GET_Y_LPARAM(lparam)),
-        );
-
+      // cursor location
+      let (cx, cy) = (
+        i32::from(util::GET_X_LPARAM(lparam)),
+        i32::from(util::GET_Y_LPARAM(lparam)),
+      );
+      let window_state = subclass_input.window_state.lock();
+      let region_hit = nc_hit_test(window, &window_state, cx, cy);
+      let window_flags = window_state.window_flags();
+      let decorations = window_flags.contains(WindowFlags::DECORATIONS);
+      let titlebar_hidden =
+        decorations && window_flags.contains(WindowFlags::TITLEBAR_HIDDEN | WindowFlags::RESIZABLE);
+      drop(window_state);
+
+      if let Some(hit) = region_hit {
+        result = ProcResult::Value(LRESULT(hit as _));
+      } else if !util::is_maximized(window) && !decorations {
+        // Allow resizing unmaximized borderless window
         result = ProcResult::Value(crate::platform_impl::hit_test(window, cx, cy));
+      } else if let Some(hit) = (titlebar_hidden && !util::is_maximized(window))
+        .then(|| hidden_titlebar_hit_test(window, cx, cy))
+        .flatten()
+      {
+        // The default hit test still places the caption over the top border
+        result = ProcResult::Value(hit);
       } else {
         result = ProcResult::DefSubclassProc;
       }
@@ -2057,6 +2480,11 @@ unsafe extern "system" fn thread_event_target_callback<T: 'static>(
       DefSubclassProc(window, msg, wparam, lparam)
     }
 
+    win32wm::WM_DISPLAYCHANGE => {
+      monitor::invalidate_monitors();
+      DefSubclassProc(window, msg, wparam, lparam)
+    }
+
     win32wm::WM_INPUT_DEVICE_CHANGE => {
       let event = match wparam.0 as u32 {
         win32wm::GIDC_ARRIVAL => DeviceEvent::Added,
@@ -2081,8 +2509,10 @@ unsafe extern "system" fn thread_event_target_callback<T: 'static>(
     }
 
     _ if msg == *USER_EVENT_MSG_ID => {
-      if let Ok(event) = subclass_input.user_event_receiver.recv() {
-        subclass_input.send_event(Event::UserEvent(event));
+      if wparam.0 == 0 {
+        if let Ok(event) = subclass_input.user_event_receiver.recv() {
+          subclass_input.send_event(Event::UserEvent(event));
+        }
       }
       LRESULT(0)
     }
@@ -2154,6 +2584,7 @@ unsafe fn handle_raw_input<T: 'static>(
 
   if data.header.dwType == RIM_TYPEMOUSE {
     let mouse = data.data.mouse;
+    set_mouse_device(None, data.header.hDevice.0 as _);
 
     if util::has_flag(mouse.usFlags, MOUSE_MOVE_RELATIVE as u16) {
       let x = mouse.lLastX as f64;
@@ -2200,6 +2631,7 @@ unsafe fn handle_raw_input<T: 'static>(
     // Left, middle, and right, respectively.
     for (index, state) in button_state.iter().enumerate() {
       if let Some(state) = *state {
+        set_mouse_device(Some(index), data.header.hDevice.0 as _);
         // This gives us consistency with X11, since there doesn't
         // seem to be anything else reasonable to do for a mouse
         // button ID.
@@ -2279,6 +2711,7 @@ unsafe fn handle_raw_input<T: 'static>(
     } else {
       code = KeyCode::from_scancode(scancode as u32);
     }
+    set_key_device(code, data.header.hDevice.0 as _);
     if keyboard.VKey == VK_SHIFT {
       match code {
         KeyCode::NumpadDecimal
@@ -2321,3 +2754,13 @@ unsafe fn handle_raw_input<T: 'static>(
     });
   }
 }
+
+/// The button of a custom titlebar for the result of `nc_hit_test`.
+fn caption_button(hit: Option<u32>) -> Option<CaptionButton> {
+  match hit {
+    Some(HTMINBUTTON) => Some(CaptionButton::Minimize),
+    Some(HTMAXBUTTON) => Some(CaptionButton::Maximize),
+    Some(HTCLOSE) => Some(CaptionButton::Close),
+    _ => None,
+  }
+}
@@ -181,10 +181,12 @@ impl<T> EventLoopRunner<T> {
     self.owned_windows.set(owned_windows);
   }
 
-  pub fn remove_window(&self, window: HWND) {
+  /// Returns `false` if the window was already removed.
+  pub fn remove_window(&self, window: HWND) -> bool {
     let mut owned_windows = self.owned_windows.take();
-    owned_windows.remove(&window.0);
+    let removed = owned_windows.remove(&window.0);
     self.owned_windows.set(owned_windows);
+    removed
   }
 
   pub fn owned_windows(&self, mut f: impl FnMut(HWND)) {
@@ -404,6 +406,7 @@ impl<T> EventLoopRunner<T> {
 
   unsafe fn call_redraw_events_cleared(&self) {
     self.call_event_handler(Event::RedrawEventsCleared);
+    self.call_event_handler(Event::AboutToWait);
     self.last_events_cleared.set(Instant::now());
   }
 }
@@ -1,11 +1,17 @@
-use super::keyboard::key_to_vk;
+use std::cell::RefCell;
+
+use super::{event_loop::GLOBAL_SHORTCUT_RELEASED_MSG_ID, keyboard::key_to_vk};
 use crate::{
   accelerator::{Accelerator, AcceleratorId},
   event_loop::EventLoopWindowTarget,
   global_shortcut::{GlobalShortcut as RootGlobalShortcut, ShortcutManagerError},
   keyboard::ModifiersState,
 };
-use windows::Win32::{Foundation::HWND, UI::Input::KeyboardAndMouse::*};
+use windows::Win32::{
+  Foundation::{HWND, LPARAM, LRESULT, PWSTR, WPARAM},
+  System::{LibraryLoader::GetModuleHandleW, Threading::GetCurrentThreadId},
+  UI::{Input::KeyboardAndMouse::*, WindowsAndMessaging::*},
+};
 
 #[derive(Debug, Clone)]
 pub struct ShortcutManager {
@@ -22,6 +28,7 @@ impl ShortcutManager {
   pub(crate) fn register(
     &mut self,
     accelerator: Accelerator,
+    report_release: bool,
   ) -> Result<RootGlobalShortcut, ShortcutManagerError> {
     unsafe {
       let mut converted_modifiers = 0;
@@ -38,6 +45,10 @@ impl ShortcutManager {
       if modifiers.control_key() {
         converted_modifiers |= MOD_CONTROL;
       }
+      // A held shortcut that reports its release is only pressed once, until it's released
+      if report_release {
+        converted_modifiers |= MOD_NOREPEAT;
+      }
 
       // get key scan code
       match key_to_vk(&accelerator.key) {
@@ -53,6 +64,9 @@ impl ShortcutManager {
               "Unable to register accelerator with `RegisterHotKey`.".into(),
             ));
           }
+          if report_release {
+            watch_release(accelerator.clone().id().0, vk_code);
+          }
           let shortcut = GlobalShortcut { accelerator };
           self.shortcuts.push(shortcut.clone());
           Ok(RootGlobalShortcut(shortcut))
@@ -64,18 +78,21 @@ impl ShortcutManager {
     }
   }
 
-  pub(crate) fn unregister_all(&self) -> Result<(), ShortcutManagerError> {
-    for shortcut in &self.shortcuts {
+  pub(crate) fn unregister_all(&mut self) -> Result<(), ShortcutManagerError> {
+    for shortcut in self.shortcuts.drain(..) {
       shortcut.unregister();
     }
     Ok(())
   }
 
   pub(crate) fn unregister(
-    &self,
+    &mut self,
     shortcut: RootGlobalShortcut,
   ) -> Result<(), ShortcutManagerError> {
     shortcut.0.unregister();
+    self
+      .shortcuts
+      .retain(|registered| registered.id() != shortcut.0.id());
     Ok(())
   }
 }
@@ -93,5 +110,108 @@ impl GlobalShortcut {
     unsafe {
       UnregisterHotKey(HWND::default(), self.accelerator.clone().id().0 as i32);
     }
+    unwatch_release(self.accelerator.clone().id().0);
+  }
+}
+
+thread_local! {
+  /// `WM_HOTKEY` is only sent on press, the release of the shortcuts registered with
+  /// `register_with_release` is detected by a low-level keyboard hook.
+  static RELEASE_WATCHER: RefCell<ReleaseWatcher> = RefCell::new(ReleaseWatcher {
+    hook: HHOOK::default(),
+    shortcuts: Vec::new(),
+  });
+}
+
+struct ReleaseWatcher {
+  hook: HHOOK,
+  shortcuts: Vec<ReleaseShortcut>,
+}
+
+struct ReleaseShortcut {
+  id: u16,
+  vk: u16,
+  /// Set by `WM_HOTKEY` while the key is down, the release is only reported after a press.
+  pressed: bool,
+}
+
+fn watch_release(id: u16, vk: u16) {
+  RELEASE_WATCHER.with(|watcher| {
+    let mut watcher = watcher.borrow_mut();
+    if watcher.hook.is_invalid() {
+      watcher.hook = unsafe {
+        SetWindowsHookExW(
+          WH_KEYBOARD_LL,
+          Some(release_hook_proc),
+          GetModuleHandleW(PWSTR::default()),
+          0,
+        )
+      };
+    }
+    watcher.shortcuts.push(ReleaseShortcut {
+      id,
+      vk,
+      pressed: false,
+    });
+  });
+}
+
+fn unwatch_release(id: u16) {
+  RELEASE_WATCHER.with(|watcher| {
+    let mut watcher = watcher.borrow_mut();
+    watcher.shortcuts.retain(|shortcut| shortcut.id != id);
+    if watcher.shortcuts.is_empty() && !watcher.hook.is_invalid() {
+      unsafe { UnhookWindowsHookEx(watcher.hook) };
+      watcher.hook = HHOOK::default();
+    }
+  });
+}
+
+/// Called by the event loop on `WM_HOTKEY`.
+pub(crate) fn on_hotkey_pressed(id: u16) {
+  RELEASE_WATCHER.with(|watcher| {
+    if let Ok(mut watcher) = watcher.try_borrow_mut() {
+      for shortcut in watcher.shortcuts.iter_mut().filter(|s| s.id == id) {
+        // A quick tap can be released before the posted `WM_HOTKEY` is dispatched, the hook
+        // ignored that key up as the shortcut wasn't pressed yet.
+        let async_state = unsafe { GetAsyncKeyState(i32::from(shortcut.vk)) };
+        if (async_state & (1 << 15)) != 0 {
+          shortcut.pressed = true;
+        } else {
+          post_released(id);
+        }
+      }
+    }
+  });
+}
+
+fn post_released(id: u16) {
+  // The event is sent by the event loop, like `WM_HOTKEY`
+  unsafe {
+    PostThreadMessageW(
+      GetCurrentThreadId(),
+      *GLOBAL_SHORTCUT_RELEASED_MSG_ID,
+      WPARAM(id as usize),
+      LPARAM(0),
+    );
+  }
+}
+
+unsafe extern "system" fn release_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+  if code == HC_ACTION as i32 && matches!(wparam.0 as u32, WM_KEYUP | WM_SYSKEYUP) {
+    let vk = (*(lparam.0 as *const KBDLLHOOKSTRUCT)).vkCode;
+    RELEASE_WATCHER.with(|watcher| {
+      if let Ok(mut watcher) = watcher.try_borrow_mut() {
+        for shortcut in watcher
+          .shortcuts
+          .iter_mut()
+          .filter(|s| s.pressed && u32::from(s.vk) == vk)
+        {
+          shortcut.pressed = false;
+          post_released(shortcut.id);
+        }
+      }
+    });
   }
+  CallNextHookEx(HHOOK::default(), code, wparam, lparam)
 }
@@ -1,15 +1,24 @@
 // Copyright 2019-2021 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{fmt, io, iter::once, mem, os::windows::ffi::OsStrExt, path::Path, sync::Arc};
+use std::{
+  ffi::c_void, fmt, io, iter::once, mem, os::windows::ffi::OsStrExt, path::Path, sync::Arc,
+  time::Duration,
+};
 
 use windows::Win32::{
   Foundation::{HINSTANCE, HWND, LPARAM, PWSTR, WPARAM},
+  Graphics::Gdi::{CreateBitmap, DeleteObject, HGDIOBJ},
   System::LibraryLoader::*,
   UI::WindowsAndMessaging::*,
 };
 
-use crate::{dpi::PhysicalSize, icon::*};
+use crate::{
+  dpi::PhysicalSize,
+  icon::*,
+  platform_impl::platform::dpi::{hwnd_dpi, BASE_DPI},
+  window::CustomCursor,
+};
 
 impl Pixel {
   fn to_bgra(&mut self) {
@@ -18,7 +27,7 @@ impl Pixel {
 }
 
 impl RgbaIcon {
-  fn into_windows_icon(self) -> Result<WinIcon, BadIcon> {
+  fn into_raii_icon(self) -> Result<RaiiIcon, BadIcon> {
     let mut rgba = self.rgba;
     let pixel_count = rgba.len() / PIXEL_SIZE;
     let mut and_mask = Vec::with_capacity(pixel_count);
@@ -40,11 +49,12 @@ impl RgbaIcon {
         rgba.as_ptr() as *const u8,
       ) as HICON
     };
-    Ok(WinIcon::from_handle(
-      handle
+    Ok(RaiiIcon {
+      handle: handle
         .ok()
         .map_err(|_| BadIcon::OsError(io::Error::last_os_error()))?,
-    ))
+      width: self.width,
+    })
   }
 }
 
@@ -55,21 +65,50 @@ pub enum IconType {
   Big = ICON_BIG as isize,
 }
 
+impl IconType {
+  /// The system icon sizes are 16x16 and 32x32 at 96 DPI.
+  pub fn width_for_dpi(&self, dpi: u32) -> u32 {
+    let width = match self {
+      IconType::Small => 16,
+      IconType::Big => 32,
+    };
+    width * dpi / BASE_DPI
+  }
+}
+
 #[derive(Debug)]
 struct RaiiIcon {
   handle: HICON,
+  /// 0 when the system picked the size.
+  width: u32,
 }
 
 #[derive(Clone)]
 pub struct WinIcon {
-  inner: Arc<RaiiIcon>,
+  /// The same icon in several sizes, sorted by width. Never empty.
+  inner: Arc<Vec<RaiiIcon>>,
 }
 
 unsafe impl Send for WinIcon {}
 
 impl WinIcon {
+  /// The handle of the largest size.
   pub fn as_raw_handle(&self) -> HICON {
-    self.inner.handle
+    self.inner.last().unwrap().handle
+  }
+
+  pub fn has_multiple_sizes(&self) -> bool {
+    self.inner.len() > 1
+  }
+
+  /// The handle of the smallest size that's at least `width` wide, or of the largest size.
+  pub fn raw_handle_for_width(&self, width: u32) -> HICON {
+    self
+      .inner
+      .iter()
+      .find(|icon| icon.width >= width)
+      .unwrap_or_else(|| self.inner.last().unwrap())
+      .handle
   }
 
   pub fn from_path<P: AsRef<Path>>(
@@ -84,7 +123,7 @@ impl WinIcon {
       .collect();
 
     // width / height of 0 along with LR_DEFAULTSIZE tells windows to load the default icon size
-    let (width, height) = size.map(Into::into).unwrap_or((0, 0));
+    let (width, height): (u32, u32) = size.map(Into::into).unwrap_or((0, 0));
 
     let handle = HICON(
       unsafe {
@@ -103,12 +142,13 @@ impl WinIcon {
       handle
         .ok()
         .map_err(|_| BadIcon::OsError(io::Error::last_os_error()))?,
+      width,
     ))
   }
 
   pub fn from_resource(resource_id: u16, size: Option<PhysicalSize<u32>>) -> Result<Self, BadIcon> {
     // width / height of 0 along with LR_DEFAULTSIZE tells windows to load the default icon size
-    let (width, height) = size.map(Into::into).unwrap_or((0, 0));
+    let (width, height): (u32, u32) = size.map(Into::into).unwrap_or((0, 0));
     let handle = HICON(unsafe {
       LoadImageW(
         GetModuleHandleW(PWSTR::default()),
@@ -124,28 +164,47 @@ impl WinIcon {
       handle
         .ok()
         .map_err(|_| BadIcon::OsError(io::Error::last_os_error()))?,
+      width,
     ))
   }
 
   pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, BadIcon> {
     let rgba_icon = RgbaIcon::from_rgba(rgba, width, height)?;
-    rgba_icon.into_windows_icon()
+    Ok(Self {
+      inner: Arc::new(vec![rgba_icon.into_raii_icon()?]),
+    })
+  }
+
+  pub fn from_rgba_sizes(sizes: Vec<(Vec<u8>, u32, u32)>) -> Result<Self, BadIcon> {
+    let mut icons = sizes
+      .into_iter()
+      .map(|(rgba, width, height)| RgbaIcon::from_rgba(rgba, width, height)?.into_raii_icon())
+      .collect::<Result<Vec<_>, _>>()?;
+    if icons.is_empty() {
+      return Err(BadIcon::NoSizes);
+    }
+    icons.sort_by_key(|icon| icon.width);
+    Ok(Self {
+      inner: Arc::new(icons),
+    })
   }
 
+  /// Sets the size of the icon matching `icon_type` at the window's DPI.
   pub fn set_for_window(&self, hwnd: HWND, icon_type: IconType) {
     unsafe {
+      let width = icon_type.width_for_dpi(hwnd_dpi(hwnd));
       SendMessageW(
         hwnd,
         WM_SETICON,
         WPARAM(icon_type as _),
-        LPARAM(self.as_raw_handle().0),
+        LPARAM(self.raw_handle_for_width(width).0),
       );
     }
   }
 
-  fn from_handle(handle: HICON) -> Self {
+  fn from_handle(handle: HICON, width: u32) -> Self {
     Self {
-      inner: Arc::new(RaiiIcon { handle }),
+      inner: Arc::new(vec![RaiiIcon { handle, width }]),
     }
   }
 }
@@ -162,8 +221,137 @@ impl fmt::Debug for WinIcon {
   }
 }
 
+/// Sets the small and big icons of the window. A window icon with several sizes is also used
+/// as the big icon when there's no taskbar icon.
+pub fn set_icons_for_window(hwnd: HWND, window_icon: Option<&Icon>, taskbar_icon: Option<&Icon>) {
+  match window_icon {
+    Some(icon) => icon.inner.set_for_window(hwnd, IconType::Small),
+    None => unset_for_window(hwnd, IconType::Small),
+  }
+  match taskbar_icon.or_else(|| window_icon.filter(|icon| icon.inner.has_multiple_sizes())) {
+    Some(icon) => icon.inner.set_for_window(hwnd, IconType::Big),
+    None => unset_for_window(hwnd, IconType::Big),
+  }
+}
+
 pub fn unset_for_window(hwnd: HWND, icon_type: IconType) {
   unsafe {
     SendMessageW(hwnd, WM_SETICON, WPARAM(icon_type as _), LPARAM(0));
   }
 }
+
+#[derive(Debug)]
+struct RaiiCursor {
+  handle: HCURSOR,
+  duration: Duration,
+}
+
+impl Drop for RaiiCursor {
+  fn drop(&mut self) {
+    unsafe { DestroyCursor(self.handle) };
+  }
+}
+
+/// The frames of a `CustomCursor`, and the one currently shown.
+#[derive(Debug, Clone)]
+pub struct WinCursor {
+  /// Never empty.
+  frames: Arc<Vec<RaiiCursor>>,
+  current: usize,
+}
+
+unsafe impl Send for WinCursor {}
+
+impl WinCursor {
+  pub fn from_custom_cursor(cursor: &CustomCursor) -> Result<Self, BadIcon> {
+    let frames = cursor
+      .frames
+      .iter()
+      .map(|frame| {
+        let handle = unsafe {
+          create_cursor(
+            frame.rgba.clone(),
+            cursor.width,
+            cursor.height,
+            cursor.hotspot.x,
+            cursor.hotspot.y,
+          )
+        }?;
+        Ok(RaiiCursor {
+          handle,
+          duration: frame.duration,
+        })
+      })
+      .collect::<Result<Vec<_>, BadIcon>>()?;
+    Ok(Self {
+      frames: Arc::new(frames),
+      current: 0,
+    })
+  }
+
+  pub fn handle(&self) -> HCURSOR {
+    self.frames[self.current].handle
+  }
+
+  pub fn is_animated(&self) -> bool {
+    self.frames.len() > 1
+  }
+
+  /// How long the current frame is shown, in milliseconds.
+  pub fn frame_interval(&self) -> u32 {
+    self.frames[self.current]
+      .duration
+      .as_millis()
+      .min(u32::MAX as u128) as u32
+  }
+
+  /// Moves on to the next frame, looping back to the first one after the last.
+  pub fn next_frame(&mut self) {
+    self.current = (self.current + 1) % self.frames.len();
+  }
+}
+
+unsafe fn create_cursor(
+  mut rgba: Vec<u8>,
+  width: u32,
+  height: u32,
+  hotspot_x: u32,
+  hotspot_y: u32,
+) -> Result<HCURSOR, BadIcon> {
+  let pixel_count = rgba.len() / PIXEL_SIZE;
+  let pixels = std::slice::from_raw_parts_mut(rgba.as_mut_ptr() as *mut Pixel, pixel_count);
+  for pixel in pixels {
+    pixel.to_bgra();
+  }
+  let color = CreateBitmap(
+    width as i32,
+    height as i32,
+    1,
+    (PIXEL_SIZE * 8) as u32,
+    rgba.as_ptr() as *const c_void,
+  );
+  // The alpha channel of the color bitmap is used, the mask only has to be empty. Its rows are
+  // aligned on 16 bits.
+  let mask_bits = vec![0u8; ((width as usize + 15) / 16) * 2 * height as usize];
+  let mask = CreateBitmap(
+    width as i32,
+    height as i32,
+    1,
+    1,
+    mask_bits.as_ptr() as *const c_void,
+  );
+  let icon_info = ICONINFO {
+    fIcon: false.into(),
+    xHotspot: hotspot_x,
+    yHotspot: hotspot_y,
+    hbmMask: mask,
+    hbmColor: color,
+  };
+  let handle = CreateIconIndirect(&icon_info);
+  DeleteObject(HGDIOBJ(color.0));
+  DeleteObject(HGDIOBJ(mask.0));
+  handle
+    .ok()
+    .map(|handle| HCURSOR(handle.0))
+    .map_err(|_| BadIcon::OsError(io::Error::last_os_error()))
+}
@@ -14,13 +14,17 @@ use windows::Win32::{
 
 use crate::{
   accelerator::Accelerator,
-  event::{Event, WindowEvent},
+  event::{Event, TrayId, WindowEvent},
   keyboard::{KeyCode, ModifiersState},
   menu::{CustomMenuItem, MenuId, MenuItem, MenuType},
   window::WindowId as RootWindowId,
 };
 
-use super::{accelerator::register_accel, keyboard::key_to_vk, util, WindowId};
+use super::{
+  accelerator::{register_accel, unregister_accel},
+  keyboard::key_to_vk,
+  util, WindowId,
+};
 
 #[derive(Copy, Clone)]
 struct AccelWrapper(ACCEL);
@@ -45,6 +49,7 @@ lazy_static! {
 
 pub struct MenuHandler {
   window_id: Option<RootWindowId>,
+  tray_id: Option<TrayId>,
   menu_type: MenuType,
   event_sender: Box<dyn Fn(Event<'static, ()>)>,
 }
@@ -57,15 +62,24 @@ impl MenuHandler {
   ) -> MenuHandler {
     MenuHandler {
       window_id,
+      tray_id: None,
       menu_type,
       event_sender,
     }
   }
+
+  /// Handles the menu of the system tray `tray_id`.
+  pub fn with_tray_id(mut self, tray_id: TrayId) -> MenuHandler {
+    self.tray_id = Some(tray_id);
+    self
+  }
+
   pub fn send_menu_event(&self, menu_id: u16) {
     (self.event_sender)(Event::MenuEvent {
       menu_id: MenuId(menu_id),
       origin: self.menu_type,
       window_id: self.window_id,
+      tray_id: self.tray_id,
     });
   }
 
@@ -300,17 +314,37 @@ impl Menu {
 
 const MENU_SUBCLASS_ID: usize = 4568;
 
-pub fn initialize(menu_builder: Menu, window: HWND, menu_handler: MenuHandler) -> HMENU {
+pub fn initialize(
+  menu_builder: Option<Menu>,
+  window: HWND,
+  menu_handler: MenuHandler,
+) -> Option<HMENU> {
   let sender: *mut MenuHandler = Box::into_raw(Box::new(menu_handler));
-  let menu = menu_builder.hmenu();
 
   unsafe {
     SetWindowSubclass(window, Some(subclass_proc), MENU_SUBCLASS_ID, sender as _);
-    SetMenu(window, menu);
   }
 
-  if let Some(accels) = menu_builder.accels() {
-    register_accel(window, &accels);
+  // Leave a menu set through `WindowBuilderExtWindows::with_menu` untouched
+  menu_builder.and_then(|menu_builder| set_menu(window, Some(menu_builder)))
+}
+
+/// Replaces the menu bar of a window whose menu events are handled since `initialize`.
+pub fn set_menu(window: HWND, menu_builder: Option<Menu>) -> Option<HMENU> {
+  let menu = menu_builder
+    .as_ref()
+    .map(|menu_builder| menu_builder.hmenu());
+
+  unsafe {
+    SetMenu(window, menu.unwrap_or_default());
+    // Windows keeps the outer size of the window, so the client area grows or shrinks
+    // by the height of the menu bar and the window gets a `WM_SIZE` for it.
+    DrawMenuBar(window);
+  }
+
+  match menu_builder.and_then(|menu_builder| menu_builder.accels()) {
+    Some(accels) => register_accel(window, &accels),
+    None => unregister_accel(window),
   }
 
   menu
@@ -333,7 +367,8 @@ pub(crate) unsafe extern "system" fn subclass_proc(
 
   match msg {
     win32wm::WM_COMMAND => {
-      match wparam.0 {
+      // The high word is 1 when the command comes from an accelerator
+      match util::LOWORD(wparam.0 as u32) as usize {
         CUT_ID => {
           execute_edit_command(EditCommand::Cut);
         }
@@ -362,8 +397,8 @@ pub(crate) unsafe extern "system" fn subclass_proc(
         MINIMIZE_ID => {
           ShowWindow(hwnd, SW_MINIMIZE);
         }
-        _ => {
-          let menu_id = util::LOWORD(wparam.0 as u32);
+        menu_id => {
+          let menu_id = menu_id as u16;
           if MENU_IDS.lock().unwrap().contains(&menu_id) {
             subclass_input.send_menu_event(menu_id);
           }
@@ -3,6 +3,7 @@
 
 #![cfg(target_os = "windows")]
 
+use std::{cell::RefCell, collections::HashMap};
 use windows::Win32::{
   Foundation::{HANDLE, HWND},
   UI::WindowsAndMessaging::HMENU,
@@ -21,7 +22,13 @@ pub use self::{
 
 pub use self::icon::WinIcon as PlatformIcon;
 
-use crate::{event::DeviceId as RootDeviceId, icon::Icon, keyboard::Key, window::Theme};
+use crate::{
+  event::DeviceId as RootDeviceId,
+  icon::Icon,
+  keyboard::{Key, KeyCode},
+  platform::windows::CornerPreference,
+  window::Theme,
+};
 mod accelerator;
 mod global_shortcut;
 mod keycode;
@@ -49,6 +56,8 @@ pub struct PlatformSpecificWindowBuilderAttributes {
   pub no_redirection_bitmap: bool,
   pub drag_and_drop: bool,
   pub preferred_theme: Option<Theme>,
+  pub corner_preference: Option<CornerPreference>,
+  pub undecorated_shadow: bool,
 }
 
 impl Default for PlatformSpecificWindowBuilderAttributes {
@@ -61,6 +70,8 @@ impl Default for PlatformSpecificWindowBuilderAttributes {
       drag_and_drop: true,
       preferred_theme: None,
       skip_taskbar: false,
+      corner_preference: None,
+      undecorated_shadow: false,
     }
   }
 }
@@ -117,6 +128,52 @@ fn wrap_device_id(id: isize) -> RootDeviceId {
   RootDeviceId(DeviceId(id))
 }
 
+// Window messages don't carry the device they come from, but the system delivers the raw input
+// of each key press or mouse action through `WM_INPUT` before the window message for it. The raw
+// input device handles are kept per key and per mouse button, so each window event is paired with
+// the device that produced it.
+#[derive(Default)]
+struct RawInputDevices {
+  keys: HashMap<KeyCode, isize>,
+  // Left, middle and right, like `raw_input::get_raw_mouse_button_state`
+  buttons: [isize; 3],
+  // The device of the last mouse input, for the cursor motion, the wheel and the other buttons
+  pointer: isize,
+}
+
+thread_local! {
+  static RAW_INPUT_DEVICES: RefCell<RawInputDevices> = RefCell::new(RawInputDevices::default());
+}
+
+fn set_key_device(key: KeyCode, device: isize) {
+  RAW_INPUT_DEVICES.with(|devices| {
+    devices.borrow_mut().keys.insert(key, device);
+  });
+}
+
+fn set_mouse_device(button: Option<usize>, device: isize) {
+  RAW_INPUT_DEVICES.with(|devices| {
+    let mut devices = devices.borrow_mut();
+    devices.pointer = device;
+    if let Some(button) = button {
+      devices.buttons[button] = device;
+    }
+  });
+}
+
+fn key_device_id(key: KeyCode) -> RootDeviceId {
+  RAW_INPUT_DEVICES
+    .with(|devices| wrap_device_id(devices.borrow().keys.get(&key).map_or(0, |d| *d)))
+}
+
+fn button_device_id(button: usize) -> RootDeviceId {
+  RAW_INPUT_DEVICES.with(|devices| wrap_device_id(devices.borrow().buttons[button]))
+}
+
+fn pointer_device_id() -> RootDeviceId {
+  RAW_INPUT_DEVICES.with(|devices| wrap_device_id(devices.borrow().pointer))
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct KeyEventExtra {
   pub text_with_all_modifiers: Option<&'static str>,
@@ -132,6 +189,15 @@ impl WindowId {
   pub unsafe fn dummy() -> Self {
     WindowId(0)
   }
+
+  pub fn into_raw(self) -> u64 {
+    self.0 as u64
+  }
+
+  pub fn from_raw(raw: u64) -> Option<Self> {
+    // `into_raw` sign-extends the handle
+    isize::try_from(raw as i64).ok().map(WindowId)
+  }
 }
 
 #[macro_use]
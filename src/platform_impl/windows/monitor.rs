@@ -6,6 +6,8 @@ use windows::Win32::{
   Graphics::Gdi::*,
 };
 
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
 use std::{
   collections::{BTreeSet, VecDeque},
   io, mem, ptr,
@@ -95,7 +97,17 @@ unsafe extern "system" fn monitor_enum_proc(
   true.into() // continue enumeration
 }
 
+lazy_static! {
+  // Monitors enumerated by the last `available_monitors` call, until the next `WM_DISPLAYCHANGE`.
+  static ref MONITORS: Mutex<Option<VecDeque<MonitorHandle>>> = Mutex::new(None);
+}
+
 pub fn available_monitors() -> VecDeque<MonitorHandle> {
+  let mut cache = MONITORS.lock();
+  if let Some(monitors) = &*cache {
+    return monitors.clone();
+  }
+
   let mut monitors: VecDeque<MonitorHandle> = VecDeque::new();
   unsafe {
     EnumDisplayMonitors(
@@ -105,9 +117,15 @@ pub fn available_monitors() -> VecDeque<MonitorHandle> {
       LPARAM(&mut monitors as *mut _ as _),
     );
   }
+  *cache = Some(monitors.clone());
   monitors
 }
 
+/// Drops the monitors cached by `available_monitors`, to be called when the displays change.
+pub(crate) fn invalidate_monitors() {
+  *MONITORS.lock() = None;
+}
+
 pub fn primary_monitor() -> MonitorHandle {
   const ORIGIN: POINT = POINT { x: 0, y: 0 };
   let hmonitor = unsafe { MonitorFromPoint(ORIGIN, MONITOR_DEFAULTTOPRIMARY) };
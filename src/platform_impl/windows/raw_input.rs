@@ -15,7 +15,7 @@ use windows::Win32::{
   },
 };
 
-use crate::{event::ElementState, platform_impl::platform::util};
+use crate::{event::ElementState, event_loop::DeviceEvents, platform_impl::platform::util};
 
 #[allow(dead_code)]
 pub fn get_raw_input_device_list() -> Option<Vec<RAWINPUTDEVICELIST>> {
@@ -131,10 +131,21 @@ pub fn register_raw_input_devices(devices: &[RAWINPUTDEVICE]) -> bool {
   success.as_bool()
 }
 
-pub fn register_all_mice_and_keyboards_for_raw_input(window_handle: HWND) -> bool {
+pub fn register_all_mice_and_keyboards_for_raw_input(
+  mut window_handle: HWND,
+  allowed: DeviceEvents,
+) -> bool {
   // RIDEV_DEVNOTIFY: receive hotplug events
   // RIDEV_INPUTSINK: receive events even if we're not in the foreground
-  let flags = RIDEV_DEVNOTIFY | RIDEV_INPUTSINK;
+  // RIDEV_REMOVE: stop receiving events, the target window must be null then
+  let flags = match allowed {
+    DeviceEvents::Always => RIDEV_DEVNOTIFY | RIDEV_INPUTSINK,
+    DeviceEvents::WhenFocused => RIDEV_DEVNOTIFY,
+    DeviceEvents::Never => {
+      window_handle = HWND::default();
+      RIDEV_REMOVE
+    }
+  };
 
   let devices: [RAWINPUTDEVICE; 2] = [
     RAWINPUTDEVICE {
@@ -8,11 +8,12 @@ use super::{
 use crate::{
   dpi::{PhysicalPosition, PhysicalSize},
   error::OsError as RootOsError,
-  event::{Event, Rectangle, TrayEvent},
+  event::{Event, Rectangle, TrayEvent, TrayId},
   event_loop::EventLoopWindowTarget,
   menu::MenuType,
   system_tray::SystemTray as RootSystemTray,
 };
+use std::sync::atomic::{AtomicU32, Ordering};
 use windows::Win32::{
   Foundation::{HWND, LPARAM, LRESULT, POINT, PSTR, PWSTR, WPARAM},
   System::LibraryLoader::*,
@@ -24,16 +25,21 @@ use windows::Win32::{
 
 const WM_USER_TRAYICON: u32 = 6001;
 const WM_USER_UPDATE_TRAYMENU: u32 = 6002;
-const TRAYICON_UID: u32 = 6003;
 const TRAY_SUBCLASS_ID: usize = 6004;
 const TRAY_MENU_SUBCLASS_ID: usize = 6005;
 
+/// The `uID` of the next tray icon, unique in the process so several icons can coexist.
+static NEXT_TRAYICON_UID: AtomicU32 = AtomicU32::new(6003);
+
 struct TrayLoopData {
+  id: TrayId,
+  uid: u32,
   hmenu: Option<HMENU>,
   sender: Box<dyn Fn(Event<'static, ()>)>,
 }
 
 pub struct SystemTrayBuilder {
+  pub(crate) id: TrayId,
   pub(crate) icon: Vec<u8>,
   pub(crate) tray_menu: Option<Menu>,
 }
@@ -41,7 +47,11 @@ pub struct SystemTrayBuilder {
 impl SystemTrayBuilder {
   #[inline]
   pub fn new(icon: Vec<u8>, tray_menu: Option<Menu>) -> Self {
-    Self { icon, tray_menu }
+    Self {
+      id: TrayId::EMPTY,
+      icon,
+      tray_menu,
+    }
   }
 
   #[inline]
@@ -85,10 +95,11 @@ impl SystemTrayBuilder {
         )));
       }
 
+      let uid = NEXT_TRAYICON_UID.fetch_add(1, Ordering::Relaxed);
       let mut nid = NOTIFYICONDATAW {
         uFlags: NIF_MESSAGE,
         hWnd: hwnd,
-        uID: TRAYICON_UID,
+        uID: uid,
         uCallbackMessage: WM_USER_TRAYICON,
         ..std::mem::zeroed()
       };
@@ -99,12 +110,20 @@ impl SystemTrayBuilder {
         )));
       }
 
-      let system_tray = SystemTray { hwnd };
+      let mut system_tray = SystemTray {
+        id: self.id,
+        uid,
+        hwnd,
+        hicon: HICON::default(),
+        visible: true,
+      };
       system_tray.set_icon_from_buffer(&self.icon, 32, 32);
 
       // system_tray event handler
       let event_loop_runner = window_target.p.runner_shared.clone();
       let traydata = TrayLoopData {
+        id: self.id,
+        uid,
         hmenu,
         sender: Box::new(move |event| {
           if let Ok(e) = event.map_nonuser_event() {
@@ -129,7 +148,8 @@ impl SystemTrayBuilder {
         }),
         MenuType::ContextMenu,
         None,
-      );
+      )
+      .with_tray_id(self.id);
       SetWindowSubclass(
         hwnd,
         Some(menu_subclass_proc),
@@ -143,27 +163,75 @@ impl SystemTrayBuilder {
 }
 
 pub struct SystemTray {
+  id: TrayId,
+  uid: u32,
   hwnd: HWND,
+  hicon: HICON,
+  /// Whether the icon is in the notification area, it can only be modified while it is.
+  visible: bool,
 }
 
 impl SystemTray {
+  pub fn id(&self) -> TrayId {
+    self.id
+  }
+
   pub fn set_icon(&mut self, icon: Vec<u8>) {
     self.set_icon_from_buffer(&icon, 32, 32);
   }
 
-  fn set_icon_from_buffer(&self, buffer: &[u8], width: u32, height: u32) {
+  pub fn set_title(&mut self, _title: &str) {}
+
+  pub fn set_visible(&mut self, visible: bool) {
+    if self.visible == visible {
+      return;
+    }
+    self.visible = visible;
+    unsafe {
+      if visible {
+        // the icon was removed from the notification area, add it back with the current icon.
+        let mut nid = NOTIFYICONDATAW {
+          uFlags: NIF_MESSAGE | NIF_ICON,
+          hWnd: self.hwnd,
+          hIcon: self.hicon,
+          uID: self.uid,
+          uCallbackMessage: WM_USER_TRAYICON,
+          ..std::mem::zeroed()
+        };
+        if !Shell_NotifyIconW(NIM_ADD, &mut nid as _).as_bool() {
+          debug!("Error showing system tray icon");
+        }
+      } else {
+        let mut nid = NOTIFYICONDATAW {
+          hWnd: self.hwnd,
+          uID: self.uid,
+          ..std::mem::zeroed()
+        };
+        if !Shell_NotifyIconW(NIM_DELETE, &mut nid as _).as_bool() {
+          debug!("Error hiding system tray icon");
+        }
+      }
+    }
+  }
+
+  fn set_icon_from_buffer(&mut self, buffer: &[u8], width: u32, height: u32) {
     if let Some(hicon) = util::get_hicon_from_buffer(buffer, width as _, height as _) {
       self.set_hicon(hicon);
     }
   }
 
-  fn set_hicon(&self, icon: HICON) {
+  fn set_hicon(&mut self, icon: HICON) {
+    self.hicon = icon;
+    // a hidden icon gets the current one when it's added back
+    if !self.visible {
+      return;
+    }
     unsafe {
       let mut nid = NOTIFYICONDATAW {
         uFlags: NIF_ICON,
         hWnd: self.hwnd,
         hIcon: icon,
-        uID: TRAYICON_UID,
+        uID: self.uid,
         ..std::mem::zeroed()
       };
       if !Shell_NotifyIconW(NIM_MODIFY, &mut nid as _).as_bool() {
@@ -189,14 +257,16 @@ impl Drop for SystemTray {
   fn drop(&mut self) {
     unsafe {
       // remove the icon from system tray
-      let mut nid = NOTIFYICONDATAW {
-        uFlags: NIF_ICON,
-        hWnd: self.hwnd,
-        uID: TRAYICON_UID,
-        ..std::mem::zeroed()
-      };
-      if !Shell_NotifyIconW(NIM_DELETE, &mut nid as _).as_bool() {
-        debug!("Error removing system tray icon");
+      if self.visible {
+        let mut nid = NOTIFYICONDATAW {
+          uFlags: NIF_ICON,
+          hWnd: self.hwnd,
+          uID: self.uid,
+          ..std::mem::zeroed()
+        };
+        if !Shell_NotifyIconW(NIM_DELETE, &mut nid as _).as_bool() {
+          debug!("Error removing system tray icon");
+        }
       }
 
       // destroy the hidden window used by the tray
@@ -233,7 +303,7 @@ unsafe extern "system" fn tray_subclass_proc(
     let nid = NOTIFYICONIDENTIFIER {
       hWnd: hwnd,
       cbSize: std::mem::size_of::<NOTIFYICONIDENTIFIER>() as _,
-      uID: TRAYICON_UID,
+      uID: subclass_input.uid,
       ..std::mem::zeroed()
     };
     let icon_rect = Shell_NotifyIconGetRect(&nid).unwrap_or_default();
@@ -253,6 +323,7 @@ unsafe extern "system" fn tray_subclass_proc(
     match lparam.0 as u32 {
       win32wm::WM_LBUTTONUP => {
         (subclass_input.sender)(Event::TrayEvent {
+          id: subclass_input.id,
           event: TrayEvent::LeftClick,
           position,
           bounds,
@@ -261,6 +332,7 @@ unsafe extern "system" fn tray_subclass_proc(
 
       win32wm::WM_RBUTTONUP => {
         (subclass_input.sender)(Event::TrayEvent {
+          id: subclass_input.id,
           event: TrayEvent::RightClick,
           position,
           bounds,
@@ -273,6 +345,7 @@ unsafe extern "system" fn tray_subclass_proc(
 
       win32wm::WM_LBUTTONDBLCLK => {
         (subclass_input.sender)(Event::TrayEvent {
+          id: subclass_input.id,
           event: TrayEvent::DoubleClick,
           position,
           bounds,
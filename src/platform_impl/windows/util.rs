@@ -18,9 +18,13 @@ use windows::{
   Win32::{
     Foundation::{BOOL, FARPROC, HWND, LPARAM, LRESULT, POINT, PWSTR, RECT, WPARAM},
     Globalization::lstrlenW,
-    Graphics::Gdi::{ClientToScreen, InvalidateRgn, HMONITOR, HRGN},
+    Graphics::{
+      Dwm::DwmExtendFrameIntoClientArea,
+      Gdi::{ClientToScreen, InvalidateRgn, HMONITOR, HRGN},
+    },
     System::LibraryLoader::*,
     UI::{
+      Controls::MARGINS,
       HiDpi::*,
       Input::KeyboardAndMouse::*,
       TextServices::HKL,
@@ -50,6 +54,15 @@ pub fn encode_wide(string: impl AsRef<std::ffi::OsStr>) -> Vec<u16> {
   string.as_ref().encode_wide().chain(once(0)).collect()
 }
 
+#[test]
+fn test_encode_wide_round_trip() {
+  for title in ["tao window", "🦀 tao 🎉", "窗口标题", "e\u{301}mojis 👩‍👩‍👧"] {
+    let wide = encode_wide(title);
+    assert_eq!(wide.last(), Some(&0));
+    assert_eq!(wchar_to_string(&wide[..wide.len() - 1]), title);
+  }
+}
+
 pub unsafe fn status_map<T, F: FnMut(&mut T) -> BOOL>(mut fun: F) -> Option<T> {
   let mut data: T = mem::zeroed();
   if fun(&mut data).as_bool() {
@@ -100,6 +113,37 @@ pub fn adjust_size(hwnd: HWND, size: PhysicalSize<u32>, is_decorated: bool) -> P
   PhysicalSize::new((rect.right - rect.left) as _, (rect.bottom - rect.top) as _)
 }
 
+/// Converts the client size `size` of `hwnd` to its window size.
+///
+/// The non-client area is measured on the window when it's restored, since
+/// `AdjustWindowRectEx` assumes that the menu bar fits on a single line. It's computed from the
+/// styles and DPI of the window otherwise, e.g. before the client area is first computed.
+pub fn client_to_window_size(
+  hwnd: HWND,
+  size: PhysicalSize<u32>,
+  is_decorated: bool,
+) -> PhysicalSize<u32> {
+  let restored = unsafe { !IsIconic(hwnd).as_bool() } && !is_maximized(hwnd);
+  let measured = if restored {
+    get_window_rect(hwnd)
+      .zip(get_client_rect(hwnd).ok())
+      .filter(|(_, client)| client.right > client.left && client.bottom > client.top)
+  } else {
+    None
+  };
+  match measured {
+    Some((window, client)) => {
+      let frame_width = (window.right - window.left) - (client.right - client.left);
+      let frame_height = (window.bottom - window.top) - (client.bottom - client.top);
+      PhysicalSize::new(
+        size.width + frame_width.max(0) as u32,
+        size.height + frame_height.max(0) as u32,
+      )
+    }
+    None => adjust_size(hwnd, size, is_decorated),
+  }
+}
+
 pub(crate) fn set_inner_size_physical(window: HWND, x: u32, y: u32, is_decorated: bool) {
   unsafe {
     let rect = adjust_window_rect(
@@ -214,7 +258,8 @@ pub fn is_focused(window: HWND) -> bool {
 }
 
 pub fn is_visible(window: HWND) -> bool {
-  unsafe { IsWindowVisible(window).as_bool() }
+  // `IsWindowVisible` is still true for minimized windows.
+  unsafe { IsWindowVisible(window).as_bool() && !IsIconic(window).as_bool() }
 }
 
 pub fn is_maximized(window: HWND) -> bool {
@@ -271,7 +316,7 @@ impl CursorIcon {
     match self {
       CursorIcon::Arrow | CursorIcon::Default => IDC_ARROW,
       CursorIcon::Hand => IDC_HAND,
-      CursorIcon::Crosshair => IDC_CROSS,
+      CursorIcon::Crosshair | CursorIcon::Cell => IDC_CROSS,
       CursorIcon::Text | CursorIcon::VerticalText => IDC_IBEAM,
       CursorIcon::NotAllowed | CursorIcon::NoDrop => IDC_NO,
       CursorIcon::Grab | CursorIcon::Grabbing | CursorIcon::Move | CursorIcon::AllScroll => {
@@ -288,7 +333,12 @@ impl CursorIcon {
       CursorIcon::Wait => IDC_WAIT,
       CursorIcon::Progress => IDC_APPSTARTING,
       CursorIcon::Help => IDC_HELP,
-      _ => IDC_ARROW, // use arrow for the missing cases.
+      // There are no system cursors for these, use the arrow instead.
+      CursorIcon::ContextMenu
+      | CursorIcon::Alias
+      | CursorIcon::Copy
+      | CursorIcon::ZoomIn
+      | CursorIcon::ZoomOut => IDC_ARROW,
     }
   }
 }
@@ -339,6 +389,32 @@ pub type AdjustWindowRectExForDpi = unsafe extern "system" fn(
   dpi: u32,
 ) -> BOOL;
 
+// The window composition attributes are undocumented, see
+// https://gist.github.com/ysc3839/b08d2bff1c7dacde529bed1d37e85ccf
+#[allow(non_snake_case)]
+#[repr(C)]
+pub struct ACCENT_POLICY {
+  pub AccentState: u32,
+  pub AccentFlags: u32,
+  pub GradientColor: u32,
+  pub AnimationId: u32,
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+pub struct WINDOWCOMPOSITIONATTRIBDATA {
+  pub Attrib: u32,
+  pub pvData: *mut std::ffi::c_void,
+  pub cbData: usize,
+}
+
+pub const WCA_ACCENT_POLICY: u32 = 19;
+pub const ACCENT_DISABLED: u32 = 0;
+pub const ACCENT_ENABLE_BLURBEHIND: u32 = 3;
+
+pub type SetWindowCompositionAttribute =
+  unsafe extern "system" fn(hwnd: HWND, data: *mut WINDOWCOMPOSITIONATTRIBDATA) -> BOOL;
+
 lazy_static! {
   pub static ref GET_DPI_FOR_WINDOW: Option<GetDpiForWindow> =
     get_function!("user32.dll", GetDpiForWindow);
@@ -354,6 +430,8 @@ lazy_static! {
     get_function!("shcore.dll", SetProcessDpiAwareness);
   pub static ref SET_PROCESS_DPI_AWARE: Option<SetProcessDPIAware> =
     get_function!("user32.dll", SetProcessDPIAware);
+  pub static ref SET_WINDOW_COMPOSITION_ATTRIBUTE: Option<SetWindowCompositionAttribute> =
+    get_function!("user32.dll", SetWindowCompositionAttribute);
 }
 
 #[allow(non_snake_case)]
@@ -380,6 +458,46 @@ pub fn GetWindowLongPtrW(window: HWND, index: WINDOW_LONG_PTR_INDEX) -> isize {
   unsafe { win32wm::GetWindowLongPtrW(window, index) }
 }
 
+/// Enables or disables the blur of what is behind the window, it shows through the
+/// transparent parts of the window.
+pub fn set_window_blur(hwnd: HWND, enable: bool) {
+  if let Some(set_window_composition_attribute) = *SET_WINDOW_COMPOSITION_ATTRIBUTE {
+    let mut policy = ACCENT_POLICY {
+      AccentState: if enable {
+        ACCENT_ENABLE_BLURBEHIND
+      } else {
+        ACCENT_DISABLED
+      },
+      AccentFlags: 0,
+      GradientColor: 0,
+      AnimationId: 0,
+    };
+    let mut data = WINDOWCOMPOSITIONATTRIBDATA {
+      Attrib: WCA_ACCENT_POLICY,
+      pvData: &mut policy as *mut _ as _,
+      cbData: mem::size_of::<ACCENT_POLICY>(),
+    };
+    unsafe {
+      set_window_composition_attribute(hwnd, &mut data);
+    }
+  }
+}
+
+/// Extends the DWM frame one pixel into the client area, so that DWM keeps drawing the shadow
+/// of a window whose frame is removed in `WM_NCCALCSIZE`.
+pub fn set_undecorated_shadow(hwnd: HWND, shadow: bool) {
+  let width = if shadow { 1 } else { 0 };
+  let margins = MARGINS {
+    cxLeftWidth: width,
+    cxRightWidth: width,
+    cyTopHeight: width,
+    cyBottomHeight: width,
+  };
+  unsafe {
+    let _ = DwmExtendFrameIntoClientArea(hwnd, &margins);
+  }
+}
+
 /// Implementation of the `LOWORD` macro.
 #[allow(non_snake_case)]
 #[inline]
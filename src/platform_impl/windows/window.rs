@@ -8,10 +8,7 @@ use parking_lot::Mutex;
 use raw_window_handle::{RawWindowHandle, Win32Handle};
 use std::{
   cell::{Cell, RefCell},
-  ffi::OsStr,
-  io, mem,
-  os::windows::ffi::OsStrExt,
-  ptr,
+  io, mem, ptr,
   sync::Arc,
 };
 
@@ -19,7 +16,10 @@ use crossbeam_channel as channel;
 use windows::Win32::{
   Foundation::{self as win32f, HINSTANCE, HWND, LPARAM, LRESULT, POINT, PWSTR, RECT, WPARAM},
   Graphics::{
-    Dwm::{DwmEnableBlurBehindWindow, DWM_BB_BLURREGION, DWM_BB_ENABLE, DWM_BLURBEHIND},
+    Dwm::{
+      DwmEnableBlurBehindWindow, DwmSetWindowAttribute, DWM_BB_BLURREGION, DWM_BB_ENABLE,
+      DWM_BLURBEHIND,
+    },
     Gdi::*,
   },
   System::{Com::*, LibraryLoader::*, Ole::*},
@@ -35,20 +35,23 @@ use crate::{
   error::{ExternalError, NotSupportedError, OsError as RootOsError},
   icon::Icon,
   menu::MenuType,
-  monitor::MonitorHandle as RootMonitorHandle,
+  monitor::{MonitorHandle as RootMonitorHandle, VideoMode as RootVideoMode},
+  platform::windows::{CornerPreference, HitRegions, RGBA},
   platform_impl::platform::{
-    dark_mode::try_theme,
+    dark_mode::{self, try_theme},
     dpi::{dpi_to_scale_factor, hwnd_dpi},
     drop_handler::FileDropHandler,
-    event_loop::{self, EventLoopWindowTarget, DESTROY_MSG_ID},
-    icon::{self, IconType},
+    event_loop::{self, EventLoopWindowTarget, CURSOR_ANIMATION_TIMER_ID, DESTROY_MSG_ID},
+    icon::{self, WinCursor},
     menu, monitor, util,
-    window_state::{CursorFlags, SavedWindow, WindowFlags, WindowState},
+    window_state::{
+      CursorFlags, ProtectedRegion, SavedDisplayMode, SavedWindow, WindowFlags, WindowState,
+    },
     OsError, Parent, PlatformSpecificWindowBuilderAttributes, WindowId,
   },
   window::{
-    CursorIcon, Fullscreen, Theme, UserAttentionType, WindowAttributes, WindowId as RootWindowId,
-    BORDERLESS_RESIZE_INSET,
+    CursorIcon, CustomCursor, Fullscreen, Insets, Orientation, Rect, Theme, UserAttentionType,
+    WindowAttributes, WindowButtons, WindowId as RootWindowId, BORDERLESS_RESIZE_INSET,
   },
 };
 
@@ -70,7 +73,7 @@ pub struct Window {
   thread_executor: event_loop::EventLoopThreadExecutor,
 
   // The menu associated with the window
-  menu: Option<HMenuWrapper>,
+  menu: Mutex<Option<HMenuWrapper>>,
 }
 
 impl Window {
@@ -135,22 +138,76 @@ impl Window {
   }
 
   pub fn set_title(&self, text: &str) {
+    // encode to a null-terminated UTF-16 string ourselves so characters outside of the BMP
+    // (emoji, some CJK) are kept as surrogate pairs.
+    let mut text = util::encode_wide(text);
     unsafe {
-      SetWindowTextW(self.window.0, text);
+      SetWindowTextW(self.window.0, PWSTR(text.as_mut_ptr()));
     }
   }
 
-  // TODO (lemarier): allow menu update
-  pub fn set_menu(&self, _new_menu: Option<menu::Menu>) {}
+  pub fn title(&self) -> String {
+    unsafe {
+      let len = GetWindowTextLengthW(self.window.0) + 1;
+      let mut buf = vec![0; len as usize];
+      let copied = GetWindowTextW(self.window.0, PWSTR(buf.as_mut_ptr()), len);
+      util::wchar_to_string(&buf[..copied as usize])
+    }
+  }
+
+  pub fn set_menu(&self, new_menu: Option<menu::Menu>) {
+    *self.menu.lock() = menu::set_menu(self.hwnd(), new_menu).map(HMenuWrapper);
+  }
 
   #[inline]
   pub fn set_visible(&self, visible: bool) {
+    self.set_visible_with_animation(visible, true);
+  }
+
+  #[inline]
+  pub fn set_visible_with_animation(&self, visible: bool, animate: bool) {
     let window = self.window.clone();
     let window_state = Arc::clone(&self.window_state);
     self.thread_executor.execute_in_thread(move || {
+      // DWM reads the attribute when the window is shown or hidden
+      if !animate {
+        set_transitions_disabled(window.0, true);
+      }
       WindowState::set_window_flags(window_state.lock(), window.0, |f| {
         f.set(WindowFlags::VISIBLE, visible)
       });
+      if !animate {
+        set_transitions_disabled(window.0, false);
+      }
+    });
+  }
+
+  #[inline]
+  pub fn set_transparent(&self, transparent: bool) -> Result<(), ExternalError> {
+    let window_flags = self.window_state.lock().window_flags();
+    if window_flags.contains(WindowFlags::NO_BACK_BUFFER) {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
+
+    let window = self.window.clone();
+    let window_state = Arc::clone(&self.window_state);
+    self.thread_executor.execute_in_thread(move || {
+      WindowState::set_window_flags(window_state.lock(), window.0, |f| {
+        f.set(WindowFlags::TRANSPARENT, transparent)
+      });
+      unsafe {
+        set_blur_behind(window.0, transparent);
+        InvalidateRgn(window.0, HRGN::default(), false);
+      }
+    });
+    Ok(())
+  }
+
+  #[inline]
+  pub fn set_blur(&self, blur: bool) {
+    let window = self.window.clone();
+    self.thread_executor.execute_in_thread(move || {
+      util::set_window_blur(window.0, blur);
     });
   }
 
@@ -170,6 +227,7 @@ impl Window {
 
   #[inline]
   pub fn request_redraw(&self) {
+    self.window_state.lock().redraw_full = true;
     unsafe {
       RedrawWindow(
         self.window.0,
@@ -180,6 +238,39 @@ impl Window {
     }
   }
 
+  #[inline]
+  pub fn request_redraw_rect(&self, position: PhysicalPosition<i32>, size: PhysicalSize<u32>) {
+    let (width, height) = (
+      size.width.min(i32::MAX as u32) as i32,
+      size.height.min(i32::MAX as u32) as i32,
+    );
+    let rect = RECT {
+      left: position.x,
+      top: position.y,
+      right: position.x.saturating_add(width),
+      bottom: position.y.saturating_add(height),
+    };
+    unsafe {
+      InvalidateRect(self.window.0, &rect, false);
+    }
+  }
+
+  #[inline]
+  pub fn redraw_rect(&self) -> Option<(PhysicalPosition<i32>, PhysicalSize<u32>)> {
+    self.window_state.lock().redraw_rect.map(|rect| {
+      (
+        PhysicalPosition::new(rect.left, rect.top),
+        PhysicalSize::new(
+          (rect.right - rect.left) as u32,
+          (rect.bottom - rect.top) as u32,
+        ),
+      )
+    })
+  }
+
+  #[inline]
+  pub fn pre_present_notify(&self) {}
+
   #[inline]
   pub fn outer_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
     util::get_window_rect(self.window.0)
@@ -246,6 +337,31 @@ impl Window {
       .unwrap()
   }
 
+  #[inline]
+  pub fn content_insets(&self) -> Insets {
+    match (
+      util::get_window_rect(self.window.0),
+      util::get_client_rect(self.window.0),
+    ) {
+      (Some(window_rect), Ok(client_rect)) => Insets {
+        top: (client_rect.top - window_rect.top).max(0) as u32,
+        left: (client_rect.left - window_rect.left).max(0) as u32,
+        bottom: (window_rect.bottom - client_rect.bottom).max(0) as u32,
+        right: (window_rect.right - client_rect.right).max(0) as u32,
+      },
+      _ => Insets::default(),
+    }
+  }
+
+  #[inline]
+  pub fn safe_area_insets(&self) -> Insets {
+    Insets::default()
+  }
+
+  pub fn set_orientation_lock(&self, _orientation: Option<Orientation>) {}
+
+  pub fn set_soft_input_visible(&self, _visible: bool) {}
+
   #[inline]
   pub fn set_inner_size(&self, size: Size) {
     let scale_factor = self.scale_factor();
@@ -268,6 +384,18 @@ impl Window {
     util::set_inner_size_physical(self.window.0, width, height, is_decorated);
   }
 
+  #[inline]
+  pub fn request_inner_size(&self, size: Size) -> Option<PhysicalSize<u32>> {
+    self.set_inner_size(size);
+    // `SWP_ASYNCWINDOWPOS` only posts the resize when called from another thread than the
+    // window's one, it's applied before `SetWindowPos` returns otherwise.
+    if self.thread_executor.in_event_loop_thread() {
+      Some(self.inner_size())
+    } else {
+      None
+    }
+  }
+
   #[inline]
   pub fn set_min_inner_size(&self, size: Option<Size>) {
     self.window_state.lock().min_size = size;
@@ -284,6 +412,12 @@ impl Window {
     self.set_inner_size(size.into());
   }
 
+  #[inline]
+  pub fn set_aspect_ratio(&self, aspect_ratio: Option<(u32, u32)>) {
+    self.window_state.lock().aspect_ratio =
+      aspect_ratio.map(|(width, height)| width as f64 / height as f64);
+  }
+
   #[inline]
   pub fn set_resizable(&self, resizable: bool) {
     let window = self.window.clone();
@@ -296,6 +430,29 @@ impl Window {
     });
   }
 
+  #[inline]
+  pub fn set_enabled_buttons(&self, buttons: WindowButtons) {
+    let window = self.window.clone();
+    let window_state = Arc::clone(&self.window_state);
+
+    self.thread_executor.execute_in_thread(move || {
+      WindowState::set_window_flags(window_state.lock(), window.0, |f| {
+        f.set(
+          WindowFlags::MINIMIZABLE,
+          buttons.contains(WindowButtons::MINIMIZE),
+        );
+        f.set(
+          WindowFlags::MAXIMIZABLE,
+          buttons.contains(WindowButtons::MAXIMIZE),
+        );
+        f.set(
+          WindowFlags::CLOSABLE,
+          buttons.contains(WindowButtons::CLOSE),
+        );
+      });
+    });
+  }
+
   /// Returns the `hwnd` of this window.
   #[inline]
   pub fn hwnd(&self) -> HWND {
@@ -317,13 +474,50 @@ impl Window {
 
   #[inline]
   pub fn set_cursor_icon(&self, cursor: CursorIcon) {
-    self.window_state.lock().mouse.cursor = cursor;
+    {
+      let mut window_state = self.window_state.lock();
+      // `WM_SETCURSOR` already re-applies the cursor whenever the mouse moves, setting the same
+      // one again would only make it flicker.
+      if window_state.mouse.cursor == cursor && window_state.mouse.custom_cursor.is_none() {
+        return;
+      }
+      window_state.mouse.cursor = cursor;
+      window_state.mouse.custom_cursor = None;
+    }
+    let window = self.window.clone();
     self.thread_executor.execute_in_thread(move || unsafe {
+      KillTimer(window.0, CURSOR_ANIMATION_TIMER_ID);
       let cursor = LoadCursorW(HINSTANCE::default(), cursor.to_windows_cursor());
       SetCursor(cursor);
     });
   }
 
+  #[inline]
+  pub fn set_custom_cursor(&self, cursor: CustomCursor) {
+    let cursor = match WinCursor::from_custom_cursor(&cursor) {
+      Ok(cursor) => cursor,
+      Err(e) => {
+        warn!("Failed to create the custom cursor: {}", e);
+        return;
+      }
+    };
+    self.window_state.lock().mouse.custom_cursor = Some(cursor.clone());
+    let window = self.window.clone();
+    self.thread_executor.execute_in_thread(move || unsafe {
+      if cursor.is_animated() {
+        SetTimer(
+          window.0,
+          CURSOR_ANIMATION_TIMER_ID,
+          cursor.frame_interval(),
+          None,
+        );
+      } else {
+        KillTimer(window.0, CURSOR_ANIMATION_TIMER_ID);
+      }
+      SetCursor(cursor.handle());
+    });
+  }
+
   #[inline]
   pub fn set_cursor_grab(&self, grab: bool) -> Result<(), ExternalError> {
     let window = self.window.clone();
@@ -384,6 +578,61 @@ impl Window {
     Ok(())
   }
 
+  #[inline]
+  pub fn set_cursor_hittest_region(&self, region: Option<Vec<Rect>>) -> Result<(), ExternalError> {
+    // Windows has no input-only region, this is the same as a shape
+    self.set_shape(region)
+  }
+
+  #[inline]
+  pub fn set_shape(&self, shape: Option<Vec<Rect>>) -> Result<(), ExternalError> {
+    let hwnd = self.window.0;
+    let scale_factor = self.scale_factor();
+
+    unsafe {
+      let region = match shape {
+        Some(rects) => {
+          // The region of `SetWindowRgn` is relative to the window rather than its client area.
+          let mut window_rect = RECT::default();
+          let mut client_origin = POINT::default();
+          if !GetWindowRect(hwnd, &mut window_rect).as_bool()
+            || !ClientToScreen(hwnd, &mut client_origin).as_bool()
+          {
+            return Err(ExternalError::Os(os_error!(OsError::IoError(
+              io::Error::last_os_error()
+            ))));
+          }
+          let offset_x = client_origin.x - window_rect.left;
+          let offset_y = client_origin.y - window_rect.top;
+
+          let region = CreateRectRgn(0, 0, 0, 0);
+          for rect in rects {
+            let (x, y): (i32, i32) = rect.position.to_physical::<i32>(scale_factor).into();
+            let (width, height): (i32, i32) = rect.size.to_physical::<i32>(scale_factor).into();
+            let rect_region = CreateRectRgn(
+              offset_x + x,
+              offset_y + y,
+              offset_x + x + width,
+              offset_y + y + height,
+            );
+            CombineRgn(region, region, rect_region, RGN_OR);
+            DeleteObject(rect_region);
+          }
+          region
+        }
+        None => HRGN::default(),
+      };
+
+      // The system owns the region from now on, it must not be deleted.
+      if SetWindowRgn(hwnd, region, true) == 0 {
+        return Err(ExternalError::Os(os_error!(OsError::IoError(
+          io::Error::last_os_error()
+        ))));
+      }
+    }
+    Ok(())
+  }
+
   #[inline]
   pub fn drag_window(&self) -> Result<(), ExternalError> {
     let mut pos = POINT::default();
@@ -442,6 +691,25 @@ impl Window {
     window_state.window_flags.contains(WindowFlags::RESIZABLE)
   }
 
+  #[inline]
+  pub fn enabled_buttons(&self) -> WindowButtons {
+    let window_flags = self.window_state.lock().window_flags;
+    let mut buttons = WindowButtons::empty();
+    buttons.set(
+      WindowButtons::MINIMIZE,
+      window_flags.contains(WindowFlags::MINIMIZABLE),
+    );
+    buttons.set(
+      WindowButtons::MAXIMIZE,
+      window_flags.contains(WindowFlags::MAXIMIZABLE),
+    );
+    buttons.set(
+      WindowButtons::CLOSE,
+      window_flags.contains(WindowFlags::CLOSABLE),
+    );
+    buttons
+  }
+
   #[inline]
   pub fn is_decorated(&self) -> bool {
     let window_state = self.window_state.lock();
@@ -473,60 +741,42 @@ impl Window {
     drop(window_state_lock);
 
     self.thread_executor.execute_in_thread(move || {
+      // Save window bounds before entering fullscreen. This has to happen before the video mode
+      // changes, otherwise the saved bounds are the ones Windows shuffled the window to.
+      if old_fullscreen.is_none() {
+        let placement = unsafe {
+          let mut placement = WINDOWPLACEMENT::default();
+          GetWindowPlacement(window.0, &mut placement);
+          placement
+        };
+        window_state.lock().saved_window = Some(SavedWindow { placement });
+      }
+
       // Change video mode if we're transitioning to or from exclusive
       // fullscreen
-      match (&old_fullscreen, &fullscreen) {
-        (&None, &Some(Fullscreen::Exclusive(ref video_mode)))
-        | (&Some(Fullscreen::Borderless(_)), &Some(Fullscreen::Exclusive(ref video_mode)))
-        | (&Some(Fullscreen::Exclusive(_)), &Some(Fullscreen::Exclusive(ref video_mode))) => {
-          let monitor = video_mode.monitor();
-
-          let mut display_name = OsStr::new(&monitor.inner.native_identifier())
-            .encode_wide()
-            .collect::<Vec<_>>();
-          // `encode_wide` does not add a null-terminator but
-          // `ChangeDisplaySettingsExW` requires a null-terminated
-          // string, so add it
-          display_name.push(0);
-
-          let native_video_mode = video_mode.video_mode.native_video_mode;
-
-          let res = unsafe {
-            ChangeDisplaySettingsExW(
-              PWSTR(display_name.as_mut_ptr()),
-              &native_video_mode,
-              HWND::default(),
-              CDS_FULLSCREEN,
-              std::ptr::null_mut(),
-            )
-          };
-
-          debug_assert!(res != DISP_CHANGE_BADFLAGS);
-          debug_assert!(res != DISP_CHANGE_BADMODE);
-          debug_assert!(res != DISP_CHANGE_BADPARAM);
-          debug_assert!(res != DISP_CHANGE_FAILED);
-          assert_eq!(res, DISP_CHANGE_SUCCESSFUL);
+      let mut saved_display_mode = window_state.lock().saved_display_mode.take();
+      match &fullscreen {
+        Some(Fullscreen::Exclusive(video_mode)) => {
+          let display_name = display_name(&video_mode.monitor());
+          // Switching to another display, give the previous one its original mode back.
+          if let Some(saved) = saved_display_mode.as_mut() {
+            if saved.display_name != display_name {
+              unsafe { restore_display_mode(saved) };
+              saved_display_mode = None;
+            }
+          }
+          let original_mode = unsafe { set_display_mode(video_mode) };
+          if saved_display_mode.is_none() {
+            saved_display_mode = original_mode;
+          }
         }
-        (&Some(Fullscreen::Exclusive(_)), &None)
-        | (&Some(Fullscreen::Exclusive(_)), &Some(Fullscreen::Borderless(_))) => {
-          let res = unsafe {
-            ChangeDisplaySettingsExW(
-              PWSTR::default(),
-              std::ptr::null_mut(),
-              HWND::default(),
-              CDS_FULLSCREEN,
-              std::ptr::null_mut(),
-            )
-          };
-
-          debug_assert!(res != DISP_CHANGE_BADFLAGS);
-          debug_assert!(res != DISP_CHANGE_BADMODE);
-          debug_assert!(res != DISP_CHANGE_BADPARAM);
-          debug_assert!(res != DISP_CHANGE_FAILED);
-          assert_eq!(res, DISP_CHANGE_SUCCESSFUL);
+        _ => {
+          if let Some(mut saved) = saved_display_mode.take() {
+            unsafe { restore_display_mode(&mut saved) };
+          }
         }
-        _ => (),
       }
+      window_state.lock().saved_display_mode = saved_display_mode;
 
       unsafe {
         // There are some scenarios where calling `ChangeDisplaySettingsExW` takes long
@@ -556,15 +806,6 @@ impl Window {
       // Update window bounds
       match &fullscreen {
         Some(fullscreen) => {
-          // Save window bounds before entering fullscreen
-          let placement = unsafe {
-            let mut placement = WINDOWPLACEMENT::default();
-            GetWindowPlacement(window.0, &mut placement);
-            placement
-          };
-
-          window_state.lock().saved_window = Some(SavedWindow { placement });
-
           let monitor = match &fullscreen {
             Fullscreen::Exclusive(video_mode) => video_mode.monitor(),
             Fullscreen::Borderless(Some(monitor)) => monitor.clone(),
@@ -591,9 +832,10 @@ impl Window {
         }
         None => {
           let mut window_state_lock = window_state.lock();
-          if let Some(SavedWindow { placement }) = window_state_lock.saved_window.take() {
+          if let Some(SavedWindow { mut placement }) = window_state_lock.saved_window.take() {
             drop(window_state_lock);
             unsafe {
+              keep_placement_on_screen(&mut placement);
               SetWindowPlacement(window.0, &placement);
               InvalidateRgn(window.0, HRGN::default(), false);
             }
@@ -613,9 +855,79 @@ impl Window {
     let window_state = Arc::clone(&self.window_state);
 
     self.thread_executor.execute_in_thread(move || {
+      let window_flags = window_state.lock().window_flags();
+      let keep_client_size = window_flags.contains(WindowFlags::DECORATIONS) != decorations
+        && !window_flags.intersects(
+          WindowFlags::MAXIMIZED
+            | WindowFlags::MINIMIZED
+            | WindowFlags::MARKER_EXCLUSIVE_FULLSCREEN
+            | WindowFlags::MARKER_BORDERLESS_FULLSCREEN,
+        );
+      let client_rect = util::get_client_rect(window.0);
+
       WindowState::set_window_flags(window_state.lock(), window.0, |f| {
         f.set(WindowFlags::DECORATIONS, decorations)
       });
+
+      // Toggling the decorations adds or removes the non-client area while keeping the outer
+      // size, so resize the frame to keep the client area the same size.
+      if let (true, Ok(rect)) = (keep_client_size, client_rect) {
+        util::set_inner_size_physical(
+          window.0,
+          (rect.right - rect.left) as u32,
+          (rect.bottom - rect.top) as u32,
+          decorations,
+        );
+      }
+    });
+  }
+
+  #[inline]
+  pub fn set_content_protection(&self, enabled: bool) {
+    unsafe { set_content_protection(self.hwnd(), enabled) };
+  }
+
+  pub fn set_protected_region(&self, region: Option<Rect>) -> Result<Option<HWND>, RootOsError> {
+    let window = self.window.clone();
+    let window_state = Arc::clone(&self.window_state);
+    let (tx, rx) = channel::unbounded();
+
+    self.thread_executor.execute_in_thread(move || unsafe {
+      let mut window_state = window_state.lock();
+      let previous = window_state
+        .protected_region
+        .take()
+        .map(|region| region.hwnd);
+      let result = match region {
+        Some(rect) => previous
+          .map(Ok)
+          .unwrap_or_else(|| create_protected_window(window.0))
+          .map(|hwnd| {
+            window_state.protected_region = Some(ProtectedRegion { hwnd, rect });
+            place_protected_window(window.0, &window_state);
+            Some(hwnd)
+          }),
+        None => {
+          if let Some(hwnd) = previous {
+            DestroyWindow(hwnd);
+          }
+          Ok(None)
+        }
+      };
+      let _ = tx.send(result);
+    });
+    rx.recv().unwrap()
+  }
+
+  #[inline]
+  pub fn set_titlebar_visible(&self, visible: bool) {
+    let window = self.window.clone();
+    let window_state = Arc::clone(&self.window_state);
+
+    self.thread_executor.execute_in_thread(move || {
+      WindowState::set_window_flags(window_state.lock(), window.0, |f| {
+        f.set(WindowFlags::TITLEBAR_HIDDEN, !visible)
+      });
     });
   }
 
@@ -633,45 +945,55 @@ impl Window {
 
   #[inline]
   pub fn current_monitor(&self) -> Option<RootMonitorHandle> {
+    let dpi_changed_monitor = self.window_state.lock().dpi_changed_monitor.clone();
     Some(RootMonitorHandle {
-      inner: monitor::current_monitor(self.window.0),
+      inner: dpi_changed_monitor.unwrap_or_else(|| monitor::current_monitor(self.window.0)),
     })
   }
 
   #[inline]
   pub fn set_window_icon(&self, window_icon: Option<Icon>) {
-    if let Some(ref window_icon) = window_icon {
-      window_icon
-        .inner
-        .set_for_window(self.window.0, IconType::Small);
-    } else {
-      icon::unset_for_window(self.window.0, IconType::Small);
-    }
-    self.window_state.lock().window_icon = window_icon;
+    let taskbar_icon = {
+      let mut window_state = self.window_state.lock();
+      window_state.window_icon = window_icon.clone();
+      window_state.taskbar_icon.clone()
+    };
+    icon::set_icons_for_window(self.window.0, window_icon.as_ref(), taskbar_icon.as_ref());
   }
 
   #[inline]
   pub fn set_taskbar_icon(&self, taskbar_icon: Option<Icon>) {
-    if let Some(ref taskbar_icon) = taskbar_icon {
-      taskbar_icon
-        .inner
-        .set_for_window(self.window.0, IconType::Big);
-    } else {
-      icon::unset_for_window(self.window.0, IconType::Big);
-    }
-    self.window_state.lock().taskbar_icon = taskbar_icon;
+    let window_icon = {
+      let mut window_state = self.window_state.lock();
+      window_state.taskbar_icon = taskbar_icon.clone();
+      window_state.window_icon.clone()
+    };
+    icon::set_icons_for_window(self.window.0, window_icon.as_ref(), taskbar_icon.as_ref());
   }
 
-  pub(crate) fn set_ime_position_physical(&self, x: i32, y: i32) {
+  pub(crate) fn set_ime_cursor_area_physical(&self, x: i32, y: i32, width: i32, height: i32) {
     if unsafe { GetSystemMetrics(SM_IMMENABLED) } != 0 {
       let composition_form = COMPOSITIONFORM {
         dwStyle: CFS_POINT,
         ptCurrentPos: POINT { x, y },
         rcArea: RECT::default(),
       };
+      // Keep the candidate window out of the cursor area so it doesn't cover the edited text.
+      let candidate_form = CANDIDATEFORM {
+        dwIndex: 0,
+        dwStyle: CFS_EXCLUDE,
+        ptCurrentPos: POINT { x, y },
+        rcArea: RECT {
+          left: x,
+          top: y,
+          right: x + width,
+          bottom: y + height,
+        },
+      };
       unsafe {
         let himc = ImmGetContext(self.window.0);
         ImmSetCompositionWindow(himc, &composition_form);
+        ImmSetCandidateWindow(himc, &candidate_form);
         ImmReleaseContext(self.window.0, himc);
       }
     }
@@ -679,8 +1001,15 @@ impl Window {
 
   #[inline]
   pub fn set_ime_position(&self, spot: Position) {
-    let (x, y) = spot.to_physical::<i32>(self.scale_factor()).into();
-    self.set_ime_position_physical(x, y);
+    self.set_ime_cursor_area(spot, PhysicalSize::new(0, 0).into());
+  }
+
+  #[inline]
+  pub fn set_ime_cursor_area(&self, position: Position, size: Size) {
+    let scale_factor = self.scale_factor();
+    let (x, y) = position.to_physical::<i32>(scale_factor).into();
+    let (width, height) = size.to_physical::<i32>(scale_factor).into();
+    self.set_ime_cursor_area_physical(x, y, width, height);
   }
 
   #[inline]
@@ -724,7 +1053,7 @@ impl Window {
 
   #[inline]
   pub fn show_menu(&self) {
-    if let Some(menu) = &self.menu {
+    if let Some(menu) = &*self.menu.lock() {
       unsafe {
         SetMenu(self.hwnd(), menu.0);
       }
@@ -767,6 +1096,45 @@ impl Window {
     }
   }
 
+  #[inline]
+  pub fn set_caption_color(&self, color: Option<RGBA>) {
+    self.set_dwm_color(DWMWA_CAPTION_COLOR, color);
+  }
+
+  #[inline]
+  pub fn set_title_text_color(&self, color: Option<RGBA>) {
+    self.set_dwm_color(DWMWA_TEXT_COLOR, color);
+  }
+
+  #[inline]
+  pub fn set_border_color(&self, color: Option<RGBA>) {
+    self.set_dwm_color(DWMWA_BORDER_COLOR, color);
+  }
+
+  #[inline]
+  pub fn set_nc_hit_regions(&self, regions: Option<HitRegions>) {
+    self.window_state.lock().nc_hit_regions = regions;
+  }
+
+  #[inline]
+  pub fn set_corner_preference(&self, preference: CornerPreference) {
+    let window = self.window.clone();
+    self.thread_executor.execute_in_thread(move || {
+      set_dwm_attribute(window.0, DWMWA_WINDOW_CORNER_PREFERENCE, preference as u32);
+    });
+  }
+
+  fn set_dwm_color(&self, attribute: u32, color: Option<RGBA>) {
+    // A COLORREF, 0x00BBGGRR
+    let color = color.map_or(DWMWA_COLOR_DEFAULT, |(r, g, b, _)| {
+      r as u32 | (g as u32) << 8 | (b as u32) << 16
+    });
+    let window = self.window.clone();
+    self.thread_executor.execute_in_thread(move || {
+      set_dwm_attribute(window.0, attribute, color);
+    });
+  }
+
   #[inline]
   pub(crate) fn set_skip_taskbar(&self, skip: bool) {
     unsafe {
@@ -818,6 +1186,10 @@ unsafe fn init<T: 'static>(
 
   let mut window_flags = WindowFlags::empty();
   window_flags.set(WindowFlags::DECORATIONS, attributes.decorations);
+  window_flags.set(
+    WindowFlags::UNDECORATED_SHADOW,
+    pl_attribs.undecorated_shadow,
+  );
   window_flags.set(WindowFlags::ALWAYS_ON_TOP, attributes.always_on_top);
   window_flags.set(
     WindowFlags::NO_BACK_BUFFER,
@@ -826,6 +1198,18 @@ unsafe fn init<T: 'static>(
   window_flags.set(WindowFlags::TRANSPARENT, attributes.transparent);
   // WindowFlags::VISIBLE and MAXIMIZED are set down below after the window has been configured.
   window_flags.set(WindowFlags::RESIZABLE, attributes.resizable);
+  window_flags.set(
+    WindowFlags::MINIMIZABLE,
+    attributes.enabled_buttons.contains(WindowButtons::MINIMIZE),
+  );
+  window_flags.set(
+    WindowFlags::MAXIMIZABLE,
+    attributes.enabled_buttons.contains(WindowButtons::MAXIMIZE),
+  );
+  window_flags.set(
+    WindowFlags::CLOSABLE,
+    attributes.enabled_buttons.contains(WindowButtons::CLOSE),
+  );
 
   let parent = match pl_attribs.parent {
     Parent::ChildOf(parent) => {
@@ -848,10 +1232,11 @@ unsafe fn init<T: 'static>(
   // creating the real window this time, by using the functions in `extra_functions`
   let real_window = {
     let (style, ex_style) = window_flags.to_window_styles();
+    let mut title = util::encode_wide(&attributes.title);
     let handle = CreateWindowExW(
       ex_style,
       PWSTR(class_name.as_mut_ptr()),
-      attributes.title.as_str(),
+      PWSTR(title.as_mut_ptr()),
       style,
       CW_USEDEFAULT,
       CW_USEDEFAULT,
@@ -883,18 +1268,11 @@ unsafe fn init<T: 'static>(
 
   // making the window transparent
   if attributes.transparent && !pl_attribs.no_redirection_bitmap {
-    // Empty region for the blur effect, so the window is fully transparent
-    let region = CreateRectRgn(0, 0, -1, -1);
-
-    let bb = DWM_BLURBEHIND {
-      dwFlags: DWM_BB_ENABLE | DWM_BB_BLURREGION,
-      fEnable: true.into(),
-      hRgnBlur: region,
-      fTransitionOnMaximized: false.into(),
-    };
+    set_blur_behind(real_window.0, true);
+  }
 
-    let _ = DwmEnableBlurBehindWindow(real_window.0, &bb);
-    DeleteObject(region);
+  if attributes.blur {
+    util::set_window_blur(real_window.0, true);
   }
 
   // If the system theme is dark, we need to set the window theme now
@@ -902,6 +1280,15 @@ unsafe fn init<T: 'static>(
   // window for the first time).
   let current_theme = try_theme(real_window.0, pl_attribs.preferred_theme);
 
+  // The class icons are shared by all the windows and don't follow their DPI
+  if attributes.window_icon.is_some() || pl_attribs.taskbar_icon.is_some() {
+    icon::set_icons_for_window(
+      real_window.0,
+      attributes.window_icon.as_ref(),
+      pl_attribs.taskbar_icon.as_ref(),
+    );
+  }
+
   let window_state = {
     let window_state = WindowState::new(
       &attributes,
@@ -919,7 +1306,7 @@ unsafe fn init<T: 'static>(
     window: real_window,
     window_state,
     thread_executor: event_loop.create_thread_executor(),
-    menu: None,
+    menu: Mutex::new(None),
   };
 
   KEY_EVENT_BUILDERS
@@ -928,6 +1315,19 @@ unsafe fn init<T: 'static>(
 
   win.set_skip_taskbar(pl_attribs.skip_taskbar);
 
+  // Must be set before the window is shown to never be captured
+  if attributes.content_protection {
+    set_content_protection(win.hwnd(), true);
+  }
+
+  if let Some(preference) = pl_attribs.corner_preference {
+    set_dwm_attribute(
+      win.hwnd(),
+      DWMWA_WINDOW_CORNER_PREFERENCE,
+      preference as u32,
+    );
+  }
+
   let dimensions = attributes
     .inner_size
     .unwrap_or_else(|| PhysicalSize::new(800, 600).into());
@@ -937,7 +1337,16 @@ unsafe fn init<T: 'static>(
     // `Window::set_inner_size` changes MAXIMIZED to false.
     win.set_maximized(true);
   }
-  win.set_visible(attributes.visible);
+  if attributes.visible && !attributes.focused {
+    // Show the window without activating it
+    win
+      .window_state
+      .lock()
+      .set_window_flags_in_place(|f| f.set(WindowFlags::VISIBLE, true));
+    ShowWindow(win.window.0, SW_SHOWNOACTIVATE);
+  } else {
+    win.set_visible(attributes.visible);
+  }
 
   if attributes.fullscreen.is_some() {
     win.set_fullscreen(attributes.fullscreen);
@@ -948,27 +1357,57 @@ unsafe fn init<T: 'static>(
     win.set_outer_position(position);
   }
 
-  if let Some(window_menu) = attributes.window_menu {
-    let event_loop_runner = event_loop.runner_shared.clone();
-    let window_id = RootWindowId(win.id());
-    let menu_handler = menu::MenuHandler::new(
-      Box::new(move |event| {
-        if let Ok(e) = event.map_nonuser_event() {
-          event_loop_runner.send_event(e)
-        }
-      }),
-      MenuType::MenuBar,
-      Some(window_id),
-    );
+  // The menu events are always handled, so a menu can be set later with `set_menu`
+  let event_loop_runner = event_loop.runner_shared.clone();
+  let window_id = RootWindowId(win.id());
+  let menu_handler = menu::MenuHandler::new(
+    Box::new(move |event| {
+      if let Ok(e) = event.map_nonuser_event() {
+        event_loop_runner.send_event(e)
+      }
+    }),
+    MenuType::MenuBar,
+    Some(window_id),
+  );
+  *win.menu.lock() =
+    menu::initialize(attributes.window_menu, win.hwnd(), menu_handler).map(HMenuWrapper);
 
-    win.menu = Some(HMenuWrapper(menu::initialize(
-      window_menu,
-      win.hwnd(),
-      menu_handler,
-    )));
+  Ok(win)
+}
+
+/// Centers the normal position of `placement` in the work area of the primary monitor when it
+/// isn't on any monitor anymore, e.g. after its monitor was disconnected.
+unsafe fn keep_placement_on_screen(placement: &mut WINDOWPLACEMENT) {
+  let info = match monitor::get_monitor_info(monitor::primary_monitor().hmonitor()) {
+    Ok(info) => info.monitorInfo,
+    Err(_) => return,
+  };
+  // The normal position is in workspace coordinates, relative to the top-left corner of the
+  // work area of the primary monitor.
+  let (offset_x, offset_y) = (
+    info.rcWork.left - info.rcMonitor.left,
+    info.rcWork.top - info.rcMonitor.top,
+  );
+  let rect = placement.rcNormalPosition;
+  let screen_rect = RECT {
+    left: rect.left + offset_x,
+    top: rect.top + offset_y,
+    right: rect.right + offset_x,
+    bottom: rect.bottom + offset_y,
+  };
+  if MonitorFromRect(&screen_rect, MONITOR_DEFAULTTONULL).0 != 0 {
+    return;
   }
 
-  Ok(win)
+  let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+  let left = ((info.rcWork.right - info.rcWork.left - width) / 2).max(0);
+  let top = ((info.rcWork.bottom - info.rcWork.top - height) / 2).max(0);
+  placement.rcNormalPosition = RECT {
+    left,
+    top,
+    right: left + width,
+    bottom: top + height,
+  };
 }
 
 unsafe fn register_window_class(
@@ -1026,12 +1465,16 @@ unsafe extern "system" fn window_proc(
         let win_flags = WindowFlags::from_bits_unchecked(userdata as _);
         if !win_flags.contains(WindowFlags::DECORATIONS) {
           // adjust the maximized borderless window so it doesn't cover the taskbar
+          let params = &mut *(lparam.0 as *mut NCCALCSIZE_PARAMS);
           if util::is_maximized(window) {
             let monitor = monitor::current_monitor(window);
             if let Ok(monitor_info) = monitor::get_monitor_info(monitor.hmonitor()) {
-              let params = &mut *(lparam.0 as *mut NCCALCSIZE_PARAMS);
               params.rgrc[0] = monitor_info.monitorInfo.rcWork;
             }
+          } else if win_flags.contains(WindowFlags::UNDECORATED_SHADOW) {
+            // leave the top pixel to the extended frame, otherwise DWM paints it white
+            params.rgrc[0].top += 1;
+            params.rgrc[0].bottom += 1;
           }
           return LRESULT(0); // return 0 here to make the window borderless
         }
@@ -1078,6 +1521,230 @@ pub fn com_initialized() {
   COM_INITIALIZED.with(|_| {});
 }
 
+// Not in the bindings yet, see
+// https://docs.microsoft.com/en-us/windows/win32/api/dwmapi/ne-dwmapi-dwmwindowattribute
+const DWMWA_WINDOW_CORNER_PREFERENCE: u32 = 33;
+const DWMWA_BORDER_COLOR: u32 = 34;
+const DWMWA_CAPTION_COLOR: u32 = 35;
+const DWMWA_TEXT_COLOR: u32 = 36;
+const DWMWA_COLOR_DEFAULT: u32 = 0xFFFFFFFF;
+
+/// Sets a DWM attribute that only exists since Windows 11.
+fn set_dwm_attribute(hwnd: HWND, attribute: u32, value: u32) {
+  // The attributes are unknown, and fail, before Windows 11
+  if !dark_mode::is_windows_11() {
+    return;
+  }
+
+  unsafe {
+    let _ = DwmSetWindowAttribute(
+      hwnd,
+      attribute,
+      &value as *const _ as _,
+      mem::size_of::<u32>() as u32,
+    );
+  }
+}
+
+/// Excludes the window from screen captures, or shows it as a black rectangle in them before
+/// `WDA_EXCLUDEFROMCAPTURE` was supported.
+unsafe fn set_content_protection(hwnd: HWND, enabled: bool) {
+  if !enabled {
+    SetWindowDisplayAffinity(hwnd, WDA_NONE);
+  } else if !SetWindowDisplayAffinity(hwnd, WDA_EXCLUDEFROMCAPTURE).as_bool() {
+    SetWindowDisplayAffinity(hwnd, WDA_MONITOR);
+  }
+}
+
+/// Creates the window drawn over a region of `owner` and excluded from screen captures.
+///
+/// `WDA_EXCLUDEFROMCAPTURE` only applies to top-level windows, so this is a popup owned by
+/// `owner` rather than a child window: it stays above it and is hidden when it's minimized, and
+/// `place_protected_window` moves it along and hides it with it.
+unsafe fn create_protected_window(owner: HWND) -> Result<HWND, RootOsError> {
+  let mut class_name = util::encode_wide("Tao Protected Region");
+  let class = WNDCLASSEXW {
+    cbSize: mem::size_of::<WNDCLASSEXW>() as u32,
+    style: CS_OWNDC,
+    lpfnWndProc: Some(protected_window_proc),
+    cbClsExtra: 0,
+    cbWndExtra: 0,
+    hInstance: GetModuleHandleW(PWSTR::default()),
+    hIcon: HICON::default(),
+    hCursor: HCURSOR::default(),
+    hbrBackground: HBRUSH::default(),
+    lpszMenuName: PWSTR::default(),
+    lpszClassName: PWSTR(class_name.as_mut_ptr()),
+    hIconSm: HICON::default(),
+  };
+  // Registering the class again fails, which is fine
+  RegisterClassExW(&class);
+
+  let hwnd = CreateWindowExW(
+    WS_EX_NOACTIVATE | WS_EX_TOOLWINDOW,
+    PWSTR(class_name.as_mut_ptr()),
+    PWSTR::default(),
+    WS_POPUP | WS_CLIPSIBLINGS,
+    0,
+    0,
+    0,
+    0,
+    owner,
+    HMENU::default(),
+    GetModuleHandleW(PWSTR::default()),
+    ptr::null_mut(),
+  );
+  if hwnd.is_invalid() {
+    return Err(os_error!(OsError::IoError(io::Error::last_os_error())));
+  }
+  set_content_protection(hwnd, true);
+  Ok(hwnd)
+}
+
+unsafe extern "system" fn protected_window_proc(
+  hwnd: HWND,
+  msg: u32,
+  wparam: WPARAM,
+  lparam: LPARAM,
+) -> LRESULT {
+  match msg {
+    // The mouse input goes through to the owner, which emits the events
+    win32wm::WM_NCHITTEST => LRESULT(HTTRANSPARENT as _),
+    win32wm::WM_MOUSEACTIVATE => LRESULT(MA_NOACTIVATE as _),
+    // The content is drawn by the application on the `RedrawRequested` of the owner
+    win32wm::WM_PAINT => {
+      ValidateRect(hwnd, ptr::null());
+      InvalidateRect(GetWindow(hwnd, GW_OWNER), ptr::null(), false);
+      LRESULT(0)
+    }
+    _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+  }
+}
+
+/// Keeps the protected window of `owner` over its region, clipped to the client area, and only
+/// shows it while `owner` is visible.
+pub(super) unsafe fn place_protected_window(owner: HWND, window_state: &WindowState) {
+  let region = match &window_state.protected_region {
+    Some(region) => region,
+    None => return,
+  };
+  let position: PhysicalPosition<i32> = region.rect.position.to_physical(window_state.scale_factor);
+  let size: PhysicalSize<i32> = region.rect.size.to_physical(window_state.scale_factor);
+
+  let mut client_rect = RECT::default();
+  let mut client_origin = POINT::default();
+  GetClientRect(owner, &mut client_rect);
+  ClientToScreen(owner, &mut client_origin);
+  let left = position.x.max(0);
+  let top = position.y.max(0);
+  let width = position.x.saturating_add(size.width).min(client_rect.right) - left;
+  let height = position
+    .y
+    .saturating_add(size.height)
+    .min(client_rect.bottom)
+    - top;
+
+  let visibility = if util::is_visible(owner) && width > 0 && height > 0 {
+    SWP_SHOWWINDOW
+  } else {
+    SWP_HIDEWINDOW
+  };
+  SetWindowPos(
+    region.hwnd,
+    HWND::default(),
+    client_origin.x + left,
+    client_origin.y + top,
+    width.max(0),
+    height.max(0),
+    SWP_NOZORDER | SWP_NOACTIVATE | visibility,
+  );
+}
+
+const DWMWA_TRANSITIONS_FORCEDISABLED: u32 = 3;
+
+/// Disables the DWM animations of the window, e.g. when showing and hiding it.
+fn set_transitions_disabled(hwnd: HWND, disabled: bool) {
+  let value = win32f::BOOL::from(disabled);
+  unsafe {
+    let _ = DwmSetWindowAttribute(
+      hwnd,
+      DWMWA_TRANSITIONS_FORCEDISABLED,
+      &value as *const _ as _,
+      mem::size_of::<win32f::BOOL>() as u32,
+    );
+  }
+}
+
+/// Enables or disables the blur behind the window. With an empty region for the blur effect,
+/// enabling it makes the window fully transparent.
+unsafe fn set_blur_behind(hwnd: HWND, enable: bool) {
+  let region = CreateRectRgn(0, 0, -1, -1);
+
+  let bb = DWM_BLURBEHIND {
+    dwFlags: DWM_BB_ENABLE | DWM_BB_BLURREGION,
+    fEnable: enable.into(),
+    hRgnBlur: region,
+    fTransitionOnMaximized: false.into(),
+  };
+
+  let _ = DwmEnableBlurBehindWindow(hwnd, &bb);
+  DeleteObject(region);
+}
+
+/// Returns the null-terminated device name of `monitor`, as expected by the
+/// display settings functions.
+fn display_name(monitor: &RootMonitorHandle) -> Vec<u16> {
+  util::encode_wide(monitor.inner.native_identifier())
+}
+
+/// Switches the display of `video_mode` to it and returns the mode the display
+/// was using before the change, `None` if the switch failed.
+///
+/// Failures are only logged, this is also called from the window procedure.
+pub(super) unsafe fn set_display_mode(video_mode: &RootVideoMode) -> Option<SavedDisplayMode> {
+  let mut display_name = display_name(&video_mode.monitor());
+
+  let mut mode: DEVMODEW = mem::zeroed();
+  mode.dmSize = mem::size_of_val(&mode) as u16;
+  EnumDisplaySettingsExW(
+    PWSTR(display_name.as_mut_ptr()),
+    ENUM_CURRENT_SETTINGS,
+    &mut mode,
+    0,
+  );
+
+  let native_video_mode = video_mode.video_mode.native_video_mode;
+  let res = ChangeDisplaySettingsExW(
+    PWSTR(display_name.as_mut_ptr()),
+    &native_video_mode,
+    HWND::default(),
+    CDS_FULLSCREEN,
+    std::ptr::null_mut(),
+  );
+
+  if res != DISP_CHANGE_SUCCESSFUL {
+    warn!("Failed to change the display mode: {:?}", res);
+    return None;
+  }
+
+  Some(SavedDisplayMode { display_name, mode })
+}
+
+/// Gives a display changed by `set_display_mode` its original mode back.
+pub(super) unsafe fn restore_display_mode(saved: &mut SavedDisplayMode) {
+  let res = ChangeDisplaySettingsExW(
+    PWSTR(saved.display_name.as_mut_ptr()),
+    &saved.mode,
+    HWND::default(),
+    CDS_FULLSCREEN,
+    std::ptr::null_mut(),
+  );
+
+  if res != DISP_CHANGE_SUCCESSFUL {
+    warn!("Failed to restore the display mode: {:?}", res);
+  }
+}
+
 // Reference Implementation:
 // https://github.com/chromium/chromium/blob/f18e79d901f56154f80eea1e2218544285e62623/ui/views/win/fullscreen_handler.cc
 //
@@ -1191,3 +1858,92 @@ pub fn hit_test(hwnd: HWND, cx: i32, cy: i32) -> LRESULT {
     }
   }
 }
+
+/// Hit-tests the point, in screen coordinates, against the regions of a custom titlebar. Returns
+/// `None` outside of them, or when there are no regions.
+pub(super) fn nc_hit_test(hwnd: HWND, window_state: &WindowState, cx: i32, cy: i32) -> Option<u32> {
+  let regions = window_state.nc_hit_regions.as_ref()?;
+  let scale_factor = window_state.scale_factor;
+  let window_flags = window_state.window_flags();
+
+  let mut point = POINT { x: cx, y: cy };
+  let mut client_rect = RECT::default();
+  unsafe {
+    if !ScreenToClient(hwnd, &mut point).as_bool()
+      || !GetClientRect(hwnd, &mut client_rect).as_bool()
+    {
+      return None;
+    }
+  }
+  if point.x < 0 || point.y < 0 || point.x >= client_rect.right || point.y >= client_rect.bottom {
+    return None;
+  }
+
+  // Undecorated windows have no frame to resize with
+  let resize_border = match regions.resize_border {
+    Some(border) => Some(border),
+    None if !window_flags.contains(WindowFlags::DECORATIONS) => {
+      Some(BORDERLESS_RESIZE_INSET as f64)
+    }
+    None => None,
+  };
+  if let (Some(border), true, false) = (
+    resize_border,
+    window_flags.contains(WindowFlags::RESIZABLE),
+    util::is_maximized(hwnd),
+  ) {
+    let inset = (border * scale_factor) as i32;
+    let left = point.x < inset;
+    let right = point.x >= client_rect.right - inset;
+    let top = point.y < inset;
+    let bottom = point.y >= client_rect.bottom - inset;
+    let hit = match (top, bottom, left, right) {
+      (true, _, true, _) => Some(HTTOPLEFT),
+      (true, _, _, true) => Some(HTTOPRIGHT),
+      (_, true, true, _) => Some(HTBOTTOMLEFT),
+      (_, true, _, true) => Some(HTBOTTOMRIGHT),
+      (true, ..) => Some(HTTOP),
+      (_, true, ..) => Some(HTBOTTOM),
+      (_, _, true, _) => Some(HTLEFT),
+      (_, _, _, true) => Some(HTRIGHT),
+      _ => None,
+    };
+    if hit.is_some() {
+      return hit;
+    }
+  }
+
+  let contains = |rect: &Rect| {
+    let (x, y): (i32, i32) = rect.position.to_physical::<i32>(scale_factor).into();
+    let (width, height): (i32, i32) = rect.size.to_physical::<i32>(scale_factor).into();
+    point.x >= x && point.y >= y && point.x < x + width && point.y < y + height
+  };
+  if regions.close_button.iter().any(&contains) {
+    Some(HTCLOSE)
+  } else if regions.maximize_button.iter().any(&contains) {
+    Some(HTMAXBUTTON)
+  } else if regions.minimize_button.iter().any(&contains) {
+    Some(HTMINBUTTON)
+  } else if regions.caption.iter().any(&contains) {
+    Some(HTCAPTION)
+  } else {
+    None
+  }
+}
+
+/// Hit test of the top resize border left when the titlebar is hidden, `None` outside of it.
+pub(super) fn hidden_titlebar_hit_test(hwnd: HWND, cx: i32, cy: i32) -> Option<LRESULT> {
+  let mut point = POINT { x: cx, y: cy };
+  unsafe {
+    if !ScreenToClient(hwnd, &mut point).as_bool() || point.y >= 0 {
+      return None;
+    }
+  }
+  // The border may be thicker than the inset of `hit_test`, keep its sides as corners
+  let hit = match hit_test(hwnd, cx, cy).0 as u32 {
+    HTLEFT | HTTOPLEFT => HTTOPLEFT,
+    HTRIGHT | HTTOPRIGHT => HTTOPRIGHT,
+    _ => HTTOP,
+  };
+  Some(LRESULT(hit as _))
+}
@@ -3,16 +3,20 @@
 
 use crate::{
   dpi::{PhysicalPosition, Size},
+  event::CaptionButton,
   icon::Icon,
   keyboard::ModifiersState,
-  platform_impl::platform::{event_loop, minimal_ime::MinimalIme, util},
-  window::{CursorIcon, Fullscreen, Theme, WindowAttributes},
+  platform::windows::HitRegions,
+  platform_impl::platform::{
+    event_loop, icon::WinCursor, minimal_ime::MinimalIme, monitor::MonitorHandle, util,
+  },
+  window::{CursorIcon, Fullscreen, Rect, Theme, WindowAttributes},
 };
 use parking_lot::MutexGuard;
 use std::io;
 use windows::Win32::{
   Foundation::{HWND, LPARAM, RECT, WPARAM},
-  Graphics::Gdi::{InvalidateRgn, HRGN},
+  Graphics::Gdi::{InvalidateRgn, DEVMODEW, HRGN},
   UI::WindowsAndMessaging::*,
 };
 
@@ -23,11 +27,18 @@ pub struct WindowState {
   /// Used by `WM_GETMINMAXINFO`.
   pub min_size: Option<Size>,
   pub max_size: Option<Size>,
+  /// Used by `WM_SIZING`.
+  pub aspect_ratio: Option<f64>,
+  /// Used by `WM_NCHITTEST`.
+  pub nc_hit_regions: Option<HitRegions>,
+  pub hovered_caption_button: Option<CaptionButton>,
 
   pub window_icon: Option<Icon>,
   pub taskbar_icon: Option<Icon>,
 
   pub saved_window: Option<SavedWindow>,
+  /// The original mode of the display we switched for exclusive fullscreen.
+  pub saved_display_mode: Option<SavedDisplayMode>,
   pub scale_factor: f64,
 
   pub modifiers_state: ModifiersState,
@@ -36,9 +47,28 @@ pub struct WindowState {
   pub preferred_theme: Option<Theme>,
   pub high_surrogate: Option<u16>,
 
+  /// The monitor the window is moving to while `ScaleFactorChanged` is being dispatched, which
+  /// `current_monitor` returns before the window is actually moved.
+  pub dpi_changed_monitor: Option<MonitorHandle>,
+
   pub ime_handler: MinimalIme,
 
   pub window_flags: WindowFlags,
+
+  /// Set by `request_redraw` so the next `WM_PAINT` redraws the whole window.
+  pub redraw_full: bool,
+  /// The region being redrawn by the current `RedrawRequested` event, or `None` for the whole
+  /// window.
+  pub redraw_rect: Option<RECT>,
+
+  /// Set by `set_protected_region`, placed on `WM_WINDOWPOSCHANGED`.
+  pub protected_region: Option<ProtectedRegion>,
+}
+
+/// A window drawn over a region of the client area and excluded from screen captures.
+pub struct ProtectedRegion {
+  pub hwnd: HWND,
+  pub rect: Rect,
 }
 
 #[derive(Clone)]
@@ -46,9 +76,17 @@ pub struct SavedWindow {
   pub placement: WINDOWPLACEMENT,
 }
 
+pub struct SavedDisplayMode {
+  /// Null-terminated device name of the display.
+  pub display_name: Vec<u16>,
+  pub mode: DEVMODEW,
+}
+
 #[derive(Clone)]
 pub struct MouseProperties {
   pub cursor: CursorIcon,
+  /// Set by `set_custom_cursor`, shown instead of `cursor`.
+  pub custom_cursor: Option<WinCursor>,
   pub capture_count: u32,
   cursor_flags: CursorFlags,
   pub last_position: Option<PhysicalPosition<f64>>,
@@ -73,6 +111,15 @@ bitflags! {
         const CHILD          = 1 << 7;
         const MAXIMIZED      = 1 << 8;
         const POPUP          = 1 << 14;
+        /// Keeps the DWM shadow of a window without decorations.
+        const UNDECORATED_SHADOW = 1 << 15;
+        /// The title bar buttons that are enabled, see `Window::set_enabled_buttons`.
+        const MINIMIZABLE    = 1 << 16;
+        const MAXIMIZABLE    = 1 << 17;
+        const CLOSABLE       = 1 << 18;
+        /// Gives the caption of a decorated window to the client area, keeping the resize
+        /// borders, see `WindowExtWindows::set_titlebar_visible`.
+        const TITLEBAR_HIDDEN = 1 << 19;
 
         /// Marker flag for fullscreen. Should always match `WindowState::fullscreen`, but is
         /// included here to make masking easier.
@@ -106,6 +153,7 @@ impl WindowState {
     WindowState {
       mouse: MouseProperties {
         cursor: CursorIcon::default(),
+        custom_cursor: None,
         capture_count: 0,
         cursor_flags: CursorFlags::empty(),
         last_position: None,
@@ -113,11 +161,15 @@ impl WindowState {
 
       min_size: attributes.min_inner_size,
       max_size: attributes.max_inner_size,
+      aspect_ratio: attributes.aspect_ratio,
+      nc_hit_regions: None,
+      hovered_caption_button: None,
 
       window_icon: attributes.window_icon.clone(),
       taskbar_icon,
 
       saved_window: None,
+      saved_display_mode: None,
       scale_factor,
 
       modifiers_state: ModifiersState::default(),
@@ -125,8 +177,12 @@ impl WindowState {
       current_theme,
       preferred_theme,
       high_surrogate: None,
+      dpi_changed_monitor: None,
       ime_handler: MinimalIme::default(),
       window_flags: WindowFlags::empty(),
+      redraw_full: false,
+      redraw_rect: None,
+      protected_region: None,
     }
   }
 
@@ -192,11 +248,17 @@ impl WindowFlags {
 
   pub fn to_window_styles(self) -> (WINDOW_STYLE, WINDOW_EX_STYLE) {
     let (mut style, mut style_ex) = (0, 0);
-    style |= WS_CLIPSIBLINGS | WS_CLIPCHILDREN | WS_SYSMENU | WS_CAPTION | WS_MINIMIZEBOX;
+    style |= WS_CLIPSIBLINGS | WS_CLIPCHILDREN | WS_SYSMENU | WS_CAPTION;
     style_ex |= WS_EX_ACCEPTFILES;
 
     if self.contains(WindowFlags::RESIZABLE) {
-      style |= WS_SIZEBOX | WS_MAXIMIZEBOX;
+      style |= WS_SIZEBOX;
+      if self.contains(WindowFlags::MAXIMIZABLE) {
+        style |= WS_MAXIMIZEBOX;
+      }
+    }
+    if self.contains(WindowFlags::MINIMIZABLE) {
+      style |= WS_MINIMIZEBOX;
     }
     if self.contains(WindowFlags::DECORATIONS) {
       style_ex |= WS_EX_WINDOWEDGE;
@@ -249,6 +311,8 @@ impl WindowFlags {
         ShowWindow(
           window,
           match new.contains(WindowFlags::VISIBLE) {
+            // Show a maximized window maximized right away, instead of at its normal size first
+            true if new.contains(WindowFlags::MAXIMIZED) => SW_MAXIMIZE,
             true => SW_SHOW,
             false => SW_HIDE,
           },
@@ -298,6 +362,28 @@ impl WindowFlags {
       }
     }
 
+    // The close button has no style bit, it follows the entry of the system menu
+    if diff.contains(WindowFlags::CLOSABLE) || !new.contains(WindowFlags::CLOSABLE) {
+      unsafe {
+        EnableMenuItem(
+          GetSystemMenu(window, false),
+          SC_CLOSE,
+          MF_BYCOMMAND
+            | match new.contains(WindowFlags::CLOSABLE) {
+              true => MF_ENABLED,
+              false => MF_DISABLED | MF_GRAYED,
+            },
+        );
+      }
+    }
+
+    if diff.intersects(WindowFlags::DECORATIONS | WindowFlags::UNDECORATED_SHADOW) {
+      util::set_undecorated_shadow(
+        window,
+        new.contains(WindowFlags::UNDECORATED_SHADOW) && !new.contains(WindowFlags::DECORATIONS),
+      );
+    }
+
     if diff != WindowFlags::empty() {
       let (style, style_ex) = new.to_window_styles();
 
@@ -18,13 +18,18 @@
 //!   .unwrap();
 //! ```
 //!
+//! Multiple trays can be created, use [`SystemTrayBuilder::with_id`] to tell their events apart.
+//! Clicks on tray menu items are identified by their [`MenuId`][menu_id].
+//!
 //! # Linux
 //! A menu is required or the tray return an error containing `assertion 'G_IS_DBUS_CONNECTION (connection)'`.
 //!
 //! [tray_builder]: crate::system_tray::SystemTrayBuilder
 //! [menu_bar]: crate::menu::MenuBar
 //! [context_menu]: crate::menu::ContextMenu
+//! [menu_id]: crate::menu::MenuId
 
+pub use crate::event::TrayId;
 use crate::{
   error::OsError,
   event_loop::EventLoopWindowTarget,
@@ -68,6 +73,17 @@ impl SystemTrayBuilder {
     ))
   }
 
+  /// Sets the id of the tray, used to identify which tray an [`Event::TrayEvent`] comes from
+  /// when using multiple trays.
+  ///
+  /// The default is [`TrayId::EMPTY`].
+  ///
+  /// [`Event::TrayEvent`]: crate::event::Event::TrayEvent
+  pub fn with_id(mut self, id: TrayId) -> Self {
+    self.0.id = id;
+    self
+  }
+
   /// Builds the SystemTray.
   ///
   /// Possible causes of error include denied permission, incompatible system, and lack of memory.
@@ -83,6 +99,11 @@ impl SystemTrayBuilder {
 pub struct SystemTray(pub SystemTrayPlatform);
 
 impl SystemTray {
+  /// Returns the id of the tray.
+  pub fn id(&self) -> TrayId {
+    self.0.id()
+  }
+
   /// Set new tray icon.
   ///
   /// ## Platform-specific
@@ -105,6 +126,27 @@ impl SystemTray {
     self.0.set_icon(icon)
   }
 
+  /// Set the text shown next to the tray icon.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** Sets the indicator label, which is only shown by some desktop environments.
+  /// - **Windows:** Unsupported, notification area icons can't show text.
+  pub fn set_title(&mut self, title: &str) {
+    self.0.set_title(title)
+  }
+
+  /// Show or hide the tray icon.
+  ///
+  /// The icon, title and menu are kept while the tray is hidden.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Requires macOS 10.12+.
+  pub fn set_visible(&mut self, visible: bool) {
+    self.0.set_visible(visible)
+  }
+
   /// Set new tray menu.
   pub fn set_menu(&mut self, tray_menu: &ContextMenu) {
     self.0.set_menu(&tray_menu.0.menu_platform)
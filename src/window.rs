@@ -2,12 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! The `Window` struct and associated types.
-use std::fmt;
+use std::{
+  fmt,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
 
 use crate::{
-  dpi::{PhysicalPosition, PhysicalSize, Position, Size},
+  dpi::{
+    LogicalPosition, PhysicalPosition, PhysicalSize, Pixel, Position, ScaleFactorRounding, Size,
+  },
   error::{ExternalError, NotSupportedError, OsError},
   event_loop::EventLoopWindowTarget,
+  icon::RgbaIcon,
   menu::MenuBar,
   monitor::{MonitorHandle, VideoMode},
   platform_impl,
@@ -43,6 +50,11 @@ pub use crate::icon::{BadIcon, Icon};
 /// ```
 pub struct Window {
   pub(crate) window: platform_impl::Window,
+  /// The last cursor set with `set_cursor_icon` or `set_custom_cursor`, restored by
+  /// `WaitCursorGuard`.
+  cursor: Mutex<Cursor>,
+  /// The `ScaleFactorRounding` of the event loop the window was built with.
+  scale_factor_rounding: ScaleFactorRounding,
 }
 
 impl fmt::Debug for Window {
@@ -82,6 +94,59 @@ impl WindowId {
   pub unsafe fn dummy() -> Self {
     WindowId(platform_impl::WindowId::dummy())
   }
+
+  /// Returns the raw value of the id, to store or compare it outside of tao.
+  ///
+  /// Ids are only valid within a single run of the process, the same window gets another id
+  /// the next time it's created. Two windows alive at the same time never share an id, but an
+  /// id can be reused once its window is destroyed.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The `HWND` of the window.
+  /// - **macOS / iOS:** The address of the `NSWindow` / `UIWindow`.
+  /// - **Linux:** The id of the `GtkApplicationWindow` within the application, not the X11
+  ///   window id.
+  /// - **Android:** Always `0`, there's only one window.
+  #[inline]
+  pub fn into_raw(self) -> u64 {
+    self.0.into_raw()
+  }
+
+  /// Creates an id from a value returned by [`WindowId::into_raw`], for advanced interop.
+  ///
+  /// The id is only meaningful if `raw` was returned in the same run of the process, see
+  /// [`WindowId::into_raw`]. It then compares and hashes equal to the original id.
+  ///
+  /// Returns `None` if `raw` can't be a window id of the platform, e.g. when it doesn't fit the
+  /// 32 bits of the Linux ids.
+  #[inline]
+  pub fn from_raw(raw: u64) -> Option<Self> {
+    platform_impl::WindowId::from_raw(raw).map(WindowId)
+  }
+}
+
+/// Serialized as the [raw value](WindowId::into_raw) of the id.
+#[cfg(feature = "serde")]
+impl serde::Serialize for WindowId {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: serde::Serializer,
+  {
+    serde::Serialize::serialize(&self.into_raw(), serializer)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for WindowId {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    let raw = <u64 as serde::Deserialize>::deserialize(deserializer)?;
+    WindowId::from_raw(raw)
+      .ok_or_else(|| <D::Error as serde::de::Error>::custom("invalid window id"))
+  }
 }
 
 /// Object that allows you to build windows.
@@ -122,6 +187,12 @@ pub struct WindowAttributes {
   /// The default is `None`.
   pub max_inner_size: Option<Size>,
 
+  /// The width to height ratio kept by the inner size when the user resizes the window, see
+  /// [`WindowSizeConstraints::aspect_ratio`].
+  ///
+  /// The default is `None`.
+  pub aspect_ratio: Option<f64>,
+
   /// The desired position of the window. If this is `None`, some platform-specific position
   /// will be chosen.
   ///
@@ -152,6 +223,11 @@ pub struct WindowAttributes {
   /// The default is `true`.
   pub resizable: bool,
 
+  /// The buttons of the title bar that are enabled.
+  ///
+  /// The default is [`WindowButtons::all`].
+  pub enabled_buttons: WindowButtons,
+
   /// Whether the window should be set as fullscreen upon creation.
   ///
   /// The default is `None`.
@@ -178,6 +254,11 @@ pub struct WindowAttributes {
   /// The default is `false`.
   pub transparent: bool,
 
+  /// Whether what is behind the window should be blurred.
+  ///
+  /// The default is `false`.
+  pub blur: bool,
+
   /// Whether the window should have borders and bars.
   ///
   /// The default is `true`.
@@ -188,6 +269,16 @@ pub struct WindowAttributes {
   /// The default is `false`.
   pub always_on_top: bool,
 
+  /// Whether the content of the window is excluded from screen captures.
+  ///
+  /// The default is `false`.
+  pub content_protection: bool,
+
+  /// Whether the window should be focused when it is first shown.
+  ///
+  /// The default is `true`.
+  pub focused: bool,
+
   /// The window icon.
   ///
   /// The default is `None`.
@@ -206,15 +297,20 @@ impl Default for WindowAttributes {
       inner_size: None,
       min_inner_size: None,
       max_inner_size: None,
+      aspect_ratio: None,
       position: None,
       resizable: true,
+      enabled_buttons: WindowButtons::all(),
       title: "tao window".to_owned(),
       maximized: false,
       fullscreen: None,
       visible: true,
       transparent: false,
+      blur: false,
       decorations: true,
       always_on_top: false,
+      content_protection: false,
+      focused: true,
       window_icon: None,
       window_menu: None,
     }
@@ -261,6 +357,26 @@ impl WindowBuilder {
     self
   }
 
+  /// Sets the minimum size, maximum size and aspect ratio of the window at once.
+  ///
+  /// See [`WindowSizeConstraints`] for details.
+  #[inline]
+  pub fn with_inner_size_constraints(mut self, constraints: WindowSizeConstraints) -> Self {
+    self.window.min_inner_size = constraints.min;
+    self.window.max_inner_size = constraints.max;
+    self.window.aspect_ratio = constraints.aspect_ratio;
+    self
+  }
+
+  /// Locks the inner size of the window to a `(width, height)` ratio while the user resizes it.
+  ///
+  /// See [`Window::set_aspect_ratio`] for details.
+  #[inline]
+  pub fn with_aspect_ratio(mut self, aspect_ratio: (u32, u32)) -> Self {
+    self.window.aspect_ratio = Some(aspect_ratio.0 as f64 / aspect_ratio.1 as f64);
+    self
+  }
+
   /// Sets a desired initial position for the window.
   ///
   /// See [`WindowAttributes::position`] for details.
@@ -283,6 +399,17 @@ impl WindowBuilder {
     self
   }
 
+  /// Sets the enabled buttons of the title bar.
+  ///
+  /// See [`Window::set_enabled_buttons`] for details.
+  ///
+  /// [`Window::set_enabled_buttons`]: crate::window::Window::set_enabled_buttons
+  #[inline]
+  pub fn with_enabled_buttons(mut self, buttons: WindowButtons) -> Self {
+    self.window.enabled_buttons = buttons;
+    self
+  }
+
   /// Requests a specific title for the window.
   ///
   /// See [`Window::set_title`] for details.
@@ -296,7 +423,7 @@ impl WindowBuilder {
 
   /// Requests a specific menu for the window.
   ///
-  /// See [`Window::set_menu`] for details.
+  /// See [`Window::set_menu`] for details, this is a no-op on macOS too.
   ///
   /// [`Window::set_menu`]: crate::window::Window::set_menu
   #[inline]
@@ -339,12 +466,28 @@ impl WindowBuilder {
   }
 
   /// Sets whether the background of the window should be transparent.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** The window uses the 32-bit ARGB visual of its screen. It stays opaque, with a
+  ///   warning logged, when there's no such visual or no compositor is running.
   #[inline]
   pub fn with_transparent(mut self, transparent: bool) -> Self {
     self.window.transparent = transparent;
     self
   }
 
+  /// Sets whether what is behind the window should be blurred.
+  ///
+  /// See [`Window::set_blur`] for details.
+  ///
+  /// [`Window::set_blur`]: crate::window::Window::set_blur
+  #[inline]
+  pub fn with_blur(mut self, blur: bool) -> Self {
+    self.window.blur = blur;
+    self
+  }
+
   /// Sets whether the window should have a border, a title bar, etc.
   ///
   /// See [`Window::set_decorations`] for details.
@@ -367,6 +510,35 @@ impl WindowBuilder {
     self
   }
 
+  /// Sets whether the content of the window is excluded from screen captures, from before it's
+  /// first shown.
+  ///
+  /// See [`Window::set_content_protection`] for details.
+  ///
+  /// [`Window::set_content_protection`]: crate::window::Window::set_content_protection
+  #[inline]
+  pub fn with_content_protection(mut self, enabled: bool) -> Self {
+    self.window.content_protection = enabled;
+    self
+  }
+
+  /// Sets whether the window will be focused when it is first shown.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn with_focused(mut self, focused: bool) -> Self {
+    self.window.focused = focused;
+    self
+  }
+
+  /// Alias for [`WindowBuilder::with_focused`].
+  #[inline]
+  pub fn with_active(self, active: bool) -> Self {
+    self.with_focused(active)
+  }
+
   /// Sets the window icon.
   ///
   /// See [`Window::set_window_icon`] for details.
@@ -389,7 +561,11 @@ impl WindowBuilder {
     platform_impl::Window::new(&window_target.p, self.window, self.platform_specific).map(
       |window| {
         window.request_redraw();
-        Window { window }
+        Window {
+          window,
+          cursor: Mutex::new(Cursor::Icon(CursorIcon::default())),
+          scale_factor_rounding: window_target.scale_factor_rounding.get(),
+        }
       },
     )
   }
@@ -425,16 +601,65 @@ impl Window {
   /// moved to another screen); as such, tracking `WindowEvent::ScaleFactorChanged` events is
   /// the most robust way to track the DPI you need to use to draw.
   ///
+  /// The value is rounded as set with
+  /// [`EventLoopBuilder::with_scale_factor_rounding`](crate::event_loop::EventLoopBuilder::with_scale_factor_rounding).
+  ///
   /// ## Platform-specific
   ///
-  /// - **Android:** Always returns 1.0.
+  /// - **Android:** Returns the display density relative to 160 DPI, or 1.0 when it's unknown.
   /// - **iOS:** Can only be called on the main thread. Returns the underlying `UIView`'s
   ///   [`contentScaleFactor`].
+  /// - **Linux:** Returns the factor set with [`WindowBuilderExtUnix::with_scale_factor_override`]
+  ///   if any. Otherwise, on Wayland with the `wayland-protocols` feature, the fractional scale
+  ///   preferred by the compositor through the `wp_fractional_scale_v1` protocol when it supports
+  ///   it, and the integer scale of GTK elsewhere.
   ///
+  /// [`WindowBuilderExtUnix::with_scale_factor_override`]: crate::platform::unix::WindowBuilderExtUnix::with_scale_factor_override
   /// [`contentScaleFactor`]: https://developer.apple.com/documentation/uikit/uiview/1622657-contentscalefactor?language=objc
   #[inline]
   pub fn scale_factor(&self) -> f64 {
-    self.window.scale_factor()
+    self.scale_factor_rounding.round(self.window.scale_factor())
+  }
+
+  /// Converts a logical `size` to physical pixels with the rounded scale factor, see
+  /// [`EventLoopBuilder::with_scale_factor_rounding`](crate::event_loop::EventLoopBuilder::with_scale_factor_rounding).
+  fn rounded_size(&self, size: Size) -> Size {
+    match size {
+      Size::Logical(size) if self.scale_factor_rounding != ScaleFactorRounding::Exact => {
+        Size::Physical(size.to_physical(self.scale_factor()))
+      }
+      size => size,
+    }
+  }
+
+  /// Like [`Window::rounded_size`], for positions.
+  fn rounded_position(&self, position: Position) -> Position {
+    match position {
+      Position::Logical(position) if self.scale_factor_rounding != ScaleFactorRounding::Exact => {
+        Position::Physical(position.to_physical(self.scale_factor()))
+      }
+      position => position,
+    }
+  }
+
+  /// Converts `position`, e.g. the one of `WindowEvent::CursorMoved`, to logical pixels using
+  /// the current [`scale_factor`](Window::scale_factor) of the window.
+  #[inline]
+  pub fn physical_to_logical<P: Pixel>(
+    &self,
+    position: PhysicalPosition<P>,
+  ) -> LogicalPosition<f64> {
+    position.to_logical(self.scale_factor())
+  }
+
+  /// Converts `position` to physical pixels using the current
+  /// [`scale_factor`](Window::scale_factor) of the window.
+  #[inline]
+  pub fn logical_to_physical<P: Pixel>(
+    &self,
+    position: LogicalPosition<P>,
+  ) -> PhysicalPosition<f64> {
+    position.to_physical(self.scale_factor())
   }
 
   /// Emits a `WindowEvent::RedrawRequested` event in the associated event loop after all OS
@@ -457,6 +682,68 @@ impl Window {
   pub fn request_redraw(&self) {
     self.window.request_redraw()
   }
+
+  /// Like [`Window::request_redraw`], but only the `rect` region of the window needs to be
+  /// redrawn.
+  ///
+  /// The regions requested until the next `RedrawRequested` event are coalesced into their
+  /// union. On Windows, [`Window::redraw_rect`] returns it while handling that event, to restrict
+  /// the rendering to it.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Invalidates the region of the view with `setNeedsDisplayInRect:`, the
+  ///   `RedrawRequested` event is emitted when AppKit draws the view. AppKit doesn't draw a view
+  ///   whose layer is replaced, e.g. by a `CAMetalLayer`, use [`Window::request_redraw`] for it.
+  /// - **Linux:** Invalidates the region of the window with `gtk_widget_queue_draw_area`.
+  /// - **iOS:** Requests a redraw of the whole window.
+  /// - **Android:** Unsupported.
+  #[inline]
+  pub fn request_redraw_rect(&self, rect: Rect) {
+    let scale_factor = self.scale_factor();
+    self.window.request_redraw_rect(
+      rect.position.to_physical(scale_factor),
+      rect.size.to_physical(scale_factor),
+    )
+  }
+
+  /// Returns the region to redraw for the `RedrawRequested` event being handled, in physical
+  /// pixels, or `None` when the whole window must be redrawn.
+  ///
+  /// The region covers both the regions requested with [`Window::request_redraw_rect`] and the
+  /// ones the system needs redrawn, e.g. after being covered by another window. A call to
+  /// [`Window::request_redraw`] makes the next redraw a full one.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS / Linux / iOS / Android:** Always returns `None`, the region isn't reported and
+  ///   the whole window must be redrawn.
+  #[inline]
+  pub fn redraw_rect(&self) -> Option<Rect> {
+    self
+      .window
+      .redraw_rect()
+      .map(|(position, size)| Rect::new(position, size))
+  }
+
+  /// Notify the windowing system that you are about to present a frame to this window.
+  ///
+  /// Call it right before submitting the buffer to the display or presenting it, e.g. before
+  /// swapping buffers. This lets platforms that synchronize presentation with the compositor
+  /// schedule the next frame, and it should be called on every frame for that to work.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** The [`RedrawRequested`] requested after it is held back until the GTK frame
+  ///   clock starts the next frame, which follows the frame callbacks of the compositor on
+  ///   Wayland.
+  /// - **Windows / macOS / iOS / Android:** Unsupported, this is a no-op.
+  ///
+  /// [`RedrawRequested`]: crate::event::Event::RedrawRequested
+  #[inline]
+  pub fn pre_present_notify(&self) {
+    self.window.pre_present_notify()
+  }
 }
 
 /// Position and size functions.
@@ -511,7 +798,9 @@ impl Window {
   /// - **Android:** Unsupported.
   #[inline]
   pub fn set_outer_position<P: Into<Position>>(&self, position: P) {
-    self.window.set_outer_position(position.into())
+    self
+      .window
+      .set_outer_position(self.rounded_position(position.into()))
   }
 
   /// Returns the physical size of the window's client area.
@@ -522,6 +811,7 @@ impl Window {
   ///
   /// - **iOS:** Can only be called on the main thread. Returns the `PhysicalSize` of the window's
   ///   [safe area] in screen space coordinates.
+  /// - **Linux:** Excludes the header bar of client-side decorations and the menu bar.
   ///
   /// [safe area]: https://developer.apple.com/documentation/uikit/uiview/2891103-safeareainsets?language=objc
   #[inline]
@@ -539,7 +829,31 @@ impl Window {
   /// - **iOS / Android:** Unsupported.
   #[inline]
   pub fn set_inner_size<S: Into<Size>>(&self, size: S) {
-    self.window.set_inner_size(size.into())
+    self.window.set_inner_size(self.rounded_size(size.into()))
+  }
+
+  /// Modifies the inner size of the window, like [`Window::set_inner_size`], and reports whether
+  /// the resize has already been applied.
+  ///
+  /// Returns `Some` with the new inner size when the resize was applied synchronously, in which
+  /// case no layout is needed before the following [`WindowEvent::Resized`]. Returns `None` when
+  /// the new size will only be known from a later [`WindowEvent::Resized`] event.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Always applied synchronously. The returned size may differ from the requested
+  ///   one because of size constraints.
+  /// - **Windows:** Applied synchronously on the thread of the event loop. Returns `None` when
+  ///   called from another thread, the resize is then posted to the event loop.
+  /// - **Linux:** Always returns `None`, as the window manager decides on the final size.
+  /// - **iOS / Android:** Unsupported, always returns `None`.
+  ///
+  /// [`WindowEvent::Resized`]: crate::event::WindowEvent::Resized
+  #[inline]
+  pub fn request_inner_size<S: Into<Size>>(&self, size: S) -> Option<PhysicalSize<u32>> {
+    self
+      .window
+      .request_inner_size(self.rounded_size(size.into()))
   }
 
   /// Returns the physical size of the entire window.
@@ -556,6 +870,62 @@ impl Window {
     self.window.outer_size()
   }
 
+  /// Returns the physical space between the edges of the window, see [`Window::outer_size`], and
+  /// its content area, see [`Window::inner_size`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** The space taken by the header bar of client-side decorations and the menu bar,
+  ///   [`WindowEvent::DecorationsChanged`] is emitted when it changes.
+  /// - **Windows:** Includes the menu bar, which is part of the non-client area.
+  /// - **iOS / Android:** Unsupported, always returns empty insets.
+  ///
+  /// [`WindowEvent::DecorationsChanged`]: crate::event::WindowEvent::DecorationsChanged
+  #[inline]
+  pub fn content_insets(&self) -> Insets {
+    self.window.content_insets()
+  }
+
+  /// Returns the space on each side of the content area of the window that is obscured by system
+  /// UI, like the notch, the status bar or the home indicator. Content drawn there may not be
+  /// visible or interactable.
+  ///
+  /// Unlike [`Window::content_insets`], the insets are in logical units, multiply them by
+  /// [`Window::scale_factor`] for physical pixels. [`WindowEvent::SafeAreaChanged`] is emitted
+  /// when they change.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS:** The `safeAreaInsets` of the window's view, in points rounded up. Empty before
+  ///   iOS 11.
+  /// - **Windows / Linux / macOS / Android:** Unsupported, always returns empty insets.
+  ///
+  /// [`WindowEvent::SafeAreaChanged`]: crate::event::WindowEvent::SafeAreaChanged
+  #[inline]
+  pub fn safe_area_insets(&self) -> Insets {
+    self.window.safe_area_insets()
+  }
+
+  /// Locks the window content to the given orientation, or allows it to rotate with the device
+  /// again with `None`.
+  ///
+  /// [`WindowEvent::OrientationChanged`] is emitted when the orientation changes.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS:** Sets the `supportedInterfaceOrientations` of the view controller, replacing the
+  ///   orientations set with `WindowExtIOS::set_valid_orientations`. `None` allows all the
+  ///   orientations supported by the device.
+  /// - **Android:** Calls `Activity.setRequestedOrientation`. `None` lets the system choose the
+  ///   orientation.
+  /// - **Windows / Linux / macOS:** Unsupported.
+  ///
+  /// [`WindowEvent::OrientationChanged`]: crate::event::WindowEvent::OrientationChanged
+  #[inline]
+  pub fn set_orientation_lock(&self, orientation: Option<Orientation>) {
+    self.window.set_orientation_lock(orientation)
+  }
+
   /// Sets a minimum dimension size for the window.
   ///
   /// ## Platform-specific
@@ -563,7 +933,9 @@ impl Window {
   /// - **iOS / Android:** Unsupported.
   #[inline]
   pub fn set_min_inner_size<S: Into<Size>>(&self, min_size: Option<S>) {
-    self.window.set_min_inner_size(min_size.map(|s| s.into()))
+    self
+      .window
+      .set_min_inner_size(min_size.map(|s| self.rounded_size(s.into())))
   }
 
   /// Sets a maximum dimension size for the window.
@@ -573,7 +945,23 @@ impl Window {
   /// - **iOS / Android:** Unsupported.
   #[inline]
   pub fn set_max_inner_size<S: Into<Size>>(&self, max_size: Option<S>) {
-    self.window.set_max_inner_size(max_size.map(|s| s.into()))
+    self
+      .window
+      .set_max_inner_size(max_size.map(|s| self.rounded_size(s.into())))
+  }
+
+  /// Locks the inner size of the window to a `(width, height)` ratio, e.g. `(16, 9)`, while the
+  /// user resizes it. `None` removes the constraint.
+  ///
+  /// See [`WindowSizeConstraints::aspect_ratio`] for how it interacts with the minimum and
+  /// maximum sizes.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_aspect_ratio(&self, aspect_ratio: Option<(u32, u32)>) {
+    self.window.set_aspect_ratio(aspect_ratio)
   }
 }
 
@@ -589,12 +977,28 @@ impl Window {
     self.window.set_title(title)
   }
 
-  /// Modifies the menu of the window.
+  /// Gets the current title of the window.
   ///
   /// ## Platform-specific
   ///
-  /// - **Windows:** Unsupported.
+  /// - **iOS / Android:** Unsupported. Returns an empty string.
+  #[inline]
+  pub fn title(&self) -> String {
+    self.window.title()
+  }
 
+  /// Sets the menu bar of this window, or removes it with `None`.
+  ///
+  /// This only affects this window. When the menu bar is removed, the content of the window
+  /// grows to fill the space it used.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Unsupported and logs a warning, the menu bar belongs to the application there,
+  ///   see [`EventLoopWindowTarget::set_application_menu`].
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`EventLoopWindowTarget::set_application_menu`]: crate::event_loop::EventLoopWindowTarget::set_application_menu
   #[inline]
   pub fn set_menu(&self, menu: Option<MenuBar>) {
     if let Some(menu) = menu {
@@ -616,6 +1020,23 @@ impl Window {
     self.window.set_visible(visible)
   }
 
+  /// Modifies the window's visibility like [`Window::set_visible`], without the animation of the
+  /// window appearing or disappearing if `animate` is `false`, e.g. for a launcher popup that
+  /// should show up instantly.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Sets the `animationBehavior` of the window to `NSWindowAnimationBehaviorNone`
+  ///   while it's ordered front or out.
+  /// - **Windows:** Sets `DWMWA_TRANSITIONS_FORCEDISABLED` while the window is shown or hidden.
+  /// - **Linux / iOS:** The animation can't be controlled, this is the same as
+  ///   [`Window::set_visible`].
+  /// - **Android:** Unsupported.
+  #[inline]
+  pub fn set_visible_with_animation(&self, visible: bool, animate: bool) {
+    self.window.set_visible_with_animation(visible, animate)
+  }
+
   /// Bring the window to front and focus.
   ///
   /// ## Platform-specific
@@ -645,6 +1066,20 @@ impl Window {
     self.window.set_resizable(resizable)
   }
 
+  /// Sets the enabled buttons of the title bar.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The maximize button stays disabled while the window isn't resizable. Disabling
+  ///   the close button grays out its entry in the system menu too.
+  /// - **Linux:** Only a hint, the window manager may ignore it, e.g. disabling the minimize and
+  ///   maximize buttons is only honored before the window is realized by some window managers.
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_enabled_buttons(&self, buttons: WindowButtons) {
+    self.window.set_enabled_buttons(buttons)
+  }
+
   /// Sets the window to minimized or back
   ///
   /// ## Platform-specific
@@ -659,7 +1094,15 @@ impl Window {
   ///
   /// ## Platform-specific
   ///
+  /// - **macOS:** Zooms the window like the green titlebar button does with the Option key, it
+  ///   never makes it fullscreen, see [`Window::set_fullscreen`]. The new frame is applied when
+  ///   this returns, so [`Window::inner_size`] and [`Window::outer_size`] report the maximized
+  ///   size right away. While the window is fullscreen, it's zoomed once it exits fullscreen.
+  /// - **Linux:** The window manager maximizes the window asynchronously, read the new size from
+  ///   the following [`WindowEvent::Resized`] event.
   /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`WindowEvent::Resized`]: crate::event::WindowEvent::Resized
   #[inline]
   pub fn set_maximized(&self, maximized: bool) {
     self.window.set_maximized(maximized)
@@ -669,13 +1112,18 @@ impl Window {
   ///
   /// ## Platform-specific
   ///
+  /// - **macOS:** Whether the window is zoomed. While the window is fullscreen, this is whether it
+  ///   will be zoomed once it exits fullscreen.
   /// - **iOS / Android:** Unsupported.
   #[inline]
   pub fn is_maximized(&self) -> bool {
     self.window.is_maximized()
   }
 
-  /// Gets the window's current vibility state.
+  /// Gets the window's current visibility state.
+  ///
+  /// This reflects [`Window::set_visible`] as well as minimization: a minimized window is not
+  /// considered visible.
   ///
   /// ## Platform-specific
   ///
@@ -685,6 +1133,17 @@ impl Window {
     self.window.is_visible()
   }
 
+  /// Returns the current theme of the window.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** The theme of the GTK settings, which is shared by all windows.
+  /// - **iOS / Android:** Unsupported, always returns [`Theme::Light`].
+  #[inline]
+  pub fn theme(&self) -> Theme {
+    self.window.theme()
+  }
+
   /// Gets the window's current resizable state.
   ///
   /// ## Platform-specific
@@ -695,6 +1154,16 @@ impl Window {
     self.window.is_resizable()
   }
 
+  /// Gets the enabled buttons of the title bar.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported, always returns [`WindowButtons::all`].
+  #[inline]
+  pub fn enabled_buttons(&self) -> WindowButtons {
+    self.window.enabled_buttons()
+  }
+
   /// Gets the window's current decoration state.
   ///
   /// ## Platform-specific
@@ -706,6 +1175,10 @@ impl Window {
 
   /// Sets the window to fullscreen or back.
   ///
+  /// Leaving fullscreen restores the position, size and maximized state the window had before
+  /// entering it. If the saved position isn't on any monitor anymore, e.g. because a monitor
+  /// was disconnected meanwhile, the window is centered on the primary monitor instead.
+  ///
   /// ## Platform-specific
   ///
   /// - **macOS:** `Fullscreen::Exclusive` provides true exclusive mode with a
@@ -720,10 +1193,17 @@ impl Window {
   ///   separate spaces are not preferred.
   ///
   ///   The dock and the menu bar are always disabled in fullscreen mode.
+  ///
+  ///   The transition is animated, read the new size from the following
+  ///   [`WindowEvent::Resized`] event.
   /// - **iOS:** Can only be called on the main thread.
-  /// - **Windows:** Screen saver is disabled in fullscreen mode.
+  /// - **Windows:** Screen saver is disabled in fullscreen mode. In `Fullscreen::Exclusive`, the
+  ///   display gets its original video mode back while the application is inactive.
   /// - **Linux:** The window will only fullscreen to current monitor no matter which enum variant.
+  ///   On Wayland, the compositor decides where the window is restored.
   /// - **Android:** Unsupported.
+  ///
+  /// [`WindowEvent::Resized`]: crate::event::WindowEvent::Resized
   #[inline]
   pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
     self.window.set_fullscreen(fullscreen)
@@ -742,6 +1222,9 @@ impl Window {
 
   /// Turn window decorations on or off.
   ///
+  /// The size of the window's client area is preserved, the outer size grows or shrinks to fit
+  /// the decorations instead. Maximized and fullscreen windows keep their outer size.
+  ///
   /// ## Platform-specific
   ///
   /// - **iOS / Android:** Unsupported.
@@ -762,6 +1245,26 @@ impl Window {
     self.window.set_always_on_top(always_on_top)
   }
 
+  /// Prevents the content of the window from being captured by screenshots and screen recordings
+  /// of other applications.
+  ///
+  /// The protection applies to the whole window. On Windows, a single sensitive region can be
+  /// protected instead with `WindowExtWindows::set_protected_region`, see the
+  /// `content_protection` example.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Uses `WDA_EXCLUDEFROMCAPTURE`, the window is left out of captures. Before
+  ///   Windows 10 version 2004, `WDA_MONITOR` is used instead and the window is captured as a
+  ///   black rectangle.
+  /// - **macOS:** Sets the `sharingType` of the window to `NSWindowSharingNone`. There is no API
+  ///   to protect only a region of a window, protect the whole window instead.
+  /// - **Linux / iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_content_protection(&self, enabled: bool) {
+    self.window.set_content_protection(enabled)
+  }
+
   /// Sets the window icon. On Windows and Linux, this is typically the small icon in the top-left
   /// corner of the title bar.
   ///
@@ -770,7 +1273,10 @@ impl Window {
   /// - **iOS / Android / macOS:** Unsupported.
   ///
   /// On Windows, this sets `ICON_SMALL`. The base size for a window icon is 16x16, but it's
-  /// recommended to account for screen scaling and pick a multiple of that, i.e. 32x32.
+  /// recommended to account for screen scaling and pick a multiple of that, i.e. 32x32. The
+  /// taskbar uses `ICON_BIG`, set it with `WindowExtWindows::set_taskbar_icon` to avoid the
+  /// small icon being stretched there. An icon created with `Icon::from_rgba_sizes` is used for
+  /// `ICON_BIG` as well when no taskbar icon is set.
   #[inline]
   pub fn set_window_icon(&self, window_icon: Option<Icon>) {
     self.window.set_window_icon(window_icon)
@@ -783,7 +1289,45 @@ impl Window {
   /// - **iOS / Android:** Unsupported.
   #[inline]
   pub fn set_ime_position<P: Into<Position>>(&self, position: P) {
-    self.window.set_ime_position(position.into())
+    self
+      .window
+      .set_ime_position(self.rounded_position(position.into()))
+  }
+
+  /// Sets the area of the text cursor in client area coordinates relative to the top left.
+  ///
+  /// Input methods use it to place their candidate window next to the text being edited
+  /// instead of covering it.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_ime_cursor_area<P: Into<Position>, S: Into<Size>>(&self, position: P, size: S) {
+    self.window.set_ime_cursor_area(
+      self.rounded_position(position.into()),
+      self.rounded_size(size.into()),
+    )
+  }
+
+  /// Shows or hides the on-screen keyboard of touch devices.
+  ///
+  /// The text typed while it is visible is received with [`WindowEvent::ReceivedImeText`], and
+  /// [`WindowEvent::SoftInputChanged`] is emitted when the keyboard appears or disappears.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS:** Makes the window's view the first responder, or resigns it. The backspace key is
+  ///   reported with `KeyboardInput` events.
+  /// - **Android:** Uses `ANativeActivity_showSoftInput` and `ANativeActivity_hideSoftInput`, the
+  ///   keys are reported with `KeyboardInput` events.
+  /// - **Windows / Linux / macOS:** Unsupported.
+  ///
+  /// [`WindowEvent::ReceivedImeText`]: crate::event::WindowEvent::ReceivedImeText
+  /// [`WindowEvent::SoftInputChanged`]: crate::event::WindowEvent::SoftInputChanged
+  #[inline]
+  pub fn set_soft_input_visible(&self, visible: bool) {
+    self.window.set_soft_input_visible(visible)
   }
 
   /// Requests user attention to the window, this has no effect if the application
@@ -838,14 +1382,69 @@ impl Window {
 impl Window {
   /// Modifies the cursor icon of the window.
   ///
+  /// Each [`CursorIcon`] maps to the nearest native cursor. Cursors that have no native
+  /// equivalent fall back to [`CursorIcon::Default`].
+  ///
   /// ## Platform-specific
   ///
+  /// - **Windows:** `Cell` uses the crosshair cursor, `ContextMenu`, `Alias`, `Copy`, `ZoomIn` and
+  ///   `ZoomOut` use the default arrow. Setting the current cursor again does nothing, so this
+  ///   can be called on every `CursorMoved` without flicker.
+  /// - **Linux:** Uses the named cursors of the current cursor theme.
   /// - **iOS / Android:** Unsupported.
   #[inline]
   pub fn set_cursor_icon(&self, cursor: CursorIcon) {
+    *self.cursor.lock().unwrap() = Cursor::Icon(cursor);
     self.window.set_cursor_icon(cursor);
   }
 
+  /// Modifies the cursor of the window to a [`CustomCursor`].
+  ///
+  /// The frames of an animated cursor are cycled while it's the cursor of the window, until
+  /// another cursor is set with this method or [`Window::set_cursor_icon`]. Animation is
+  /// best-effort: where it isn't supported, the first frame is shown.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows / macOS / Linux:** The frames are cycled with a timer of the event loop.
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_custom_cursor(&self, cursor: &CustomCursor) {
+    *self.cursor.lock().unwrap() = Cursor::Custom(cursor.clone());
+    self.window.set_custom_cursor(cursor.clone());
+  }
+
+  /// Shows the [`CursorIcon::Wait`] cursor until the returned guard is dropped, which restores
+  /// the cursor set before.
+  ///
+  /// Guards can be nested, each one restores the cursor that was set when it was created, so
+  /// they must be dropped in the reverse order of their creation. Use
+  /// [`Window::set_cursor_icon`] with [`CursorIcon::Progress`] instead when the application
+  /// can still be interacted with.
+  ///
+  /// ```no_run
+  /// # use tao::{event_loop::EventLoop, window::Window};
+  /// # let event_loop = EventLoop::new();
+  /// # let window = Window::new(&event_loop).unwrap();
+  /// {
+  ///   let _wait = window.set_wait_cursor();
+  ///   // Blocking work
+  /// }
+  /// ```
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_wait_cursor(&self) -> WaitCursorGuard<'_> {
+    let previous = self.cursor.lock().unwrap().clone();
+    self.set_cursor_icon(CursorIcon::Wait);
+    WaitCursorGuard {
+      window: self,
+      previous,
+    }
+  }
+
   /// Changes the position of the cursor in window coordinates.
   ///
   /// ## Platform-specific
@@ -853,7 +1452,9 @@ impl Window {
   /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
   #[inline]
   pub fn set_cursor_position<P: Into<Position>>(&self, position: P) -> Result<(), ExternalError> {
-    self.window.set_cursor_position(position.into())
+    self
+      .window
+      .set_cursor_position(self.rounded_position(position.into()))
   }
 
   /// Grabs the cursor, preventing it from leaving the window.
@@ -861,15 +1462,58 @@ impl Window {
   /// There's no guarantee that the cursor will be hidden. You should
   /// hide it by yourself if you want so.
   ///
+  /// The grab only holds while the window is focused: it's released when a
+  /// [`WindowEvent::Focused(false)`] is emitted and applied again on the next
+  /// [`WindowEvent::Focused(true)`], until `set_cursor_grab(false)` is called.
+  ///
   /// ## Platform-specific
   ///
   /// - **macOS:** This locks the cursor in a fixed location, which looks visually awkward.
+  /// - **Linux:** On X11, this grabs the pointer of the default seat, it's not confined to the
+  ///   window. On Wayland, this confines the pointer to the window with the
+  ///   `zwp_pointer_constraints_v1` protocol, and returns an [`ExternalError::NotSupported`]
+  ///   without the `wayland-protocols` feature or if the compositor doesn't support it. See also
+  ///   `WindowExtUnix::set_cursor_lock`.
   /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  ///
+  /// [`WindowEvent::Focused(false)`]: crate::event::WindowEvent::Focused
+  /// [`WindowEvent::Focused(true)`]: crate::event::WindowEvent::Focused
   #[inline]
   pub fn set_cursor_grab(&self, grab: bool) -> Result<(), ExternalError> {
     self.window.set_cursor_grab(grab)
   }
 
+  /// Sets whether the background of the window should be transparent.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Returns an [`ExternalError::NotSupported`] if the window was created without a
+  ///   redirection bitmap, its transparency is up to the renderer then.
+  /// - **Linux:** Making the window transparent requires it to be created with
+  ///   [`WindowBuilder::with_transparent`] on a screen that supports it, otherwise an
+  ///   [`ExternalError::NotSupported`] is returned.
+  /// - **macOS:** The shadow is disabled while the window is transparent, unless it was set
+  ///   explicitly with `WindowExtMacOS::set_has_shadow`.
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  #[inline]
+  pub fn set_transparent(&self, transparent: bool) -> Result<(), ExternalError> {
+    self.window.set_transparent(transparent)
+  }
+
+  /// Sets whether what is behind the window should be blurred.
+  ///
+  /// The blur is only visible through the transparent parts of the window, see
+  /// [`Window::set_transparent`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** The blur uses the `Vibrancy` of `WindowBuilderExtMacOS::with_vibrancy`.
+  /// - **Linux / iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_blur(&self, blur: bool) {
+    self.window.set_blur(blur)
+  }
+
   /// Modifies the cursor's visibility.
   ///
   /// If `false`, this will hide the cursor. If `true`, this will show the cursor.
@@ -898,6 +1542,40 @@ impl Window {
   pub fn drag_window(&self) -> Result<(), ExternalError> {
     self.window.drag_window()
   }
+
+  /// Makes only the given rectangles of the window receive mouse events, the rest of the window
+  /// lets them pass through. `None` makes the whole window interactive again.
+  ///
+  /// The rectangles are relative to the top-left corner of the client area.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The window is also clipped to the rectangles, nothing is drawn outside of them,
+  ///   like with [`Window::set_shape`] which this replaces.
+  /// - **macOS:** Mouse events outside of the rectangles are not delivered to the window, they
+  ///   only reach other applications while the window ignores the mouse entirely.
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  #[inline]
+  pub fn set_cursor_hittest_region(&self, region: Option<Vec<Rect>>) -> Result<(), ExternalError> {
+    self.window.set_cursor_hittest_region(region)
+  }
+
+  /// Shapes the window to the union of the given rectangles, outside of them the window is
+  /// neither drawn nor receives mouse events. `None` restores the rectangular shape.
+  ///
+  /// The rectangles are relative to the top-left corner of the client area.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** This replaces the region of [`Window::set_cursor_hittest_region`].
+  /// - **macOS:** Always returns an [`ExternalError::NotSupported`]. Shape the window with a
+  ///   transparent window instead, drawing transparent pixels outside of the shape, and call
+  ///   `WindowExtMacOS::invalidate_shadow` after drawing so the shadow follows it.
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  #[inline]
+  pub fn set_shape(&self, shape: Option<Vec<Rect>>) -> Result<(), ExternalError> {
+    self.window.set_shape(shape)
+  }
 }
 
 /// Monitor info functions.
@@ -945,6 +1623,23 @@ impl Window {
   }
 }
 
+/// Restores the cursor when dropped, see [`Window::set_wait_cursor`].
+#[must_use = "the cursor is restored as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct WaitCursorGuard<'a> {
+  window: &'a Window,
+  previous: Cursor,
+}
+
+impl Drop for WaitCursorGuard<'_> {
+  fn drop(&mut self) {
+    match &self.previous {
+      Cursor::Icon(cursor) => self.window.set_cursor_icon(*cursor),
+      Cursor::Custom(cursor) => self.window.set_custom_cursor(cursor),
+    }
+  }
+}
+
 // Safety: objc runtime calls are unsafe
 unsafe impl raw_window_handle::HasRawWindowHandle for Window {
   /// Returns a `raw_window_handle::RawWindowHandle` for the Window
@@ -958,6 +1653,73 @@ unsafe impl raw_window_handle::HasRawWindowHandle for Window {
   }
 }
 
+/// The space on each side of the content area of a window, in physical pixels for
+/// [`Window::content_insets`] and in logical units for [`Window::safe_area_insets`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Insets {
+  pub top: u32,
+  pub left: u32,
+  pub bottom: u32,
+  pub right: u32,
+}
+
+/// The orientation of the content of a window relative to the device, see
+/// [`Window::set_orientation_lock`].
+///
+/// The flipped variants are rotated by 180 degrees, `Landscape` is the orientation of a device
+/// rotated counterclockwise from `Portrait`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Orientation {
+  Portrait,
+  PortraitFlipped,
+  Landscape,
+  LandscapeFlipped,
+}
+
+/// A rectangle of a window, see [`Window::set_cursor_hittest_region`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+  pub position: Position,
+  pub size: Size,
+}
+
+impl Rect {
+  pub fn new<P: Into<Position>, S: Into<Size>>(position: P, size: S) -> Self {
+    Self {
+      position: position.into(),
+      size: size.into(),
+    }
+  }
+}
+
+/// Constraints on the inner size of a window, see [`WindowBuilder::with_inner_size_constraints`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WindowSizeConstraints {
+  /// The minimum inner size, see [`Window::set_min_inner_size`].
+  pub min: Option<Size>,
+  /// The maximum inner size, see [`Window::set_max_inner_size`].
+  pub max: Option<Size>,
+  /// The width to height ratio of the inner size, e.g. `16.0 / 9.0`.
+  ///
+  /// It's only enforced while the user resizes the window, sizes set programmatically are
+  /// applied as is. The minimum and maximum sizes take precedence: when the ratio can't be kept
+  /// within them, the size is clamped and the ratio is broken.
+  pub aspect_ratio: Option<f64>,
+}
+
+impl WindowSizeConstraints {
+  pub fn new(min: Option<Size>, max: Option<Size>, aspect_ratio: Option<f64>) -> Self {
+    Self {
+      min,
+      max,
+      aspect_ratio,
+    }
+  }
+}
+
 /// Describes the appearance of the mouse cursor.
 #[non_exhaustive]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -986,18 +1748,27 @@ pub enum CursorIcon {
 
   /// Cursor showing that something cannot be done.
   NotAllowed,
+  /// Indicates a context menu is available.
   ContextMenu,
+  /// Indicates a cell or set of cells may be selected.
   Cell,
+  /// Indicates vertical text that may be selected or edited.
   VerticalText,
+  /// Indicates an alias or shortcut is to be created.
   Alias,
+  /// Indicates something is to be copied.
   Copy,
+  /// Indicates that the dragged item cannot be dropped here.
   NoDrop,
   /// Indicates something can be grabbed.
   Grab,
   /// Indicates something is grabbed.
   Grabbing,
+  /// Indicates something can be scrolled in any direction.
   AllScroll,
+  /// Indicates something can be zoomed in.
   ZoomIn,
+  /// Indicates something can be zoomed out.
   ZoomOut,
 
   /// Indicate that some edge is to be moved. For example, the 'SeResize' cursor
@@ -1024,6 +1795,98 @@ impl Default for CursorIcon {
   }
 }
 
+/// A cursor made from 32bpp RGBA images, set with [`Window::set_custom_cursor`].
+///
+/// Cloning a `CustomCursor` is cheap, the images are shared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomCursor {
+  pub(crate) frames: Arc<Vec<CursorFrame>>,
+  pub(crate) width: u32,
+  pub(crate) height: u32,
+  pub(crate) hotspot: PhysicalPosition<u32>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct CursorFrame {
+  pub(crate) rgba: Vec<u8>,
+  pub(crate) duration: Duration,
+}
+
+impl CustomCursor {
+  /// Creates a static `CustomCursor` from 32bpp RGBA data.
+  ///
+  /// `hotspot` is the point of the image, from its top-left corner, which is at the cursor
+  /// position, it must be inside of the image. The length of `rgba` must be divisible by 4, and
+  /// `width * height` must equal `rgba.len() / 4`. Otherwise, this will return a `BadIcon` error.
+  pub fn from_rgba(
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+    hotspot: PhysicalPosition<u32>,
+  ) -> Result<Self, BadIcon> {
+    Self::from_animation(vec![(rgba, Duration::ZERO)], width, height, hotspot)
+  }
+
+  /// Creates an animated `CustomCursor` from frames of 32bpp RGBA data, each shown for its
+  /// duration before moving on to the next one, looping back to the first frame after the last.
+  ///
+  /// All the frames have the same `width`, `height` and `hotspot`, with the same requirements
+  /// as [`CustomCursor::from_rgba`]. Returns [`BadIcon::NoFrames`] when `frames` is empty, and
+  /// [`BadIcon::ZeroFrameDuration`] when there are several frames and one of them has a zero
+  /// duration.
+  pub fn from_animation(
+    frames: Vec<(Vec<u8>, Duration)>,
+    width: u32,
+    height: u32,
+    hotspot: PhysicalPosition<u32>,
+  ) -> Result<Self, BadIcon> {
+    if frames.is_empty() {
+      return Err(BadIcon::NoFrames);
+    }
+    // The duration of a single frame is never used
+    if frames.len() > 1 && frames.iter().any(|(_, duration)| duration.is_zero()) {
+      return Err(BadIcon::ZeroFrameDuration);
+    }
+    if hotspot.x >= width || hotspot.y >= height {
+      return Err(BadIcon::HotspotOutOfBounds {
+        width,
+        height,
+        hotspot_x: hotspot.x,
+        hotspot_y: hotspot.y,
+      });
+    }
+    let frames = frames
+      .into_iter()
+      .map(|(rgba, duration)| {
+        let icon = RgbaIcon::from_rgba(rgba, width, height)?;
+        Ok(CursorFrame {
+          rgba: icon.rgba,
+          duration,
+        })
+      })
+      .collect::<Result<Vec<_>, BadIcon>>()?;
+    Ok(CustomCursor {
+      frames: Arc::new(frames),
+      width,
+      height,
+      hotspot,
+    })
+  }
+
+  /// Whether the cursor has several frames to cycle through.
+  #[allow(dead_code)] // Not used on every platform
+  pub(crate) fn is_animated(&self) -> bool {
+    self.frames.len() > 1
+  }
+}
+
+/// The cursor set on a [`Window`].
+#[derive(Debug, Clone)]
+enum Cursor {
+  Icon(CursorIcon),
+  Custom(CustomCursor),
+}
+
 /// Fullscreen modes.
 #[non_exhaustive]
 #[derive(Clone, Debug, PartialEq)]
@@ -1034,6 +1897,21 @@ pub enum Fullscreen {
   Borderless(Option<MonitorHandle>),
 }
 
+bitflags! {
+  /// The buttons of a window's title bar, see [`Window::set_enabled_buttons`].
+  pub struct WindowButtons: u32 {
+    const CLOSE = 1 << 0;
+    const MINIMIZE = 1 << 1;
+    const MAXIMIZE = 1 << 2;
+  }
+}
+
+impl Default for WindowButtons {
+  fn default() -> Self {
+    WindowButtons::all()
+  }
+}
+
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Theme {
@@ -1063,3 +1941,52 @@ impl Default for UserAttentionType {
 /// A constant used to determine how much inside the window, the resize handler should appear (only used in Linux(gtk) and Windows).
 /// You probably need to scale it by the scale_factor of the window.
 pub const BORDERLESS_RESIZE_INSET: i32 = 5;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn cursor_zero_frame_duration() {
+    let frame = || vec![0; 4 * 4 * 4];
+    let hotspot = PhysicalPosition::new(0, 0);
+    assert!(CustomCursor::from_rgba(frame(), 4, 4, hotspot).is_ok());
+    assert!(matches!(
+      CustomCursor::from_animation(
+        vec![
+          (frame(), Duration::from_millis(50)),
+          (frame(), Duration::ZERO)
+        ],
+        4,
+        4,
+        hotspot,
+      ),
+      Err(BadIcon::ZeroFrameDuration)
+    ));
+    assert!(CustomCursor::from_animation(
+      vec![
+        (frame(), Duration::from_millis(50)),
+        (frame(), Duration::from_millis(50))
+      ],
+      4,
+      4,
+      hotspot,
+    )
+    .is_ok());
+  }
+
+  #[test]
+  fn cursor_hotspot_out_of_bounds() {
+    let cursor =
+      |x, y| CustomCursor::from_rgba(vec![0; 4 * 3 * 2], 3, 2, PhysicalPosition::new(x, y));
+    assert!(cursor(2, 1).is_ok());
+    assert!(matches!(
+      cursor(3, 0),
+      Err(BadIcon::HotspotOutOfBounds { .. })
+    ));
+    assert!(matches!(
+      cursor(0, 2),
+      Err(BadIcon::HotspotOutOfBounds { .. })
+    ));
+  }
+}
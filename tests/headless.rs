@@ -0,0 +1,291 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(feature = "headless")]
+
+use tao::{
+  dpi::{PhysicalPosition, PhysicalSize, ScaleFactorRounding},
+  event::{ElementState, Event, StartCause, WindowEvent},
+  event_loop::{ControlFlow, EventLoop, EventLoopBuilder},
+  keyboard::{Key, KeyCode},
+  platform::{
+    headless::{key_event, EventLoopWindowTargetExtHeadless, HeadlessMonitor},
+    run_return::EventLoopExtRunReturn,
+  },
+  window::WindowBuilder,
+};
+
+#[test]
+fn headless_monitors_per_event_loop() {
+  let event_loop = EventLoop::<()>::new();
+  event_loop.set_monitors(vec![HeadlessMonitor {
+    name: "Configured".to_owned(),
+    ..Default::default()
+  }]);
+  let monitor = event_loop.primary_monitor().unwrap();
+  drop(event_loop);
+
+  let event_loop = EventLoop::<()>::new();
+  assert_eq!(
+    event_loop
+      .primary_monitor()
+      .and_then(|monitor| monitor.name()),
+    Some("Headless".to_owned())
+  );
+  assert_ne!(event_loop.primary_monitor(), Some(monitor.clone()));
+  assert_eq!(monitor.name(), Some("Configured".to_owned()));
+}
+
+#[test]
+fn headless_scale_factor_rounding_per_event_loop() {
+  let monitors = vec![HeadlessMonitor {
+    scale_factor: 1.5,
+    ..Default::default()
+  }];
+  let rounded = EventLoopBuilder::new()
+    .with_scale_factor_rounding(ScaleFactorRounding::RoundToNearestInt)
+    .build()
+    .unwrap();
+  rounded.set_monitors(monitors.clone());
+  let exact = EventLoop::new();
+  exact.set_monitors(monitors);
+
+  let rounded_window = WindowBuilder::new().build(&rounded).unwrap();
+  let exact_window = WindowBuilder::new().build(&exact).unwrap();
+  assert_eq!(rounded_window.scale_factor(), 2.0);
+  assert_eq!(exact_window.scale_factor(), 1.5);
+  // The rounding isn't reset by dropping another event loop
+  drop(exact_window);
+  drop(exact);
+  assert_eq!(rounded_window.scale_factor(), 2.0);
+}
+
+#[test]
+fn headless_event_loop() {
+  let mut event_loop = EventLoop::new();
+  event_loop.set_monitors(vec![
+    HeadlessMonitor::default(),
+    HeadlessMonitor {
+      name: "Second".to_owned(),
+      position: PhysicalPosition::new(1920, 0),
+      scale_factor: 2.0,
+      ..Default::default()
+    },
+  ]);
+  assert_eq!(event_loop.available_monitors().count(), 2);
+
+  let window = WindowBuilder::new()
+    .with_inner_size(PhysicalSize::new(400, 300))
+    .build(&event_loop)
+    .unwrap();
+  let window_id = window.id();
+  assert_eq!(window.scale_factor(), 1.0);
+  window.set_outer_position(PhysicalPosition::new(2000, 100));
+  assert_eq!(window.scale_factor(), 2.0);
+  assert_eq!(
+    window.current_monitor().and_then(|monitor| monitor.name()),
+    Some("Second".to_owned())
+  );
+
+  window.set_inner_size(PhysicalSize::new(640, 480));
+  window.request_redraw();
+  event_loop.inject_keyboard_input(
+    window_id,
+    key_event(
+      KeyCode::KeyA,
+      Key::Character("a"),
+      Some("a"),
+      ElementState::Pressed,
+    ),
+  );
+  event_loop.inject_window_event(window_id, WindowEvent::CloseRequested);
+
+  let mut events = Vec::new();
+  event_loop.run_return(|event, _, control_flow| match event {
+    Event::WindowEvent {
+      event: WindowEvent::CloseRequested,
+      ..
+    } => *control_flow = ControlFlow::Exit,
+    Event::WindowEvent { .. } | Event::RedrawRequested(_) => {
+      events.push(event.to_static().unwrap())
+    }
+    _ => (),
+  });
+
+  assert!(events.iter().any(|event| matches!(
+    event,
+    Event::WindowEvent {
+      event: WindowEvent::Resized(size),
+      ..
+    } if *size == PhysicalSize::new(640, 480)
+  )));
+  assert!(events.iter().any(|event| matches!(
+    event,
+    Event::WindowEvent {
+      event: WindowEvent::KeyboardInput { event, .. },
+      ..
+    } if event.text == Some("a")
+  )));
+  assert!(events.contains(&Event::RedrawRequested(window_id)));
+}
+
+#[test]
+fn headless_set_control_flow() {
+  let mut event_loop = EventLoop::new();
+  let mut iterations = 0;
+  let exit_code = event_loop.run_return(|event, target, _| {
+    if let Event::MainEventsCleared = event {
+      iterations += 1;
+      if iterations == 3 {
+        target.set_control_flow(ControlFlow::ExitWithCode(7));
+      }
+    }
+  });
+  assert_eq!(iterations, 3);
+  assert_eq!(exit_code, 7);
+}
+
+#[test]
+fn headless_set_control_flow_after_direct_write() {
+  let mut event_loop = EventLoop::new();
+  let mut iterations = 0;
+  let exit_code = event_loop.run_return(|event, target, control_flow| {
+    *control_flow = ControlFlow::Poll;
+    if let Event::MainEventsCleared = event {
+      iterations += 1;
+      // The write from the previous event is seen
+      assert_eq!(target.control_flow(), ControlFlow::Poll);
+      if iterations >= 3 {
+        *control_flow = ControlFlow::Wait;
+        target.set_control_flow(ControlFlow::ExitWithCode(7));
+      }
+    }
+  });
+  assert_eq!(iterations, 3);
+  assert_eq!(exit_code, 7);
+}
+
+#[test]
+fn headless_direct_write_over_set_control_flow() {
+  let mut event_loop = EventLoop::new();
+  let mut iterations = 0;
+  let exit_code = event_loop.run_return(|event, target, control_flow| {
+    if let Event::MainEventsCleared = event {
+      iterations += 1;
+      target.set_control_flow(ControlFlow::Poll);
+      if iterations >= 3 {
+        *control_flow = ControlFlow::ExitWithCode(3);
+      }
+    }
+  });
+  assert_eq!(iterations, 3);
+  assert_eq!(exit_code, 3);
+}
+
+#[test]
+fn headless_proxy_waker() {
+  let mut event_loop = EventLoop::<()>::new();
+  let waker = event_loop.create_proxy().waker();
+  let mut woken = false;
+  event_loop.run_return(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+    match event {
+      Event::NewEvents(StartCause::Init) => {
+        let waker = waker.clone();
+        std::thread::spawn(move || waker.wake());
+      }
+      Event::NewEvents(_) => woken = true,
+      Event::MainEventsCleared if woken => *control_flow = ControlFlow::Exit,
+      _ => (),
+    }
+  });
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn headless_proxy_inject_window_event() {
+  let mut event_loop = EventLoop::<()>::new();
+  let window = WindowBuilder::new().build(&event_loop).unwrap();
+  let window_id = window.id();
+  let proxy = event_loop.create_proxy();
+  let mut events = Vec::new();
+  event_loop.run_return(|event, _, control_flow| match event {
+    Event::NewEvents(StartCause::Init) => {
+      *control_flow = ControlFlow::Wait;
+      let proxy = proxy.clone();
+      std::thread::spawn(move || {
+        proxy
+          .inject_window_event(window_id, WindowEvent::Focused(true))
+          .unwrap();
+        proxy
+          .inject_window_event(window_id, WindowEvent::CloseRequested)
+          .unwrap();
+      });
+    }
+    Event::WindowEvent {
+      window_id: id,
+      event,
+      ..
+    } if id == window_id && *control_flow != ControlFlow::Exit => {
+      if event == WindowEvent::CloseRequested {
+        *control_flow = ControlFlow::Exit;
+      }
+      events.push(event.to_static().unwrap());
+    }
+    _ => (),
+  });
+  assert!(events.ends_with(&[WindowEvent::Focused(true), WindowEvent::CloseRequested]));
+}
+
+#[test]
+fn headless_proxy_user_events_order() {
+  const COUNT: u32 = 100;
+  let mut event_loop = EventLoop::<u32>::with_user_event();
+  let window = WindowBuilder::new().build(&event_loop).unwrap();
+  let window_id = window.id();
+  let proxy = event_loop.create_proxy();
+  let mut in_iteration = false;
+  let mut user_event_seen = false;
+  let mut received = Vec::new();
+  event_loop.run_return(|event, _, control_flow| match event {
+    Event::NewEvents(cause) => {
+      in_iteration = true;
+      user_event_seen = false;
+      if cause == StartCause::Init {
+        *control_flow = ControlFlow::Wait;
+        // Interleave window changes with user events sent from this thread...
+        for i in 0..COUNT / 2 {
+          window.set_inner_size(PhysicalSize::new(100 + i, 100));
+          proxy.send_event(i).unwrap();
+        }
+        // ...and from another one.
+        let proxy = proxy.clone();
+        std::thread::spawn(move || {
+          for i in COUNT / 2..COUNT {
+            proxy.send_event(i).unwrap();
+          }
+        });
+      }
+    }
+    Event::WindowEvent { window_id: id, .. } if id == window_id => {
+      assert!(!user_event_seen, "window event emitted after a user event");
+    }
+    Event::UserEvent(i) => {
+      assert!(
+        in_iteration,
+        "user event {} emitted outside an iteration",
+        i
+      );
+      user_event_seen = true;
+      received.push(i);
+    }
+    Event::MainEventsCleared => {
+      in_iteration = false;
+      if received.len() == COUNT as usize {
+        *control_flow = ControlFlow::Exit;
+      }
+    }
+    _ => (),
+  });
+  assert_eq!(received, (0..COUNT).collect::<Vec<_>>());
+}
@@ -0,0 +1,74 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+// This test doesn't use the default harness, as AppKit windows can only be created on the main
+// thread. The native fullscreen transitions are animated, so the event loop runs while waiting
+// for them.
+
+#[cfg(all(target_os = "macos", not(feature = "headless")))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    dpi::LogicalSize,
+    event::Event,
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::{Fullscreen, WindowBuilder},
+  };
+
+  // Longer than the fullscreen animations
+  const TRANSITION: Duration = Duration::from_secs(2);
+
+  let mut event_loop = EventLoop::new();
+  let window = WindowBuilder::new()
+    .with_inner_size(LogicalSize::new(400.0, 300.0))
+    .build(&event_loop)
+    .unwrap();
+
+  let mut step = 0;
+  let mut step_start = Instant::now();
+  let mut size_before_maximize = None;
+  event_loop.run_return(|event, _, control_flow| {
+    *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(50));
+    if event != Event::MainEventsCleared || step_start.elapsed() < TRANSITION {
+      return;
+    }
+    step_start = Instant::now();
+    step += 1;
+    match step {
+      1 => window.set_fullscreen(Some(Fullscreen::Borderless(None))),
+      2 => window.set_fullscreen(None),
+      3 => {
+        size_before_maximize = Some(window.outer_size());
+        window.set_maximized(true);
+      }
+      4 => {
+        // Exiting fullscreen used to leave the window without the resizable style, which
+        // disables zooming
+        assert!(window.is_maximized());
+        assert_ne!(Some(window.outer_size()), size_before_maximize);
+        println!("test maximize_after_fullscreen ... ok");
+
+        window.set_maximized(false);
+        window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+      }
+      // The resizable state set while fullscreen is the one of the restored window
+      5 => window.set_resizable(false),
+      6 => window.set_fullscreen(None),
+      7 => {
+        assert!(!window.is_resizable());
+        window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+      }
+      8 => window.set_resizable(true),
+      9 => window.set_fullscreen(None),
+      _ => {
+        assert!(window.is_resizable());
+        println!("test resizable_during_fullscreen ... ok");
+        *control_flow = ControlFlow::Exit;
+      }
+    }
+  });
+}
+
+#[cfg(not(all(target_os = "macos", not(feature = "headless"))))]
+fn main() {}
@@ -0,0 +1,39 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+// This test doesn't use the default harness, as AppKit windows can only be created on the main
+// thread.
+
+#[cfg(all(target_os = "macos", not(feature = "headless")))]
+fn main() {
+  use tao::{
+    dpi::LogicalSize,
+    event_loop::EventLoop,
+    window::{Fullscreen, WindowBuilder},
+  };
+
+  let event_loop = EventLoop::new();
+  let window = WindowBuilder::new()
+    .with_visible(false)
+    .with_inner_size(LogicalSize::new(400.0, 300.0))
+    .build(&event_loop)
+    .unwrap();
+
+  // Maximizing zooms the window, it doesn't make it fullscreen
+  window.set_maximized(true);
+  assert!(window.is_maximized());
+  assert_eq!(window.fullscreen(), None);
+
+  window.set_maximized(false);
+  assert!(!window.is_maximized());
+
+  // A fullscreen window isn't maximized, even though `isZoomed` is true while it's fullscreen
+  window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+  assert!(window.fullscreen().is_some());
+  assert!(!window.is_maximized());
+
+  println!("test maximized_is_not_fullscreen ... ok");
+}
+
+#[cfg(not(all(target_os = "macos", not(feature = "headless"))))]
+fn main() {}
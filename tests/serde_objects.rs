@@ -8,7 +8,7 @@ use tao::{
   dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize},
   event::{ElementState, MouseButton, MouseScrollDelta, TouchPhase},
   keyboard::{Key, KeyCode, KeyLocation, ModifiersState},
-  window::CursorIcon,
+  window::{CursorIcon, WindowId},
 };
 
 #[allow(dead_code)]
@@ -17,6 +17,7 @@ fn needs_serde<S: Serialize + Deserialize<'static>>() {}
 #[test]
 fn window_serde() {
   needs_serde::<CursorIcon>();
+  needs_serde::<WindowId>();
 }
 
 #[test]
@@ -0,0 +1,32 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(all(target_os = "windows", not(feature = "headless")))]
+
+use tao::{
+  dpi::PhysicalSize,
+  event_loop::EventLoop,
+  menu::{MenuBar, MenuItemAttributes},
+  platform::windows::EventLoopExtWindows,
+  window::WindowBuilder,
+};
+
+#[test]
+fn min_inner_size_is_reachable() {
+  let event_loop = EventLoop::<()>::new_any_thread();
+  let mut menu = MenuBar::new();
+  let mut file = MenuBar::new();
+  file.add_item(MenuItemAttributes::new("Open"));
+  menu.add_submenu("File", true, file);
+  let window = WindowBuilder::new()
+    .with_visible(false)
+    .with_menu(menu)
+    .with_inner_size(PhysicalSize::new(400, 300))
+    .build(&event_loop)
+    .unwrap();
+
+  let min_size = PhysicalSize::new(200, 150);
+  window.set_min_inner_size(Some(min_size));
+  window.set_inner_size(PhysicalSize::new(100, 100));
+  assert_eq!(window.inner_size(), min_size);
+}
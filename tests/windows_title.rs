@@ -0,0 +1,22 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(all(target_os = "windows", not(feature = "headless")))]
+
+use tao::{event_loop::EventLoop, platform::windows::EventLoopExtWindows, window::WindowBuilder};
+
+#[test]
+fn title_round_trips_unicode() {
+  let event_loop = EventLoop::<()>::new_any_thread();
+  let title = "Emoji 🦀👩‍💻, CJK 漢字かなカナ한글, combining e\u{301}";
+  let window = WindowBuilder::new()
+    .with_visible(false)
+    .with_title(title)
+    .build(&event_loop)
+    .unwrap();
+  assert_eq!(window.title(), title);
+
+  let title = "𝕋𝕒𝕠 ✓";
+  window.set_title(title);
+  assert_eq!(window.title(), title);
+}